@@ -37,6 +37,7 @@ use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 
 use compiler_base_span::{Loc, Span};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use uuid;
 
@@ -402,6 +403,10 @@ pub struct Module {
     pub name: String,
     pub body: Vec<NodeRef<Stmt>>,
     pub comments: Vec<NodeRef<Comment>>,
+    /// The comments attached to the statements and schema attributes keyed by the
+    /// node id, which is only filled when parsing with the `keep_comments` option.
+    #[serde(skip)]
+    pub attached_comments: HashMap<AstIndex, AttachedComments>,
 }
 
 impl Module {
@@ -1357,6 +1362,17 @@ pub struct Comment {
     pub text: String,
 }
 
+/// AttachedComments denotes the comments attached to a statement or a schema attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AttachedComments {
+    /// The comments on the lines before the node.
+    pub leading: Vec<NodeRef<Comment>>,
+    /// The comments on the lines of the node, e.g., `a = 1  # comment`.
+    pub inline: Vec<NodeRef<Comment>>,
+    /// The comments after the last node of a block.
+    pub trailing: Vec<NodeRef<Comment>>,
+}
+
 /*
  * Operators and context
  */
@@ -185,6 +185,7 @@ fn test_filter_schema_with_no_schema() {
         name: "".to_string(),
         body: vec![],
         comments: vec![],
+        attached_comments: Default::default(),
     };
     let schema_stmts = ast_mod.filter_schema_stmt_from_module();
     assert_eq!(schema_stmts.len(), 0);
@@ -199,6 +200,7 @@ fn test_filter_schema_with_one_schema() {
         name: "".to_string(),
         body: vec![],
         comments: vec![],
+        attached_comments: Default::default(),
     };
     let mut gen_schema_stmts = gen_schema_stmt(1);
     ast_mod.body.append(&mut gen_schema_stmts);
@@ -216,6 +218,7 @@ fn test_filter_schema_with_mult_schema() {
         name: "".to_string(),
         body: vec![],
         comments: vec![],
+        attached_comments: Default::default(),
     };
     let mut gen_schema_stmts = gen_schema_stmt(10);
     ast_mod.body.append(&mut gen_schema_stmts);
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use kclvm_loader::{option::list_options, LoadPackageOptions};
+use kclvm_runner::{expand_files, ExecProgramArgs};
+use kclvm_sema::builtin::option::{print_option_help, OptionHelp};
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::util::{hashmaps_from_matches, strings_from_matches};
+
+/// Run the KCL args command, which statically lists the top-level arguments read by
+/// the `option` calls of the program with their types and defaults, e.g.,
+/// `kcl args main.k`, and nothing is executed.
+pub fn args_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list = strings_from_matches(matches, "input").unwrap_or_default();
+    if let Some(package_maps) = hashmaps_from_matches(matches, "package_map").transpose()? {
+        args.set_external_pkg_from_package_maps(package_maps);
+    }
+    args.offline = matches.get_flag("offline");
+    writeln!(writer, "{}", print_option_help(&program_options(&args)?))?;
+    Ok(())
+}
+
+/// Check every top-level argument read by the program without a default value in
+/// the code is supplied by the arguments, and all the missing ones are reported
+/// in a single error before the program is executed.
+pub(crate) fn check_required_args(args: &ExecProgramArgs) -> Result<()> {
+    let supplied: HashSet<String> = args.option_args().into_iter().map(|arg| arg.name).collect();
+    let mut missing: Vec<String> = vec![];
+    for option in program_options(args)? {
+        if option.default_value.is_empty()
+            && !supplied.contains(&option.name)
+            && !missing.contains(&option.name)
+        {
+            missing.push(option.name);
+        }
+    }
+    if !missing.is_empty() {
+        bail!(
+            "the top-level arguments read by the program are not supplied: {}, set them with -D",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the `option` calls of the program in the arguments.
+fn program_options(args: &ExecProgramArgs) -> Result<Vec<OptionHelp>> {
+    list_options(&LoadPackageOptions {
+        paths: expand_files(args)?,
+        load_opts: Some(args.get_load_program_options()),
+        resolve_ast: true,
+        load_builtin: false,
+    })
+}
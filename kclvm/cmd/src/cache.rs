@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_runner::cache_archive::{export_cache, import_cache};
+use kclvm_runner::cache_stats::{cache_stats, CacheStatsFormat};
+use kclvm_runner::ExecProgramArgs;
+use std::io::Write;
+
+/// Run the KCL cache command.
+pub fn cache_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    match matches.subcommand() {
+        Some(("stats", sub_matches)) => cache_stats_command(sub_matches, writer),
+        Some(("export", sub_matches)) => cache_export_command(sub_matches, writer),
+        Some(("import", sub_matches)) => cache_import_command(sub_matches, writer),
+        _ => Ok(()),
+    }
+}
+
+/// Compile every config in the directory and print the cache hits, misses, hit
+/// ratio and the cache size.
+pub fn cache_stats_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let dir = matches
+        .get_one::<String>("dir")
+        .ok_or(anyhow::anyhow!("the workspace directory is required"))?;
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => CacheStatsFormat::try_from(format.as_str())?,
+        None => CacheStatsFormat::default(),
+    };
+    let args = ExecProgramArgs {
+        fast_eval: matches.get_flag("fast_eval"),
+        ..Default::default()
+    };
+    let stats = cache_stats(dir, &args)?;
+    writeln!(writer, "{}", stats.render(format)?)?;
+    Ok(())
+}
+
+/// Archive the object cache of the program root into the tar file.
+pub fn cache_export_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let output = matches
+        .get_one::<String>("output")
+        .ok_or(anyhow::anyhow!("the output file is required"))?;
+    let root = program_root(matches)?;
+    let files = export_cache(&root, output)?;
+    writeln!(writer, "exported {} cache file(s) to {}", files, output)?;
+    Ok(())
+}
+
+/// Restore the object cache of the program root from the tar file.
+pub fn cache_import_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input file is required"))?;
+    let root = program_root(matches)?;
+    let files = import_cache(&root, input)?;
+    writeln!(writer, "imported {} cache file(s) from {}", files, input)?;
+    Ok(())
+}
+
+/// Returns the absolute program root, which defaults to the current directory.
+fn program_root(matches: &ArgMatches) -> Result<String> {
+    let root = match matches.get_one::<String>("root") {
+        Some(root) => std::path::PathBuf::from(root),
+        None => std::env::current_dir()?,
+    };
+    Ok(root.canonicalize()?.display().to_string())
+}
@@ -0,0 +1,23 @@
+use anyhow::{anyhow, Result};
+use clap::ArgMatches;
+use clap_complete::{generate, Shell};
+use std::io::Write;
+
+use crate::app;
+
+/// The shells supported by the completions command.
+pub const COMPLETION_SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+
+/// The binary name the completion scripts are registered for.
+const BIN_NAME: &str = "kcl";
+
+/// Run the KCL completions command, which writes the completion script of the shell
+/// generated from the CLI command tree, so the script always follows the arguments.
+pub fn completions_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let shell = matches
+        .get_one::<String>("shell")
+        .ok_or_else(|| anyhow!("the shell is required"))?;
+    let shell = shell.parse::<Shell>().map_err(|err| anyhow!(err))?;
+    generate(shell, &mut app(), BIN_NAME, writer);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_tools::coverage::{schema_coverage, CoverageFormat};
+use std::io::Write;
+
+/// Run the KCL coverage command.
+pub fn coverage_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input KCL file is required"))?;
+    let data = matches
+        .get_one::<String>("data")
+        .ok_or(anyhow::anyhow!("the data file or directory is required"))?;
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => CoverageFormat::try_from(format.as_str())?,
+        None => CoverageFormat::default(),
+    };
+    let schema = matches.get_one::<String>("schema").map(|s| s.as_str());
+    let report = schema_coverage(input, schema, data)?;
+    writeln!(writer, "{}", report.render(&format)?)?;
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_tools::doc::{gen_doc, DocFormat};
+use std::io::Write;
+
+/// Run the KCL doc command.
+pub fn doc_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input KCL file is required"))?;
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => DocFormat::try_from(format.as_str())?,
+        None => DocFormat::default(),
+    };
+    let doc = gen_doc(input, &format)?;
+    match matches.get_one::<String>("output") {
+        Some(o) => std::fs::write(o, doc)?,
+        None => writeln!(writer, "{}", doc)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use kclvm_runner::doctor::run_checks;
+use std::io::Write;
+
+/// Run the KCL doctor command, which prints a pass or fail line of each self check
+/// and fails when any critical check fails.
+pub fn doctor_command<W: Write>(_matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let checks = run_checks(".");
+    for check in &checks {
+        let status = match (check.passed, check.critical) {
+            (true, _) => "pass",
+            (false, true) => "fail",
+            (false, false) => "warn",
+        };
+        writeln!(writer, "{} {}: {}", status, check.name, check.detail)?;
+    }
+    let failed = checks.iter().filter(|c| !c.passed && c.critical).count();
+    if failed > 0 {
+        bail!("{} critical check(s) failed", failed);
+    }
+    Ok(())
+}
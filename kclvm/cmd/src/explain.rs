@@ -0,0 +1,14 @@
+use anyhow::{anyhow, Result};
+use clap::ArgMatches;
+use kclvm_error::explain;
+use std::io::Write;
+
+/// Run the KCL explain command.
+pub fn explain_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let code = matches
+        .get_one::<String>("code")
+        .ok_or(anyhow!("the diagnostic code is required"))?;
+    let description = explain(code).ok_or_else(|| anyhow!("unknown diagnostic code '{}'", code))?;
+    writeln!(writer, "{}", description.trim())?;
+    Ok(())
+}
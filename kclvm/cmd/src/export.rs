@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_error::{Handler, WarningKind};
+use kclvm_tools::export::{export, ExportTarget};
+use std::io::Write;
+
+/// Run the KCL export command.
+pub fn export_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input KCL file is required"))?;
+    let target = match matches.get_one::<String>("to") {
+        Some(to) => ExportTarget::try_from(to.as_str())?,
+        None => ExportTarget::default(),
+    };
+    let result = export(input, target)?;
+    if !result.warnings.is_empty() {
+        let mut handler = Handler::default();
+        for warning in result.warnings {
+            handler.add_warning(WarningKind::CompilerWarning, &[warning]);
+        }
+        handler.emit()?;
+    }
+    match matches.get_one::<String>("output") {
+        Some(o) => std::fs::write(o, result.code)?,
+        None => write!(writer, "{}", result.code)?,
+    }
+    Ok(())
+}
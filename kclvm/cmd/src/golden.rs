@@ -0,0 +1,88 @@
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use kclvm_runner::golden::compare_kcl_output;
+use kclvm_runner::ExecProgramArgs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::util::strings_from_matches;
+
+/// The suffix of the golden files which are paired with the KCL files in the directory mode.
+pub const GOLDEN_FILE_SUFFIX: &str = "golden";
+
+/// Run the KCL test command, which runs each KCL file and compares its output with
+/// the expected file, e.g., `kcl test main.k --expect expected.yaml`. The input
+/// directories pair every `*.k` file with the `*.golden` file of the same name.
+/// The mismatch is printed with a line diff and the command fails when any test fails.
+pub fn test_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let inputs = strings_from_matches(matches, "input").unwrap_or_default();
+    let expects = strings_from_matches(matches, "expect").unwrap_or_default();
+    let pairs = golden_pairs(&inputs, &expects)?;
+    let mut failed = 0;
+    for (kcl_file, golden_file) in &pairs {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list.push(kcl_file.display().to_string());
+        args.fast_eval = matches.get_flag("fast_eval");
+        match compare_kcl_output(&args, golden_file) {
+            Ok(()) => writeln!(writer, "ok {}", kcl_file.display())?,
+            Err(err) => {
+                failed += 1;
+                writeln!(writer, "FAIL {}\n{}", kcl_file.display(), err)?;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} test(s) failed", failed, pairs.len());
+    }
+    Ok(())
+}
+
+/// Pair the KCL files with the expected files. The files are paired in order with
+/// the `--expect` files, otherwise the directories are expanded into the `*.k` files
+/// which have a `*.golden` file of the same name.
+pub(crate) fn golden_pairs(
+    inputs: &[String],
+    expects: &[String],
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if !expects.is_empty() {
+        if inputs.len() != expects.len() {
+            bail!(
+                "{} input file(s) are paired with {} expected file(s), the counts must be equal",
+                inputs.len(),
+                expects.len()
+            );
+        }
+        return Ok(inputs
+            .iter()
+            .zip(expects)
+            .map(|(input, expect)| (PathBuf::from(input), PathBuf::from(expect)))
+            .collect());
+    }
+    let mut pairs = vec![];
+    for input in inputs {
+        let path = Path::new(input);
+        if !path.is_dir() {
+            bail!(
+                "the input file {} requires an expected file, specify it with --expect",
+                input
+            );
+        }
+        let mut files = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<PathBuf>>>()?;
+        files.sort();
+        for file in files {
+            let golden = file.with_extension(GOLDEN_FILE_SUFFIX);
+            if file.extension().map_or(false, |ext| ext == "k") && golden.is_file() {
+                pairs.push((file, golden));
+            }
+        }
+    }
+    if pairs.is_empty() {
+        bail!(
+            "no KCL file paired with a .{} file is found",
+            GOLDEN_FILE_SUFFIX
+        );
+    }
+    Ok(pairs)
+}
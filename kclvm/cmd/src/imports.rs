@@ -0,0 +1,57 @@
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use kclvm_error::sort_diagnostics;
+use kclvm_parser::{load_program, ParseSession};
+use kclvm_runner::{expand_files, ExecProgramArgs};
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::util::{hashmaps_from_matches, strings_from_matches};
+
+/// Run the KCL imports-check command, which only loads the program to resolve the
+/// imports without the type checking and codegen, e.g., `kcl imports-check main.k`.
+/// The unresolved imports and the parse errors are reported with the importing
+/// file and line, and the command fails when any error is found.
+pub fn imports_check_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list = strings_from_matches(matches, "input").unwrap_or_default();
+    if let Some(package_maps) = hashmaps_from_matches(matches, "package_map").transpose()? {
+        args.set_external_pkg_from_package_maps(package_maps);
+    }
+    args.offline = matches.get_flag("offline");
+    let files = expand_files(&args)?;
+    let files = files.iter().map(|f| f.as_str()).collect::<Vec<&str>>();
+    let result = load_program(
+        Arc::new(ParseSession::default()),
+        &files,
+        Some(args.get_load_program_options()),
+        None,
+    )?;
+    let errors = sort_diagnostics(result.errors.iter().filter(|diag| diag.is_error()));
+    for diag in &errors {
+        for msg in &diag.messages {
+            let pos = &msg.range.0;
+            match pos.column {
+                Some(column) => writeln!(
+                    writer,
+                    "{}:{}:{}: {}",
+                    pos.filename,
+                    pos.line,
+                    column + 1,
+                    msg.message
+                )?,
+                None => writeln!(writer, "{}:{}: {}", pos.filename, pos.line, msg.message)?,
+            }
+        }
+    }
+    if !errors.is_empty() {
+        bail!("{} error(s) found when resolving the imports", errors.len());
+    }
+    let pkgs = result.program.pkgs.len();
+    writeln!(
+        writer,
+        "all the imports of {} package(s) are resolved",
+        pkgs
+    )?;
+    Ok(())
+}
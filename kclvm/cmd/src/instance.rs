@@ -0,0 +1,20 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_tools::instance::gen_instance;
+use std::io::Write;
+
+/// Run the KCL init-instance command.
+pub fn init_instance_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input KCL file is required"))?;
+    let schema = matches
+        .get_one::<String>("schema")
+        .ok_or(anyhow::anyhow!("the schema name is required"))?;
+    let code = gen_instance(input, schema)?;
+    match matches.get_one::<String>("output") {
+        Some(o) => std::fs::write(o, code)?,
+        None => write!(writer, "{}", code)?,
+    }
+    Ok(())
+}
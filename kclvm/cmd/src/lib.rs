@@ -3,9 +3,26 @@
 #[macro_use]
 extern crate clap;
 
+pub mod args;
+pub mod cache;
+pub mod completions;
+pub mod coverage;
+pub mod doc;
+pub mod doctor;
+pub mod explain;
+pub mod export;
+pub mod golden;
+pub mod imports;
+pub mod instance;
+pub mod normalize;
+pub mod remote;
 pub mod run;
+pub mod schemas;
 pub mod settings;
+pub mod sink;
 pub(crate) mod util;
+pub mod vet;
+pub mod wrap;
 
 #[cfg(test)]
 mod tests;
@@ -15,14 +32,63 @@ use clap::{ArgAction, Command};
 use std::io;
 
 use anyhow::Result;
+use args::args_command;
+use cache::cache_command;
+use completions::{completions_command, COMPLETION_SHELLS};
+use coverage::coverage_command;
+use doc::doc_command;
+use doctor::doctor_command;
+use explain::explain_command;
+use export::export_command;
+use golden::test_command;
+use imports::imports_check_command;
+use instance::init_instance_command;
+use kclvm_runner::EXEC_STACK_SIZE;
+use normalize::normalize_command;
 use run::run_command;
+use schemas::schemas_command;
+use settings::settings_command;
+use vet::vet_command;
 
-/// Run the KCL main command.
+/// Run the KCL main command in a thread with [EXEC_STACK_SIZE], which the default
+/// depth limits of the parser and the evaluator fit in.
 pub fn main(args: &[&str]) -> Result<()> {
+    std::thread::scope(|scope| {
+        let handle = std::thread::Builder::new()
+            .name("kcl-main".to_string())
+            .stack_size(EXEC_STACK_SIZE)
+            .spawn_scoped(scope, || run_main(args))?;
+        // Propagate the panic to the caller, which reports it as the error.
+        handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+fn run_main(args: &[&str]) -> Result<()> {
     let matches = app().arg_required_else_help(true).get_matches_from(args);
     // Sub commands
     match matches.subcommand() {
         Some(("run", sub_matches)) => run_command(sub_matches, &mut io::stdout()),
+        Some(("doc", sub_matches)) => doc_command(sub_matches, &mut io::stdout()),
+        Some(("init-instance", sub_matches)) => {
+            init_instance_command(sub_matches, &mut io::stdout())
+        }
+        Some(("settings", sub_matches)) => settings_command(sub_matches, &mut io::stdout()),
+        Some(("normalize", sub_matches)) => normalize_command(sub_matches, &mut io::stdout()),
+        Some(("explain", sub_matches)) => explain_command(sub_matches, &mut io::stdout()),
+        Some(("export", sub_matches)) => export_command(sub_matches, &mut io::stdout()),
+        Some(("doctor", sub_matches)) => doctor_command(sub_matches, &mut io::stdout()),
+        Some(("test", sub_matches)) => test_command(sub_matches, &mut io::stdout()),
+        Some(("coverage", sub_matches)) => coverage_command(sub_matches, &mut io::stdout()),
+        Some(("imports-check", sub_matches)) => {
+            imports_check_command(sub_matches, &mut io::stdout())
+        }
+        Some(("schemas", sub_matches)) => schemas_command(sub_matches, &mut io::stdout()),
+        Some(("vet", sub_matches)) => vet_command(sub_matches),
+        Some(("args", sub_matches)) => args_command(sub_matches, &mut io::stdout()),
+        Some(("completions", sub_matches)) => completions_command(sub_matches, &mut io::stdout()),
+        Some(("cache", sub_matches)) => cache_command(sub_matches, &mut io::stdout()),
         Some(("server", _)) => kclvm_api::service::jsonrpc::start_stdio_server(),
         Some(("version", _)) => {
             println!("{}", kclvm_version::get_version_info());
@@ -38,11 +104,12 @@ pub fn app() -> Command {
     Command::new("kclvm_cli")
         .version(kclvm_version::VERSION)
         .about("KCL main CLI.")
+        .arg(arg!(offline: --offline "Forbid all the network access, e.g., fetching the dependencies and the remote setting files").global(true))
         .subcommand(
             Command::new("run")
             .about("run")
             .arg(arg!([input] ... "Specify the input files to run").num_args(0..))
-            .arg(arg!(output: -o --output <output> "Specify the YAML output file path"))
+            .arg(arg!(output: -o --output <output> ... "Specify the output file path with an optional format, e.g., out.json:json, which can be specified multiple times").num_args(1))
             .arg(arg!(setting: -Y --setting <setting> ... "Specify the input setting file").num_args(1..))
             .arg(arg!(verbose: -v --verbose "Print test information verbosely").action(ArgAction::Count))
             .arg(arg!(disable_none: -n --disable_none "Disable dumping None values"))
@@ -52,11 +119,53 @@ pub fn app() -> Command {
             .arg(arg!(show_hidden: -H --show_hidden "Display hidden attributes"))
             .arg(arg!(fast_eval: -K --fast_eval "Use the fast evaluation mode"))
             .arg(arg!(arguments: -D --argument <arguments> ... "Specify the top-level argument").num_args(1..))
+            .arg(arg!(features: -F --feature <features> ... "Enable the build-time features, which are bound to the top-level argument features as a list when any feature is enabled").num_args(1).value_delimiter(','))
             .arg(arg!(path_selector: -S --path_selector <path_selector> ... "Specify the path selector").num_args(1..))
             .arg(arg!(overrides: -O --overrides <overrides> ... "Specify the configuration override path and value").num_args(1..))
+            .arg(arg!(strict_overrides: --strict_overrides "Error on the overrides which match nothing or set the values equal to the existing ones"))
+            .arg(arg!(merge_keys: --merge_key <merge_keys> ... "Union the lists at the path by matching the elements with the key field instead of the position, e.g., containers=name").num_args(1))
+            .arg(arg!(explain_plan: --explain_plan "Print the ordered build plan of the files, packages with the cache status, link and execution steps without running them"))
+            .arg(arg!(fold_constants: --fold_constants "Precompute the constant expressions before the execution"))
+            .arg(arg!(deny_warnings: --deny_warnings "Fail on the compile warnings, e.g., setting the deprecated schema attributes"))
+            .arg(arg!(stream_diagnostics: --stream_diagnostics "Emit the diagnostics of each package as soon as it is resolved instead of at the end"))
+            .arg(arg!(check_determinism: --check_determinism "Run the evaluation twice and fail with a diff when the outputs differ, e.g., depending on the time"))
+            .arg(arg!(patch: --patch <patch> "Apply a JSON Patch (RFC 6902) file to the result before it is serialized"))
             .arg(arg!(target: --target <target> "Specify the target type"))
             .arg(arg!(recursive: -R --recursive "Compile the files directory recursively"))
-            .arg(arg!(package_map: -E --external <package_map> ... "Mapping of package name and path where the package is located").num_args(1..)),
+            .arg(arg!(package_map: -E --external <package_map> ... "Mapping of package name and path where the package is located").num_args(1..))
+            .arg(arg!(strict: --strict "Error instead of warning when the output path is inside the source tree"))
+            .arg(arg!(strict_settings: --strict_settings "Error on unknown keys in the setting files"))
+            .arg(arg!(trace_provenance: --trace_provenance "Output the source file and line of the statement that last set each top-level key"))
+            .arg(arg!(with_schema_meta: --with_schema_meta "Add the schema name and source location into every schema instance as the __schema__ attribute"))
+            .arg(arg!(emit_version: --emit_version "Add the top-level key __kcl_version__ holding the KCL version and backend into the output"))
+            .arg(arg!(args_file: --args_file <args_file> "Bind the top-level arguments from a JSON or YAML map file, which are overridden by the -D arguments"))
+            .arg(arg!(arg_env: --arg_env <prefix> "Bind the top-level arguments from the environment variables with the prefix, e.g., KCL_ARG_replicas=5 binds replicas, which override the arguments file and are overridden by the -D arguments"))
+            .arg(arg!(require_all_args: --require_all_args "Error before the evaluation when any top-level argument read by the program without a default is not supplied"))
+            .arg(arg!(attach: --attach <attach> ... "Bind the content of a file to the top-level argument, e.g., key=@path or key=base64@path").num_args(1..))
+            .arg(arg!(include_keys: --include <include_keys> ... "Only output the specified top-level keys").num_args(1..).value_delimiter(','))
+            .arg(arg!(exclude_keys: --exclude <exclude_keys> ... "Do not output the specified top-level keys").num_args(1..).value_delimiter(','))
+            .arg(arg!(yaml_spec: --yaml_spec <yaml_spec> "Specify the YAML version which the ambiguous string scalars are quoted under").value_parser(["1.1", "1.2"]))
+            .arg(arg!(units: --units <units> "Output the number multiplier values, e.g., 1Mi, as the expanded numbers or the suffixed strings").value_parser(["expanded", "suffixed"]))
+            .arg(arg!(emit: --emit <emit> "Write the intermediate code of each package into the current directory, e.g., llvm-ir").value_parser(["llvm-ir"]))
+            .arg(arg!(diagnostic_format: --diagnostic_format <diagnostic_format> "Specify the output format of the compile diagnostics, rich shows the source snippets with carets").value_parser(["plain", "rich"]))
+            .arg(arg!(format: --format <format> "Specify the output format, tfjson outputs the Terraform JSON configuration syntax, ndjson outputs one JSON value per line").value_parser(["yaml", "json", "tfjson", "ndjson"]))
+            .arg(arg!(select_doc: --select_doc <select_doc> "Only output the documents of a stream result matching the index from 0 or the field predicate, e.g., 1 or kind==Service"))
+            .arg(arg!(topo_sort: --topo_sort <ref_field> "Output the documents of a stream result after the documents named by their reference field, e.g., dependsOn"))
+            .arg(arg!(assert: --assert <predicate> ... "Fail the run when the predicate over the result is false, e.g., 'spec.replicas >= 1', which can be specified multiple times").num_args(1))
+            .arg(arg!(template: --template <template> "Render the output through a handlebars template file instead of the output format, e.g., to produce env files").conflicts_with_all(["format", "wrap"]))
+            .arg(arg!(wrap: --wrap <wrap> "Wrap the output in the data of a Kubernetes manifest, e.g., configmap:name or secret:name"))
+            .arg(arg!(max_parallel_link: --max_parallel_link <max_parallel_link> "Specify the maximum number of concurrent link invocations, defaults to the CPU count").value_parser(clap::value_parser!(usize)))
+            .arg(arg!(max_output_bytes: --max_output_bytes <max_output_bytes> "Abort the serialization once the YAML or JSON output exceeds the bytes").value_parser(clap::value_parser!(usize)))
+            .arg(arg!(print_settings: --print_settings "Print the merged settings of the setting files and the command line flags as JSON and exit"))
+            .arg(arg!(print_env: --print_env "Print the resolved top-level arguments, overrides and selectors of the evaluation as JSON and exit"))
+            .arg(arg!(metrics: --metrics <metrics> "Print the metrics of the program complexity in the format to stderr after the execution, which requires the fast evaluation mode").value_parser(["json"]))
+            .arg(arg!(metrics_output: --metrics_output <metrics_output> "Write the metrics of the program complexity into the file instead of stderr").requires("metrics"))
+            .arg(arg!(print_digest: --print_digest "Print the SHA-256 digest of the canonical output with the sorted keys instead of the output to stdout"))
+            .arg(arg!(fail_fast: --fail_fast "Stop at the first failure of the entry files, which is the default behavior").conflicts_with("keep_going"))
+            .arg(arg!(keep_going: --keep_going "Run every entry file separately when the program fails and report all the failures at the end"))
+            .arg(arg!(time_limit: --time_limit <time_limit> "Specify the wall-clock time limit of the whole command in seconds").value_parser(clap::value_parser!(f64)))
+            .arg(arg!(shared_cache_dir: --shared_cache_dir <shared_cache_dir> "Reuse the object files in the read-only cache, i.e., the program root or KCL_CACHE_PATH of the run which populated it"))
+            .arg(arg!(sandbox_root: --sandbox_root <sandbox_root> "Error when any file written by the run, e.g., the cache, temp and output files, is outside the directory")),
         )
         .subcommand(
             Command::new("lint")
@@ -94,7 +203,119 @@ pub fn app() -> Command {
                 .arg(arg!(<kcl_file> "KCL file"))
                 .arg(arg!(schema: -d --schema <schema> "Iterate through subdirectories recursively").num_args(1..))
                 .arg(arg!(attribute_name: -n --attribute_name <attribute_name> "The attribute name for the data loading"))
-                .arg(arg!(format: --format <format> "Validation data file format, support YAML and JSON, default is JSON")),
+                .arg(arg!(format: --format <format> "Validation data file format, support YAML and JSON, default is JSON, and the YAML documents are validated one at a time")),
+        )
+        .subcommand(
+            Command::new("doc")
+                .about("Generate the document of schemas in a KCL file")
+                .arg(arg!(<input> "Input KCL file"))
+                .arg(arg!(format: --format <format> "Document format, support markdown and JSON, default is markdown"))
+                .arg(arg!(output: -o --output <output> "Specify the document output file path")),
+        )
+        .subcommand(
+            Command::new("init-instance")
+                .about("Generate a KCL instance of a schema filled with default values")
+                .arg(arg!(<input> "Input KCL file"))
+                .arg(arg!(<schema> "Schema name"))
+                .arg(arg!(output: -o --output <output> "Specify the instance output file path")),
+        )
+        .subcommand(
+            Command::new("settings")
+                .about("Inspect KCL setting files")
+                .subcommand(
+                    Command::new("show")
+                        .about("Print setting files as the normalized JSON arguments")
+                        .arg(arg!([input] ... "Setting files to show").num_args(1..).required(true))
+                        .arg(arg!(effective: --effective "Show the merged result of all the setting files")),
+                )
+                .subcommand(
+                    Command::new("schema")
+                        .about("Print the JSON Schema of the setting files for the editors"),
+                ),
+        )
+        .subcommand(
+            Command::new("normalize")
+                .about("Normalize YAML or JSON data with sorted keys and without none values")
+                .arg(arg!(<input> "Input YAML or JSON file"))
+                .arg(arg!(format: --format <format> "Output format, support YAML and JSON, default is YAML"))
+                .arg(arg!(output: -o --output <output> "Specify the normalized output file path")),
+        )
+        .subcommand(
+            Command::new("explain")
+                .about("Show the description and example of a diagnostic code")
+                .arg(arg!(<code> "Diagnostic code, e.g., E2G22")),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export the schemas in a KCL file as the type definitions of other languages")
+                .arg(arg!(<input> "Input KCL file"))
+                .arg(arg!(to: --to <to> "Target language of the type definitions, support rust and proto, default is rust"))
+                .arg(arg!(output: -o --output <output> "Specify the type definition output file path")),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the linker, the cache dir and the backends of the KCL installation"),
+        )
+        .subcommand(
+            Command::new("test")
+                .about("Run the KCL files and compare the outputs with the expected files")
+                .arg(arg!([input] ... "Input KCL files, or directories pairing each *.k file with the *.golden file").num_args(1..).required(true))
+                .arg(arg!(expect: --expect <expect> ... "Expected output files paired with the input files in order").num_args(1..))
+                .arg(arg!(fast_eval: -K --fast_eval "Use the fast evaluation mode")),
+        )
+        .subcommand(
+            Command::new("coverage")
+                .about("Report the set and unset counts of the schema attributes by the data files")
+                .arg(arg!(<input> "Input KCL file"))
+                .arg(arg!(<data> "Data file, or directory of the JSON and YAML data files"))
+                .arg(arg!(schema: -s --schema <schema> "Schema name, which can be omitted when the KCL file defines only one schema"))
+                .arg(arg!(format: --format <format> "Report format, support table and JSON, default is table")),
+        )
+        .subcommand(
+            Command::new("imports-check")
+                .about("Check that all the imports resolve without the type checking and codegen")
+                .arg(arg!([input] ... "Input KCL files or paths").num_args(1..).required(true))
+                .arg(arg!(package_map: -E --external <package_map> ... "Mapping of package name and path where the package is located").num_args(1..)),
+        )
+        .subcommand(
+            Command::new("schemas")
+                .about("List the schemas with their base schemas and attributes as an inheritance tree")
+                .arg(arg!(<input> "Input KCL file"))
+                .arg(arg!(format: --format <format> "Output format, support tree and JSON, default is tree")),
+        )
+        .subcommand(
+            Command::new("args")
+                .about("List the top-level arguments read by the option calls of the program with their types and defaults")
+                .arg(arg!([input] ... "Input KCL files or paths").num_args(1..).required(true))
+                .arg(arg!(package_map: -E --external <package_map> ... "Mapping of package name and path where the package is located").num_args(1..)),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Generate the shell completion script of the KCL CLI")
+                .arg(arg!(<shell> "Shell to generate the completion script for").value_parser(COMPLETION_SHELLS)),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Inspect, export and import the object cache of the compiled packages")
+                .subcommand(
+                    Command::new("stats")
+                        .about("Compile the main.k configs in the directory and report the cache hit ratio and size")
+                        .arg(arg!(<dir> "Workspace directory of the configs"))
+                        .arg(arg!(format: --format <format> "Report format, support table and JSON, default is table"))
+                        .arg(arg!(fast_eval: -K --fast_eval "Use the fast evaluation mode, which compiles and caches nothing")),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Archive the object cache of the program root into a tar file")
+                        .arg(arg!(<output> "Output tar file of the cache"))
+                        .arg(arg!([root] "Program root of the cache, default is the current directory")),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Restore the object cache archived by the export command under the program root")
+                        .arg(arg!(<input> "Input tar file of the cache"))
+                        .arg(arg!([root] "Program root of the cache, default is the current directory")),
+                ),
         )
     .subcommand(Command::new("server").about("Start a rpc server for APIs"))
     .subcommand(Command::new("version").about("Show the KCL version"))
@@ -0,0 +1,191 @@
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use kclvm_runtime::{
+    Context, JsonEncodeOptions, ValueRef, YamlEncodeOptions, JSON_STREAM_SEP, YAML_STREAM_SEP,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The output format of the normalize command.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+impl TryFrom<&str> for NormalizeFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(NormalizeFormat::Yaml),
+            "json" => Ok(NormalizeFormat::Json),
+            _ => bail!(
+                "unsupported normalize format '{}', expected yaml or json",
+                value
+            ),
+        }
+    }
+}
+
+/// Run the KCL normalize command. The input is normalized document by document,
+/// thus the memory is bounded by the largest document instead of the whole file.
+pub fn normalize_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input YAML or JSON file is required"))?;
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => NormalizeFormat::try_from(format.as_str())?,
+        None => NormalizeFormat::default(),
+    };
+    let reader = BufReader::new(File::open(input)?);
+    match matches.get_one::<String>("output") {
+        Some(o) => {
+            // The output is written into a temp file beside it, which is renamed over
+            // the output at the end, thus the input is kept when the output is the input.
+            let temp = PathBuf::from(format!("{}.{}.tmp", o, std::process::id()));
+            let result =
+                write_output(reader, &temp, format).and_then(|_| Ok(std::fs::rename(&temp, o)?));
+            if result.is_err() {
+                let _ = std::fs::remove_file(&temp);
+            }
+            result?;
+        }
+        None => {
+            normalize_stream(reader, writer, format)?;
+        }
+    }
+    Ok(())
+}
+
+/// Normalize the stream into the output file.
+fn write_output<R: BufRead>(reader: R, output: &Path, format: NormalizeFormat) -> Result<()> {
+    let mut file = BufWriter::new(File::create(output)?);
+    normalize_stream(reader, &mut file, format)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Load the YAML or JSON documents as KCL values and emit them with sorted keys
+/// and without none values. The line endings of the output are always `\n` and
+/// the output ends with exactly one line break.
+pub fn normalize(content: &str, format: NormalizeFormat) -> Result<String> {
+    let mut output = vec![];
+    normalize_stream(content.as_bytes(), &mut output, format)?;
+    Ok(String::from_utf8(output)?)
+}
+
+/// Normalize the YAML stream like [normalize], but read and write the documents
+/// one at a time with [read_documents], and returns the number of the documents.
+pub fn normalize_stream<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    format: NormalizeFormat,
+) -> Result<usize> {
+    let mut count = 0;
+    read_documents(reader, |document| {
+        count += write_documents(document, writer, format, count)?;
+        Ok(())
+    })?;
+    writeln!(writer)?;
+    Ok(count)
+}
+
+/// Read the YAML stream and call `f` with the text of each document in order. A
+/// document starts at the `---` line and ends at the next `---` or `...` line,
+/// which begin at the first column, thus only one document is held in the memory.
+/// The document without any content except the comments is skipped.
+pub(crate) fn read_documents<R: BufRead>(
+    mut reader: R,
+    mut f: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let mut emit = |document: &str| {
+        let is_empty = document.lines().all(|line| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with('#')
+        });
+        if is_empty {
+            Ok(())
+        } else {
+            f(document)
+        }
+    };
+    let mut document = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let eof = reader.read_line(&mut line)? == 0;
+        let content = line.trim_end_matches(['\n', '\r']);
+        let rest = match content.strip_prefix("---") {
+            Some(rest) if rest.is_empty() || rest.starts_with([' ', '\t']) => Some(rest),
+            _ if content == "..." => Some(""),
+            _ => None,
+        };
+        match rest {
+            Some(rest) if !eof => {
+                emit(&document)?;
+                document.clear();
+                document.push_str(rest.trim_start());
+                document.push('\n');
+            }
+            _ if eof => {
+                emit(&document)?;
+                break;
+            }
+            _ => {
+                document.push_str(content);
+                document.push('\n');
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Normalize the documents in the YAML text, which is one document of the stream,
+/// and write them after the documents written before. Returns the number of the
+/// written documents.
+fn write_documents<W: Write>(
+    content: &str,
+    writer: &mut W,
+    format: NormalizeFormat,
+    written: usize,
+) -> Result<usize> {
+    let mut ctx = Context::new();
+    let documents = ValueRef::from_yaml_documents(&mut ctx, content)?;
+    for (i, document) in documents.iter().enumerate() {
+        if written + i > 0 {
+            match format {
+                NormalizeFormat::Yaml => write!(writer, "{}", YAML_STREAM_SEP)?,
+                NormalizeFormat::Json => write!(writer, "{}", JSON_STREAM_SEP)?,
+            }
+        }
+        write!(writer, "{}", normalize_document(document, format))?;
+    }
+    Ok(documents.len())
+}
+
+/// Encode the document with sorted keys and without none values.
+fn normalize_document(document: &ValueRef, format: NormalizeFormat) -> String {
+    match format {
+        NormalizeFormat::Yaml => {
+            let opts = YamlEncodeOptions {
+                sort_keys: true,
+                ignore_none: true,
+                ..Default::default()
+            };
+            let yaml = document.to_yaml_string_with_options(&opts);
+            yaml.strip_suffix('\n').unwrap_or(&yaml).to_string()
+        }
+        NormalizeFormat::Json => {
+            let opts = JsonEncodeOptions {
+                sort_keys: true,
+                indent: 4,
+                ignore_none: true,
+                ..Default::default()
+            };
+            document.to_json_string_with_options(&opts)
+        }
+    }
+}
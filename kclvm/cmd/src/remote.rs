@@ -0,0 +1,198 @@
+//! Load the setting files from the remote URLs, e.g.,
+//! `--setting https://example.com/settings.yaml`.
+//!
+//! The fetched file is cached under the cache dir with its ETag, which is sent
+//! in the `If-None-Match` header of the next fetch to avoid re-downloading the
+//! unchanged file. The cached file is used with a warning when the fetch fails,
+//! and it is the only source in the offline mode, see [kclvm_config::offline].
+//! The relative input files in the remote setting files are resolved against the
+//! work directory instead of the cache dir.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use kclvm_config::offline::ensure_online;
+use kclvm_config::settings::SettingsPathBuf;
+use kclvm_error::{Handler, Message, Position, Style, WarningKind};
+use md5::{Digest, Md5};
+
+/// The response of fetching a remote setting file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchResponse {
+    /// The file body and its ETag.
+    Modified { body: String, etag: Option<String> },
+    /// The cached file with the ETag is still up to date.
+    NotModified,
+}
+
+/// Fetch the remote setting files, which is replaced by a stub in tests.
+pub trait SettingsFetcher {
+    /// Fetch the file at the URL, the cached ETag is sent when it exists.
+    fn fetch(&self, url: &str, etag: Option<&str>) -> Result<FetchResponse>;
+}
+
+/// The settings fetcher over HTTP.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HttpFetcher;
+
+impl SettingsFetcher for HttpFetcher {
+    fn fetch(&self, url: &str, etag: Option<&str>) -> Result<FetchResponse> {
+        let mut request = ureq::get(url);
+        if let Some(etag) = etag {
+            request = request.set("If-None-Match", etag);
+        }
+        let response = request.call()?;
+        if response.status() == 304 {
+            return Ok(FetchResponse::NotModified);
+        }
+        let etag = response.header("ETag").map(|etag| etag.to_string());
+        Ok(FetchResponse::Modified {
+            body: response.into_string()?,
+            etag,
+        })
+    }
+}
+
+/// Returns whether the setting file is a remote URL.
+pub fn is_remote_setting(file: &str) -> bool {
+    file.starts_with("http://") || file.starts_with("https://")
+}
+
+/// Returns the default cache dir of the remote setting files.
+pub fn default_settings_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("kcl")
+        .join("settings")
+}
+
+/// The remote setting files cached under the cache dir.
+#[derive(Debug, Clone)]
+pub struct RemoteSettings {
+    cache_dir: PathBuf,
+    /// Only use the cached files without fetching them.
+    offline: bool,
+}
+
+impl RemoteSettings {
+    pub fn new(cache_dir: impl Into<PathBuf>, offline: bool) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            offline,
+        }
+    }
+
+    /// Returns the cache dir of the remote setting files.
+    #[inline]
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Returns the local path of the setting file, which is the file itself for the
+    /// local files, or the cached file of the remote URL.
+    pub fn resolve(
+        &self,
+        file: &str,
+        fetcher: &dyn SettingsFetcher,
+        handler: &mut Handler,
+    ) -> Result<String> {
+        if !is_remote_setting(file) {
+            return Ok(file.to_string());
+        }
+        let path = self.fetch(file, fetcher, handler)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Fetch the remote setting file into the cache and returns the cached file path.
+    /// The warning of using the cached file is added into the handler.
+    pub fn fetch(
+        &self,
+        url: &str,
+        fetcher: &dyn SettingsFetcher,
+        handler: &mut Handler,
+    ) -> Result<PathBuf> {
+        let (path, etag_path) = self.cache_paths(url);
+        let cached = path.is_file();
+        if self.offline {
+            if !cached {
+                ensure_online(true, &format!("the uncached remote setting file {}", url))?;
+            }
+            return Ok(path);
+        }
+        let etag = if cached {
+            fs::read_to_string(&etag_path).ok()
+        } else {
+            None
+        };
+        match fetcher.fetch(url, etag.as_deref()) {
+            Ok(FetchResponse::Modified { body, etag }) => {
+                fs::create_dir_all(&self.cache_dir).with_context(|| {
+                    format!(
+                        "failed to create the cache dir {}",
+                        self.cache_dir.display()
+                    )
+                })?;
+                fs::write(&path, body)?;
+                match etag {
+                    Some(etag) => fs::write(&etag_path, etag)?,
+                    None if etag_path.exists() => fs::remove_file(&etag_path)?,
+                    None => {}
+                }
+                Ok(path)
+            }
+            Ok(FetchResponse::NotModified) if cached => Ok(path),
+            Ok(FetchResponse::NotModified) => {
+                bail!(
+                    "the remote setting file {} is not modified but not cached",
+                    url
+                )
+            }
+            Err(err) if cached => {
+                handler.add_warning(
+                    WarningKind::CompilerWarning,
+                    &[Message {
+                        range: (Position::dummy_pos(), Position::dummy_pos()),
+                        style: Style::Line,
+                        message: format!(
+                            "failed to fetch the setting file {}, use the cached file: {}",
+                            url, err
+                        ),
+                        note: None,
+                        suggested_replacement: None,
+                    }],
+                );
+                Ok(path)
+            }
+            Err(err) => Err(err.context(format!("failed to fetch the setting file {}", url))),
+        }
+    }
+
+    /// Returns the settings whose relative input files are resolved against the work
+    /// directory when they are from a remote setting file in the cache dir.
+    pub fn rebase(&self, settings: SettingsPathBuf) -> Result<SettingsPathBuf> {
+        match settings.path() {
+            Some(path) if path == &self.cache_dir => Ok(SettingsPathBuf::new(
+                Some(std::env::current_dir()?),
+                settings.settings().clone(),
+            )),
+            _ => Ok(settings),
+        }
+    }
+
+    /// Returns the cached file path and its ETag file path, which are named by the
+    /// hash of the URL.
+    fn cache_paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let mut hasher = Md5::new();
+        hasher.input(url.as_bytes());
+        let name = hasher
+            .result()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        (
+            self.cache_dir.join(format!("{}.yaml", name)),
+            self.cache_dir.join(format!("{}.etag", name)),
+        )
+    }
+}
@@ -1,38 +1,369 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::ArgMatches;
-use kclvm_error::StringError;
+use kclvm_ast::ast::CmdArgSpec;
+use kclvm_error::{DiagnosticFormat, Handler, Message, Position, StringError, Style, WarningKind};
 use kclvm_parser::ParseSession;
-use kclvm_runner::exec_program;
+use kclvm_runner::determinism::check_determinism;
+use kclvm_runner::format::DocumentSelector;
+use kclvm_runner::patch::load_patch_file;
+use kclvm_runner::plan::explain_plan;
+use kclvm_runner::runner::parse_merge_key_spec;
+use kclvm_runner::{
+    exec_program, expand_files, ExecProgramArgs, ExecProgramResult, OutputFormat, EXEC_STACK_SIZE,
+};
+use kclvm_runtime::{
+    CancelFlag, UnitsFormat, ValueRef, YamlSpec, JSON_STREAM_SEP, YAML_STREAM_SEP,
+};
+use std::ffi::OsString;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::args::check_required_args;
 use crate::settings::must_build_settings;
+use crate::sink::{open_output_sink, output_scheme, OutputSink, WriterSink};
+use crate::util::strings_from_matches;
+use crate::wrap::{wrap_output, Envelope};
 
 /// Run the KCL run command.
 pub fn run_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    match matches.get_one::<f64>("time_limit") {
+        Some(time_limit) => {
+            if !time_limit.is_finite() || *time_limit <= 0.0 {
+                bail!("the time limit must be a positive number of seconds");
+            }
+            run_with_time_limit(matches, writer, Duration::from_secs_f64(*time_limit))
+        }
+        None => run_program(matches, writer),
+    }
+}
+
+/// The time which the cancelled worker of the time limit is waited for to stop.
+const TIME_LIMIT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Run the command in a worker thread and abort it with an error when the whole
+/// command, i.e., the parse, resolve, compile, execute and serialize stages,
+/// exceeds the wall-clock time limit. The worker only renders the output, which
+/// is written into the writer and the output sinks by the caller thread after the
+/// command finishes within the limit, thus nothing is written after the timeout.
+///
+/// On timeout, the execution of the worker is cancelled, which stops at the next
+/// stage or evaluation step, and the worker is joined when it stops within the
+/// grace period. The worker blocked where the cancellation is not checked, e.g.,
+/// in the LLVM code generation or a builtin call, is detached and only reclaimed
+/// when the CLI process exits.
+fn run_with_time_limit<W: Write>(
+    matches: &ArgMatches,
+    writer: &mut W,
+    time_limit: Duration,
+) -> Result<()> {
+    let matches = matches.clone();
+    let cancel_flag = CancelFlag::default();
+    let worker_cancel_flag = cancel_flag.clone();
+    let (sender, receiver) = mpsc::channel();
+    let handle = std::thread::Builder::new()
+        .name("kcl-run".to_string())
+        .stack_size(EXEC_STACK_SIZE)
+        .spawn(move || {
+            let mut output = vec![];
+            let mut rendered = vec![];
+            let result = render_program(&matches, &mut output, &mut rendered, &worker_cancel_flag);
+            // The receiver is dropped when the time limit is exceeded.
+            let _ = sender.send((result, output, rendered));
+        })?;
+    match receiver.recv_timeout(time_limit) {
+        Ok((result, output, rendered)) => {
+            writer.write_all(&output)?;
+            write_rendered_outputs(rendered)?;
+            result
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            cancel_flag.cancel();
+            if !matches!(
+                receiver.recv_timeout(TIME_LIMIT_GRACE_PERIOD),
+                Err(RecvTimeoutError::Timeout)
+            ) {
+                let _ = handle.join();
+            }
+            bail!(
+                "the run command exceeded the time limit of {:?}, which is set by --time_limit",
+                time_limit
+            )
+        }
+        // The worker thread panicked, propagate the panic to the caller.
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => bail!("the run command is aborted unexpectedly"),
+        },
+    }
+}
+
+/// The rendered contents of the output targets, e.g., the `-o` files.
+type RenderedOutputs = Vec<(String, Vec<u8>)>;
+
+/// Write the rendered contents into the sinks of the output targets in order.
+fn write_rendered_outputs(rendered: RenderedOutputs) -> Result<()> {
+    for (target, content) in rendered {
+        open_output_sink(&target)?.write_output(&content)?;
+    }
+    Ok(())
+}
+
+fn run_program<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let mut rendered = vec![];
+    let result = render_program(matches, writer, &mut rendered, &CancelFlag::default());
+    // The outputs rendered before the failure, e.g., the files which run successfully
+    // with --keep_going, are still written.
+    write_rendered_outputs(rendered)?;
+    result
+}
+
+/// Run the program and write the output into the writer, the contents of the other
+/// output targets are rendered into `rendered` and not written. The execution is
+/// aborted when the cancel flag is set.
+fn render_program<W: Write>(
+    matches: &ArgMatches,
+    writer: &mut W,
+    rendered: &mut RenderedOutputs,
+    cancel_flag: &CancelFlag,
+) -> Result<()> {
     // Config settings building
     let settings = must_build_settings(matches);
     let output = settings.output();
+    let mut args: ExecProgramArgs = settings.try_into()?;
+    args.cancel_flag = cancel_flag.clone();
+    args.trace_provenance = matches.get_flag("trace_provenance");
+    args.emit_version = matches.get_flag("emit_version");
+    args.include_schema_meta = matches.get_flag("with_schema_meta");
+    args.offline = matches.get_flag("offline");
+    args.strict_overrides = matches.get_flag("strict_overrides");
+    args.sandbox_root = matches.get_one::<String>("sandbox_root").map(PathBuf::from);
+    args.shared_cache_dir = matches
+        .get_one::<String>("shared_cache_dir")
+        .map(PathBuf::from);
+    // The later arguments win, thus the -D arguments override the environment
+    // arguments, which override the file arguments.
+    if let Some(prefix) = matches.get_one::<String>("arg_env") {
+        let mut env_args = env_args(prefix, std::env::vars_os());
+        env_args.append(&mut args.args);
+        args.args = env_args;
+    }
+    if let Some(path) = matches.get_one::<String>("args_file") {
+        let mut file_args = args_file_args(path)?;
+        file_args.append(&mut args.args);
+        args.args = file_args;
+    }
+    if let Some(specs) = strings_from_matches(matches, "attach") {
+        for spec in specs {
+            args.args.push(attach_arg(&spec)?);
+        }
+    }
+    if matches.get_flag("require_all_args") {
+        check_required_args(&args)?;
+    }
+    args.include_keys = strings_from_matches(matches, "include_keys").unwrap_or_default();
+    args.exclude_keys = strings_from_matches(matches, "exclude_keys").unwrap_or_default();
+    args.features = strings_from_matches(matches, "features").unwrap_or_default();
+    args.collect_metrics = matches.get_one::<String>("metrics").is_some();
+    args.merge_keys = strings_from_matches(matches, "merge_keys")
+        .unwrap_or_default()
+        .iter()
+        .map(|spec| parse_merge_key_spec(spec))
+        .collect::<Result<Vec<_>>>()?;
+    args.fold_constants = matches.get_flag("fold_constants");
+    args.deny_warnings = matches.get_flag("deny_warnings");
+    args.stream_diagnostics = matches.get_flag("stream_diagnostics");
+    if let Some(max_output_bytes) = matches.get_one::<usize>("max_output_bytes") {
+        args.max_output_bytes = *max_output_bytes;
+    }
+    if let Some(patch) = matches.get_one::<String>("patch") {
+        args.json_patch = load_patch_file(patch)?;
+    }
+    if let Some(yaml_spec) = matches.get_one::<String>("yaml_spec") {
+        args.yaml_spec = match yaml_spec.as_str() {
+            "1.1" => YamlSpec::Yaml11,
+            _ => YamlSpec::Yaml12,
+        };
+    }
+    if matches.get_one::<String>("units").map(|u| u.as_str()) == Some("suffixed") {
+        args.units = UnitsFormat::Suffixed;
+    }
+    if matches.get_one::<String>("emit").map(|e| e.as_str()) == Some("llvm-ir") {
+        args.llvm_ir_dir = Some(".".to_string());
+    }
+    if let Some(format) = matches.get_one::<String>("diagnostic_format") {
+        args.diagnostic_format = DiagnosticFormat::try_from(format.as_str())?;
+    }
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => OutputFormat::try_from(format.as_str())?,
+        None => OutputFormat::default(),
+    };
+    // All the outputs are produced from a single evaluation.
+    let outputs = match strings_from_matches(matches, "output") {
+        Some(specs) => specs
+            .iter()
+            .map(|spec| output_spec(spec, format))
+            .collect::<Vec<(String, OutputFormat)>>(),
+        None => output.into_iter().map(|o| (o, format)).collect(),
+    };
+    let template = match matches.get_one::<String>("template") {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read the template file {}", path))?,
+        ),
+        None => None,
+    };
+    let selector = match matches.get_one::<String>("select_doc") {
+        Some(selector) => Some(DocumentSelector::try_from(selector.as_str())?),
+        None => None,
+    };
+    let topo_sort = matches.get_one::<String>("topo_sort");
+    let assertions = strings_from_matches(matches, "assert").unwrap_or_default();
+    let envelope = match matches.get_one::<String>("wrap") {
+        Some(wrap) => Some(Envelope::try_from(wrap.as_str())?),
+        None => None,
+    };
+    if let Some(max_parallel_link) = matches.get_one::<usize>("max_parallel_link") {
+        if *max_parallel_link == 0 {
+            bail!("the max parallel link must be greater than 0");
+        }
+        kclvm_runner::linker::set_max_parallel_link(*max_parallel_link);
+    }
+    if matches.get_flag("print_settings") {
+        writeln!(writer, "{}", args.to_json())?;
+        return Ok(());
+    }
+    if matches.get_flag("print_env") {
+        writeln!(writer, "{:#}", args.eval_env())?;
+        return Ok(());
+    }
+    if matches.get_flag("explain_plan") {
+        let plan = explain_plan(Arc::new(ParseSession::default()), &args)?;
+        write!(writer, "{}", plan.render())?;
+        return Ok(());
+    }
+    // The outputs with a URL scheme are not in the source tree.
+    for (path, _) in outputs.iter().filter(|(o, _)| output_scheme(o).is_none()) {
+        args.check_sandbox_path(path)?;
+        check_output_path(path, &args, matches.get_flag("strict"))?;
+    }
+    let metrics_output = matches.get_one::<String>("metrics_output");
+    if let Some(path) = metrics_output.filter(|o| output_scheme(o).is_none()) {
+        args.check_sandbox_path(path)?;
+        check_output_path(path, &args, matches.get_flag("strict"))?;
+    }
+    let print_digest = matches.get_flag("print_digest");
+    let mut write_result = |result: &ExecProgramResult, writer: &mut W| -> Result<()> {
+        let selected;
+        let result = match &selector {
+            Some(selector) => {
+                selected = result.select_documents(selector)?;
+                &selected
+            }
+            None => result,
+        };
+        let sorted;
+        let result = match topo_sort {
+            Some(ref_field) => {
+                sorted = result.topo_sort_documents(ref_field)?;
+                &sorted
+            }
+            None => result,
+        };
+        // The result failing an assertion is not output.
+        for predicate in &assertions {
+            result.check_assertion(predicate, &args)?;
+        }
+        // Output log message
+        if !result.log_message.is_empty() {
+            write!(writer, "{}", result.log_message)?;
+        }
+        // The digest is printed instead of the output, and the output files are still written.
+        if print_digest {
+            writeln!(writer, "{}", result.digest()?)?;
+        }
+        if !result.yaml_result.is_empty() {
+            let render = |format: OutputFormat| -> Result<String> {
+                let content = match &template {
+                    Some(template) => result.render_template(template)?,
+                    None => result.to_format(format)?,
+                };
+                match &envelope {
+                    Some(envelope) => wrap_output(&content, envelope, format),
+                    None => Ok(content),
+                }
+            };
+            if outputs.is_empty() && !print_digest {
+                WriterSink::new(writer).write_output(render(format)?.as_bytes())?;
+            }
+            for (target, format) in &outputs {
+                rendered.push((target.clone(), render(*format)?.into_bytes()));
+            }
+        }
+        Ok(())
+    };
     let sess = Arc::new(ParseSession::default());
-    match exec_program(sess.clone(), &settings.try_into()?) {
+    let exec_result = if matches.get_flag("check_determinism") {
+        check_determinism(sess.clone(), &args)
+    } else {
+        exec_program(sess.clone(), &args)
+    };
+    // The entry files are compiled into a single program, and run separately to
+    // report the failure of every file only when the program fails.
+    let exec_failed = match &exec_result {
+        Ok(result) => !result.err_message.is_empty(),
+        Err(_) => true,
+    };
+    if exec_failed && matches.get_flag("keep_going") && args.k_filename_list.len() > 1 {
+        let (result, outcomes) = exec_keep_going(&args);
+        let failed = outcomes.iter().filter(|(_, err)| err.is_some()).count();
+        // The files failing only together are reported with the program error below.
+        if failed > 0 {
+            write_result(&result, writer)?;
+            let summary = outcomes
+                .iter()
+                .map(|(file, err)| match err {
+                    Some(err) => format!("FAIL {}\n{}", file, err),
+                    None => format!("ok {}", file),
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+            bail!(
+                "{} of {} file(s) failed\n{}",
+                failed,
+                outcomes.len(),
+                summary
+            );
+        }
+    }
+    match exec_result {
         Ok(result) => {
-            // Output log message
-            if !result.log_message.is_empty() {
-                write!(writer, "{}", result.log_message)?;
-            }
             // Output execute error message
             if !result.err_message.is_empty() {
+                if !result.log_message.is_empty() {
+                    write!(writer, "{}", result.log_message)?;
+                }
                 if !sess.0.diag_handler.has_errors()? {
                     sess.0.add_err(StringError(result.err_message))?;
                 }
                 sess.0.emit_stashed_diagnostics_and_abort()?;
             }
-            if !result.yaml_result.is_empty() {
-                match output {
-                    Some(o) => std::fs::write(o, result.yaml_result)?,
-                    // [`println!`] is not a good way to output content to stdout,
-                    // using [`writeln`] can be better to redirect the output.
-                    None => writeln!(writer, "{}", result.yaml_result)?,
+            // The warnings are emitted to stderr and don't fail the run, and the
+            // streamed warnings are already emitted.
+            if !result.warnings.is_empty() && !args.stream_diagnostics {
+                let mut handler = Handler::default();
+                for warning in &result.warnings {
+                    handler.add_diagnostic(warning.clone());
+                }
+                handler.emit()?;
+            }
+            write_result(&result, writer)?;
+            if let Some(metrics) = result.metrics()? {
+                match metrics_output {
+                    Some(path) => rendered.push((path.to_string(), metrics.to_json().into_bytes())),
+                    None => eprintln!("{}", metrics.to_json()),
                 }
             }
         }
@@ -46,3 +377,204 @@ pub fn run_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()>
     }
     Ok(())
 }
+
+/// Run every entry file as a separate program when the program of all the entry
+/// files fails, and returns the merged result of the files which run successfully
+/// and the error of each failed file. The outputs of the files are merged into the
+/// YAML and JSON streams in order.
+pub(crate) fn exec_keep_going(
+    args: &ExecProgramArgs,
+) -> (ExecProgramResult, Vec<(String, Option<String>)>) {
+    let mut merged = ExecProgramResult::default();
+    let mut yaml_results = vec![];
+    let mut json_results = vec![];
+    let mut outcomes = vec![];
+    for file in &args.k_filename_list {
+        let mut file_args = args.clone();
+        file_args.k_filename_list = vec![file.clone()];
+        let err = match exec_program(Arc::new(ParseSession::default()), &file_args) {
+            Ok(result) if result.err_message.is_empty() => {
+                merged.log_message.push_str(&result.log_message);
+                if !result.yaml_result.is_empty() {
+                    yaml_results.push(result.yaml_result);
+                    json_results.push(result.json_result);
+                }
+                None
+            }
+            Ok(result) => Some(result.err_message),
+            Err(err) => Some(err.to_string()),
+        };
+        outcomes.push((file.clone(), err));
+    }
+    merged.yaml_result = yaml_results.join(YAML_STREAM_SEP);
+    merged.json_result = json_results.join(JSON_STREAM_SEP);
+    (merged, outcomes)
+}
+
+/// Parse the output spec `path` or `path:format`, e.g., `out.json:json`. The path
+/// without a known format suffix is output in the default format.
+pub(crate) fn output_spec(spec: &str, default: OutputFormat) -> (String, OutputFormat) {
+    if let Some((path, format)) = spec.rsplit_once(':') {
+        if let (false, Ok(format)) = (path.is_empty(), OutputFormat::try_from(format)) {
+            return (path.to_string(), format);
+        }
+    }
+    (spec.to_string(), default)
+}
+
+/// Parse the attach spec `key=@path` or `key=base64@path` and bind the file content,
+/// which is optionally base64-encoded, to the top-level argument `key`.
+pub(crate) fn attach_arg(spec: &str) -> Result<CmdArgSpec> {
+    let (name, source) = match spec.split_once('=') {
+        Some((name, source)) if !name.trim().is_empty() => (name.trim(), source),
+        _ => bail!("invalid attach spec '{}', expected key=@path", spec),
+    };
+    let (encoding, path) = match source.split_once('@') {
+        Some((encoding, path)) if !path.is_empty() => (encoding, path),
+        _ => bail!("invalid attach spec '{}', expected key=@path", spec),
+    };
+    let content = std::fs::read(path).with_context(|| {
+        format!(
+            "failed to read the attached file '{}' of the argument '{}'",
+            path, name
+        )
+    })?;
+    let content = match encoding {
+        "" => String::from_utf8(content).with_context(|| {
+            format!(
+                "the attached file '{}' is not valid UTF-8, use {}=base64@{} instead",
+                path, name, path
+            )
+        })?,
+        "base64" => base64::encode(content),
+        _ => bail!(
+            "unsupported attach encoding '{}' in '{}', expected key=@path or key=base64@path",
+            encoding,
+            spec
+        ),
+    };
+    Ok(CmdArgSpec {
+        name: name.to_string(),
+        // The argument value is a JSON string.
+        value: ValueRef::str(&content).to_json_string(),
+    })
+}
+
+/// Load the top-level arguments from the JSON or YAML file of a map, where the
+/// values keep their types in the file, e.g., the numbers are bound as numbers.
+pub(crate) fn args_file_args(path: &str) -> Result<Vec<CmdArgSpec>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read the arguments file {}", path))?;
+    // JSON is a subset of YAML, thus both of them are loaded as YAML.
+    let value = ValueRef::from_yaml(&mut kclvm_runtime::Context::new(), &content)
+        .with_context(|| format!("invalid arguments file {}", path))?;
+    if !value.is_config() {
+        bail!(
+            "the arguments file {} must be a map of the top-level argument names to the values",
+            path
+        );
+    }
+    let dict = value.as_dict_ref();
+    Ok(dict
+        .values
+        .iter()
+        .map(|(name, value)| CmdArgSpec {
+            name: name.to_string(),
+            // The argument value is a JSON value.
+            value: value.to_json_string(),
+        })
+        .collect())
+}
+
+/// Bind the top-level arguments from the environment variables with the prefix,
+/// the argument names are the variable names without the prefix, and the values
+/// are bound as the -D argument values. The arguments are sorted by the names.
+pub(crate) fn env_args<I>(prefix: &str, vars: I) -> Vec<CmdArgSpec>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    let mut args: Vec<CmdArgSpec> = vars
+        .into_iter()
+        // The variables which are not valid UTF-8 are not arguments.
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter_map(|(name, value)| {
+            let name = name.strip_prefix(prefix)?;
+            (!name.is_empty()).then(|| CmdArgSpec {
+                name: name.to_string(),
+                value,
+            })
+        })
+        .collect();
+    args.sort_by(|a, b| a.name.cmp(&b.name));
+    args
+}
+
+/// Emit a warning, or an error in the strict mode, when the output path is
+/// inside the source tree of the program, because writing into the compiled
+/// directory may cause the output to be loaded again in the next run.
+pub(crate) fn check_output_path(output: &str, args: &ExecProgramArgs, strict: bool) -> Result<()> {
+    if let Some(root) = output_in_source_tree(output, args)? {
+        let msg = format!(
+            "the output path '{}' is inside the source tree '{}'",
+            output,
+            root.display()
+        );
+        if strict {
+            bail!(msg);
+        }
+        Handler::default()
+            .add_warning(
+                WarningKind::CompilerWarning,
+                &[Message {
+                    range: (Position::dummy_pos(), Position::dummy_pos()),
+                    style: Style::Line,
+                    message: msg,
+                    note: None,
+                    suggested_replacement: None,
+                }],
+            )
+            .emit()?;
+    }
+    Ok(())
+}
+
+/// Returns the source root that contains the output path if exists.
+pub(crate) fn output_in_source_tree(
+    output: &str,
+    args: &ExecProgramArgs,
+) -> Result<Option<PathBuf>> {
+    let output = normalize_output_path(Path::new(output))?;
+    // Input errors are reported by the compiler later, so ignore them here.
+    for file in expand_files(args).unwrap_or_default() {
+        let path = PathBuf::from(&file);
+        let root = if path.is_dir() {
+            path
+        } else {
+            match path.parent() {
+                Some(parent) => parent.to_path_buf(),
+                None => continue,
+            }
+        };
+        if let Ok(root) = root.canonicalize() {
+            if output.starts_with(&root) {
+                return Ok(Some(root));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The output file may not exist yet, thus only canonicalize its parent directory.
+fn normalize_output_path(output: &Path) -> Result<PathBuf> {
+    let output = if output.is_absolute() {
+        output.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(output)
+    };
+    if let (Some(parent), Some(name)) = (output.parent(), output.file_name()) {
+        if let Ok(parent) = parent.canonicalize() {
+            return Ok(parent.join(name));
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,21 @@
+use anyhow::Result;
+use clap::ArgMatches;
+use kclvm_tools::schemas::{schema_tree, SchemaTreeFormat};
+use std::io::Write;
+
+/// Run the KCL schemas command, which lists the schemas as an inheritance tree.
+pub fn schemas_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let input = matches
+        .get_one::<String>("input")
+        .ok_or(anyhow::anyhow!("the input KCL file is required"))?;
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => SchemaTreeFormat::try_from(format.as_str())?,
+        None => SchemaTreeFormat::default(),
+    };
+    let tree = schema_tree(input)?;
+    write!(writer, "{}", tree.render(&format)?)?;
+    if format == SchemaTreeFormat::Json {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
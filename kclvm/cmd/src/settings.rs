@@ -1,10 +1,49 @@
+use crate::remote::{default_settings_cache_dir, HttpFetcher, RemoteSettings};
 use crate::util::*;
 use anyhow::Result;
 use clap::ArgMatches;
-use kclvm_config::settings::{build_settings_pathbuf, Config, SettingsFile, SettingsPathBuf};
+use kclvm_config::offline::is_offline;
+use kclvm_config::settings::{
+    build_settings_pathbuf, check_unknown_settings_keys, load_file, merge_settings,
+    settings_json_schema, Config, SettingsFile, SettingsPathBuf, DEFAULT_SETTING_FILE,
+};
 use kclvm_driver::arguments::parse_key_value_pair;
 use kclvm_error::Handler;
+use kclvm_runner::ExecProgramArgs;
 use kclvm_runtime::PanicInfo;
+use std::io::Write;
+use std::path::Path;
+
+/// Run the KCL settings command.
+pub fn settings_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    match matches.subcommand() {
+        Some(("show", sub_matches)) => settings_show_command(sub_matches, writer),
+        // The alternate format pretty prints the JSON value.
+        Some(("schema", _)) => Ok(writeln!(writer, "{:#}", settings_json_schema())?),
+        _ => Ok(()),
+    }
+}
+
+/// Print the settings files as the normalized JSON of the execute program arguments.
+/// With `--effective`, all the settings files are merged in order and a single
+/// JSON result is printed.
+pub fn settings_show_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let files = strings_from_matches(matches, "input").unwrap_or_default();
+    let settings = files
+        .iter()
+        .map(|f| load_file(f))
+        .collect::<Result<Vec<SettingsFile>>>()?;
+    if matches.get_flag("effective") {
+        let args = ExecProgramArgs::try_from(merge_settings(&settings))?;
+        writeln!(writer, "{}", args.to_json())?;
+    } else {
+        for setting in settings {
+            let args = ExecProgramArgs::try_from(setting)?;
+            writeln!(writer, "{}", args.to_json())?;
+        }
+    }
+    Ok(())
+}
 
 /// Build settings from arg matches.
 pub(crate) fn must_build_settings(matches: &ArgMatches) -> SettingsPathBuf {
@@ -31,15 +70,45 @@ pub(crate) fn build_settings(matches: &ArgMatches) -> Result<SettingsPathBuf> {
         None => vec![],
     };
 
+    // The remote setting files are fetched into the cache and loaded like the local files.
+    let remote = RemoteSettings::new(
+        default_settings_cache_dir(),
+        matches.get_flag("offline") || is_offline(),
+    );
+    let mut handler = Handler::default();
     let setting_files = matches
         .get_many::<String>("setting")
-        .map(|files| files.into_iter().map(|f| f.as_str()).collect::<Vec<&str>>());
+        .map(|files| {
+            files
+                .into_iter()
+                .map(|f| remote.resolve(f, &HttpFetcher, &mut handler))
+                .collect::<Result<Vec<String>>>()
+        })
+        .transpose()?;
+    handler.emit()?;
+    let setting_files = setting_files
+        .as_ref()
+        .map(|files| files.iter().map(|f| f.as_str()).collect::<Vec<&str>>());
+
+    if matches.get_flag("strict_settings") {
+        match &setting_files {
+            Some(setting_files) => {
+                for file in setting_files {
+                    check_unknown_settings_keys(file)?;
+                }
+            }
+            None if Path::new(DEFAULT_SETTING_FILE).is_file() => {
+                check_unknown_settings_keys(DEFAULT_SETTING_FILE)?
+            }
+            None => {}
+        }
+    }
 
     let arguments = strings_from_matches(matches, "arguments");
 
     let package_maps = hashmaps_from_matches(matches, "package_map").transpose()?;
 
-    build_settings_pathbuf(
+    let settings = build_settings_pathbuf(
         files.as_slice(),
         setting_files,
         Some(SettingsFile {
@@ -69,5 +138,6 @@ pub(crate) fn build_settings(matches: &ArgMatches) -> Result<SettingsPathBuf> {
                 None
             },
         }),
-    )
+    )?;
+    remote.rebase(settings)
 }
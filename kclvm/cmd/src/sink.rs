@@ -0,0 +1,116 @@
+//! The output sinks which the rendered output of the run command is written to,
+//! e.g., the local file, stdout or an object store like `s3://bucket/key`.
+//!
+//! The file and stdout sinks are built in. The sinks of other URL schemes are
+//! registered by the embedder with [register_output_sink], and the outputs with
+//! an unregistered scheme are reported as errors.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+
+/// The destination of the rendered output.
+pub trait OutputSink {
+    /// Write the whole output content into the sink.
+    fn write_output(&mut self, content: &[u8]) -> Result<()>;
+}
+
+/// Open the sink of the output target URL, e.g., `s3://bucket/key`.
+pub type OutputSinkFactory = dyn Fn(&str) -> Result<Box<dyn OutputSink>> + Send + Sync;
+
+static OUTPUT_SINKS: Lazy<RwLock<HashMap<String, Arc<OutputSinkFactory>>>> =
+    Lazy::new(Default::default);
+
+/// Register the sink factory of the URL scheme, e.g., `s3` or `gs`, which
+/// replaces the factory registered before.
+pub fn register_output_sink(
+    scheme: &str,
+    factory: impl Fn(&str) -> Result<Box<dyn OutputSink>> + Send + Sync + 'static,
+) {
+    OUTPUT_SINKS
+        .write()
+        .unwrap()
+        .insert(scheme.to_lowercase(), Arc::new(factory));
+}
+
+/// Returns the URL scheme of the output target, the local paths have no scheme.
+pub fn output_scheme(target: &str) -> Option<&str> {
+    let (scheme, _) = target.split_once("://")?;
+    // The single letter is the drive of the Windows paths.
+    if scheme.len() > 1
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+')
+    {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+/// Open the sink of the output target, which is a local file or a URL of the
+/// registered scheme.
+pub fn open_output_sink(target: &str) -> Result<Box<dyn OutputSink>> {
+    match output_scheme(target) {
+        None => Ok(Box::new(FileSink::new(target))),
+        Some(scheme) => {
+            let factory = OUTPUT_SINKS
+                .read()
+                .unwrap()
+                .get(&scheme.to_lowercase())
+                .cloned();
+            match factory {
+                Some(factory) => factory(target),
+                None => bail!(
+                    "no output sink is registered for the scheme '{}' of the output {}",
+                    scheme,
+                    target
+                ),
+            }
+        }
+    }
+}
+
+/// The local file sink.
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    path: String,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_output(&mut self, content: &[u8]) -> Result<()> {
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// The sink writing to the command writer, which is stdout in the CLI. The
+/// content is followed by a newline.
+pub struct WriterSink<'a, W: Write> {
+    writer: &'a mut W,
+}
+
+impl<'a, W: Write> WriterSink<'a, W> {
+    pub fn new(writer: &'a mut W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<'a, W: Write> OutputSink for WriterSink<'a, W> {
+    fn write_output(&mut self, content: &[u8]) -> Result<()> {
+        self.writer.write_all(content)?;
+        // [`println!`] is not a good way to output content to stdout,
+        // using [`writeln`] can be better to redirect the output.
+        writeln!(self.writer)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+[package]
+name = "cache_stats"
+edition = "0.0.1"
+version = "0.0.1"
@@ -1,19 +1,46 @@
 use std::{
+    cell::Cell,
     env,
+    ffi::OsString,
     fs::{self, remove_file},
+    io::{BufReader, Read, Write},
     path::{Path, PathBuf},
+    rc::Rc,
     sync::Arc,
 };
 
-use kclvm_config::modfile::KCL_PKG_PATH;
+use kclvm_config::{
+    modfile::KCL_PKG_PATH,
+    settings::{build_settings_pathbuf, load_file},
+};
+use kclvm_error::Handler;
 use kclvm_parser::ParseSession;
-use kclvm_runner::{exec_program, MapErrorResult};
+use kclvm_runner::{exec_program, ExecProgramArgs, MapErrorResult, OutputFormat};
+use kclvm_runtime::{Context, ValueRef};
+use kclvm_tools::vet::validator::LoaderKind;
 
 use crate::{
     app,
-    run::run_command,
-    settings::{build_settings, must_build_settings},
+    args::args_command,
+    cache::cache_command,
+    completions::{completions_command, COMPLETION_SHELLS},
+    coverage::coverage_command,
+    doc::doc_command,
+    doctor::doctor_command,
+    explain::explain_command,
+    export::export_command,
+    golden::test_command,
+    imports::imports_check_command,
+    instance::init_instance_command,
+    normalize::{normalize, normalize_command, normalize_stream, NormalizeFormat},
+    remote::{FetchResponse, RemoteSettings, SettingsFetcher},
+    run::{args_file_args, attach_arg, env_args, output_in_source_tree, output_spec, run_command},
+    schemas::schemas_command,
+    settings::{build_settings, must_build_settings, settings_command},
+    sink::{open_output_sink, output_scheme, register_output_sink, OutputSink},
     util::hashmaps_from_matches,
+    vet::{vet_command, vet_stream, VetOptions},
+    wrap::{wrap_output, Envelope},
 };
 
 #[cfg(unix)]
@@ -66,6 +93,130 @@ fn test_build_settings_fail() {
     assert!(build_settings(matches).is_err());
 }
 
+#[test]
+fn test_build_settings_with_unknown_key() {
+    let settings_file = work_dir().join("unknown_key_kcl.yaml");
+    let matches = app().get_matches_from(settings_arguments(settings_file.clone()));
+    let matches = matches.subcommand_matches("run").unwrap();
+    assert!(build_settings(matches).is_ok());
+
+    let mut args = settings_arguments(settings_file);
+    args.push("--strict_settings".to_string());
+    let matches = app().get_matches_from(args);
+    let matches = matches.subcommand_matches("run").unwrap();
+    let err = build_settings(matches).unwrap_err().to_string();
+    assert!(err.contains("unknown settings key 'sort_key'"), "{err}");
+    assert!(err.ends_with("unknown_key_kcl.yaml:4"), "{err}");
+}
+
+/// The settings fetcher stub which returns a known settings body.
+struct StubFetcher {
+    response: Option<FetchResponse>,
+    expected_etag: Option<&'static str>,
+}
+
+impl SettingsFetcher for StubFetcher {
+    fn fetch(&self, _url: &str, etag: Option<&str>) -> anyhow::Result<FetchResponse> {
+        assert_eq!(etag, self.expected_etag);
+        self.response
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("network is unreachable"))
+    }
+}
+
+#[test]
+fn test_remote_settings() {
+    let cache_dir = env::temp_dir().join(format!("kcl_remote_settings_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cache_dir);
+    let url = "https://example.com/settings.yaml";
+    let remote = RemoteSettings::new(&cache_dir, false);
+    let mut handler = Handler::default();
+    // The local setting files are loaded as they are.
+    assert_eq!(
+        remote
+            .resolve(
+                "kcl.yaml",
+                &StubFetcher {
+                    response: None,
+                    expected_etag: None,
+                },
+                &mut handler,
+            )
+            .unwrap(),
+        "kcl.yaml"
+    );
+    // Fetch and cache the remote setting file with its ETag.
+    let body = "kcl_cli_configs:\n  files:\n    - main.k\n  disable_none: true\n";
+    let fetcher = StubFetcher {
+        response: Some(FetchResponse::Modified {
+            body: body.to_string(),
+            etag: Some("\"v1\"".to_string()),
+        }),
+        expected_etag: None,
+    };
+    let path = remote.resolve(url, &fetcher, &mut handler).unwrap();
+    let settings = load_file(&path).unwrap();
+    let configs = settings.kcl_cli_configs.unwrap();
+    assert_eq!(configs.files, Some(vec!["main.k".to_string()]));
+    assert_eq!(configs.disable_none, Some(true));
+    // The cached ETag is sent and the unchanged file is not downloaded again.
+    let fetcher = StubFetcher {
+        response: Some(FetchResponse::NotModified),
+        expected_etag: Some("\"v1\""),
+    };
+    assert_eq!(remote.resolve(url, &fetcher, &mut handler).unwrap(), path);
+    assert!(handler.diagnostics.is_empty());
+    // Fall back to the cached file with a warning when the fetch fails.
+    let fetcher = StubFetcher {
+        response: None,
+        expected_etag: Some("\"v1\""),
+    };
+    assert_eq!(remote.resolve(url, &fetcher, &mut handler).unwrap(), path);
+    let (_, warnings) = handler.classification();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].messages[0]
+        .message
+        .starts_with("failed to fetch the setting file https://example.com/settings.yaml"));
+    // The relative input files of the remote setting file are resolved against the
+    // work directory instead of the cache dir.
+    let settings = remote
+        .rebase(build_settings_pathbuf(&[], Some(vec![path.as_str()]), None).unwrap())
+        .unwrap();
+    assert_eq!(settings.path(), &Some(env::current_dir().unwrap()));
+    let args = ExecProgramArgs::try_from(settings).unwrap();
+    assert_eq!(
+        args.k_filename_list,
+        vec![env::current_dir()
+            .unwrap()
+            .join("main.k")
+            .display()
+            .to_string()]
+    );
+    // The offline mode only uses the cached files.
+    let offline = RemoteSettings::new(&cache_dir, true);
+    assert_eq!(offline.resolve(url, &fetcher, &mut handler).unwrap(), path);
+    let err = offline
+        .resolve("https://example.com/other.yaml", &fetcher, &mut handler)
+        .unwrap_err();
+    assert!(err.to_string().contains("offline mode"), "{err}");
+    // The fetch failure is an error without the cached file.
+    let err = remote
+        .resolve(
+            "https://example.com/other.yaml",
+            &StubFetcher {
+                response: None,
+                expected_etag: None,
+            },
+            &mut handler,
+        )
+        .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("network is unreachable"),
+        "{err:#}"
+    );
+    fs::remove_dir_all(&cache_dir).unwrap();
+}
+
 fn work_dir() -> std::path::PathBuf {
     std::path::Path::new(".")
         .join("src")
@@ -542,7 +693,7 @@ fn test_error_message_fuzz_matched() {
     {
         Ok(_) => panic!("unreachable code."),
         Err(msg) => {
-            assert!(msg.contains("attribute 'a' not found in 'Person', did you mean '[\"aa\"]'?"))
+            assert!(msg.contains("attribute 'a' not found in 'Person', did you mean 'aa'?"))
         }
     }
 }
@@ -586,3 +737,1204 @@ fn test_keyword_argument_error_message() {
         }
     }
 }
+
+#[test]
+fn test_output_in_source_tree() {
+    let test_case_path = PathBuf::from("./src/test_data/output_in_source");
+    let matches = app().arg_required_else_help(true).get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &test_case_path.join("main.k").display().to_string(),
+    ]);
+    let settings = must_build_settings(matches.subcommand_matches("run").unwrap());
+    let args = settings.try_into().unwrap();
+    let root = output_in_source_tree(
+        &test_case_path.join("out.yaml").display().to_string(),
+        &args,
+    )
+    .unwrap();
+    assert_eq!(root, Some(test_case_path.canonicalize().unwrap()));
+    let root = output_in_source_tree(
+        &PathBuf::from("./src/test_data/out.yaml")
+            .display()
+            .to_string(),
+        &args,
+    )
+    .unwrap();
+    assert_eq!(root, None);
+}
+
+#[test]
+fn test_output_in_source_tree_strict() {
+    let test_case_path = PathBuf::from("./src/test_data/output_in_source");
+    let output = test_case_path.join("out.yaml");
+    let matches = app().arg_required_else_help(true).get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &test_case_path.join("main.k").display().to_string(),
+        "-o",
+        &output.display().to_string(),
+        "--strict",
+    ]);
+    let mut buf = Vec::new();
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap_err();
+    assert!(err.to_string().contains("is inside the source tree"));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_run_command_with_template() {
+    let dir = Path::new("../runner/src/test_datas/template");
+    let input = dir.join("main.k").display().to_string();
+    let template = dir.join("env.hbs").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &input, "-K", "--template", &template]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(
+        output.contains("APP_NAME=web & api\nDEBUG=false\nREPLICAS=3\n"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_run_command_check_determinism() {
+    let input = Path::new("../runner/src/test_datas/determinism/deterministic/main.k");
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &input.display().to_string(),
+        "-K",
+        "--check_determinism",
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(output.contains("tier: frontend"), "{output}");
+}
+
+#[test]
+fn test_run_command_with_patch() {
+    let dir = Path::new("../runner/src/test_datas/json_patch");
+    let input = dir.join("main.k").display().to_string();
+    let patch = dir.join("patch.json").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &input, "-K", "--patch", &patch]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(output.contains("replicas: 3"), "{output}");
+    assert!(!output.contains("debug"), "{output}");
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &input, "--patch", "not_found.json"]);
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap_err();
+    assert!(
+        err.to_string().contains("failed to read the patch file"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_run_command_with_select_doc() {
+    let input = "../runner/src/test_datas/select_doc/main.k";
+    let run = |selector: &str| {
+        let matches =
+            app().get_matches_from(&[ROOT_CMD, "run", input, "-K", "--select_doc", selector]);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf)
+            .map(|_| String::from_utf8(buf).unwrap())
+    };
+    let output = run("kind==Service").unwrap();
+    assert_eq!(output.matches("kind: Service").count(), 2, "{output}");
+    assert!(!output.contains("Deployment"), "{output}");
+    let output = run("0").unwrap();
+    assert!(output.contains("kind: Deployment"), "{output}");
+    assert!(!output.contains("Service"), "{output}");
+    let err = run("5").unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+}
+
+#[test]
+fn test_run_command_with_topo_sort() {
+    let input = "../runner/src/test_datas/topo_sort/main.k";
+    let matches =
+        app().get_matches_from(&[ROOT_CMD, "run", input, "-K", "--topo_sort", "dependsOn"]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    let names: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("name: "))
+        .collect();
+    assert_eq!(names, vec!["db", "api", "config", "app"], "{output}");
+}
+
+#[test]
+fn test_run_command_with_assert() {
+    let input = "../runner/src/test_datas/assert/main.k";
+    let run = |predicate: &str| {
+        let matches =
+            app().get_matches_from(&[ROOT_CMD, "run", input, "-K", "--assert", predicate]);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf).map(|_| buf)
+    };
+    let output = String::from_utf8(run("spec.replicas >= 1").unwrap()).unwrap();
+    assert!(output.contains("replicas: 2"), "{output}");
+    let err = run("spec.replicas >= 3").unwrap_err().to_string();
+    assert!(
+        err.contains("the assertion 'spec.replicas >= 3' failed"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_doc_cmd() {
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "doc",
+        "../tools/src/doc/test_data/schema.k",
+        "--format",
+        "json",
+    ]);
+    let mut buf = Vec::new();
+    doc_command(matches.subcommand_matches("doc").unwrap(), &mut buf).unwrap();
+    let doc = String::from_utf8(buf).unwrap();
+    assert!(doc.contains("\"name\": \"Server\""));
+}
+
+#[test]
+fn test_settings_show_cmd() {
+    let settings_dir = Path::new("../runner/src/test_datas/settings_file");
+    let settings_file = settings_dir.join("settings.yaml").display().to_string();
+    let expected = fs::read_to_string(settings_dir.join("settings.json")).unwrap();
+    for args in [
+        vec![ROOT_CMD, "settings", "show", &settings_file],
+        vec![ROOT_CMD, "settings", "show", &settings_file, "--effective"],
+    ] {
+        let matches = app().get_matches_from(args);
+        let mut buf = Vec::new();
+        settings_command(matches.subcommand_matches("settings").unwrap(), &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap().trim(), expected.trim());
+    }
+}
+
+#[test]
+fn test_settings_schema_cmd() {
+    let matches = app().get_matches_from(&[ROOT_CMD, "settings", "schema"]);
+    let mut buf = Vec::new();
+    settings_command(matches.subcommand_matches("settings").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(output.contains("\"kcl_cli_configs\": {"), "{output}");
+    assert!(output.contains("\"package_maps\": {"), "{output}");
+}
+
+#[test]
+fn test_init_instance_cmd() {
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "init-instance",
+        "../tools/src/instance/test_data/schema.k",
+        "App",
+    ]);
+    let mut buf = Vec::new();
+    init_instance_command(
+        matches.subcommand_matches("init-instance").unwrap(),
+        &mut buf,
+    )
+    .unwrap();
+    let code = String::from_utf8(buf).unwrap();
+    assert!(code.starts_with("app = App {"));
+}
+
+#[test]
+fn test_run_print_settings() {
+    let mut args = settings_arguments(work_dir().join("kcl.yaml"));
+    args.push("--print_settings".to_string());
+    let matches = app().get_matches_from(args);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let printed = ExecProgramArgs::from_str(&String::from_utf8(buf).unwrap());
+    // Merged from the setting file.
+    assert_eq!(printed.k_filename_list.len(), 1);
+    assert!(printed.k_filename_list[0].ends_with("hello.k"));
+    assert!(printed.disable_none);
+    // Merged from the command line flags.
+    assert!(printed.strict_range_check);
+    assert_eq!(printed.path_selector, vec!["a.b.c".to_string()]);
+    assert_eq!(
+        printed
+            .overrides
+            .iter()
+            .map(|o| o.field_path.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["c.a", "c.b"]
+    );
+}
+
+#[test]
+fn test_run_print_env() {
+    let test_case_path = PathBuf::from("./src/test_data/args_file");
+    env::set_var("KCL_PRINT_ENV_replicas", "4");
+    env::set_var("KCL_PRINT_ENV_name", "api");
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &test_case_path.join("main.k").display().to_string(),
+        "--args_file",
+        &test_case_path.join("values.yaml").display().to_string(),
+        "--arg_env",
+        "KCL_PRINT_ENV_",
+        "-D",
+        "env=prod",
+        "-D",
+        "replicas=5",
+        "-F",
+        "debug",
+        "-S",
+        "name",
+        "--print_env",
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let env = String::from_utf8(buf).unwrap();
+    // The -D arguments override the environment arguments and the arguments file.
+    assert!(env.contains(r#""env": "prod""#), "{env}");
+    assert!(env.contains(r#""replicas": 5"#), "{env}");
+    // The environment arguments override the arguments file.
+    assert!(env.contains(r#""name": "api""#), "{env}");
+    // The arguments only in the file keep their types.
+    assert!(env.contains(r#""ratio": 0.5"#), "{env}");
+    assert!(env.contains(r#""enabled": true"#), "{env}");
+    assert!(
+        env.contains("\"features\": [\n      \"debug\"\n    ]"),
+        "{env}"
+    );
+    assert!(
+        env.contains("\"path_selector\": [\n    \"name\"\n  ]"),
+        "{env}"
+    );
+}
+
+#[test]
+fn test_env_args() {
+    let vars = [
+        ("KCL_ARG_replicas", "3"),
+        ("KCL_ARG_", "empty"),
+        ("HOME", "/root"),
+        ("KCL_ARG_env", "dev"),
+    ]
+    .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+    let args = env_args("KCL_ARG_", vars);
+    // Only the variables with the prefix and a non-empty name are bound in order.
+    assert_eq!(
+        args.iter()
+            .map(|arg| (arg.name.as_str(), arg.value.as_str()))
+            .collect::<Vec<_>>(),
+        [("env", "dev"), ("replicas", "3")]
+    );
+}
+
+#[test]
+fn test_run_max_parallel_link() {
+    let mut args = settings_arguments(work_dir().join("kcl.yaml"));
+    args.extend(["--max_parallel_link".to_string(), "0".to_string()]);
+    let matches = app().get_matches_from(args);
+    let mut buf = Vec::new();
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap_err();
+    assert!(err.to_string().contains("greater than 0"), "{err}");
+    let mut args = settings_arguments(work_dir().join("kcl.yaml"));
+    args.extend(["--max_parallel_link".to_string(), "many".to_string()]);
+    assert!(app().try_get_matches_from(args).is_err());
+}
+
+#[test]
+fn test_normalize_cmd() {
+    let input = "./src/test_data/normalize/unordered.yaml";
+    let expected = "a:\n- 3\n- 1\nb:\n  c: 1\n  d: 2\n---\ny:\n  b: true\nz: x\n";
+    let matches = app().get_matches_from(&[ROOT_CMD, "normalize", input]);
+    let mut buf = Vec::new();
+    normalize_command(matches.subcommand_matches("normalize").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert_eq!(output, expected);
+    // The normalized output is stable.
+    assert_eq!(normalize(&output, NormalizeFormat::Yaml).unwrap(), expected);
+    // JSON output
+    let matches = app().get_matches_from(&[ROOT_CMD, "normalize", input, "--format", "json"]);
+    let mut buf = Vec::new();
+    normalize_command(matches.subcommand_matches("normalize").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(
+        !output.contains("null") && !output.contains('\r'),
+        "{output}"
+    );
+    assert!(output.find("\"a\"").unwrap() < output.find("\"b\"").unwrap());
+    assert!(output.find("\"y\"").unwrap() < output.find("\"z\"").unwrap());
+    // The input is normalized in place when the output is the input.
+    let dir = env::temp_dir().join(format!("kcl_normalize_in_place_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let in_place = dir.join("unordered.yaml").display().to_string();
+    std::fs::copy(input, &in_place).unwrap();
+    let matches = app().get_matches_from(&[ROOT_CMD, "normalize", &in_place, "-o", &in_place]);
+    normalize_command(
+        matches.subcommand_matches("normalize").unwrap(),
+        &mut Vec::new(),
+    )
+    .unwrap();
+    assert_eq!(std::fs::read_to_string(&in_place).unwrap(), expected);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The reader which counts the bytes read from the inner reader.
+struct CountingReader<R> {
+    inner: R,
+    read: Rc<Cell<usize>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.set(self.read.get() + n);
+        Ok(n)
+    }
+}
+
+/// The writer which records the bytes read from the input at the first write.
+struct FirstWriteRecorder {
+    read: Rc<Cell<usize>>,
+    read_at_first_write: Option<usize>,
+    output: Vec<u8>,
+}
+
+impl Write for FirstWriteRecorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.read_at_first_write.get_or_insert(self.read.get());
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_vet_cmd() {
+    let dir = Path::new("./src/test_data/vet");
+    let kcl_file = dir.join("test.k").display().to_string();
+    let data_file = dir.join("data.json").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "vet", &data_file, &kcl_file]);
+    vet_command(matches.subcommand_matches("vet").unwrap()).unwrap();
+    // The second document of the YAML stream fails the check.
+    let data_file = dir.join("stream.yaml").display().to_string();
+    let matches =
+        app().get_matches_from(&[ROOT_CMD, "vet", &data_file, &kcl_file, "--format", "yaml"]);
+    let err = vet_command(matches.subcommand_matches("vet").unwrap()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains(&format!("the document 2 of '{data_file}' is invalid")),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_vet_stream() {
+    let opts = VetOptions {
+        data_file: "stream.yaml".to_string(),
+        kcl_file: "./src/test_data/vet/test.k".to_string(),
+        schema: None,
+        attribute_name: "value".to_string(),
+    };
+    let person = |age: i32| format!("name: {}\nage: {}\nmessage: hello\n", "x".repeat(64), age);
+    // Every document is validated.
+    let input = vec![person(18); 8].join("---\n");
+    let count = vet_stream(input.as_bytes(), LoaderKind::YAML, &opts).unwrap();
+    assert_eq!(count, 8);
+    // The validation stops at the invalid document before the whole input is read.
+    let mut documents = vec![person(18); 4096];
+    documents[1] = person(-1);
+    let input = documents.join("---\n");
+    let read = Rc::new(Cell::new(0));
+    let reader = BufReader::new(CountingReader {
+        inner: input.as_bytes(),
+        read: read.clone(),
+    });
+    let err = vet_stream(reader, LoaderKind::YAML, &opts).unwrap_err();
+    assert!(err.to_string().contains("the document 2 of"), "{err}");
+    assert!(
+        read.get() <= 16 * 1024,
+        "{} of {} bytes are read before the invalid document is reported",
+        read.get(),
+        input.len()
+    );
+}
+
+#[test]
+fn test_normalize_stream() {
+    let input = concat!(
+        "# leading comment\r\nb: 2\r\na: 1\r\n",
+        "--- {d: [2, 1], c: null}\n...\n",
+        "---\n# only comments\n",
+        "---\nitems:\n- b\n- a",
+    );
+    let mut output = vec![];
+    let count = normalize_stream(input.as_bytes(), &mut output, NormalizeFormat::Yaml).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "a: 1\nb: 2\n---\nd:\n- 2\n- 1\n---\nitems:\n- b\n- a\n"
+    );
+    // The documents are written before the whole input is read.
+    let document = format!("name: {}\nitems: [1, 2, 3]\n", "x".repeat(64));
+    let input = vec![document.as_str(); 4096].join("---\n");
+    let read = Rc::new(Cell::new(0));
+    let reader = BufReader::new(CountingReader {
+        inner: input.as_bytes(),
+        read: read.clone(),
+    });
+    let mut writer = FirstWriteRecorder {
+        read: read.clone(),
+        read_at_first_write: None,
+        output: vec![],
+    };
+    let count = normalize_stream(reader, &mut writer, NormalizeFormat::Json).unwrap();
+    assert_eq!(count, 4096);
+    assert_eq!(read.get(), input.len());
+    let read_at_first_write = writer.read_at_first_write.unwrap();
+    assert!(
+        read_at_first_write <= 16 * 1024,
+        "{read_at_first_write} of {} bytes are read before the first write",
+        input.len()
+    );
+    let output = String::from_utf8(writer.output).unwrap();
+    assert_eq!(output.matches("\"name\"").count(), 4096);
+}
+
+#[test]
+fn test_run_command_with_args_file() {
+    let test_case_path = PathBuf::from("./src/test_data/args_file");
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &test_case_path.join("main.k").display().to_string(),
+        "-K",
+        "--args_file",
+        &test_case_path.join("values.yaml").display().to_string(),
+        "-D",
+        "env=prod",
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "name: web\nreplicas: 3\nratio: 0.5\nenabled: true\nenv: prod\n"
+    );
+    // The file must be a map.
+    let list = test_case_path.join("list.json").display().to_string();
+    let err = args_file_args(&list).unwrap_err().to_string();
+    assert!(err.contains("must be a map"), "{err}");
+    assert!(args_file_args("not_found.yaml").is_err());
+}
+
+#[test]
+fn test_run_command_with_attach() {
+    let test_case_path = PathBuf::from("./src/test_data/attach");
+    let cert = test_case_path.join("cert.txt").display().to_string();
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &test_case_path.join("main.k").display().to_string(),
+        "-K",
+        "--attach",
+        &format!("cert=@{}", cert),
+        &format!("cert_b64=base64@{}", cert),
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "cert: hello kcl\ncert_b64: aGVsbG8ga2Ns\n"
+    );
+    // A missing file errors clearly.
+    let err = attach_arg("cert=@not_found.txt").unwrap_err().to_string();
+    assert!(
+        err.contains("failed to read the attached file 'not_found.txt' of the argument 'cert'"),
+        "{err}"
+    );
+    // Invalid specs
+    assert!(attach_arg("cert").is_err());
+    assert!(attach_arg("cert=path").is_err());
+    assert!(attach_arg(&format!("cert=gzip@{}", cert)).is_err());
+}
+
+#[test]
+fn test_run_command_with_wrap() {
+    let test_case_path = PathBuf::from("./src/test_data/attach");
+    let cert = test_case_path.join("cert.txt").display().to_string();
+    let run = |wrap: &str| {
+        let matches = app().get_matches_from(&[
+            ROOT_CMD,
+            "run",
+            &test_case_path.join("main.k").display().to_string(),
+            "-K",
+            "--attach",
+            &format!("cert=@{}", cert),
+            &format!("cert_b64=base64@{}", cert),
+            "--wrap",
+            wrap,
+        ]);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+    let output = "cert: hello kcl\ncert_b64: aGVsbG8ga2Ns";
+    let configmap = run("configmap:app-config");
+    assert!(
+        configmap.starts_with(concat!(
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: app-config\n",
+            "data:\n  config.yaml: |-\n    cert: hello kcl\n"
+        )),
+        "{configmap}"
+    );
+    let secret = run("secret:app-secret");
+    assert!(
+        secret.starts_with(
+            "apiVersion: v1\nkind: Secret\nmetadata:\n  name: app-secret\ntype: Opaque\ndata:\n"
+        ),
+        "{secret}"
+    );
+    assert!(
+        secret.contains(&format!("config.yaml: {}", base64::encode(output))),
+        "{secret}"
+    );
+    // The JSON output is wrapped in a JSON manifest.
+    let json = wrap_output(
+        "{}",
+        &Envelope::ConfigMap("app".to_string()),
+        OutputFormat::Json,
+    )
+    .unwrap();
+    assert!(json.contains("\"kind\": \"ConfigMap\""), "{json}");
+    assert!(json.contains("\"config.json\": \"{}\""), "{json}");
+    // Invalid specs
+    assert!(Envelope::try_from("configmap").is_err());
+    assert!(Envelope::try_from("configmap:App").is_err());
+    assert!(Envelope::try_from("deployment:app").is_err());
+}
+
+#[test]
+fn test_explain_cmd() {
+    let matches = app().get_matches_from(&[ROOT_CMD, "explain", "E2G22"]);
+    let mut buf = Vec::new();
+    explain_command(matches.subcommand_matches("explain").unwrap(), &mut buf).unwrap();
+    let explanation = String::from_utf8(buf).unwrap();
+    assert!(
+        explanation.starts_with("### TypeError (E2G22)"),
+        "{explanation}"
+    );
+    assert!(explanation.contains("For example:"), "{explanation}");
+
+    let matches = app().get_matches_from(&[ROOT_CMD, "explain", "E9999"]);
+    let mut buf = Vec::new();
+    let err = explain_command(matches.subcommand_matches("explain").unwrap(), &mut buf)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(err, "unknown diagnostic code 'E9999'");
+}
+
+#[test]
+fn test_export_cmd() {
+    let input = "../tools/src/export/test_data/schema.k";
+    let matches = app().get_matches_from(&[ROOT_CMD, "export", input, "--to", "rust"]);
+    let mut buf = Vec::new();
+    export_command(matches.subcommand_matches("export").unwrap(), &mut buf).unwrap();
+    let code = String::from_utf8(buf).unwrap();
+    assert!(code.contains("pub struct App {"), "{code}");
+    assert!(code.contains("pub metadata: Metadata,"), "{code}");
+    let matches = app().get_matches_from(&[ROOT_CMD, "export", input, "--to", "proto"]);
+    let mut buf = Vec::new();
+    export_command(matches.subcommand_matches("export").unwrap(), &mut buf).unwrap();
+    let code = String::from_utf8(buf).unwrap();
+    assert!(code.contains("message App {"), "{code}");
+    let matches = app().get_matches_from(&[ROOT_CMD, "export", input, "--to", "go"]);
+    let mut buf = Vec::new();
+    assert!(export_command(matches.subcommand_matches("export").unwrap(), &mut buf).is_err());
+}
+
+#[test]
+fn test_coverage_cmd() {
+    let input = "../tools/src/coverage/test_data/schema.k";
+    let data = "../tools/src/coverage/test_data/data";
+    let matches = app().get_matches_from(&[
+        ROOT_CMD, "coverage", input, data, "--schema", "App", "--format", "json",
+    ]);
+    let mut buf = Vec::new();
+    coverage_command(matches.subcommand_matches("coverage").unwrap(), &mut buf).unwrap();
+    let report = String::from_utf8(buf).unwrap();
+    assert!(
+        report.contains("\"unused\": [\n    \"debug\"\n  ]"),
+        "{report}"
+    );
+}
+
+#[test]
+fn test_doctor_cmd() {
+    let matches = app().get_matches_from(&[ROOT_CMD, "doctor"]);
+    let mut buf = Vec::new();
+    // The linker check may fail without the installed KCL runtime library.
+    let _ = doctor_command(matches.subcommand_matches("doctor").unwrap(), &mut buf);
+    let output = String::from_utf8(buf).unwrap();
+    assert!(
+        output.contains("pass compile: the program runs with the evaluator"),
+        "{output}"
+    );
+    assert!(output.contains("pass backends: evaluator"), "{output}");
+}
+
+#[test]
+fn test_test_cmd() {
+    let dir = Path::new(".").join("src").join("test_data").join("golden");
+    let file = |name: &str| dir.join(name).display().to_string();
+    // The matching pair passes.
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "test",
+        &file("match.k"),
+        "--expect",
+        &file("match.golden"),
+    ]);
+    let mut buf = Vec::new();
+    test_command(matches.subcommand_matches("test").unwrap(), &mut buf).unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert!(output.starts_with("ok "), "{output}");
+    // The mismatching pair fails with a diff.
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "test",
+        &file("match.k"),
+        &file("mismatch.k"),
+        "--expect",
+        &file("match.golden"),
+        &file("mismatch.golden"),
+    ]);
+    let mut buf = Vec::new();
+    let err = test_command(matches.subcommand_matches("test").unwrap(), &mut buf).unwrap_err();
+    assert_eq!(err.to_string(), "1 of 2 test(s) failed");
+    let output = String::from_utf8(buf).unwrap();
+    assert!(
+        output.contains(&format!("FAIL {}", file("mismatch.k"))),
+        "{output}"
+    );
+    assert!(output.contains("-replicas: 2\n+replicas: 3"), "{output}");
+    // The directory mode pairs the KCL files with the golden files.
+    let dir_arg = dir.display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "test", &dir_arg]);
+    let mut buf = Vec::new();
+    let err = test_command(matches.subcommand_matches("test").unwrap(), &mut buf).unwrap_err();
+    assert_eq!(err.to_string(), "1 of 2 test(s) failed");
+    let output = String::from_utf8(buf).unwrap();
+    assert!(
+        output.contains(&format!("ok {}", file("match.k"))),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_run_cmd_with_time_limit() {
+    let dir = Path::new(".").join("src").join("test_data");
+    let slow = dir.join("time_limit").join("slow.k").display().to_string();
+    // The deliberately slow program exceeds the tight time limit, and its worker
+    // is cancelled at the next evaluation step and joined before returning.
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &slow, "-K", "--time_limit", "0.01"]);
+    let mut buf = Vec::new();
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the run command exceeded the time limit of 10ms"),
+        "{err}"
+    );
+    assert!(buf.is_empty());
+    // The output is written when the command finishes within the time limit.
+    let fast = dir.join("golden").join("match.k").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &fast, "--time_limit", "60"]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    assert!(!buf.is_empty());
+    // The time limit must be positive.
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &fast, "--time_limit", "0"]);
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut Vec::new())
+        .unwrap_err()
+        .to_string();
+    assert_eq!(err, "the time limit must be a positive number of seconds");
+    // The output files are written by the caller, thus they are not written after
+    // the timeout, and are written when the command finishes within the limit.
+    let out_dir = env::temp_dir().join(format!("kcl_time_limit_{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out = out_dir.join("out.yaml").display().to_string();
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &slow,
+        "-K",
+        "-o",
+        &out,
+        "--time_limit",
+        "0.01",
+    ]);
+    assert!(run_command(matches.subcommand_matches("run").unwrap(), &mut Vec::new()).is_err());
+    assert!(!Path::new(&out).exists());
+    let matches =
+        app().get_matches_from(&[ROOT_CMD, "run", &fast, "-o", &out, "--time_limit", "60"]);
+    run_command(matches.subcommand_matches("run").unwrap(), &mut Vec::new()).unwrap();
+    assert!(!std::fs::read_to_string(&out).unwrap().is_empty());
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_run_cmd_with_metrics() {
+    let input = "./src/test_data/metrics/main.k";
+    let dir = env::temp_dir().join(format!("kcl_metrics_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let metrics = dir.join("metrics.json").display().to_string();
+    // The metrics flag selects the format and the metrics output requires it.
+    for args in [
+        vec![ROOT_CMD, "run", input, "-K", "--metrics", &metrics],
+        vec![ROOT_CMD, "run", input, "-K", "--metrics_output", &metrics],
+    ] {
+        assert!(app().try_get_matches_from(args).is_err());
+    }
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        input,
+        "-K",
+        "--metrics",
+        "json",
+        "--metrics_output",
+        &metrics,
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    // The metrics are written into the file instead of the output.
+    assert_eq!(String::from_utf8(buf).unwrap(), "a: 1\nb: 2\n");
+    assert_eq!(
+        fs::read_to_string(&metrics).unwrap(),
+        r#"{"schema_instances":0,"eval_steps":19,"max_call_depth":2,"max_nesting_depth":1,"output_bytes":9}"#
+    );
+    fs::remove_dir_all(&dir).unwrap();
+    // The metrics output inside the source tree is rejected in the strict mode.
+    let output = Path::new("./src/test_data/metrics/metrics.json");
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        input,
+        "-K",
+        "--metrics",
+        "json",
+        "--metrics_output",
+        &output.display().to_string(),
+        "--strict",
+    ]);
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut Vec::new()).unwrap_err();
+    assert!(
+        err.to_string().contains("is inside the source tree"),
+        "{err}"
+    );
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_run_cmd_with_multiple_outputs() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("golden")
+        .join("match.k")
+        .display()
+        .to_string();
+    let dir = env::temp_dir().join(format!("kcl_multiple_outputs_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let yaml_output = dir.join("out.yaml").display().to_string();
+    let json_output = dir.join("out.json").display().to_string();
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "run",
+        &input,
+        "--output",
+        &format!("{}:yaml", yaml_output),
+        "-o",
+        &format!("{}:json", json_output),
+    ]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    // Both the files are written from one evaluation, and nothing is written to stdout.
+    assert!(buf.is_empty());
+    let yaml = fs::read_to_string(&yaml_output).unwrap();
+    let json = fs::read_to_string(&json_output).unwrap();
+    assert!(yaml.starts_with("name: app\nreplicas: 2"), "{yaml}");
+    assert!(json.starts_with('{'), "{json}");
+    let mut ctx = Context::new();
+    assert_eq!(
+        ValueRef::from_json(&mut ctx, &json).unwrap(),
+        ValueRef::from_yaml(&mut ctx, &yaml).unwrap()
+    );
+    fs::remove_dir_all(&dir).unwrap();
+    // The path without a known format suffix is output in the default format.
+    assert_eq!(
+        output_spec("out.json:json", OutputFormat::Yaml),
+        ("out.json".to_string(), OutputFormat::Json)
+    );
+    assert_eq!(
+        output_spec("c:/out.yaml", OutputFormat::Yaml),
+        ("c:/out.yaml".to_string(), OutputFormat::Yaml)
+    );
+}
+
+/// The in-memory sink of the object store keyed by the URLs.
+struct MemorySink {
+    key: String,
+    store: Arc<std::sync::Mutex<std::collections::HashMap<String, Vec<u8>>>>,
+}
+
+impl OutputSink for MemorySink {
+    fn write_output(&mut self, content: &[u8]) -> anyhow::Result<()> {
+        self.store
+            .lock()
+            .unwrap()
+            .insert(self.key.clone(), content.to_vec());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_cmd_with_output_sink() {
+    let store = Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+    let sink_store = store.clone();
+    register_output_sink("mem", move |url| {
+        Ok(Box::new(MemorySink {
+            key: url.to_string(),
+            store: sink_store.clone(),
+        }))
+    });
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("golden")
+        .join("match.k")
+        .display()
+        .to_string();
+    let matches =
+        app().get_matches_from(&[ROOT_CMD, "run", &input, "-o", "mem://bucket/app.json:json"]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    assert!(buf.is_empty());
+    let store = store.lock().unwrap();
+    let content = String::from_utf8(store["mem://bucket/app.json"].clone()).unwrap();
+    let mut ctx = Context::new();
+    assert_eq!(
+        ValueRef::from_json(&mut ctx, &content).unwrap(),
+        ValueRef::from_json(&mut ctx, r#"{"name": "app", "replicas": 2}"#).unwrap()
+    );
+    // The outputs of the unregistered schemes are reported.
+    let err = open_output_sink("s3://bucket/key")
+        .err()
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        err,
+        "no output sink is registered for the scheme 's3' of the output s3://bucket/key"
+    );
+    assert_eq!(output_scheme("gs://bucket/key"), Some("gs"));
+    assert_eq!(output_scheme("c://out.yaml"), None);
+    assert_eq!(output_scheme("out.yaml"), None);
+}
+
+#[test]
+fn test_run_cmd_with_keep_going() {
+    let dir = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("keep_going");
+    let fail = dir.join("fail.k").display().to_string();
+    let ok = dir.join("ok.k").display().to_string();
+    // The second file still runs after the first file fails.
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &fail, &ok, "--keep_going"]);
+    let mut buf = Vec::new();
+    let err = run_command(matches.subcommand_matches("run").unwrap(), &mut buf)
+        .unwrap_err()
+        .to_string();
+    let output = String::from_utf8(buf).unwrap();
+    assert_eq!(output, "name: second\n");
+    assert!(err.starts_with("1 of 2 file(s) failed\n"), "{err}");
+    assert!(err.contains(&format!("FAIL {}\n", fail)), "{err}");
+    assert!(err.contains("the first file fails"), "{err}");
+    assert!(err.contains(&format!("ok {}", ok)), "{err}");
+    // The entry files are compiled into a single program, thus the files may refer
+    // to each other, and nothing is reported when the program runs successfully.
+    let shared = dir.join("shared.k").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &ok, &shared, "--keep_going"]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "name: second\nfull_name: second-app\n"
+    );
+    // The toggles conflict with each other.
+    assert!(app()
+        .try_get_matches_from(&[ROOT_CMD, "run", &ok, "--keep_going", "--fail_fast"])
+        .is_err());
+}
+
+#[test]
+fn test_imports_check_cmd() {
+    let dir = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("imports_check");
+    // The dangling import is reported with the importing file and line.
+    let main = dir.join("main.k").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "imports-check", &main]);
+    let mut buf = Vec::new();
+    let err = imports_check_command(
+        matches.subcommand_matches("imports-check").unwrap(),
+        &mut buf,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "1 error(s) found when resolving the imports"
+    );
+    let output = String::from_utf8(buf).unwrap();
+    let location = format!("{}:1", Path::new("pkg").join("pkg.k").display());
+    assert!(output.contains(&location), "{output}");
+    assert!(
+        output.contains("pkgpath pkg.missing not found in the program"),
+        "{output}"
+    );
+    // The type errors are not checked.
+    let resolved = dir.join("resolved.k").display().to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "imports-check", &resolved]);
+    let mut buf = Vec::new();
+    imports_check_command(
+        matches.subcommand_matches("imports-check").unwrap(),
+        &mut buf,
+    )
+    .unwrap();
+    let output = String::from_utf8(buf).unwrap();
+    assert_eq!(output, "all the imports of 2 package(s) are resolved\n");
+}
+
+#[test]
+fn test_run_cmd_with_print_digest() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("golden")
+        .join("match.k")
+        .display()
+        .to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "run", &input, "--print_digest"]);
+    let mut buf = Vec::new();
+    run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+    let digest = String::from_utf8(buf).unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(input);
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(digest, format!("{}\n", result.digest().unwrap()));
+}
+
+#[test]
+fn test_run_cmd_with_sandbox_root() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("golden")
+        .join("match.k")
+        .display()
+        .to_string();
+    let sandbox = env::temp_dir().join(format!("kcl_sandbox_root_{}", std::process::id()));
+    fs::create_dir_all(sandbox.join("sub")).unwrap();
+    let sandbox_root = sandbox.display().to_string();
+    let run = |output: &Path| {
+        let output = output.display().to_string();
+        let matches = app().get_matches_from(&[
+            ROOT_CMD,
+            "run",
+            &input,
+            "-K",
+            "--sandbox_root",
+            &sandbox_root,
+            "-o",
+            &output,
+        ]);
+        run_command(matches.subcommand_matches("run").unwrap(), &mut Vec::new())
+    };
+    // The output path escaping the sandbox root via `..` is rejected before running.
+    let escaped = sandbox
+        .join("sub")
+        .join("..")
+        .join("..")
+        .join("escaped.yaml");
+    let err = run(&escaped).unwrap_err().to_string();
+    assert!(err.contains("outside the sandbox root"), "{err}");
+    assert!(!escaped.exists());
+    // The `..` components which stay inside the sandbox root are allowed.
+    let inside = sandbox.join("sub").join("..").join("out.yaml");
+    run(&inside).unwrap();
+    assert!(sandbox.join("out.yaml").is_file());
+    fs::remove_dir_all(&sandbox).unwrap();
+}
+
+#[test]
+fn test_run_cmd_with_features() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("features")
+        .join("main.k")
+        .display()
+        .to_string();
+    let run = |features: &[&str]| {
+        let mut argv = vec![ROOT_CMD, "run", &input];
+        argv.extend_from_slice(features);
+        let matches = app().get_matches_from(argv);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+    assert_eq!(run(&[]), "env: dev\n");
+    assert_eq!(run(&["-F", "prod"]), "env: prod\n");
+    assert_eq!(run(&["--feature", "debug,prod"]), "env: prod\n");
+}
+
+#[test]
+fn test_args_cmd() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("args")
+        .join("main.k")
+        .display()
+        .to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "args", &input]);
+    let mut buf = Vec::new();
+    args_command(matches.subcommand_matches("args").unwrap(), &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "option list:\n  -D name=? (str,required) The application name\n  -D replicas=1 (int)\n"
+    );
+}
+
+#[test]
+fn test_run_command_require_all_args() {
+    let input = "./src/test_data/require_args/main.k";
+    let run = |extra: &[&str]| {
+        let mut args = vec![ROOT_CMD, "run", input, "-K", "--require_all_args"];
+        args.extend(extra);
+        let matches = app().get_matches_from(args);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf)
+            .map(|_| String::from_utf8(buf).unwrap())
+    };
+    // All the missing arguments are reported at once, and the ones with defaults are optional.
+    let err = run(&[]).unwrap_err().to_string();
+    assert!(
+        err.ends_with("not supplied: name, region, set them with -D"),
+        "{err}"
+    );
+    let err = run(&["-D", "name=web"]).unwrap_err().to_string();
+    assert!(
+        err.ends_with("not supplied: region, set them with -D"),
+        "{err}"
+    );
+    let output = run(&["-D", "name=web", "-D", "region=us"]).unwrap();
+    assert_eq!(output, "name: web\nregion: us\nreplicas: 1\n");
+}
+
+#[test]
+fn test_schemas_cmd() {
+    let input = Path::new(".")
+        .join("src")
+        .join("test_data")
+        .join("schemas")
+        .join("main.k")
+        .display()
+        .to_string();
+    let matches = app().get_matches_from(&[ROOT_CMD, "schemas", &input]);
+    let mut buf = Vec::new();
+    schemas_command(matches.subcommand_matches("schemas").unwrap(), &mut buf).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "Base: name\n├── Backend: port\n└── Frontend: path\n"
+    );
+    let matches = app().get_matches_from(&[ROOT_CMD, "schemas", &input, "--format", "json"]);
+    let mut buf = Vec::new();
+    schemas_command(matches.subcommand_matches("schemas").unwrap(), &mut buf).unwrap();
+    let mut ctx = Context::new();
+    let json = ValueRef::from_json(&mut ctx, &String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!(
+        json.get_by_key("roots").unwrap(),
+        ValueRef::from_json(&mut ctx, "[\"Base\"]").unwrap()
+    );
+}
+
+#[test]
+fn test_completions_cmd() {
+    for shell in COMPLETION_SHELLS {
+        let matches = app().get_matches_from(&[ROOT_CMD, "completions", shell]);
+        let mut buf = Vec::new();
+        completions_command(matches.subcommand_matches("completions").unwrap(), &mut buf).unwrap();
+        let script = String::from_utf8(buf).unwrap();
+        assert!(!script.is_empty(), "{shell}");
+        // The script is generated from the command tree including the subcommands.
+        assert!(script.contains("completions"), "{shell}");
+    }
+}
+
+#[test]
+fn test_cache_stats_cmd() {
+    let dir = PathBuf::from("./src/test_data/cache_stats");
+    let matches = app().get_matches_from(&[
+        ROOT_CMD,
+        "cache",
+        "stats",
+        dir.to_str().unwrap(),
+        "--fast_eval",
+    ]);
+    let mut buf = Vec::new();
+    cache_command(matches.subcommand_matches("cache").unwrap(), &mut buf).unwrap();
+    let table = String::from_utf8(buf).unwrap();
+    // The evaluator compiles and caches nothing.
+    assert!(
+        table.starts_with("0 package(s) in 1 config(s) with evaluator"),
+        "{table}"
+    );
+    assert!(table.contains("app/main.k | 0 | 0 |"), "{table}");
+}
+
+#[test]
+fn test_cache_export_import_cmd() {
+    let root = env::temp_dir().join(format!("kcl_cache_archive_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let cache_dir = kclvm_runner::assembler::default_cache_dir(root.to_str().unwrap());
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("sub.o"), "").unwrap();
+    let archive = root.join("cache.tar");
+    let run = |args: &[&str]| {
+        let matches = app().get_matches_from(args);
+        let mut buf = Vec::new();
+        cache_command(matches.subcommand_matches("cache").unwrap(), &mut buf).map(|_| buf)
+    };
+    let (archive_path, root_path) = (archive.to_str().unwrap(), root.to_str().unwrap());
+    let output = run(&[ROOT_CMD, "cache", "export", archive_path, root_path]).unwrap();
+    assert!(String::from_utf8(output)
+        .unwrap()
+        .starts_with("exported 1 cache file(s)"));
+    fs::remove_dir_all(root.join(".kclvm")).unwrap();
+    let output = run(&[ROOT_CMD, "cache", "import", archive_path, root_path]).unwrap();
+    assert!(String::from_utf8(output)
+        .unwrap()
+        .starts_with("imported 1 cache file(s)"));
+    assert!(cache_dir.join("sub.o").exists());
+    // Nothing to export after the cache is cleaned.
+    fs::remove_dir_all(root.join(".kclvm")).unwrap();
+    assert!(run(&[ROOT_CMD, "cache", "export", archive_path, root_path]).is_err());
+    fs::remove_dir_all(&root).unwrap();
+}
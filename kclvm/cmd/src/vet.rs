@@ -0,0 +1,88 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use kclvm_tools::vet::validator::{validate_str, LoaderKind, ValidateOption};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::normalize::read_documents;
+
+/// The options of the vet command, which are shared by the validated documents.
+#[derive(Debug, Clone, Default)]
+pub struct VetOptions {
+    pub data_file: String,
+    pub kcl_file: String,
+    pub schema: Option<String>,
+    pub attribute_name: String,
+}
+
+/// Run the KCL vet command, which validates the JSON or YAML data file with the
+/// schema in the KCL file. The documents of a YAML stream are validated one at a
+/// time, thus the memory is bounded by the largest document instead of the file.
+pub fn vet_command(matches: &ArgMatches) -> Result<()> {
+    let opts = VetOptions {
+        data_file: matches
+            .get_one::<String>("data_file")
+            .ok_or(anyhow!("the validation data file is required"))?
+            .to_string(),
+        kcl_file: matches
+            .get_one::<String>("kcl_file")
+            .ok_or(anyhow!("the KCL file is required"))?
+            .to_string(),
+        schema: matches.get_one::<String>("schema").cloned(),
+        attribute_name: matches
+            .get_one::<String>("attribute_name")
+            .cloned()
+            .unwrap_or_else(|| "value".to_string()),
+    };
+    let kind = match matches.get_one::<String>("format") {
+        Some(format) => match format.to_lowercase().as_str() {
+            "yaml" | "yml" => LoaderKind::YAML,
+            "json" => LoaderKind::JSON,
+            _ => bail!("unsupported vet format '{}', expected yaml or json", format),
+        },
+        None => LoaderKind::JSON,
+    };
+    let reader = BufReader::new(File::open(&opts.data_file)?);
+    vet_stream(reader, kind, &opts)?;
+    Ok(())
+}
+
+/// Validate the data read from the reader and returns the number of the validated
+/// documents. The YAML documents are validated one at a time with [read_documents]
+/// and the validation stops at the first invalid document, the JSON data is one
+/// document.
+pub fn vet_stream<R: BufRead>(mut reader: R, kind: LoaderKind, opts: &VetOptions) -> Result<usize> {
+    let validate = |content: &str, index: usize| -> Result<()> {
+        let val_opt = ValidateOption::new(
+            opts.schema.clone(),
+            opts.attribute_name.clone(),
+            opts.data_file.clone(),
+            kind,
+            Some(opts.kcl_file.clone()),
+            None,
+        );
+        validate_str(val_opt, content).map_err(|err| {
+            anyhow!(
+                "the document {} of '{}' is invalid: {}",
+                index,
+                opts.data_file,
+                err
+            )
+        })?;
+        Ok(())
+    };
+    let mut count = 0;
+    match kind {
+        LoaderKind::YAML => read_documents(reader, |document| {
+            count += 1;
+            validate(document, count)
+        })?,
+        LoaderKind::JSON => {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            count += 1;
+            validate(&content, count)?;
+        }
+    }
+    Ok(count)
+}
@@ -0,0 +1,80 @@
+use anyhow::{bail, Result};
+use kclvm_runner::OutputFormat;
+use kclvm_runtime::{JsonEncodeOptions, ValueRef};
+
+/// The Kubernetes manifest which wraps the serialized output under its data,
+/// e.g., `--wrap configmap:app-config` or `--wrap secret:app-secret`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Envelope {
+    ConfigMap(String),
+    Secret(String),
+}
+
+impl TryFrom<&str> for Envelope {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        let (kind, name) = match value.split_once(':') {
+            Some((kind, name)) => (kind.trim(), name.trim()),
+            None => bail!(
+                "invalid wrap spec '{}', expected configmap:name or secret:name",
+                value
+            ),
+        };
+        // The Kubernetes object names are DNS subdomain names.
+        if name.is_empty()
+            || name.len() > 253
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        {
+            bail!(
+                "invalid name '{}' in the wrap spec '{}', expected lowercase alphanumeric characters, '-' or '.'",
+                name,
+                value
+            );
+        }
+        match kind.to_lowercase().as_str() {
+            "configmap" | "cm" => Ok(Envelope::ConfigMap(name.to_string())),
+            "secret" => Ok(Envelope::Secret(name.to_string())),
+            _ => bail!(
+                "unsupported wrap kind '{}', expected configmap or secret",
+                kind
+            ),
+        }
+    }
+}
+
+/// Wrap the serialized output in the ConfigMap or Secret manifest, the output is
+/// the value of the key `config.yaml` or `config.json` according to the format,
+/// and the Secret value is base64-encoded.
+pub fn wrap_output(output: &str, envelope: &Envelope, format: OutputFormat) -> Result<String> {
+    let key = match format {
+        OutputFormat::Yaml => "config.yaml",
+        OutputFormat::Json => "config.json",
+        OutputFormat::TfJson => bail!("the tfjson output can not be wrapped in a manifest"),
+        OutputFormat::NdJson => bail!("the ndjson output can not be wrapped in a manifest"),
+    };
+    let (kind, name, value) = match envelope {
+        Envelope::ConfigMap(name) => ("ConfigMap", name, output.to_string()),
+        Envelope::Secret(name) => ("Secret", name, base64::encode(output)),
+    };
+    let metadata = ValueRef::dict(Some(&[("name", &ValueRef::str(name))]));
+    let data = ValueRef::dict(Some(&[(key, &ValueRef::str(&value))]));
+    let mut manifest = ValueRef::dict(Some(&[
+        ("apiVersion", &ValueRef::str("v1")),
+        ("kind", &ValueRef::str(kind)),
+        ("metadata", &metadata),
+    ]));
+    if let Envelope::Secret(_) = envelope {
+        manifest.dict_update_key_value("type", ValueRef::str("Opaque"));
+    }
+    manifest.dict_update_key_value("data", data);
+    Ok(match format {
+        OutputFormat::Json => manifest.to_json_string_with_options(&JsonEncodeOptions {
+            indent: 4,
+            ..Default::default()
+        }),
+        _ => manifest.to_yaml_string().trim_end_matches('\n').to_string(),
+    })
+}
@@ -115,7 +115,7 @@ pub struct LLVMCodeGenContext<'ctx> {
     /// Debug mode
     pub debug: bool,
     /// Program modules according to AST modules
-    pub modules: RefCell<HashMap<String, RefCell<DebugModule<'ctx>>>>,
+    pub modules: RefCell<IndexMap<String, RefCell<DebugModule<'ctx>>>>,
     /// Program workdir
     pub workdir: String,
 }
@@ -513,7 +513,7 @@ impl<'ctx> ValueMethods for LLVMCodeGenContext<'ctx> {
     fn struct_function_value(
         &self,
         functions: &[FunctionValue<'ctx>],
-        attr_functions: &HashMap<String, Vec<FunctionValue<'ctx>>>,
+        attr_functions: &IndexMap<String, Vec<FunctionValue<'ctx>>>,
         runtime_type: &str,
     ) -> Self::Value {
         if functions.is_empty() {
@@ -1272,7 +1272,7 @@ impl<'ctx> LLVMCodeGenContext<'ctx> {
             import_names,
             no_link,
             debug: false,
-            modules: RefCell::new(HashMap::new()),
+            modules: RefCell::new(IndexMap::default()),
             workdir,
         }
     }
@@ -1417,6 +1417,14 @@ impl<'ctx> LLVMCodeGenContext<'ctx> {
             if opt.no_link {
                 let modules = self.modules.borrow_mut();
                 for (index, (_, module)) in modules.iter().enumerate() {
+                    if let Some(ir_path) = &opt.ir_path {
+                        let ir_path = if modules.len() == 1 {
+                            ir_path.to_string()
+                        } else {
+                            format!("{}_{}", ir_path, index)
+                        };
+                        module.borrow().inner.print_to_file(&ir_path)?;
+                    }
                     let path = if modules.len() == 1 {
                         format!("{}{}", path_str, OBJECT_FILE_SUFFIX)
                     } else {
@@ -1424,25 +1432,33 @@ impl<'ctx> LLVMCodeGenContext<'ctx> {
                     };
                     let path = std::path::Path::new(&path);
                     // Build LLVM module to a `.o` object file.
-                    self.build_object_file(&module.borrow().inner, path)?;
+                    self.build_object_file(&module.borrow().inner, path, opt.target_triple)?;
                 }
             } else {
+                if let Some(ir_path) = &opt.ir_path {
+                    self.module.print_to_file(ir_path)?;
+                }
                 // Build LLVM module to a `.o` object file.
-                self.build_object_file(&self.module, path)?;
+                self.build_object_file(&self.module, path, opt.target_triple)?;
             }
         }
         Ok(())
     }
 
-    /// Build LLVM module to a `.o` object file.
+    /// Build LLVM module to a `.o` object file for the target triple, which
+    /// defaults to the host triple.
     ///
-    /// TODO: WASM and cross platform build.
+    /// TODO: WASM build.
     fn build_object_file(
         self: &LLVMCodeGenContext<'ctx>,
         module: &Module,
         path: &Path,
+        target_triple: Option<&str>,
     ) -> Result<(), LLVMString> {
-        let triple = inkwell::targets::TargetMachine::get_default_triple();
+        let triple = match target_triple {
+            Some(triple) => inkwell::targets::TargetTriple::create(triple),
+            None => inkwell::targets::TargetMachine::get_default_triple(),
+        };
         let target = inkwell::targets::Target::from_triple(&triple)?;
         // Convert LLVM module to ll file.
         module.print_to_file(path)?;
@@ -12,6 +12,7 @@ use crate::codegen::{EmitOptions, MODULE_NAME};
 use super::context::LLVMCodeGenContext;
 
 static LLVM_INIT: OnceCell<()> = OnceCell::new();
+static LLVM_INIT_ALL: OnceCell<()> = OnceCell::new();
 static RUNTIME_LLVM_BC: &[u8] = include_bytes!("../../../../runtime/src/_kclvm.bc");
 
 /// Load runtime libraries and parse it to a module.
@@ -37,6 +38,10 @@ pub fn emit_code(
         #[cfg(not(target_os = "linux"))]
         inkwell::targets::Target::initialize_all(&Default::default());
     });
+    // Init all the LLVM targets for the cross compilation.
+    if opts.target_triple.is_some() {
+        init_all_targets();
+    }
     // Create a LLVM context
     let context = Context::create();
     // Create a LLVM module using an exist LLVM bitcode file
@@ -57,3 +62,15 @@ pub fn emit_code(
     // Generate user KCL code LLVM IR
     crate::codegen::emit_code_with(ctx, opts)
 }
+
+/// Whether the LLVM backend can emit the object files for the target triple.
+pub fn is_target_available(triple: &str) -> bool {
+    init_all_targets();
+    inkwell::targets::Target::from_triple(&inkwell::targets::TargetTriple::create(triple)).is_ok()
+}
+
+fn init_all_targets() {
+    LLVM_INIT_ALL.get_or_init(|| {
+        inkwell::targets::Target::initialize_all(&Default::default());
+    });
+}
@@ -14,4 +14,4 @@ mod node;
 mod schema;
 mod utils;
 
-pub use emit::emit_code;
+pub use emit::{emit_code, is_target_available};
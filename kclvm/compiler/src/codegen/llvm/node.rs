@@ -1,9 +1,9 @@
 // Copyright The KCL Authors. All rights reserved.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use indexmap::IndexMap;
 use inkwell::basic_block::BasicBlock;
 use inkwell::module::Linkage;
 use inkwell::values::{BasicValueEnum, CallableValue, FunctionValue};
@@ -423,8 +423,8 @@ impl<'ctx> TypedResultWalker<'ctx> for LLVMCodeGenContext<'ctx> {
             value::SCHEMA_CHECK_BLOCK_NAME,
             pkgpath_without_prefix!(runtime_type),
         ));
-        let mut place_holder_map: HashMap<String, Vec<FunctionValue<'ctx>>> = HashMap::new();
-        let mut body_map: HashMap<String, Vec<&ast::Node<ast::Stmt>>> = HashMap::new();
+        let mut place_holder_map: IndexMap<String, Vec<FunctionValue<'ctx>>> = IndexMap::new();
+        let mut body_map: IndexMap<String, Vec<&ast::Node<ast::Stmt>>> = IndexMap::new();
         // Enter the function
         self.push_function(function);
         // Lambda function body
@@ -1218,8 +1218,11 @@ impl<'ctx> TypedResultWalker<'ctx> for LLVMCodeGenContext<'ctx> {
             self.builder.position_at_end(func_before_block);
             self.pop_function();
         }
-        let function =
-            self.struct_function_value(&[function, check_function], &HashMap::new(), &runtime_type);
+        let function = self.struct_function_value(
+            &[function, check_function],
+            &IndexMap::new(),
+            &runtime_type,
+        );
         self.leave_scope();
         self.pop_function();
         self.schema_stack.borrow_mut().pop();
@@ -1,11 +1,11 @@
 // Copyright The KCL Authors. All rights reserved.
 
+use indexmap::IndexMap;
 use inkwell::values::{BasicValueEnum, FunctionValue};
 use inkwell::{AddressSpace, IntPredicate};
 use kclvm_ast::ast;
 use kclvm_runtime::ApiFunc;
 use kclvm_sema::pkgpath_without_prefix;
-use std::collections::HashMap;
 use std::str;
 
 use super::context::LLVMCodeGenContext;
@@ -25,8 +25,8 @@ impl<'ctx> LLVMCodeGenContext<'ctx> {
         cal_map: BasicValueEnum<'ctx>,
         runtime_type: &str,
         is_in_if: bool,
-        place_holder_map: &mut HashMap<String, Vec<FunctionValue<'ctx>>>,
-        body_map: &mut HashMap<String, Vec<&'ctx ast::Node<ast::Stmt>>>,
+        place_holder_map: &mut IndexMap<String, Vec<FunctionValue<'ctx>>>,
+        body_map: &mut IndexMap<String, Vec<&'ctx ast::Node<ast::Stmt>>>,
         in_if_names: &mut Vec<String>,
     ) {
         let schema_value = self
@@ -36,8 +36,8 @@ impl<'ctx> LLVMCodeGenContext<'ctx> {
         let add_stmt =
             |name: &str,
              stmt: &'ctx ast::Node<ast::Stmt>,
-             place_holder_map: &mut HashMap<String, Vec<FunctionValue<'ctx>>>,
-             body_map: &mut HashMap<String, Vec<&'ctx ast::Node<ast::Stmt>>>| {
+             place_holder_map: &mut IndexMap<String, Vec<FunctionValue<'ctx>>>,
+             body_map: &mut IndexMap<String, Vec<&'ctx ast::Node<ast::Stmt>>>| {
                 let function = self.add_function(&format!(
                     "{}.{}.{}",
                     value::SCHEMA_ATTR_NAME,
@@ -32,6 +32,10 @@ pub const OBJECT_FILE_SUFFIX: &str = ".obj";
 pub const OBJECT_FILE_SUFFIX: &str = ".o";
 /// LLVM IR text format suffix .ll
 pub const LL_FILE_SUFFIX: &str = ".ll";
+/// The architectures of the target triples which the object files can be emitted for.
+pub const SUPPORTED_TARGET_ARCHS: &[&str] = &[
+    "x86_64", "i686", "aarch64", "arm64", "armv7", "riscv64", "wasm32",
+];
 
 /// CodeGenContext is a trait used by the compiler to emit code to different targets.
 pub trait CodeGenContext: traits::ProgramCodeGen {
@@ -47,6 +51,32 @@ pub struct EmitOptions<'a> {
     pub emit_path: Option<&'a str>,
     /// no_link indicates whether to link the generated code of different KCL packages to the same module.
     pub no_link: bool,
+    /// The target triple of the emitted object files, defaults to the host triple.
+    pub target_triple: Option<&'a str>,
+    /// Path to write the textual LLVM IR of the module before emitting the object file,
+    /// the index of the module is appended when there are multiple modules.
+    pub ir_path: Option<&'a str>,
+}
+
+/// Check the target triple in the form `<arch>-<vendor>-<os>[-<env>]`, e.g.,
+/// `x86_64-unknown-linux-gnu`, and its architecture must be supported.
+pub fn check_target_triple(triple: &str) -> Result<(), String> {
+    let parts = triple.split('-').collect::<Vec<&str>>();
+    if parts.len() < 3 || parts.len() > 4 || parts.iter().any(|p| p.is_empty()) {
+        return Err(format!(
+            "invalid target triple '{}', expected the form <arch>-<vendor>-<os>[-<env>], e.g., x86_64-unknown-linux-gnu",
+            triple
+        ));
+    }
+    if !SUPPORTED_TARGET_ARCHS.contains(&parts[0]) {
+        return Err(format!(
+            "unsupported architecture '{}' of the target triple '{}', expected one of {}",
+            parts[0],
+            triple,
+            SUPPORTED_TARGET_ARCHS.join(", ")
+        ));
+    }
+    Ok(())
 }
 
 /// Emit code with the options using CodeGenContext.
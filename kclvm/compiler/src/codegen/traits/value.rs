@@ -1,6 +1,6 @@
 //! Copyright The KCL Authors. All rights reserved.
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use super::BackendTypes;
 
@@ -30,11 +30,12 @@ pub trait ValueMethods: BackendTypes {
     fn function_value(&self, function: Self::Function) -> Self::Value;
     /// Construct a closure function value with the closure variable.
     fn closure_value(&self, function: Self::Function, closure: Self::Value) -> Self::Value;
-    /// Construct a structure function value using native functions.
+    /// Construct a structure function value using native functions, the attribute
+    /// functions are ordered to generate the same code for the same source.
     fn struct_function_value(
         &self,
         functions: &[Self::Function],
-        attr_functions: &HashMap<String, Vec<Self::Function>>,
+        attr_functions: &IndexMap<String, Vec<Self::Function>>,
         runtime_type: &str,
     ) -> Self::Value;
     /// Construct a builtin function value using the function name.
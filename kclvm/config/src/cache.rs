@@ -28,6 +28,22 @@ pub struct CacheOption {
     cache_dir: String,
 }
 
+impl CacheOption {
+    /// The cache under the absolute cache root instead of the program root, e.g.,
+    /// the program root or the [KCL_CACHE_PATH_ENV_VAR] of the run which populated
+    /// the cache.
+    pub fn with_cache_root<P: AsRef<Path>>(cache_root: P) -> Self {
+        // The absolute cache dir replaces the program root when they are joined.
+        Self {
+            cache_dir: cache_root
+                .as_ref()
+                .join(DEFAULT_CACHE_DIR)
+                .display()
+                .to_string(),
+        }
+    }
+}
+
 impl Default for CacheOption {
     fn default() -> Self {
         Self {
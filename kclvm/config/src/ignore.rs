@@ -0,0 +1,131 @@
+//! Copyright The KCL Authors. All rights reserved.
+//!
+//! Support for the `.kclignore` file placed at the module root. The file uses
+//! gitignore-style patterns: one pattern per line, `#` starts a comment, a
+//! leading `!` negates a pattern and the last matching pattern wins.
+
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use std::path::{Path, PathBuf};
+
+use crate::modfile::get_pkg_root;
+
+pub const KCL_IGNORE_FILE: &str = ".kclignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    patterns: Vec<Pattern>,
+    negated: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let line = line.trim_end_matches('/');
+        // Patterns containing a separator are anchored to the module root,
+        // others match the file or directory name at any depth.
+        let base = match line.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if line.contains('/') => line.to_string(),
+            None => format!("**/{}", line),
+        };
+        if base.is_empty() || base == "**/" {
+            return Ok(None);
+        }
+        // The second pattern matches everything inside an ignored directory.
+        let patterns = vec![Pattern::new(&base)?, Pattern::new(&format!("{}/**", base))?];
+        Ok(Some(Self { patterns, negated }))
+    }
+
+    fn matches(&self, rel_path: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.matches_with(rel_path, MATCH_OPTIONS))
+    }
+}
+
+/// [`KclIgnore`] holds the rules loaded from a `.kclignore` file and
+/// decides whether a path under the module root is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct KclIgnore {
+    root: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl KclIgnore {
+    /// Parse the ignore rules from the `.kclignore` content relative to `root`.
+    pub fn from_content(root: &Path, content: &str) -> Result<Self> {
+        let mut rules = vec![];
+        for line in content.lines() {
+            if let Some(rule) = IgnoreRule::parse(line)? {
+                rules.push(rule);
+            }
+        }
+        Ok(Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            rules,
+        })
+    }
+
+    /// Load the `.kclignore` file in the module root. An empty rule set is
+    /// returned when the file does not exist.
+    pub fn load(root: &Path) -> Result<Self> {
+        let ignore_file = root.join(KCL_IGNORE_FILE);
+        if !ignore_file.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&ignore_file)?;
+        Self::from_content(root, &content)
+    }
+
+    /// Load the `.kclignore` file of the module containing `path`.
+    pub fn load_from_path(path: &str) -> Result<Self> {
+        match get_pkg_root(path) {
+            Some(root) => Self::load(Path::new(&root)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Whether there are no ignore rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether the `path` is ignored. Paths outside the module root are never ignored.
+    pub fn is_ignored<P: AsRef<Path>>(&self, path: P) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let rel_path = match path.strip_prefix(&self.root) {
+            Ok(rel_path) => rel_path,
+            Err(_) => return false,
+        };
+        let rel_path = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.matches(&rel_path) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
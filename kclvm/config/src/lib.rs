@@ -1,7 +1,9 @@
 //! Copyright The KCL Authors. All rights reserved.
 
 pub mod cache;
+pub mod ignore;
 pub mod modfile;
+pub mod offline;
 pub mod path;
 pub mod settings;
 pub mod vfs;
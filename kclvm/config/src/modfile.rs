@@ -3,7 +3,7 @@
 use anyhow::Result;
 use kclvm_utils::path::PathPrefix;
 use serde::Deserialize;
-use std::{env, fs, io::Read, path::PathBuf};
+use std::{collections::BTreeMap, env, fs, io::Read, path::PathBuf};
 use toml;
 
 use crate::path::ModRelativePath;
@@ -15,6 +15,8 @@ pub const KCL_MOD_PATH_ENV: &str = "${KCL_MOD}";
 pub const KCL_PKG_PATH: &str = "KCL_PKG_PATH";
 pub const DEFAULT_KCL_HOME: &str = ".kcl";
 pub const DEFAULT_KPM_SUBDIR: &str = "kpm";
+/// The default alias of the module root in imports, e.g., `import @root/common`.
+pub const DEFAULT_KCL_ROOT_ALIAS: &str = "@root";
 
 /// Get the path holding the external kcl package.
 /// From the environment variable KCL_PKG_PATH.
@@ -62,8 +64,48 @@ pub fn create_default_vendor_home() -> Option<String> {
 pub struct KCLModFile {
     pub root: Option<String>,
     pub root_pkg: Option<String>,
+    /// The alias of the module root in imports, which is [DEFAULT_KCL_ROOT_ALIAS] by default.
+    pub root_alias: Option<String>,
     pub build: Option<KCLModFileBuildSection>,
     pub expected: Option<KCLModFileExpectedSection>,
+    pub profile: Option<KCLModFileProfileSection>,
+    /// The dependencies keyed by the package names, which are the registry versions,
+    /// e.g., `helper = "0.1.0"`, or the tables with the `git`, `oci` or local `path` source.
+    pub dependencies: Option<BTreeMap<String, toml::Value>>,
+}
+
+impl KCLModFile {
+    /// Returns the alias of the module root in imports with the leading `@`.
+    pub fn root_alias(&self) -> String {
+        match &self.root_alias {
+            Some(alias) => format!("@{}", alias.trim().trim_start_matches('@')),
+            None => DEFAULT_KCL_ROOT_ALIAS.to_string(),
+        }
+    }
+
+    /// Returns the names and sources of the dependencies which are fetched from the
+    /// network, i.e., all the dependencies except the ones with a local `path`.
+    pub fn remote_dependencies(&self) -> Vec<(String, String)> {
+        let mut deps = vec![];
+        for (name, dep) in self.dependencies.iter().flatten() {
+            let source = match dep {
+                toml::Value::String(version) => format!("registry version {}", version),
+                toml::Value::Table(table) if table.contains_key("path") => continue,
+                toml::Value::Table(table) => {
+                    let source = |key: &str| table.get(key).and_then(|v| v.as_str());
+                    match (source("git"), source("oci"), source("version")) {
+                        (Some(git), _, _) => git.to_string(),
+                        (None, Some(oci), _) => oci.to_string(),
+                        (None, None, Some(version)) => format!("registry version {}", version),
+                        (None, None, None) => "unknown source".to_string(),
+                    }
+                }
+                _ => "unknown source".to_string(),
+            };
+            deps.push((name.clone(), source));
+        }
+        deps
+    }
 }
 
 #[allow(dead_code)]
@@ -84,6 +126,13 @@ pub struct KCLModFileExpectedSection {
     pub global_version: Option<String>,
 }
 
+#[allow(dead_code)]
+#[derive(Default, Deserialize)]
+pub struct KCLModFileProfileSection {
+    /// The entry files of the module, which are relative to the module root.
+    pub entries: Option<Vec<String>>,
+}
+
 pub fn get_pkg_root_from_paths(file_paths: &[String], workdir: String) -> Result<String, String> {
     if file_paths.is_empty() {
         return Err("No input KCL files or paths".to_string());
@@ -144,14 +193,22 @@ pub fn get_pkg_root(k_file_path: &str) -> Option<String> {
 }
 
 pub fn load_mod_file(root: &str) -> KCLModFile {
+    try_load_mod_file(root).unwrap()
+}
+
+/// Load the `kcl.mod` file in the root directory, returning the error when the
+/// file can not be read or is malformed. The default mod file is returned when
+/// the root does not contain a `kcl.mod` file.
+pub fn try_load_mod_file(root: &str) -> Result<KCLModFile> {
     let k_mod_file_path = std::path::Path::new(root).join(KCL_MOD_FILE);
     if !k_mod_file_path.exists() {
-        return KCLModFile::default();
+        return Ok(KCLModFile::default());
     }
-    let mut file = std::fs::File::open(k_mod_file_path.to_str().unwrap()).unwrap();
+    let mut file = std::fs::File::open(&k_mod_file_path)?;
     let mut buffer: Vec<u8> = vec![];
-    file.read_to_end(&mut buffer).unwrap();
-    toml::from_slice(buffer.as_slice()).unwrap()
+    file.read_to_end(&mut buffer)?;
+    toml::from_slice(buffer.as_slice())
+        .map_err(|err| anyhow::anyhow!("failed to parse {}: {}", k_mod_file_path.display(), err))
 }
 
 #[cfg(test)]
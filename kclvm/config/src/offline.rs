@@ -0,0 +1,30 @@
+//! The offline mode forbids all the network access, e.g., the dependency
+//! resolution, the remote setting fetch and the registry pull, for the
+//! air-gapped and reproducible builds. It is enabled by the `--offline` flag
+//! or the `KCL_OFFLINE` environment variable.
+
+use anyhow::{bail, Result};
+use std::env;
+
+/// The environment variable which enables the offline mode.
+pub const KCL_OFFLINE_ENV_VAR: &str = "KCL_OFFLINE";
+
+/// Returns whether the offline mode is enabled by the environment variable,
+/// which is any value except the empty string, `0` and `false`.
+pub fn is_offline() -> bool {
+    match env::var(KCL_OFFLINE_ENV_VAR) {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false"),
+        Err(_) => false,
+    }
+}
+
+/// Returns an error naming what would have been fetched in the offline mode.
+pub fn ensure_online(offline: bool, what: &str) -> Result<()> {
+    if offline {
+        bail!(
+            "the network access is forbidden in the offline mode, {} can not be fetched",
+            what
+        );
+    }
+    Ok(())
+}
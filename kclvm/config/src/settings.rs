@@ -1,5 +1,10 @@
 //! Copyright The KCL Authors. All rights reserved.
 use anyhow::{Context, Result};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
 use serde::{
     de::{DeserializeSeed, Error, MapAccess, SeqAccess, Unexpected, Visitor},
     Deserialize, Serialize,
@@ -42,24 +47,40 @@ impl SettingsPathBuf {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The settings file, e.g., `kcl.yaml`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[schemars(title = "KCL settings", deny_unknown_fields)]
 pub struct SettingsFile {
+    /// The command line configs.
     pub kcl_cli_configs: Option<Config>,
+    /// The top-level arguments.
     pub kcl_options: Option<Vec<KeyValuePair>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
+    /// The KCL input files.
     pub files: Option<Vec<String>>,
+    /// The KCL input files, which are used when `files` is empty.
     pub file: Option<Vec<String>>,
+    /// The file to write the result into.
     pub output: Option<String>,
+    /// The configuration overrides applied before the execution.
     pub overrides: Option<Vec<String>>,
+    /// The path selectors of the output values.
     pub path_selector: Option<Vec<String>>,
+    /// Check the range of the integer and float values.
     pub strict_range_check: Option<bool>,
+    /// Do not output the none values.
     pub disable_none: Option<bool>,
+    /// The verbose level.
     pub verbose: Option<u32>,
+    /// Run in the debug mode.
     pub debug: Option<bool>,
+    /// Sort the keys of the YAML and JSON result.
     pub sort_keys: Option<bool>,
+    /// Output the hidden attributes.
     pub show_hidden: Option<bool>,
     /// Whether including schema type in JSON/YAML result.
     pub include_schema_type_path: Option<bool>,
@@ -119,6 +140,35 @@ impl SettingsFile {
             None => vec![],
         }
     }
+
+    /// Substitute the environment variables in the string fields of the settings
+    /// such as input files, output and overrides. See [`interpolate_env`].
+    pub fn interpolate_env(&mut self) -> Result<()> {
+        if let Some(config) = &mut self.kcl_cli_configs {
+            for list in [
+                &mut config.files,
+                &mut config.file,
+                &mut config.overrides,
+                &mut config.path_selector,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                for item in list.iter_mut() {
+                    *item = interpolate_env(item)?;
+                }
+            }
+            if let Some(output) = &mut config.output {
+                *output = interpolate_env(output)?;
+            }
+            if let Some(package_maps) = &mut config.package_maps {
+                for path in package_maps.values_mut() {
+                    *path = interpolate_env(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for SettingsFile {
@@ -128,10 +178,12 @@ impl Default for SettingsFile {
 }
 
 /// Top level argument key value pair.
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+#[schemars(deny_unknown_fields)]
 pub struct KeyValuePair {
     /// key is the top level argument key.
     pub key: String,
+    /// value is the top level argument value, i.e., any JSON value.
     // Note: here is a normal json string including int, float, string, bool list and dict.
     pub value: ValueString,
 }
@@ -194,6 +246,17 @@ impl Deref for ValueString {
     }
 }
 
+/// The value string is deserialized from any JSON value.
+impl JsonSchema for ValueString {
+    fn schema_name() -> String {
+        "ValueString".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        Schema::Bool(true)
+    }
+}
+
 impl From<String> for ValueString {
     fn from(value: String) -> Self {
         Self(value)
@@ -350,15 +413,141 @@ pub struct TestSettingsFile {
     kcl_options: Option<String>,
 }
 
+/// Returns the JSON Schema (draft 7) of the settings file, which lets the editors
+/// complete and validate the settings files. It is derived from [SettingsFile], thus
+/// the field names, types, enum values and docs are always in sync with the struct.
+pub fn settings_json_schema() -> serde_json::Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    serde_json::to_value(generator.into_root_schema_for::<SettingsFile>())
+        .expect("the settings schema is always serializable")
+}
+
 /// Load kcl settings file.
 pub fn load_file(filename: &str) -> Result<SettingsFile> {
     let f = std::fs::File::open(filename)
         .with_context(|| format!("Failed to load '{}', no such file or directory", filename))?;
-    let data: SettingsFile = serde_yaml::from_reader(f)
+    let mut data: SettingsFile = serde_yaml::from_reader(f)
+        .with_context(|| format!("Failed to load '{}', invalid setting file format", filename))?;
+    data.interpolate_env()
         .with_context(|| format!("Failed to load '{}', invalid setting file format", filename))?;
     Ok(data)
 }
 
+/// Check that the settings file does not contain unknown keys, such as misspelled
+/// `kcl_cli_configs` fields. The error reports the offending key with its location.
+pub fn check_unknown_settings_keys(filename: &str) -> Result<()> {
+    let content = std::fs::read_to_string(filename)
+        .with_context(|| format!("Failed to load '{}', no such file or directory", filename))?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to load '{}', invalid setting file format", filename))?;
+    // All the fields of the default settings are set, so its keys are the known keys.
+    let known = serde_yaml::to_value(SettingsFile::new())?;
+    let option_keys = vec!["key".to_string(), "value".to_string()];
+    let mut unknown = unknown_keys(&value, &mapping_keys(&known));
+    if let Some(configs) = value.get("kcl_cli_configs") {
+        let config_keys = known
+            .get("kcl_cli_configs")
+            .map(mapping_keys)
+            .unwrap_or_default();
+        unknown.extend(unknown_keys(configs, &config_keys));
+    }
+    if let Some(options) = value.get("kcl_options").and_then(|v| v.as_sequence()) {
+        for option in options {
+            unknown.extend(unknown_keys(option, &option_keys));
+        }
+    }
+    match unknown.first() {
+        Some(key) => {
+            let line = content
+                .lines()
+                .position(|l| {
+                    l.trim_start()
+                        .trim_start_matches("- ")
+                        .strip_prefix(key.as_str())
+                        .map_or(false, |rest| rest.trim_start().starts_with(':'))
+                })
+                .map_or(1, |l| l + 1);
+            Err(anyhow::anyhow!(
+                "unknown settings key '{}' at {}:{}",
+                key,
+                filename,
+                line
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
+fn mapping_keys(value: &serde_yaml::Value) -> Vec<String> {
+    value
+        .as_mapping()
+        .map(|m| {
+            m.keys()
+                .filter_map(|k| k.as_str().map(|k| k.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn unknown_keys(value: &serde_yaml::Value, known: &[String]) -> Vec<String> {
+    mapping_keys(value)
+        .into_iter()
+        .filter(|k| !known.contains(k))
+        .collect()
+}
+
+/// Substitute the environment variables in the string `s`.
+///
+/// - `${VAR}` is replaced with the value of `VAR` and errors when `VAR` is not set.
+/// - `${VAR:-default}` uses `default` when `VAR` is not set or empty.
+/// - `$$` is an escaped literal `$`.
+/// - KCL module paths such as `${KCL_MOD}` and `${name:KCL_MOD}` are kept untouched.
+pub fn interpolate_env(s: &str) -> Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(idx) = rest.find('$') {
+        result.push_str(&rest[..idx]);
+        let tail = &rest[idx + 1..];
+        if let Some(tail) = tail.strip_prefix('$') {
+            result.push('$');
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix('{') {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("unclosed '${{' in '{}'", s))?;
+            let expr = &tail[..end];
+            if expr == "KCL_MOD" || expr.ends_with(":KCL_MOD") {
+                result.push_str(&rest[idx..idx + end + 3]);
+            } else {
+                let (name, default) = match expr.split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (expr, None),
+                };
+                match (std::env::var(name), default) {
+                    (Ok(value), None) => result.push_str(&value),
+                    (Ok(value), Some(_)) if !value.is_empty() => result.push_str(&value),
+                    (_, Some(default)) => result.push_str(default),
+                    (Err(_), None) => {
+                        return Err(anyhow::anyhow!(
+                            "environment variable '{}' is not set in '{}'",
+                            name,
+                            s
+                        ))
+                    }
+                }
+            }
+            rest = &tail[end + 1..];
+        } else {
+            result.push('$');
+            rest = tail;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
 macro_rules! set_if {
     ($result: expr, $attr: ident, $setting: expr) => {
         if $setting.$attr.is_some() {
@@ -496,6 +685,93 @@ mod settings_test {
         }
     }
 
+    #[test]
+    fn test_settings_load_file_with_env() {
+        std::env::set_var("KCL_SETTINGS_TEST_DIR", "/configs");
+        let settings = load_file("./src/testdata/settings_env.yaml").unwrap();
+        let kcl_cli_configs = settings.kcl_cli_configs.unwrap();
+        assert_eq!(
+            kcl_cli_configs.files,
+            Some(vec![
+                "/configs/main.k".to_string(),
+                "./default/main.k".to_string(),
+                "${KCL_MOD}/sub/sub.k".to_string(),
+            ])
+        );
+        assert_eq!(
+            kcl_cli_configs.output,
+            Some("${KCL_SETTINGS_TEST_DIR}/out.yaml".to_string())
+        );
+        std::env::remove_var("KCL_SETTINGS_TEST_DIR");
+        assert!(load_file("./src/testdata/settings_env.yaml").is_err());
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        assert_eq!(interpolate_env("a$b").unwrap(), "a$b");
+        assert_eq!(interpolate_env("$$").unwrap(), "$");
+        assert_eq!(
+            interpolate_env("${KCL_INTERPOLATE_TEST_UNSET:-}x").unwrap(),
+            "x"
+        );
+        assert_eq!(
+            interpolate_env("${pkg:KCL_MOD}/main.k").unwrap(),
+            "${pkg:KCL_MOD}/main.k"
+        );
+        assert!(interpolate_env("${KCL_INTERPOLATE_TEST_UNSET}").is_err());
+        assert!(interpolate_env("${HOME").is_err());
+    }
+
+    #[test]
+    fn test_check_unknown_settings_keys() {
+        assert!(check_unknown_settings_keys(SETTINGS_FILE).is_ok());
+        let err = check_unknown_settings_keys("./src/testdata/settings_unknown_key.yaml")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "unknown settings key 'disable_nonee' at ./src/testdata/settings_unknown_key.yaml:4"
+        );
+        // The unknown key is ignored in the default lenient mode.
+        assert!(load_file("./src/testdata/settings_unknown_key.yaml").is_ok());
+    }
+
+    #[test]
+    fn test_settings_json_schema() {
+        let schema = settings_json_schema();
+        assert_eq!(schema["title"], "KCL settings");
+        let properties = schema["properties"].as_object().unwrap();
+        assert_eq!(properties.len(), 2);
+        let configs = &properties["kcl_cli_configs"];
+        assert_eq!(configs["description"], "The command line configs.");
+        let config_properties = configs["properties"].as_object().unwrap();
+        assert_eq!(
+            config_properties["files"]["type"],
+            serde_json::json!(["array", "null"])
+        );
+        assert_eq!(
+            config_properties["fast_eval"]["type"],
+            serde_json::json!(["boolean", "null"])
+        );
+        assert_eq!(configs["additionalProperties"], false);
+        // Every settings key is in the schema with a description.
+        let known = serde_json::to_value(SettingsFile::new()).unwrap();
+        for key in known["kcl_cli_configs"].as_object().unwrap().keys() {
+            let description = config_properties[key]["description"]
+                .as_str()
+                .unwrap_or_default();
+            assert!(!description.is_empty(), "{key} has no description");
+        }
+        assert_eq!(
+            config_properties.len(),
+            known["kcl_cli_configs"].as_object().unwrap().len()
+        );
+        let option = &properties["kcl_options"]["items"];
+        assert_eq!(option["properties"]["key"]["type"], "string");
+        assert_eq!(option["properties"].as_object().unwrap().len(), 2);
+        assert_eq!(option["required"], serde_json::json!(["key", "value"]));
+    }
+
     #[test]
     fn test_merge_settings() -> anyhow::Result<()> {
         let settings1 = load_file(SETTINGS_FILE)?;
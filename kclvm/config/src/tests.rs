@@ -72,3 +72,23 @@ fn test_pkg_cache() {
         Some("test_data".to_string())
     )
 }
+
+#[test]
+fn test_kcl_ignore() {
+    use crate::ignore::KclIgnore;
+    let root = PathBuf::from("./src/testdata");
+    let ignore = KclIgnore::from_content(
+        &root,
+        "# comment\n\n**/test_*.k\n!test_vendor/test_keep.k\n/settings.yaml\nvendor/\n",
+    )
+    .unwrap();
+    let root = root.canonicalize().unwrap();
+    assert!(ignore.is_ignored(root.join("test_a.k")));
+    assert!(ignore.is_ignored(root.join("a/b/test_a.k")));
+    assert!(!ignore.is_ignored(root.join("test_vendor/test_keep.k")));
+    assert!(!ignore.is_ignored(root.join("main.k")));
+    assert!(ignore.is_ignored(root.join("settings.yaml")));
+    assert!(!ignore.is_ignored(root.join("sub/settings.yaml")));
+    assert!(ignore.is_ignored(root.join("a/vendor/main.k")));
+    assert!(!ignore.is_ignored(PathBuf::from("/outside/test_a.k")));
+}
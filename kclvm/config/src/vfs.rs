@@ -27,6 +27,15 @@ pub fn is_rel_pkgpath(pkgpath: &str) -> bool {
     pkgpath.starts_with('.')
 }
 
+/// Split the import path with the root alias, e.g., `@root/path.to.sub`, into the
+/// alias `@root` and the package path `path.to.sub` from the module root.
+pub fn split_root_alias(import_path: &str) -> Option<(&str, &str)> {
+    if !import_path.starts_with('@') {
+        return None;
+    }
+    import_path.split_once('/')
+}
+
 pub fn fix_import_path(root: &str, filepath: &str, import_path: &str) -> String {
     // relpath: import .sub
     // FixImportPath(root, "path/to/app/file.k", ".sub")        => path.to.app.sub
@@ -94,6 +103,17 @@ pub fn fix_import_path(root: &str, filepath: &str, import_path: &str) -> String
     "".to_string()
 }
 
+#[test]
+fn test_split_root_alias() {
+    assert_eq!(
+        split_root_alias("@root/path.to.sub"),
+        Some(("@root", "path.to.sub"))
+    );
+    assert_eq!(split_root_alias("@mono/sub"), Some(("@mono", "sub")));
+    assert_eq!(split_root_alias("path.to.sub"), None);
+    assert_eq!(split_root_alias(".sub"), None);
+}
+
 #[test]
 fn test_fix_import_path() {
     #[cfg(not(target_os = "windows"))]
@@ -1,6 +1,7 @@
 use crate::{kcl, lookup_the_nearest_file_dir};
 use anyhow::{bail, Result};
-use kclvm_config::modfile::KCL_MOD_FILE;
+use kclvm_config::modfile::{load_mod_file, KCL_MOD_FILE};
+use kclvm_config::offline::{ensure_online, is_offline};
 use kclvm_parser::LoadProgramOptions;
 use notify::{RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
@@ -9,7 +10,7 @@ use std::{
     collections::HashMap,
     fs::File,
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
     sync::{mpsc::channel, Arc, Mutex},
 };
@@ -31,6 +32,7 @@ pub(crate) fn fill_pkg_maps_for_k_file(
 ) -> Result<()> {
     match lookup_the_nearest_file_dir(k_file_path, KCL_MOD_FILE) {
         Some(mod_dir) => {
+            check_offline_dependencies(&mod_dir, opts)?;
             let metadata = fetch_metadata(mod_dir.canonicalize()?)?;
             let maps: HashMap<String, String> = metadata
                 .packages
@@ -45,6 +47,51 @@ pub(crate) fn fill_pkg_maps_for_k_file(
     Ok(())
 }
 
+/// Searches for the nearest kcl.mod directory containing the given file and returns an
+/// error naming the dependencies which would be fetched from the network in the offline
+/// mode. The dependencies mapped in the package maps or found in the vendor dirs are local.
+///
+/// # Arguments
+///
+/// * `k_file_path` - Path to the K file whose module dependencies are checked.
+/// * `opts` - The compilation options holding the offline mode and the local packages.
+///
+/// # Returns
+///
+/// * `Result<()>` - Empty result if no dependency would be fetched, error otherwise.
+pub fn check_offline_dependencies(k_file_path: &Path, opts: &LoadProgramOptions) -> Result<()> {
+    if !opts.offline {
+        return Ok(());
+    }
+    let mod_dir = match lookup_the_nearest_file_dir(k_file_path.to_path_buf(), KCL_MOD_FILE) {
+        Some(mod_dir) => mod_dir,
+        None => return Ok(()),
+    };
+    let deps: Vec<String> = load_mod_file(&mod_dir.to_string_lossy())
+        .remote_dependencies()
+        .into_iter()
+        .filter(|(name, _)| {
+            !opts.package_maps.contains_key(name)
+                && !opts
+                    .vendor_dirs
+                    .iter()
+                    .any(|dir| Path::new(dir).join(name).is_dir())
+        })
+        .map(|(name, source)| format!("{} ({})", name, source))
+        .collect();
+    if deps.is_empty() {
+        return Ok(());
+    }
+    ensure_online(
+        true,
+        &format!(
+            "the dependencies {} of the module {}",
+            deps.join(", "),
+            mod_dir.display()
+        ),
+    )
+}
+
 /// Trait for writing messages to a file.
 pub trait Writer {
     fn write_message(&mut self, message: &str) -> Result<()>;
@@ -255,6 +302,10 @@ pub fn fetch_metadata(manifest_path: PathBuf) -> Result<Metadata> {
 pub fn update_dependencies(work_dir: PathBuf) -> Result<()> {
     match lookup_the_nearest_file_dir(work_dir.clone(), KCL_MOD_FILE) {
         Some(mod_dir) => {
+            ensure_online(
+                is_offline(),
+                &format!("the dependencies of the module {}", mod_dir.display()),
+            )?;
             match Command::new(kcl())
                 .arg("mod")
                 .arg("update")
@@ -8,6 +8,7 @@ mod tests;
 
 use glob::glob;
 use kclvm_config::{
+    ignore::KclIgnore,
     modfile::{get_pkg_root, KCL_FILE_EXTENSION, KCL_FILE_SUFFIX, KCL_MOD_PATH_ENV},
     path::ModRelativePath,
     settings::{build_settings_pathbuf, DEFAULT_SETTING_FILE},
@@ -262,6 +263,7 @@ pub fn lookup_compile_unit_path(file: &str) -> io::Result<PathBuf> {
 
 /// Get kcl files from path.
 pub fn get_kcl_files<P: AsRef<Path>>(path: P, recursively: bool) -> Result<Vec<String>> {
+    let ignore = KclIgnore::load_from_path(&path.as_ref().to_string_lossy())?;
     let mut files = vec![];
     let walkdir = if recursively {
         WalkDir::new(path)
@@ -272,7 +274,7 @@ pub fn get_kcl_files<P: AsRef<Path>>(path: P, recursively: bool) -> Result<Vec<S
         let path = entry.path();
         if path.is_file() {
             let file = path.to_str().unwrap();
-            if file.ends_with(KCL_FILE_SUFFIX) {
+            if file.ends_with(KCL_FILE_SUFFIX) && !ignore.is_ignored(path) {
                 files.push(file.to_string())
             }
         }
@@ -125,6 +125,42 @@ impl Diagnostic {
     pub fn is_error(&self) -> bool {
         matches!(self.level, Level::Error)
     }
+
+    /// Returns the key which orders the diagnostics by the file, line, column and
+    /// code of the first message.
+    pub fn sort_key(&self) -> (String, u64, u64, String) {
+        let (filename, line, column) = match self.messages.first() {
+            Some(msg) => (
+                msg.range.0.filename.clone(),
+                msg.range.0.line,
+                msg.range.0.column.unwrap_or_default(),
+            ),
+            None => Default::default(),
+        };
+        let code = match &self.code {
+            Some(DiagnosticId::Error(kind)) => kind.code(),
+            Some(DiagnosticId::Warning(kind)) => kind.code(),
+            Some(DiagnosticId::Suggestions) | None => String::new(),
+        };
+        (filename, line, column, code)
+    }
+}
+
+/// Sort the diagnostics by the file, line, column and code for the stable emission
+/// order, because the packages may be checked in any order. The suggestions have no
+/// position and are kept after the diagnostic they follow.
+pub fn sort_diagnostics<'a>(
+    diagnostics: impl IntoIterator<Item = &'a Diagnostic>,
+) -> Vec<&'a Diagnostic> {
+    let mut groups: Vec<Vec<&Diagnostic>> = vec![];
+    for diag in diagnostics {
+        match groups.last_mut() {
+            Some(group) if diag.level == Level::Suggestions => group.push(diag),
+            _ => groups.push(vec![diag]),
+        }
+    }
+    groups.sort_by_cached_key(|group| group[0].sort_key());
+    groups.into_iter().flatten().collect()
 }
 
 pub type Range = (Position, Position);
@@ -51,13 +51,30 @@ register_errors! {
     E2L28: ErrorKind::UniqueKeyError, include_str!("./error_codes/E2L28.md"),
     E2D34: ErrorKind::IllegalInheritError, include_str!("./error_codes/E2D34.md"),
     // E3XXX Runtime Errors
-    E3M38: ErrorKind::EvaluationError, include_str!("./error_codes/E2D34.md"),
+    E3M38: ErrorKind::EvaluationError, include_str!("./error_codes/E3M38.md"),
 }
 
 // Error messages for WXXXX errors. Each message should start and end with a
 // new line.
 register_warnings! {
     W1001: WarningKind::CompilerWarning, include_str!("./warning_codes/W1001.md"),
+    W1002: WarningKind::DeprecatedWarning, include_str!("./warning_codes/W1002.md"),
+}
+
+/// Returns the long description with examples of the error or warning code,
+/// e.g., `E2G22`. The code is case-insensitive.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.trim();
+    ERRORS
+        .iter()
+        .find(|(ecode, _)| ecode.eq_ignore_ascii_case(code))
+        .and_then(|(_, error)| error.message)
+        .or_else(|| {
+            WARNINGS
+                .iter()
+                .find(|(wcode, _)| wcode.eq_ignore_ascii_case(code))
+                .and_then(|(_, warning)| warning.message)
+        })
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -144,6 +161,7 @@ pub enum WarningKind {
     UnusedImportWarning,
     ReimportWarning,
     ImportPositionWarning,
+    DeprecatedWarning,
 }
 
 /// Test warning `fmt`
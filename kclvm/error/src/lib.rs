@@ -5,6 +5,7 @@
 
 pub mod diagnostic;
 mod error;
+mod render;
 
 use annotate_snippets::{
     display_list::DisplayList,
@@ -22,11 +23,12 @@ use compiler_base_session::{Session, SessionDiagnostic};
 use compiler_base_span::{span::new_byte_pos, Span};
 use diagnostic::Range;
 use indexmap::IndexSet;
-use kclvm_runtime::PanicInfo;
+use kclvm_runtime::{CheckFailure, PanicInfo};
 use std::{any::Any, sync::Arc};
 
-pub use diagnostic::{Diagnostic, DiagnosticId, Level, Message, Position, Style};
+pub use diagnostic::{sort_diagnostics, Diagnostic, DiagnosticId, Level, Message, Position, Style};
 pub use error::*;
+pub use render::{render_rich_diagnostic, render_rich_diagnostics, DiagnosticFormat};
 
 /// A handler deals with errors and other compiler output.
 /// Certain errors (error, bug) may cause immediate exit,
@@ -60,7 +62,7 @@ impl Handler {
     /// Emit all diagnostics and return whether has errors.
     pub fn emit(&mut self) -> Result<bool> {
         let sess = Session::default();
-        for diag in &self.diagnostics {
+        for diag in sort_diagnostics(&self.diagnostics) {
             sess.add_err(diag.clone())?;
         }
         sess.emit_stashed_diagnostics()?;
@@ -70,7 +72,7 @@ impl Handler {
     /// Emit diagnostic to string.
     pub fn emit_to_string(&mut self) -> Result<String> {
         let sess = Session::default();
-        for diag in &self.diagnostics {
+        for diag in sort_diagnostics(&self.diagnostics) {
             sess.add_err(diag.clone())?;
         }
         let errors = sess.emit_all_diags_into_string()?;
@@ -255,8 +257,38 @@ impl Handler {
     }
 }
 
+impl From<CheckFailure> for Diagnostic {
+    fn from(failure: CheckFailure) -> Self {
+        let pos = Position {
+            filename: failure.filename,
+            line: failure.line as u64,
+            column: Some(failure.col as u64),
+        };
+        let message = match (failure.schema.is_empty(), failure.message.is_empty()) {
+            (true, true) => "Assertion failed".to_string(),
+            (true, false) => failure.message,
+            (false, true) => "Check failed on the condition".to_string(),
+            (false, false) => format!("Check failed on the condition: {}", failure.message),
+        };
+        Diagnostic::new_with_code(Level::Error, &message, None, (pos.clone(), pos), None, None)
+    }
+}
+
 impl From<PanicInfo> for Diagnostic {
     fn from(panic_info: PanicInfo) -> Self {
+        // The collected check failures are reported together.
+        if !panic_info.check_failures.is_empty() {
+            let mut diag = Diagnostic {
+                level: Level::Error,
+                messages: vec![],
+                code: None,
+            };
+            for failure in panic_info.check_failures {
+                diag.messages
+                    .append(&mut Diagnostic::from(failure).messages);
+            }
+            return diag;
+        }
         let panic_msg = if panic_info.kcl_arg_msg.is_empty() {
             &panic_info.message
         } else {
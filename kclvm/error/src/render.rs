@@ -0,0 +1,134 @@
+//! Render diagnostics as plain text with the offending source lines and carets
+//! under the spans, e.g.,
+//!
+//! ```text
+//! error[E2L23]: CompileError
+//!  --> main.k:1:5
+//!   |
+//! 1 | a = b
+//!   |     ^ name 'b' is not defined
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::{Diagnostic, DiagnosticId, Level, Message};
+
+/// The output format of the diagnostics.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticFormat {
+    /// The default format which is used by the golden tests.
+    #[default]
+    Plain,
+    /// The format with the source snippets and the carets under the whole spans.
+    Rich,
+}
+
+impl TryFrom<&str> for DiagnosticFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value.to_lowercase().as_str() {
+            "plain" => Ok(DiagnosticFormat::Plain),
+            "rich" => Ok(DiagnosticFormat::Rich),
+            _ => anyhow::bail!(
+                "unsupported diagnostic format '{}', expected plain or rich",
+                value
+            ),
+        }
+    }
+}
+
+/// Render the diagnostics in the rich format, the source files are read from the
+/// file system and the message is output without the snippet when the source
+/// line is not found.
+pub fn render_rich_diagnostics<'a, I: IntoIterator<Item = &'a Diagnostic>>(diags: I) -> String {
+    diags
+        .into_iter()
+        .map(render_rich_diagnostic)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Render one diagnostic in the rich format.
+pub fn render_rich_diagnostic(diag: &Diagnostic) -> String {
+    let mut out = match &diag.code {
+        Some(DiagnosticId::Error(kind)) => format!("error[{}]: {}\n", kind.code(), kind.name()),
+        Some(DiagnosticId::Warning(kind)) => {
+            format!("warning[{}]: {}\n", kind.code(), kind.name())
+        }
+        Some(DiagnosticId::Suggestions) => "suggestion\n".to_string(),
+        None => match diag.level {
+            Level::Error => "error\n",
+            Level::Warning => "warning\n",
+            Level::Note => "note\n",
+            Level::Suggestions => "suggestion\n",
+        }
+        .to_string(),
+    };
+    for msg in &diag.messages {
+        render_message(&mut out, msg);
+    }
+    out
+}
+
+fn render_message(out: &mut String, msg: &Message) {
+    let (start, end) = &msg.range;
+    let _ = writeln!(
+        out,
+        " --> {}:{}:{}",
+        start.filename,
+        start.line,
+        start.column.unwrap_or(0) + 1
+    );
+    let source = std::fs::read_to_string(&start.filename).unwrap_or_default();
+    let lines: Vec<&str> = source.lines().collect();
+    let start_line = start.line as usize;
+    // Multi-line spans are only rendered when the end is after the start in the same file.
+    let end_line = if end.filename == start.filename && end.line >= start.line {
+        end.line as usize
+    } else {
+        start_line
+    };
+    if start_line == 0 || start_line > lines.len() {
+        let _ = writeln!(out, "   = {}", msg.message);
+    } else {
+        let end_line = end_line.min(lines.len());
+        let width = end_line.to_string().len();
+        let _ = writeln!(out, "{:width$} |", "");
+        for line in start_line..=end_line {
+            let content = lines[line - 1].trim_end_matches('\r');
+            let length = content.chars().count();
+            let mut start_col = if line == start_line {
+                start.column.unwrap_or(0) as usize
+            } else {
+                content.chars().take_while(|c| c.is_whitespace()).count()
+            };
+            let mut end_col = match end.column {
+                Some(column) if line == end_line && end_line == end.line as usize => {
+                    column as usize
+                }
+                _ if line == end_line && start_line == end_line => start_col + 1,
+                _ => length,
+            };
+            // Put the caret at the end of the line when the span exceeds the content.
+            start_col = start_col.min(length.saturating_sub(1));
+            end_col = end_col.min(length).max(start_col + 1);
+            let _ = writeln!(out, "{:>width$} | {}", line, content);
+            let _ = write!(
+                out,
+                "{:width$} | {}{}",
+                "",
+                " ".repeat(start_col),
+                "^".repeat(end_col - start_col)
+            );
+            if line == end_line {
+                let _ = write!(out, " {}", msg.message);
+            }
+            out.push('\n');
+        }
+    }
+    if let Some(note) = &msg.note {
+        let _ = writeln!(out, "   = note: {}", note);
+    }
+}
@@ -1,7 +1,9 @@
 /* Calculation methods */
 
 use kclvm_ast::ast;
-use kclvm_runtime::{ConfigEntryOperationKind, DictValue, UnionOptions, Value, ValueRef};
+use kclvm_runtime::{
+    ConfigEntryLocation, ConfigEntryOperationKind, DictValue, UnionOptions, Value, ValueRef,
+};
 
 use crate::ty::{resolve_schema, type_pack_and_check};
 use crate::union::union_entry;
@@ -186,11 +188,18 @@ impl<'ctx> Evaluator<'ctx> {
     /// Append a item into the list.
     #[inline]
     pub(crate) fn list_append(&self, list: &mut ValueRef, item: &ValueRef) {
+        self.alloc_value(item, 1);
         list.list_append(item)
     }
     /// Append a list item and unpack it into the list.
     #[inline]
     pub(crate) fn list_append_unpack(&self, list: &mut ValueRef, item: &ValueRef) {
+        let count = if item.is_list_or_config() {
+            item.len()
+        } else {
+            1
+        };
+        self.alloc_value(item, count);
         list.list_append_unpack(item)
     }
     #[inline]
@@ -216,6 +225,42 @@ impl<'ctx> Evaluator<'ctx> {
         self.dict_merge_key_value_pair(dict, key, value, op, insert_index, false);
     }
 
+    /// Insert a config entry into the dict with the location of the entry, which is
+    /// used to find the keys set by different entries under the duplicate key policy.
+    #[inline]
+    pub(crate) fn dict_insert_with_location(
+        &self,
+        dict: &mut ValueRef,
+        key: &str,
+        value: &ValueRef,
+        op: &ast::ConfigEntryOperation,
+        insert_index: i32,
+        location: ConfigEntryLocation,
+    ) {
+        let op = match op {
+            ast::ConfigEntryOperation::Union => ConfigEntryOperationKind::Union,
+            ast::ConfigEntryOperation::Override => ConfigEntryOperationKind::Override,
+            ast::ConfigEntryOperation::Insert => ConfigEntryOperationKind::Insert,
+        };
+        self.dict_merge_entry(dict, key, value, op, insert_index, false, Some(location));
+    }
+
+    /// Deep-merge the dict `value` into a copy of the dict `base`, the nested dicts are
+    /// merged and the other values of `value` override the ones of `base`.
+    pub(crate) fn dict_deep_merge(&self, base: &ValueRef, value: &ValueRef) -> ValueRef {
+        let mut merged = base.deep_copy();
+        for (key, item) in value.as_dict_ref().values.iter() {
+            let item = match merged.dict_get_value(key) {
+                Some(former) if former.is_dict() && item.is_dict() => {
+                    self.dict_deep_merge(&former, item)
+                }
+                _ => item.clone(),
+            };
+            merged.dict_update_key_value(key, item);
+        }
+        merged
+    }
+
     /// Insert a dict entry including key, value, op and insert_index into the dict,
     /// and the type of key is `&str`
     #[inline]
@@ -250,6 +295,7 @@ impl<'ctx> Evaluator<'ctx> {
     /// Insert an entry including key and value into the dict.
     #[inline]
     pub(crate) fn dict_insert_value(&self, dict: &mut ValueRef, key: &str, value: &ValueRef) {
+        self.alloc_dict_entry(key, value);
         dict.dict_update_key_value(key, value.clone())
     }
 
@@ -290,6 +336,7 @@ impl<'ctx> Evaluator<'ctx> {
     }
 
     /// Private dict merge key value pair with the idempotent check option
+    #[inline]
     pub(crate) fn dict_merge_key_value_pair(
         &self,
         p: &mut ValueRef,
@@ -298,12 +345,32 @@ impl<'ctx> Evaluator<'ctx> {
         op: ConfigEntryOperationKind,
         insert_index: i32,
         idempotent_check: bool,
+    ) {
+        self.dict_merge_entry(p, key, v, op, insert_index, idempotent_check, None);
+    }
+
+    /// Merge the dict entry with the idempotent check option and the optional
+    /// location of the config entry.
+    #[allow(clippy::too_many_arguments)]
+    fn dict_merge_entry(
+        &self,
+        p: &mut ValueRef,
+        key: &str,
+        v: &ValueRef,
+        op: ConfigEntryOperationKind,
+        insert_index: i32,
+        idempotent_check: bool,
+        location: Option<ConfigEntryLocation>,
     ) {
         if p.is_config() {
+            self.alloc_dict_entry(key, v);
             let mut dict: DictValue = Default::default();
             dict.values.insert(key.to_string(), v.clone());
             dict.ops.insert(key.to_string(), op);
             dict.insert_indexs.insert(key.to_string(), insert_index);
+            if let Some(location) = location {
+                dict.key_locations.insert(key.to_string(), location);
+            }
             union_entry(
                 self,
                 p,
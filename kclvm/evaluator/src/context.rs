@@ -1,8 +1,10 @@
 use std::rc::Rc;
 
 use generational_arena::Index;
-use kclvm_ast::ast;
-use kclvm_runtime::{BacktraceFrame, MAIN_PKG_PATH};
+use kclvm_ast::{ast, walker::MutSelfWalker};
+use kclvm_runtime::{
+    BacktraceFrame, CheckFailure, DuplicateKeyPolicy, Value, ValueRef, MAIN_PKG_PATH,
+};
 
 use crate::{
     error as kcl_error,
@@ -11,7 +13,7 @@ use crate::{
     proxy::{Frame, Proxy},
     rule::RuleCaller,
     schema::SchemaCaller,
-    EvalContext, Evaluator, GLOBAL_LEVEL,
+    EvalContext, Evaluator, DEFAULT_MAX_EVAL_DEPTH, GLOBAL_LEVEL,
 };
 
 impl<'ctx> Evaluator<'ctx> {
@@ -197,8 +199,30 @@ impl<'ctx> Evaluator<'ctx> {
         }))
     }
 
+    /// Push the frame into the backtrace and check the max call depth, which
+    /// reports an error instead of overflowing the stack for unbounded recursions.
     pub(crate) fn push_backtrace(&self, frame: &Frame) {
+        let max_depth = match self.runtime_ctx.borrow().cfg.max_eval_depth {
+            0 => DEFAULT_MAX_EVAL_DEPTH,
+            depth => depth,
+        };
+        let depth = {
+            let mut call_depth = self.call_depth.borrow_mut();
+            *call_depth += 1;
+            *call_depth
+        };
+        if depth > max_depth {
+            panic!(
+                "the max evaluation depth {} is exceeded when calling '{}', consider checking for unbounded recursions",
+                max_depth,
+                frame.proxy.get_name()
+            );
+        }
         let ctx = &mut self.runtime_ctx.borrow_mut();
+        if self.collect_metrics {
+            let metrics = &mut ctx.eval_metrics;
+            metrics.max_call_depth = metrics.max_call_depth.max(depth);
+        }
         if ctx.cfg.debug_mode {
             let backtrace_frame = BacktraceFrame::from_panic_info(&ctx.panic_info);
             ctx.backtrace.push(backtrace_frame);
@@ -207,6 +231,10 @@ impl<'ctx> Evaluator<'ctx> {
     }
 
     pub(crate) fn pop_backtrace(&self) {
+        {
+            let mut call_depth = self.call_depth.borrow_mut();
+            *call_depth = call_depth.saturating_sub(1);
+        }
         let ctx = &mut self.runtime_ctx.borrow_mut();
         if ctx.cfg.debug_mode {
             if let Some(backtrace_frame) = ctx.backtrace.pop() {
@@ -217,6 +245,117 @@ impl<'ctx> Evaluator<'ctx> {
         }
     }
 
+    /// Abort the evaluation at the statement or expression when it is cancelled.
+    #[inline]
+    pub(crate) fn check_cancelled(&self) {
+        if self.cancel_flag.is_cancelled() {
+            panic!("the evaluation is cancelled");
+        }
+    }
+
+    /// Count an evaluated statement or expression when the metrics are collected.
+    #[inline]
+    pub(crate) fn count_eval_step(&self) {
+        if self.collect_metrics {
+            self.runtime_ctx.borrow_mut().eval_metrics.eval_steps += 1;
+        }
+    }
+
+    /// Count a created schema instance when the metrics are collected.
+    #[inline]
+    pub(crate) fn count_schema_instance(&self) {
+        if self.collect_metrics {
+            self.runtime_ctx.borrow_mut().eval_metrics.schema_instances += 1;
+        }
+    }
+
+    /// Returns the key field of the lists at the union path, which are unioned
+    /// element by element instead of by position.
+    #[inline]
+    pub(crate) fn merge_key(&self, path: &[String]) -> Option<String> {
+        self.runtime_ctx.borrow().cfg.merge_key(path)
+    }
+
+    /// Returns the policy of the keys set by different config entries.
+    #[inline]
+    pub(crate) fn duplicate_key_policy(&self) -> DuplicateKeyPolicy {
+        self.runtime_ctx.borrow().cfg.duplicate_key_policy
+    }
+
+    /// Account the approximate memory of `count` values like `value` stored into
+    /// lists, dicts and schemas.
+    #[inline]
+    pub(crate) fn alloc_value(&self, value: &ValueRef, count: usize) {
+        self.alloc_bytes(|| value_bytes(value).saturating_mul(count));
+    }
+
+    /// Account the approximate memory of a dict entry.
+    #[inline]
+    pub(crate) fn alloc_dict_entry(&self, key: &str, value: &ValueRef) {
+        self.alloc_bytes(|| std::mem::size_of::<String>() + key.len() + value_bytes(value));
+    }
+
+    /// Add the allocated bytes and report an error when the max evaluation memory is
+    /// exceeded. Note this is a best-effort accounting instead of an allocator cap.
+    fn alloc_bytes<F: FnOnce() -> usize>(&self, bytes: F) {
+        let max_bytes = self.runtime_ctx.borrow().cfg.max_eval_memory_bytes;
+        if max_bytes == 0 {
+            return;
+        }
+        let allocated_bytes = {
+            let mut allocated_bytes = self.allocated_bytes.borrow_mut();
+            *allocated_bytes = allocated_bytes.saturating_add(bytes());
+            *allocated_bytes
+        };
+        if allocated_bytes > max_bytes {
+            panic!(
+                "the max evaluation memory {} bytes is exceeded, consider checking for huge data such as large list comprehensions",
+                max_bytes
+            );
+        }
+    }
+
+    /// Record the failed check or assert when the check failures are collected,
+    /// and returns whether the failure is recorded instead of raised.
+    pub(crate) fn collect_check_failure(&self, test: &ast::NodeRef<ast::Expr>, msg: &str) -> bool {
+        if !self.runtime_ctx.borrow().cfg.collect_check_failures {
+            return false;
+        }
+        let (schema, value) = match (self.get_schema_eval_context(), self.get_rule_eval_context()) {
+            (Some(ctx), _) => (
+                ctx.borrow().node.name.node.clone(),
+                ctx.borrow().value.clone(),
+            ),
+            (None, Some(ctx)) => (
+                ctx.borrow().node.name.node.clone(),
+                ctx.borrow().value.clone(),
+            ),
+            (None, None) => (String::new(), self.undefined_value()),
+        };
+        let mut names = IdentifierNames::default();
+        names.walk_expr(&test.node);
+        let attribute = names
+            .0
+            .iter()
+            .find(|name| value.dict_get_value(name).is_some())
+            .or(names.0.first())
+            .cloned()
+            .unwrap_or_default();
+        self.runtime_ctx
+            .borrow_mut()
+            .panic_info
+            .check_failures
+            .push(CheckFailure {
+                schema,
+                attribute,
+                message: msg.to_string(),
+                filename: test.filename.clone(),
+                line: test.line as i32,
+                col: test.column as i32,
+            });
+        true
+    }
+
     pub(crate) fn push_backtrack_meta(&self, setter: &Setter) {
         let meta = &mut self.backtrack_meta.borrow_mut();
         meta.push(BacktrackMeta {
@@ -230,3 +369,25 @@ impl<'ctx> Evaluator<'ctx> {
         meta.pop();
     }
 }
+
+/// The first names of the identifiers in an expression in the source order.
+#[derive(Default)]
+struct IdentifierNames(Vec<String>);
+
+impl MutSelfWalker for IdentifierNames {
+    fn walk_identifier(&mut self, identifier: &ast::Identifier) {
+        if let Some(name) = identifier.names.first() {
+            self.0.push(name.node.clone());
+        }
+    }
+}
+
+/// The approximate memory bytes of a value without its nested items.
+fn value_bytes(value: &ValueRef) -> usize {
+    std::mem::size_of::<ValueRef>()
+        + std::mem::size_of::<Value>()
+        + match &*value.rc.borrow() {
+            Value::str_value(s) => s.len(),
+            _ => 0,
+        }
+}
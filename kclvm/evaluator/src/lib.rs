@@ -35,9 +35,9 @@ use std::str;
 use std::{cell::RefCell, panic::UnwindSafe};
 
 use crate::error as kcl_error;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use kclvm_ast::ast;
-use kclvm_runtime::{Context, ValueRef};
+use kclvm_runtime::{CancelFlag, Context, RuntimeErrorType, ValueRef};
 
 /// SCALAR_KEY denotes the temp scalar key for the global variable json plan process.
 const SCALAR_KEY: &str = "";
@@ -45,6 +45,9 @@ const SCALAR_KEY: &str = "";
 const GLOBAL_LEVEL: usize = 1;
 /// Inner level
 const INNER_LEVEL: usize = 2;
+/// The default max depth of function, schema and rule calls, which needs a large
+/// stack of the evaluating thread, e.g., the 256 MiB stack of the CLI.
+pub const DEFAULT_MAX_EVAL_DEPTH: usize = 1000;
 
 /// The evaluator function result
 pub type EvalResult = Result<ValueRef>;
@@ -82,6 +85,16 @@ pub struct Evaluator<'ctx> {
     pub local_vars: RefCell<HashSet<String>>,
     /// Schema attr backtrack meta
     pub backtrack_meta: RefCell<Vec<BacktrackMeta>>,
+    /// The current depth of function, schema and rule calls.
+    pub call_depth: RefCell<usize>,
+    /// The approximate memory bytes of values stored into lists, dicts and schemas.
+    pub allocated_bytes: RefCell<usize>,
+    /// Whether to gather the evaluation metrics, which is read from the runtime
+    /// context config once to keep the counters cheap when it is disabled.
+    pub collect_metrics: bool,
+    /// The flag which cancels the evaluation, which is read from the runtime
+    /// context config once and checked at every statement and expression.
+    pub cancel_flag: CancelFlag,
 }
 
 pub enum EvalContext {
@@ -102,6 +115,8 @@ impl<'ctx> Evaluator<'ctx> {
         program: &'ctx ast::Program,
         runtime_ctx: Rc<RefCell<Context>>,
     ) -> Evaluator<'ctx> {
+        let collect_metrics = runtime_ctx.borrow().cfg.collect_metrics;
+        let cancel_flag = runtime_ctx.borrow().cfg.cancel_flag.clone();
         Evaluator {
             runtime_ctx,
             program,
@@ -119,6 +134,10 @@ impl<'ctx> Evaluator<'ctx> {
             lazy_scopes: RefCell::new(Default::default()),
             local_vars: RefCell::new(Default::default()),
             backtrack_meta: RefCell::new(Default::default()),
+            call_depth: RefCell::new(0),
+            allocated_bytes: RefCell::new(0),
+            collect_metrics,
+            cancel_flag,
         }
     }
 
@@ -128,9 +147,39 @@ impl<'ctx> Evaluator<'ctx> {
             self.init_scope(kclvm_ast::MAIN_PKG);
             self.compile_ast_modules(modules)
         }
+        self.check_failures_result()?;
         Ok(self.plan_globals_to_string())
     }
 
+    /// Returns the error holding all the collected check failures, the location
+    /// and the message of the error are the ones of the first failure.
+    /// The failures are left in the panic info and the evaluation goes on when
+    /// continuing on errors.
+    fn check_failures_result(&self) -> Result<()> {
+        let mut ctx = self.runtime_ctx.borrow_mut();
+        if ctx.cfg.continue_on_error {
+            return Ok(());
+        }
+        let first = match ctx.panic_info.check_failures.first() {
+            Some(first) => first.clone(),
+            None => return Ok(()),
+        };
+        ctx.set_err_type(&if first.schema.is_empty() {
+            RuntimeErrorType::AssertionError
+        } else {
+            RuntimeErrorType::SchemaCheckFailure
+        });
+        let arg_msg = format!("{} check(s) failed", ctx.panic_info.check_failures.len());
+        ctx.set_kcl_location_info(
+            Some(arg_msg.as_str()),
+            Some(first.filename.as_str()),
+            Some(first.line),
+            Some(first.col),
+        );
+        ctx.panic_info.message = first.message;
+        bail!("{}", ctx.panic_info.to_json_string())
+    }
+
     /// Plan globals to a planed json and yaml string.
     pub fn plan_globals_to_string(&self) -> (String, String) {
         let current_pkgpath = self.current_pkgpath();
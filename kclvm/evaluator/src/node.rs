@@ -11,8 +11,8 @@ use kclvm_ast::walker::TypedResultWalker;
 use kclvm_runtime::val_func::invoke_function;
 use kclvm_runtime::walker::walk_value_mut;
 use kclvm_runtime::{
-    schema_assert, schema_runtime_type, ConfigEntryOperationKind, DecoratorValue, RuntimeErrorType,
-    UnionOptions, ValueRef, PKG_PATH_PREFIX,
+    schema_assert, schema_runtime_type, ConfigEntryOperationKind, DecoratorValue,
+    DuplicateKeyPolicy, RuntimeErrorType, UnionOptions, ValueRef, PKG_PATH_PREFIX,
 };
 use kclvm_sema::{builtin, plugin};
 
@@ -39,6 +39,8 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
     fn walk_stmt(&self, stmt: &'ctx ast::Node<ast::Stmt>) -> Self::Result {
         backtrack_break_here!(self, stmt);
         self.update_ctx_panic_info(stmt);
+        self.check_cancelled();
+        self.count_eval_step();
         let value = match &stmt.node {
             ast::Stmt::TypeAlias(type_alias) => self.walk_type_alias_stmt(type_alias),
             ast::Stmt::Expr(expr_stmt) => self.walk_expr_stmt(expr_stmt),
@@ -224,6 +226,9 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
                 }
             };
             if !assert_result.is_truthy() {
+                if self.collect_check_failure(&assert_stmt.test, &msg.as_str()) {
+                    return;
+                }
                 let mut ctx = self.runtime_ctx.borrow_mut();
                 ctx.set_err_type(&RuntimeErrorType::AssertionError);
                 let msg = msg.as_str();
@@ -334,6 +339,8 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
 
     fn walk_expr(&self, expr: &'ctx ast::Node<ast::Expr>) -> Self::Result {
         self.update_ctx_panic_info(expr);
+        self.check_cancelled();
+        self.count_eval_step();
         match &expr.node {
             ast::Expr::Identifier(identifier) => self.walk_identifier(identifier),
             ast::Expr::Unary(unary_expr) => self.walk_unary_expr(unary_expr),
@@ -986,6 +993,9 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
             }
         }
         .as_str();
+        if !check_result.is_truthy() && self.collect_check_failure(&check_expr.test, &msg) {
+            return self.ok_result();
+        }
         let (_, _, config_meta) = self
             .get_schema_or_rule_config_info()
             .expect(kcl_error::INTERNAL_ERROR_MSG);
@@ -1536,6 +1546,7 @@ impl<'ctx> Evaluator<'ctx> {
 
     pub(crate) fn walk_config_entries(&self, items: &'ctx [NodeRef<ConfigEntry>]) -> EvalResult {
         let mut config_value = self.dict_value();
+        let record_locations = self.duplicate_key_policy() != DuplicateKeyPolicy::LastWins;
         self.enter_scope();
         for item in items {
             let value = self.walk_expr(&item.node.value)?;
@@ -1565,13 +1576,25 @@ impl<'ctx> Evaluator<'ctx> {
                     Some(name) if !self.is_local_var(name) => self.string_value(name),
                     _ => self.walk_expr(key)?,
                 };
-                self.dict_insert(
-                    &mut config_value,
-                    &key.as_str(),
-                    &value,
-                    &item.node.operation,
-                    insert_index as i32,
-                );
+                if record_locations {
+                    // The keys set by different entries are found with the locations.
+                    self.dict_insert_with_location(
+                        &mut config_value,
+                        &key.as_str(),
+                        &value,
+                        &item.node.operation,
+                        insert_index as i32,
+                        (item.filename.clone(), item.line, item.column),
+                    );
+                } else {
+                    self.dict_insert(
+                        &mut config_value,
+                        &key.as_str(),
+                        &value,
+                        &item.node.operation,
+                        insert_index as i32,
+                    );
+                }
                 if let Some(name) = &optional_name {
                     let value = self.dict_get_value(&config_value, name);
                     self.add_or_update_local_variable(name, value);
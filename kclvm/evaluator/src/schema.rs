@@ -509,6 +509,7 @@ pub(crate) fn schema_with_config(
     // Dict to schema
     let is_sub_schema = { ctx.borrow().is_sub_schema };
     if is_sub_schema {
+        s.count_schema_instance();
         schema
     } else {
         schema_dict.clone()
@@ -2,7 +2,10 @@
 
 use crate::*;
 use kclvm_runtime::unification::value_subsume;
-use kclvm_runtime::{ConfigEntryOperationKind, DictValue, UnionContext, UnionOptions, Value};
+use kclvm_runtime::{
+    ConfigEntryOperationKind, DictValue, DuplicateKeyPolicy, ListValue, RuntimeErrorType,
+    UnionContext, UnionOptions, Value,
+};
 
 use self::ty::resolve_schema;
 
@@ -40,13 +43,52 @@ fn do_union(
             } else {
                 -1
             };
+            // The keys set by different config entries are found by the locations of the
+            // entries, which are only recorded under the duplicate key policies.
+            if let Some(latter) = delta.key_locations.get(k) {
+                match obj.key_locations.get(k) {
+                    Some(former)
+                        if former != latter
+                            && obj.values.contains_key(k)
+                            && !matches!(operation, ConfigEntryOperationKind::Insert) =>
+                    {
+                        match s.duplicate_key_policy() {
+                            DuplicateKeyPolicy::Error => {
+                                union_context.conflict = true;
+                                union_context.duplicate_key =
+                                    Some((k.clone(), former.clone(), latter.clone()));
+                                return;
+                            }
+                            DuplicateKeyPolicy::Merge => {
+                                let obj_value = obj.values.get(k).unwrap();
+                                if obj_value.is_dict() && v.is_dict() {
+                                    let merged = s.dict_deep_merge(obj_value, v);
+                                    obj.values.insert(k.clone(), merged);
+                                    continue;
+                                }
+                            }
+                            DuplicateKeyPolicy::LastWins => {}
+                        }
+                    }
+                    Some(_) => {}
+                    None => {
+                        obj.key_locations.insert(k.clone(), latter.clone());
+                    }
+                }
+            }
             if !obj.values.contains_key(k) {
                 obj.values.insert(k.clone(), v.clone());
             } else {
                 match operation {
                     ConfigEntryOperationKind::Union => {
                         let obj_value = obj.values.get_mut(k).unwrap();
-                        if opts.idempotent_check && !value_subsume(v, obj_value, false) {
+                        union_context.path.push(k.clone());
+                        // The lists with a merge key are matched by the key, so
+                        // the different lengths are not conflicts.
+                        let keyed = obj_value.is_list()
+                            && v.is_list()
+                            && s.merge_key(&union_context.path).is_some();
+                        if opts.idempotent_check && !keyed && !value_subsume(v, obj_value, false) {
                             union_context.conflict = true;
                             union_context.path_backtrace.push(k.clone());
                             union_context.obj_json = if obj_value.is_config() {
@@ -67,6 +109,7 @@ fn do_union(
                             return;
                         }
                         union(s, obj_value, v, false, opts, union_context);
+                        union_context.path.pop();
                         if union_context.conflict {
                             union_context.path_backtrace.push(k.clone());
                             return;
@@ -132,27 +175,31 @@ fn do_union(
     match (&mut *p.rc.borrow_mut(), &*x.rc.borrow()) {
         (Value::list_value(obj), Value::list_value(delta)) => {
             if !opts.list_override {
-                let length = if obj.values.len() > delta.values.len() {
-                    obj.values.len()
+                if let Some(merge_key) = s.merge_key(&union_context.path) {
+                    union_list_by_key(s, obj, delta, &merge_key, opts, union_context);
                 } else {
-                    delta.values.len()
-                };
-                let obj_len = obj.values.len();
-                let delta_len = delta.values.len();
-                for idx in 0..length {
-                    if idx >= obj_len {
-                        obj.values.push(delta.values[idx].clone());
-                    } else if idx < delta_len {
-                        union(
-                            s,
-                            &mut obj.values[idx],
-                            &delta.values[idx],
-                            false,
-                            opts,
-                            union_context,
-                        );
-                        if union_context.conflict {
-                            union_context.path_backtrace.push(format!("list[{idx}]"));
+                    let length = if obj.values.len() > delta.values.len() {
+                        obj.values.len()
+                    } else {
+                        delta.values.len()
+                    };
+                    let obj_len = obj.values.len();
+                    let delta_len = delta.values.len();
+                    for idx in 0..length {
+                        if idx >= obj_len {
+                            obj.values.push(delta.values[idx].clone());
+                        } else if idx < delta_len {
+                            union(
+                                s,
+                                &mut obj.values[idx],
+                                &delta.values[idx],
+                                false,
+                                opts,
+                                union_context,
+                            );
+                            if union_context.conflict {
+                                union_context.path_backtrace.push(format!("list[{idx}]"));
+                            }
                         }
                     }
                 }
@@ -237,6 +284,35 @@ fn do_union(
     p.clone()
 }
 
+/// Union the elements of the lists which have the same value of the merge key,
+/// and append the other elements of the delta list.
+fn union_list_by_key(
+    s: &Evaluator,
+    obj: &mut ListValue,
+    delta: &ListValue,
+    merge_key: &str,
+    opts: &UnionOptions,
+    union_context: &mut UnionContext,
+) {
+    for elem in &delta.values {
+        let matched = elem.dict_get_value(merge_key).and_then(|key| {
+            obj.values
+                .iter()
+                .position(|origin| origin.dict_get_value(merge_key).as_ref() == Some(&key))
+        });
+        match matched {
+            Some(idx) => {
+                union(s, &mut obj.values[idx], elem, false, opts, union_context);
+                if union_context.conflict {
+                    union_context.path_backtrace.push(format!("list[{idx}]"));
+                    return;
+                }
+            }
+            None => obj.values.push(elem.clone()),
+        }
+    }
+}
+
 fn union(
     s: &Evaluator,
     p: &mut ValueRef,
@@ -281,6 +357,29 @@ pub fn union_entry(
 ) -> ValueRef {
     let mut union_context = UnionContext::default();
     let ret = union(s, p, x, or_mode, opts, &mut union_context);
+    // The key set by two config entries is reported at the latter entry.
+    if let Some((key, former, latter)) = &union_context.duplicate_key {
+        {
+            let mut ctx = s.runtime_ctx.borrow_mut();
+            ctx.set_err_type(&RuntimeErrorType::EvaluationError);
+            ctx.set_kcl_location_info(
+                None,
+                Some(&latter.0),
+                Some(latter.1 as i32),
+                Some(latter.2 as i32),
+            );
+        }
+        panic!(
+            "duplicate key '{}' is set at {}:{}:{} and {}:{}:{}",
+            key,
+            former.0,
+            former.1,
+            former.2 + 1,
+            latter.0,
+            latter.1,
+            latter.2 + 1
+        );
+    }
     if union_context.conflict {
         union_context.path_backtrace.reverse();
         let conflict_key = union_context.path_backtrace.last().unwrap();
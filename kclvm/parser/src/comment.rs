@@ -0,0 +1,141 @@
+//! Copyright The KCL Authors. All rights reserved.
+//!
+//! Attach the comments of a module to the statements and schema attributes.
+//!
+//! A comment on the lines of a statement is an inline comment of the innermost
+//! statement, a comment before a statement at the same indentation is a leading
+//! comment, and a comment after the last statement of a block is a trailing
+//! comment of that statement.
+
+use kclvm_ast::ast::{AstIndex, Module, Node, NodeRef, Stmt};
+
+/// The position range of a statement which can hold comments.
+struct Target {
+    id: AstIndex,
+    start: (u64, u64),
+    end: (u64, u64),
+}
+
+impl Target {
+    /// Whether the comment at `pos` is after the start and not after the end line.
+    #[inline]
+    fn contains(&self, pos: (u64, u64)) -> bool {
+        self.start < pos && pos.0 <= self.end.0
+    }
+
+    /// Whether the target is nested in the `other` target.
+    #[inline]
+    fn nested_in(&self, other: &Target) -> bool {
+        self.start > other.start && self.end <= other.end
+    }
+}
+
+enum AttachKind {
+    Leading,
+    Inline,
+    Trailing,
+}
+
+/// Attach all the comments of the module to its statements and schema attributes.
+pub(crate) fn attach_comments(module: &mut Module) {
+    let mut targets = vec![];
+    collect_targets(&module.body, &mut targets);
+    for comment in &module.comments {
+        let (id, kind) = match find_attachment(&targets, (comment.line, comment.column)) {
+            Some(attachment) => attachment,
+            None => continue,
+        };
+        let attached = module.attached_comments.entry(id).or_default();
+        match kind {
+            AttachKind::Leading => attached.leading.push(comment.clone()),
+            AttachKind::Inline => attached.inline.push(comment.clone()),
+            AttachKind::Trailing => attached.trailing.push(comment.clone()),
+        }
+    }
+}
+
+/// Collect the statements in the pre-order, which are sorted by the start position.
+fn collect_targets(stmts: &[NodeRef<Stmt>], targets: &mut Vec<Target>) {
+    for stmt in stmts {
+        targets.push(Target {
+            id: stmt.id.clone(),
+            start: (stmt.line, stmt.column),
+            end: stmt_end(stmt),
+        });
+        match &stmt.node {
+            Stmt::Schema(schema_stmt) => collect_targets(&schema_stmt.body, targets),
+            Stmt::If(if_stmt) => {
+                collect_targets(&if_stmt.body, targets);
+                collect_targets(&if_stmt.orelse, targets);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The end position of the last component of a statement. The end position of
+/// a block statement recorded by the parser is the start of the next statement,
+/// which includes the comments between them.
+fn stmt_end(stmt: &NodeRef<Stmt>) -> (u64, u64) {
+    fn end<T>(node: &Node<T>) -> (u64, u64) {
+        (node.end_line, node.end_column)
+    }
+    let ends = match &stmt.node {
+        Stmt::Schema(schema_stmt) => vec![
+            Some(end(&schema_stmt.name)),
+            schema_stmt.args.as_ref().map(|n| end(n)),
+            schema_stmt.doc.as_ref().map(|n| end(n)),
+            schema_stmt.body.last().map(stmt_end),
+            schema_stmt.index_signature.as_ref().map(|n| end(n)),
+            schema_stmt.checks.last().map(|n| end(n)),
+        ],
+        Stmt::Rule(rule_stmt) => vec![
+            Some(end(&rule_stmt.name)),
+            rule_stmt.doc.as_ref().map(|n| end(n)),
+            rule_stmt.checks.last().map(|n| end(n)),
+        ],
+        Stmt::If(if_stmt) => vec![
+            Some(end(&if_stmt.cond)),
+            if_stmt.body.last().map(stmt_end),
+            if_stmt.orelse.last().map(stmt_end),
+        ],
+        _ => vec![Some(end(stmt))],
+    };
+    ends.into_iter()
+        .flatten()
+        .max()
+        .unwrap_or_else(|| end(stmt))
+}
+
+fn find_attachment(targets: &[Target], pos: (u64, u64)) -> Option<(AstIndex, AttachKind)> {
+    // The innermost statement containing the comment.
+    let container = targets.iter().rev().find(|t| t.contains(pos));
+    let in_container = |t: &&Target| match container {
+        Some(c) => t.nested_in(c),
+        None => true,
+    };
+    if let Some(c) = container {
+        // The comment at the start or end line of the container is an inline comment.
+        if c.start.0 == pos.0 || c.end.0 == pos.0 {
+            return Some((c.id.clone(), AttachKind::Inline));
+        }
+    }
+    let next = targets
+        .iter()
+        .find(|t| in_container(t) && t.start.0 > pos.0);
+    let prev = targets
+        .iter()
+        .rev()
+        .find(|t| in_container(t) && t.end.0 < pos.0);
+    let prev_at_indent = targets
+        .iter()
+        .rev()
+        .find(|t| in_container(t) && t.end.0 < pos.0 && t.start.1 == pos.1);
+    match (next, prev_at_indent, prev) {
+        (Some(next), _, _) if next.start.1 == pos.1 => Some((next.id.clone(), AttachKind::Leading)),
+        (_, Some(prev), _) => Some((prev.id.clone(), AttachKind::Trailing)),
+        (Some(next), None, _) => Some((next.id.clone(), AttachKind::Leading)),
+        (None, None, Some(prev)) => Some((prev.id.clone(), AttachKind::Trailing)),
+        (None, None, None) => container.map(|c| (c.id.clone(), AttachKind::Inline)),
+    }
+}
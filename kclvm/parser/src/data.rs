@@ -0,0 +1,262 @@
+//! Data file imports, e.g. `import data.yaml as cfg`.
+//!
+//! A YAML, JSON or TOML file imported as a package is parsed into a synthetic
+//! KCL module in which every top-level key of the document that is a valid
+//! identifier is bound to a literal expression of its value. The whole document
+//! is bound to [DATA_VALUE_NAME], which the import name denotes when it is used
+//! without an attribute, e.g. `cfg["not-an-identifier"]`.
+
+use std::path::{Path, PathBuf};
+
+use kclvm_ast::ast;
+use kclvm_error::{ErrorKind, Message, Position, Style, WarningKind};
+use kclvm_sema::pre_process::{DATA_FILE_EXTENSIONS, DATA_VALUE_NAME};
+use serde_yaml::Value;
+
+use crate::ParseSession;
+
+/// Returns the data file path of the import `pkgpath` under `pkgroot` if the
+/// last part of the pkgpath is a data file extension and the file exists,
+/// e.g. the pkgpath `config.data.yaml` denotes the file `config/data.yaml`.
+pub(crate) fn data_file_path(pkgroot: &str, pkgpath: &str) -> Option<PathBuf> {
+    let (stem, ext) = pkgpath.rsplit_once('.')?;
+    if stem.is_empty() || !DATA_FILE_EXTENSIONS.contains(&ext) {
+        return None;
+    }
+    let path = Path::new(pkgroot).join(format!("{}.{}", stem.replace('.', "/"), ext));
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Parse the data file into a KCL module of the package `pkg`. When the file
+/// can't be read or parsed, a syntax error at the offending location is added
+/// into the parse session and an empty module is returned.
+pub(crate) fn parse_data_file(
+    sess: &ParseSession,
+    filename: &str,
+    pkg: &str,
+    code: Option<String>,
+) -> ast::Module {
+    let mut module = ast::Module {
+        filename: filename.to_string(),
+        pkg: pkg.to_string(),
+        ..Default::default()
+    };
+    let value = match code {
+        Some(code) => parse_data_value(filename, &code),
+        None => match std::fs::read_to_string(filename) {
+            Ok(code) => parse_data_value(filename, &code),
+            Err(err) => Err((1, 0, format!("failed to read the data file: {}", err))),
+        },
+    };
+    match value {
+        Ok(Value::Mapping(mapping)) => {
+            for (key, value) in mapping.iter() {
+                match key {
+                    Value::String(name) if is_identifier(name) && name != DATA_VALUE_NAME => {
+                        module.body.push(assign_stmt(filename, name, value))
+                    }
+                    // The keys which can't be variable names are only accessible
+                    // through the whole document.
+                    _ => report_skipped_key(sess, filename, key),
+                }
+            }
+            module.body.push(assign_stmt(
+                filename,
+                DATA_VALUE_NAME,
+                &Value::Mapping(mapping),
+            ));
+        }
+        Ok(_) => report_error(
+            sess,
+            filename,
+            1,
+            0,
+            "the top level of the data file must be a mapping",
+        ),
+        Err((line, column, msg)) => report_error(sess, filename, line, column, &msg),
+    }
+    module
+}
+
+/// Parse the data file content by the file extension, the error holds the
+/// 1-based line, the 0-based column and the message.
+fn parse_data_value(filename: &str, code: &str) -> Result<Value, (u64, u64, String)> {
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    match ext {
+        "json" => serde_json::from_str::<Value>(code).map_err(|err| {
+            (
+                err.line() as u64,
+                (err.column() as u64).saturating_sub(1),
+                err.to_string(),
+            )
+        }),
+        "toml" => toml::from_str::<Value>(code).map_err(|err| {
+            let (line, column) = err.line_col().unwrap_or_default();
+            (line as u64 + 1, column as u64, err.to_string())
+        }),
+        _ => serde_yaml::from_str::<Value>(code).map_err(|err| match err.location() {
+            Some(loc) => (
+                loc.line() as u64,
+                (loc.column() as u64).saturating_sub(1),
+                err.to_string(),
+            ),
+            None => (1, 0, err.to_string()),
+        }),
+    }
+}
+
+/// Returns the statement binding the data value to the variable name.
+fn assign_stmt(filename: &str, name: &str, value: &Value) -> ast::NodeRef<ast::Stmt> {
+    let target = node(
+        filename,
+        ast::Identifier {
+            names: vec![node(filename, name.to_string())],
+            pkgpath: "".to_string(),
+            ctx: ast::ExprContext::Store,
+        },
+    );
+    Box::new(node(
+        filename,
+        ast::Stmt::Assign(ast::AssignStmt {
+            targets: vec![Box::new(target)],
+            value: Box::new(value_to_expr(filename, value)),
+            ty: None,
+        }),
+    ))
+}
+
+fn report_skipped_key(sess: &ParseSession, filename: &str, key: &Value) {
+    let pos = Position {
+        filename: filename.to_string(),
+        line: 1,
+        column: Some(0),
+    };
+    let key = match key {
+        Value::String(s) => format!("{:?}", s),
+        _ => serde_yaml::to_string(key)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    };
+    sess.1.borrow_mut().add_warning(
+        WarningKind::CompilerWarning,
+        &[Message {
+            range: (pos.clone(), pos),
+            style: Style::Line,
+            message: format!(
+                "the key {} of the data file can not be bound to a variable, access it by indexing the import name",
+                key
+            ),
+            note: None,
+            suggested_replacement: None,
+        }],
+    );
+}
+
+fn report_error(sess: &ParseSession, filename: &str, line: u64, column: u64, msg: &str) {
+    let pos = Position {
+        filename: filename.to_string(),
+        line,
+        column: Some(column),
+    };
+    sess.1.borrow_mut().add_error(
+        ErrorKind::InvalidSyntax,
+        &[Message {
+            range: (pos.clone(), pos),
+            style: Style::Line,
+            message: format!("invalid data file: {}", msg),
+            note: None,
+            suggested_replacement: None,
+        }],
+    );
+}
+
+/// Convert the data value to a literal expression, all the nodes are placed
+/// at the start of the data file.
+fn value_to_expr(filename: &str, value: &Value) -> ast::Node<ast::Expr> {
+    let expr = match value {
+        Value::Null => ast::Expr::NameConstantLit(ast::NameConstantLit {
+            value: ast::NameConstant::None,
+        }),
+        Value::Bool(b) => ast::Expr::NameConstantLit(ast::NameConstantLit {
+            value: if *b {
+                ast::NameConstant::True
+            } else {
+                ast::NameConstant::False
+            },
+        }),
+        Value::Number(n) => ast::Expr::NumberLit(ast::NumberLit {
+            binary_suffix: None,
+            value: match n.as_i64() {
+                Some(i) => ast::NumberLitValue::Int(i),
+                None => ast::NumberLitValue::Float(n.as_f64().unwrap_or_default()),
+            },
+        }),
+        Value::String(s) => ast::Expr::StringLit(string_lit(s)),
+        Value::Sequence(seq) => ast::Expr::List(ast::ListExpr {
+            elts: seq
+                .iter()
+                .map(|v| Box::new(value_to_expr(filename, v)))
+                .collect(),
+            ctx: ast::ExprContext::Load,
+        }),
+        Value::Mapping(mapping) => ast::Expr::Config(ast::ConfigExpr {
+            items: mapping
+                .iter()
+                .filter_map(|(k, v)| {
+                    let key = match k {
+                        Value::String(s) => s.to_string(),
+                        Value::Number(n) => n.to_string(),
+                        Value::Bool(b) => b.to_string(),
+                        Value::Null => "null".to_string(),
+                        _ => return None,
+                    };
+                    Some(Box::new(node(
+                        filename,
+                        ast::ConfigEntry {
+                            key: Some(Box::new(node(
+                                filename,
+                                ast::Expr::StringLit(string_lit(&key)),
+                            ))),
+                            value: Box::new(value_to_expr(filename, v)),
+                            operation: ast::ConfigEntryOperation::Override,
+                            insert_index: -1,
+                        },
+                    )))
+                })
+                .collect(),
+        }),
+        Value::Tagged(tagged) => return value_to_expr(filename, &tagged.value),
+    };
+    node(filename, expr)
+}
+
+#[inline]
+fn string_lit(s: &str) -> ast::StringLit {
+    ast::StringLit {
+        is_long_string: false,
+        raw_value: format!("{:?}", s),
+        value: s.to_string(),
+    }
+}
+
+#[inline]
+fn node<T>(filename: &str, node: T) -> ast::Node<T> {
+    ast::Node::new(node, filename.to_string(), 1, 0, 1, 0)
+}
+
+/// Whether the data key can be used as a KCL variable name.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
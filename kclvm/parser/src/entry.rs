@@ -1,4 +1,5 @@
 use anyhow::Result;
+use kclvm_config::ignore::KclIgnore;
 use kclvm_config::modfile::get_pkg_root;
 use kclvm_config::modfile::KCL_FILE_SUFFIX;
 use kclvm_config::path::ModRelativePath;
@@ -455,6 +456,7 @@ pub fn get_dir_files(dir: &str, is_recursive: bool) -> Result<Vec<String>> {
         return Ok(Vec::new());
     }
 
+    let ignore = KclIgnore::load_from_path(dir)?;
     let mut list = Vec::new();
     let mut queue: VecDeque<String> = VecDeque::new();
     queue.push_back(dir.to_string());
@@ -467,6 +469,9 @@ pub fn get_dir_files(dir: &str, is_recursive: bool) -> Result<Vec<String>> {
                     for entry in entries {
                         if let Ok(entry) = entry {
                             let path = entry.path();
+                            if ignore.is_ignored(&path) {
+                                continue;
+                            }
                             if path.is_dir() && is_recursive {
                                 queue.push_back(path.to_string_lossy().to_string());
                             } else if !is_ignored_file(&path.display().to_string()) {
@@ -0,0 +1,133 @@
+//! The import resolver which finds the package of an import statement, e.g.,
+//! `import pkg.sub`, see [ImportResolver].
+//!
+//! The program loader resolves the imports with [LoadProgramOptions::import_resolver],
+//! which defaults to the [FileSystemImportResolver] looking for the packages in the
+//! package root and the vendor directories. The embedders can plug in their own
+//! resolvers to load the packages from a database, a bundle or a monorepo layout.
+//! The plugin, builtin and data file imports are not resolved by the resolver.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use anyhow::Result;
+use kclvm_error::diagnostic::Range;
+
+use crate::{LoadProgramOptions, PackageFinder, ParseSessionRef, PkgInfo};
+
+/// An import statement to resolve.
+#[derive(Debug, Clone)]
+pub struct ImportRequest<'a> {
+    /// The import path with the relative and root alias prefixes resolved, e.g.,
+    /// `pkg.sub`.
+    pub pkgpath: &'a str,
+    /// The name of the package which the importer belongs to, `__main__` for the
+    /// main package.
+    pub pkg_name: &'a str,
+    /// The root directory of the package which the importer belongs to.
+    pub pkg_root: &'a str,
+    /// The range of the import statement, which holds the importer file name.
+    pub range: Range,
+}
+
+impl ImportRequest<'_> {
+    /// Returns the file name of the importer.
+    #[inline]
+    pub fn importer(&self) -> &str {
+        &self.range.0.filename
+    }
+}
+
+/// The package resolved from an import statement.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedPackage {
+    /// The name of the package which the resolved package belongs to, i.e., the
+    /// external package name or `__main__`.
+    pub pkg_name: String,
+    /// The root directory which the imports of the resolved package are resolved
+    /// against.
+    pub pkg_root: String,
+    /// The full package path, i.e., the key of the package in the program.
+    pub pkg_path: String,
+    /// The KCL files of the package.
+    pub k_files: Vec<String>,
+    /// The source codes of the files keyed by the file paths. The files without
+    /// the source codes are looked up in [LoadProgramOptions::sources] and read
+    /// from the file system.
+    pub sources: HashMap<String, String>,
+}
+
+/// Resolve the import statements into the packages.
+pub trait ImportResolver: Debug + Send + Sync {
+    /// Returns the package of the import, or `None` when it is not resolved.
+    /// The resolver reports the diagnostics, e.g., the package is not found,
+    /// into the parse session by itself.
+    fn resolve(
+        &self,
+        sess: &ParseSessionRef,
+        request: &ImportRequest,
+    ) -> Result<Option<ResolvedPackage>>;
+}
+
+/// The default import resolver, which looks for the packages in the package root,
+/// the external package maps and the vendor directories of the load options.
+#[derive(Debug, Clone, Default)]
+pub struct FileSystemImportResolver {
+    opts: LoadProgramOptions,
+}
+
+impl FileSystemImportResolver {
+    /// New a file system resolver with the load options.
+    pub fn new(opts: LoadProgramOptions) -> Self {
+        Self { opts }
+    }
+
+    /// Find the package of the import with the package finder, which is used by the
+    /// resolver and by the program loader when no resolver is set.
+    pub(crate) fn find(finder: &PackageFinder, request: &ImportRequest) -> Result<Option<PkgInfo>> {
+        finder.find_packages(
+            request.range.clone(),
+            request.pkg_name,
+            request.pkg_root,
+            request.pkgpath,
+        )
+    }
+}
+
+impl ImportResolver for FileSystemImportResolver {
+    fn resolve(
+        &self,
+        sess: &ParseSessionRef,
+        request: &ImportRequest,
+    ) -> Result<Option<ResolvedPackage>> {
+        let finder = PackageFinder {
+            sess,
+            opts: &self.opts,
+        };
+        Ok(Self::find(&finder, request)?.map(ResolvedPackage::from))
+    }
+}
+
+impl From<PkgInfo> for ResolvedPackage {
+    fn from(info: PkgInfo) -> Self {
+        Self {
+            pkg_name: info.pkg_name,
+            pkg_root: info.pkg_root,
+            pkg_path: info.pkg_path,
+            k_files: info.k_files,
+            sources: info.k_codes,
+        }
+    }
+}
+
+impl From<ResolvedPackage> for PkgInfo {
+    fn from(package: ResolvedPackage) -> Self {
+        Self {
+            pkg_name: package.pkg_name,
+            pkg_root: package.pkg_root,
+            pkg_path: package.pkg_path,
+            k_files: package.k_files,
+            k_codes: package.sources,
+        }
+    }
+}
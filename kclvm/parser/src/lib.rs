@@ -1,10 +1,15 @@
 //! Copyright The KCL Authors. All rights reserved.
 
+mod comment;
+mod data;
 pub mod entry;
 pub mod file_graph;
+pub mod import_resolver;
 mod lexer;
+mod package;
 mod parser;
 mod session;
+mod stream;
 
 #[cfg(test)]
 mod tests;
@@ -17,9 +22,17 @@ use compiler_base_macros::bug;
 use compiler_base_session::Session;
 use compiler_base_span::span::new_byte_pos;
 use file_graph::FileGraph;
+pub use import_resolver::{
+    FileSystemImportResolver, ImportRequest, ImportResolver, ResolvedPackage,
+};
 use indexmap::{IndexMap, IndexSet};
 use kclvm_ast::ast;
-use kclvm_config::modfile::{get_vendor_home, KCL_FILE_EXTENSION, KCL_FILE_SUFFIX, KCL_MOD_FILE};
+use kclvm_config::ignore::KclIgnore;
+use kclvm_config::modfile::{
+    get_vendor_home, try_load_mod_file, DEFAULT_KCL_ROOT_ALIAS, KCL_FILE_EXTENSION,
+    KCL_FILE_SUFFIX, KCL_MOD_FILE,
+};
+use kclvm_config::offline::is_offline;
 use kclvm_error::diagnostic::{Diagnostic, Range};
 use kclvm_error::{ErrorKind, Message, Position, Style};
 use kclvm_sema::plugin::PLUGIN_MODULE_PREFIX;
@@ -29,13 +42,16 @@ use kclvm_utils::pkgpath::rm_external_pkg_name;
 use anyhow::Result;
 use lexer::parse_token_streams;
 use parser::Parser;
+pub use parser::DEFAULT_MAX_PARSE_DEPTH;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
+pub use stream::DEFAULT_STREAM_CHUNK_SIZE;
 
 use kclvm_span::create_session_globals_then;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 /// [`PkgInfo`] is some basic information about a kcl package.
 pub(crate) struct PkgInfo {
     /// the name of the kcl package.
@@ -46,6 +62,8 @@ pub(crate) struct PkgInfo {
     pkg_path: String,
     /// The kcl files that need to be compiled in this package.
     k_files: Vec<String>,
+    /// The source codes of the kcl files given by the import resolver.
+    k_codes: HashMap<String, String>,
 }
 
 impl PkgInfo {
@@ -61,6 +79,7 @@ impl PkgInfo {
             pkg_root,
             pkg_path,
             k_files,
+            k_codes: HashMap::new(),
         }
     }
 }
@@ -138,6 +157,43 @@ pub fn parse_file(filename: &str, code: Option<String>) -> Result<ParseFileResul
     })
 }
 
+/// The stack size of the thread which [`parse_file_safe`] parses the file in. The
+/// recursion of the parser is bounded by [`DEFAULT_MAX_PARSE_DEPTH`], which never
+/// overflows the stack of this size.
+const SAFE_PARSE_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Parse a KCL file to the AST module with parse errors like [`parse_file`], but any
+/// internal panic of the parser is converted into an error instead of unwinding into
+/// the caller, which makes it suitable for long-running service embeddings.
+///
+/// The file is parsed in a dedicated thread with a large stack, and the nesting
+/// deeper than [`DEFAULT_MAX_PARSE_DEPTH`] is reported as a parse error, thus the
+/// deeply nested input never overflows the stack of the caller thread.
+pub fn parse_file_safe(filename: &str, code: Option<String>) -> Result<ParseFileResult> {
+    let name = filename.to_string();
+    let handle = std::thread::Builder::new()
+        .name("kcl-parse".to_string())
+        .stack_size(SAFE_PARSE_STACK_SIZE)
+        .spawn(move || parse_file(&name, code))?;
+    match handle.join() {
+        Ok(result) => result,
+        Err(err) => {
+            let msg = if let Some(s) = err.downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = err.downcast_ref::<String>() {
+                s.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            Err(anyhow::anyhow!(
+                "internal error when parsing '{}': {}",
+                filename,
+                msg
+            ))
+        }
+    }
+}
+
 /// Parse a KCL file to the AST module and return errors when meets parse errors as result.
 pub fn parse_file_force_errors(filename: &str, code: Option<String>) -> Result<ast::Module> {
     let sess = Arc::new(ParseSession::default());
@@ -154,10 +210,51 @@ pub fn parse_file_force_errors(filename: &str, code: Option<String>) -> Result<a
 }
 
 /// Parse a KCL file to the AST module with the parse session .
+#[inline]
 pub fn parse_file_with_session(
     sess: ParseSessionRef,
     filename: &str,
     code: Option<String>,
+) -> Result<ast::Module> {
+    parse_file_with_max_depth(sess, filename, code, DEFAULT_MAX_PARSE_DEPTH)
+}
+
+/// Parse a KCL file to the AST module with the parse session and the max nesting
+/// depth of statements and expressions.
+pub fn parse_file_with_max_depth(
+    sess: ParseSessionRef,
+    filename: &str,
+    code: Option<String>,
+    max_depth: usize,
+) -> Result<ast::Module> {
+    parse_file_with_chunk_size(sess, filename, code, max_depth, None)
+}
+
+/// Parse a large KCL file to the AST module chunk by chunk. The source is split
+/// at top-level statements and each chunk is lexed and parsed separately, which
+/// reduces the peak memory of the tokens. The produced AST is the same as the
+/// one produced by [parse_file_with_max_depth].
+pub fn parse_file_streaming(
+    sess: ParseSessionRef,
+    filename: &str,
+    code: Option<String>,
+    max_depth: usize,
+) -> Result<ast::Module> {
+    parse_file_with_chunk_size(
+        sess,
+        filename,
+        code,
+        max_depth,
+        Some(DEFAULT_STREAM_CHUNK_SIZE),
+    )
+}
+
+fn parse_file_with_chunk_size(
+    sess: ParseSessionRef,
+    filename: &str,
+    code: Option<String>,
+    max_depth: usize,
+    chunk_size: Option<usize>,
 ) -> Result<ast::Module> {
     // Code source.
     let src = if let Some(s) = code {
@@ -188,11 +285,38 @@ pub fn parse_file_with_session(
         }
     };
 
-    // Lexer
-    let stream = lexer::parse_token_streams(&sess, src_from_sf.as_str(), sf.start_pos);
-    // Parser
-    let mut p = parser::Parser::new(&sess, stream);
-    let mut m = p.parse_module();
+    let mut m = match chunk_size {
+        Some(chunk_size) => {
+            let src = src_from_sf.as_str();
+            let mut m = ast::Module::default();
+            for (i, range) in stream::split_stmt_chunks(src, chunk_size)
+                .into_iter()
+                .enumerate()
+            {
+                let start_pos = sf.start_pos + new_byte_pos(range.start as u32);
+                // Lexer
+                let stream = lexer::parse_token_streams(&sess, &src[range], start_pos);
+                // Parser
+                let mut p = parser::Parser::new(&sess, stream);
+                p.set_max_depth(max_depth);
+                let chunk = p.parse_module();
+                if i == 0 {
+                    m.doc = chunk.doc;
+                }
+                m.body.extend(chunk.body);
+                m.comments.extend(chunk.comments);
+            }
+            m
+        }
+        None => {
+            // Lexer
+            let stream = lexer::parse_token_streams(&sess, src_from_sf.as_str(), sf.start_pos);
+            // Parser
+            let mut p = parser::Parser::new(&sess, stream);
+            p.set_max_depth(max_depth);
+            p.parse_module()
+        }
+    };
     m.filename = filename.to_string();
     m.pkg = kclvm_ast::MAIN_PKG.to_string();
     m.name = kclvm_ast::MAIN_PKG.to_string();
@@ -200,6 +324,24 @@ pub fn parse_file_with_session(
     Ok(m)
 }
 
+/// Parse a KCL file with the load program options.
+fn parse_file_with_opts(
+    sess: ParseSessionRef,
+    filename: &str,
+    code: Option<String>,
+    opts: &LoadProgramOptions,
+) -> Result<ast::Module> {
+    let mut m = if opts.streaming {
+        parse_file_streaming(sess, filename, code, opts.max_parse_depth)
+    } else {
+        parse_file_with_max_depth(sess, filename, code, opts.max_parse_depth)
+    }?;
+    if opts.keep_comments {
+        comment::attach_comments(&mut m);
+    }
+    Ok(m)
+}
+
 /// Parse a KCL file to the AST module with the parse session and the global session
 #[inline]
 pub fn parse_file_with_global_session(
@@ -248,6 +390,10 @@ pub fn parse_expr(src: &str) -> Option<ast::NodeRef<ast::Expr>> {
     }
 }
 
+/// The package name of the modules loaded from the stdlib snapshot, e.g., the
+/// snapshot module `math` has the package path `__stdlib__.math`.
+pub const STDLIB_SNAPSHOT_PKG: &str = "__stdlib__";
+
 #[derive(Debug, Clone)]
 pub struct LoadProgramOptions {
     pub work_dir: String,
@@ -260,6 +406,32 @@ pub struct LoadProgramOptions {
     pub load_packages: bool,
     /// Whether to load plugins
     pub load_plugins: bool,
+    /// The max nesting depth of statements and expressions.
+    pub max_parse_depth: usize,
+    /// Whether to parse files chunk by chunk to reduce the peak memory of large files.
+    pub streaming: bool,
+    /// Whether to attach the comments to the statements and schema attributes, which
+    /// is used by the formatter and the doc tools and not needed by the evaluation.
+    pub keep_comments: bool,
+    /// The number of threads to parse the files of a package in parallel, files are
+    /// parsed sequentially when it is not greater than 1.
+    pub parse_threads: usize,
+    /// The in-memory source codes keyed by the file paths. The import packages are
+    /// looked up in them before the file system, and the paths are compared with the
+    /// package root joined by the package path, e.g., `pkg.k` or `pkg/sub.k` for
+    /// `import pkg` under the empty root.
+    pub sources: HashMap<String, String>,
+    /// Whether to forbid the network access when resolving the dependencies,
+    /// defaults to the `KCL_OFFLINE` environment variable.
+    pub offline: bool,
+    /// The pinned standard library snapshot directory. When it is set, the standard
+    /// system modules, e.g., `import math`, are loaded as the KCL packages in it
+    /// instead of the builtin ones, and a module missing in the snapshot is an error.
+    /// The snapshot files can also be embedded in [LoadProgramOptions::sources].
+    pub stdlib_snapshot: Option<String>,
+    /// The resolver of the import packages, the [FileSystemImportResolver] is used
+    /// when it is not set.
+    pub import_resolver: Option<Arc<dyn ImportResolver>>,
 }
 
 impl Default for LoadProgramOptions {
@@ -272,6 +444,14 @@ impl Default for LoadProgramOptions {
             mode: ParseMode::ParseComments,
             load_packages: true,
             load_plugins: false,
+            max_parse_depth: DEFAULT_MAX_PARSE_DEPTH,
+            streaming: false,
+            keep_comments: false,
+            parse_threads: 1,
+            sources: Default::default(),
+            offline: is_offline(),
+            stdlib_snapshot: None,
+            import_resolver: None,
         }
     }
 }
@@ -315,6 +495,9 @@ struct Loader {
     missing_pkgs: Vec<String>,
     module_cache: Option<KCLModuleCache>,
     file_graph: FileGraph,
+    /// The packages whose files declare another package, keyed by the inferred
+    /// package path.
+    declared_pkgs: HashMap<String, PkgInfo>,
 }
 
 impl Loader {
@@ -331,6 +514,16 @@ impl Loader {
             module_cache,
             missing_pkgs: Default::default(),
             file_graph: FileGraph::default(),
+            declared_pkgs: HashMap::new(),
+        }
+    }
+
+    /// Returns the package finder with the session and the options of the loader.
+    #[inline]
+    fn finder(&self) -> PackageFinder<'_> {
+        PackageFinder {
+            sess: &self.sess,
+            opts: &self.opts,
         }
     }
 
@@ -344,31 +537,58 @@ impl Loader {
         let workdir = compile_entries.get_root_path().to_string();
         let mut pkgs = HashMap::new();
         let mut pkg_files = Vec::new();
+        let mut entry_files = Vec::new();
         for entry in compile_entries.iter() {
             // Get files from options with root.
             // let k_files = self.get_main_files_from_pkg(entry.path(), entry.name())?;
             let k_files = entry.get_k_files();
             let maybe_k_codes = entry.get_k_codes();
             // Load main package.
-            for (i, filename) in k_files.iter().enumerate() {
-                let mut m = if let Some(module_cache) = self.module_cache.as_ref() {
-                    let m = parse_file_with_session(
-                        self.sess.clone(),
-                        filename,
-                        maybe_k_codes[i].clone(),
-                    )?;
-                    let mut module_cache_ref = module_cache.write().unwrap();
-                    module_cache_ref.insert(filename.clone(), m.clone());
-                    m
-                } else {
-                    parse_file_with_session(self.sess.clone(), filename, maybe_k_codes[i].clone())?
-                };
-                fix_rel_import_path(entry.path(), &mut m);
-                pkg_files.push(m);
+            let files = k_files
+                .iter()
+                .enumerate()
+                .map(|(i, filename)| (filename.clone(), maybe_k_codes[i].clone()))
+                .collect::<Vec<_>>();
+            let mut files = self.parse_files(&files, false)?;
+            for m in &mut files {
+                fix_rel_import_path(entry.path(), m);
+            }
+            entry_files.push(files);
+        }
+        // The files declaring another package are loaded into the declared package.
+        let declared_dirs =
+            package::declared_dir_packages(&self.sess, entry_files.iter().flatten());
+        let mut declared_pkg_files: IndexMap<String, Vec<ast::Module>> = IndexMap::new();
+        for (entry, files) in compile_entries.iter().zip(entry_files) {
+            let mut declared: IndexMap<String, Vec<ast::Module>> = IndexMap::new();
+            for mut m in files {
+                match declared_dirs.get(&package::module_dir(&m)) {
+                    Some(pkgpath) if pkgpath != kclvm_ast::MAIN_PKG => {
+                        m.pkg = pkgpath.clone();
+                        m.name = "".to_string();
+                        declared.entry(pkgpath.clone()).or_default().push(m);
+                    }
+                    _ => pkg_files.push(m),
+                }
             }
 
             // Insert an empty vec to determine whether there is a circular import.
             pkgs.insert(kclvm_ast::MAIN_PKG.to_string(), vec![]);
+            for pkgpath in declared.keys() {
+                pkgs.entry(pkgpath.clone()).or_default();
+            }
+            for (pkgpath, mut modules) in declared {
+                self.load_import_package(
+                    entry.path(),
+                    entry.name().to_string(),
+                    &mut modules,
+                    &mut pkgs,
+                )?;
+                declared_pkg_files
+                    .entry(pkgpath)
+                    .or_default()
+                    .append(&mut modules);
+            }
             self.load_import_package(
                 entry.path(),
                 entry.name().to_string(),
@@ -378,6 +598,9 @@ impl Loader {
         }
         // Insert the complete ast to replace the empty list.
         pkgs.insert(kclvm_ast::MAIN_PKG.to_string(), pkg_files);
+        for (pkgpath, mut modules) in declared_pkg_files {
+            pkgs.entry(pkgpath).or_default().append(&mut modules);
+        }
         let program = ast::Program {
             root: workdir,
             pkgs,
@@ -417,38 +640,26 @@ impl Loader {
         })
     }
 
-    /// [`find_packages`] will find the kcl package.
-    /// If the package is found, the basic information of the package [`PkgInfo`] will be returned.
-    ///
-    /// # Errors
-    ///
-    ///  This method will return an error in the following two cases:
-    ///
-    /// 1. The package not found.
-    /// 2. The package was found both internal and external the current package.
-    fn find_packages(
+    /// [`find_stdlib_snapshot_package`] will find the standard system module in the
+    /// stdlib snapshot, the module is never looked up in the other packages. The
+    /// package path is prefixed with [`STDLIB_SNAPSHOT_PKG`] so that it is resolved
+    /// as a KCL package instead of the builtin system module.
+    fn find_stdlib_snapshot_package(
         &self,
         pos: ast::Pos,
-        pkg_name: &str,
-        pkg_root: &str,
-        pkg_path: &str,
+        snapshot: &str,
+        pkgpath: &str,
     ) -> Result<Option<PkgInfo>> {
-        // 1. Look for in the current package's directory.
-        let is_internal = self.is_internal_pkg(pkg_name, pkg_root, pkg_path)?;
-
-        // 2. Look for in the vendor path.
-        let is_external = self.is_external_pkg(pkg_path)?;
-
-        // 3. Internal and external packages cannot be duplicated
-        if is_external.is_some() && is_internal.is_some() {
+        let k_files = self.finder().get_pkg_kfile_list(snapshot, pkgpath)?;
+        if k_files.is_empty() {
             self.sess.1.borrow_mut().add_error(
                 ErrorKind::CannotFindModule,
                 &[Message {
                     range: Into::<Range>::into(pos),
                     style: Style::Line,
                     message: format!(
-                        "the `{}` is found multiple times in the current package and vendor package",
-                        pkg_path
+                        "the system module {} is not found in the stdlib snapshot {}",
+                        pkgpath, snapshot
                     ),
                     note: None,
                     suggested_replacement: None,
@@ -456,37 +667,12 @@ impl Loader {
             );
             return Ok(None);
         }
-
-        // 4. Get package information based on whether the package is internal or external.
-        match is_internal.or(is_external) {
-            Some(pkg_info) => Ok(Some(pkg_info)),
-            None => {
-                self.sess.1.borrow_mut().add_error(
-                    ErrorKind::CannotFindModule,
-                    &[Message {
-                        range: Into::<Range>::into(pos),
-                        style: Style::Line,
-                        message: format!("pkgpath {} not found in the program", pkg_path),
-                        note: None,
-                        suggested_replacement: None,
-                    }],
-                );
-                let mut suggestions =
-                    vec![format!("find more package on 'https://artifacthub.io'")];
-
-                if let Ok(pkg_name) = parse_external_pkg_name(pkg_path) {
-                    suggestions.insert(
-                        0,
-                        format!(
-                            "try 'kcl mod add {}' to download the package not found",
-                            pkg_name
-                        ),
-                    );
-                }
-                self.sess.1.borrow_mut().add_suggestions(suggestions);
-                Ok(None)
-            }
-        }
+        Ok(Some(PkgInfo::new(
+            STDLIB_SNAPSHOT_PKG.to_string(),
+            snapshot.to_string(),
+            format!("{}.{}", STDLIB_SNAPSHOT_PKG, pkgpath),
+            k_files,
+        )))
     }
 
     /// [`load_import_package`] will traverse all the [`kclvm_ast::ImportStmt`] on the input AST nodes [`pkg`],
@@ -503,11 +689,57 @@ impl Loader {
         pkg: &mut [ast::Module],
         pkgs: &mut HashMap<String, Vec<ast::Module>>,
     ) -> Result<()> {
+        // The module root alias is loaded from `kcl.mod` when it is used.
+        let mut root_alias: Option<String> = None;
         for m in pkg {
             let mut to_paths: Vec<PathBuf> = vec![];
             for stmt in &mut m.body {
                 let pos = stmt.pos().clone();
                 if let ast::Stmt::Import(ref mut import_spec) = &mut stmt.node {
+                    if let Some((alias, path)) =
+                        kclvm_config::vfs::split_root_alias(&import_spec.path.node)
+                    {
+                        let expected = root_alias.get_or_insert_with(|| {
+                            match try_load_mod_file(pkgroot) {
+                                Ok(modfile) => modfile.root_alias(),
+                                Err(err) => {
+                                    // Report the malformed `kcl.mod` once per package and
+                                    // resolve the imports with the default root alias.
+                                    self.sess.1.borrow_mut().add_error(
+                                        ErrorKind::CompileError,
+                                        &[Message {
+                                            range: Into::<Range>::into(pos.clone()),
+                                            style: Style::Line,
+                                            message: format!(
+                                                "failed to load the module root alias: {}",
+                                                err
+                                            ),
+                                            note: None,
+                                            suggested_replacement: None,
+                                        }],
+                                    );
+                                    DEFAULT_KCL_ROOT_ALIAS.to_string()
+                                }
+                            }
+                        });
+                        if alias != expected.as_str() {
+                            self.sess.1.borrow_mut().add_error(
+                                ErrorKind::CannotFindModule,
+                                &[Message {
+                                    range: Into::<Range>::into(pos),
+                                    style: Style::Line,
+                                    message: format!(
+                                        "unknown import alias `{}`, the module root alias is `{}`",
+                                        alias, expected
+                                    ),
+                                    note: None,
+                                    suggested_replacement: None,
+                                }],
+                            );
+                            continue;
+                        }
+                        import_spec.path.node = path.to_string();
+                    }
                     import_spec.path.node = kclvm_config::vfs::fix_import_path(
                         pkgroot,
                         &m.filename,
@@ -561,7 +793,7 @@ impl Loader {
         }
 
         // plugin pkgs
-        if self.is_plugin_pkg(pkgpath.as_str()) {
+        if self.finder().is_plugin_pkg(pkgpath.as_str()) {
             if !self.opts.load_plugins {
                 self.sess.1.borrow_mut().add_error(
                     ErrorKind::CannotFindModule,
@@ -577,17 +809,62 @@ impl Loader {
             return Ok(None);
         }
 
-        // builtin pkgs
-        if self.is_builtin_pkg(pkgpath.as_str()) {
+        // builtin pkgs, which are loaded from the stdlib snapshot when it is set.
+        if self.finder().is_builtin_pkg(pkgpath.as_str()) && self.opts.stdlib_snapshot.is_none() {
             return Ok(None);
         }
 
+        // data file pkgs, e.g. `import data.yaml`
+        if let Some(path) = data::data_file_path(pkgroot, &pkgpath) {
+            let filename = path.to_string_lossy().to_string();
+            let fullpath = if pkgname == kclvm_ast::MAIN_PKG {
+                pkgpath
+            } else {
+                format!("{}.{}", pkgname, pkgpath)
+            };
+            let m = data::parse_data_file(
+                &self.sess,
+                &filename,
+                &fullpath,
+                self.opts.sources.get(&filename).cloned(),
+            );
+            pkgs.insert(fullpath.clone(), vec![m]);
+            return Ok(Some(PkgInfo::new(
+                pkgname,
+                pkgroot.to_string(),
+                fullpath,
+                vec![filename],
+            )));
+        }
+
         // find the package.
-        let pkg_info = match self.find_packages(pos.clone(), &pkgname, pkgroot, &pkgpath)? {
+        let pkg_info = match &self.opts.stdlib_snapshot {
+            Some(snapshot) if self.finder().is_builtin_pkg(pkgpath.as_str()) => {
+                self.find_stdlib_snapshot_package(pos.clone(), snapshot, &pkgpath)?
+            }
+            _ => {
+                let request = ImportRequest {
+                    pkgpath: &pkgpath,
+                    pkg_name: &pkgname,
+                    pkg_root: pkgroot,
+                    range: pos.clone().into(),
+                };
+                match &self.opts.import_resolver {
+                    Some(resolver) => resolver.resolve(&self.sess, &request)?.map(PkgInfo::from),
+                    None => FileSystemImportResolver::find(&self.finder(), &request)?,
+                }
+            }
+        };
+        let mut pkg_info = match pkg_info {
             Some(info) => info,
             None => return Ok(None),
         };
 
+        // The package is loaded into the package declared by its files.
+        if let Some(declared_info) = self.declared_pkgs.get(&pkg_info.pkg_path) {
+            return Ok(Some(declared_info.clone()));
+        }
+
         // If there is a circular import, return the information of the found package.
         if pkgs.contains_key(&pkg_info.pkg_path) {
             return Ok(Some(pkg_info));
@@ -604,23 +881,18 @@ impl Loader {
 
         // Load packages
         let mut pkg_files = Vec::new();
-        let k_files = pkg_info.k_files.clone();
-        for filename in k_files {
-            let mut m = if let Some(module_cache) = self.module_cache.as_ref() {
-                let module_cache_ref = module_cache.read().unwrap();
-                if let Some(module) = module_cache_ref.get(&filename) {
-                    module.clone()
-                } else {
-                    let m = parse_file_with_session(self.sess.clone(), &filename, None)?;
-                    drop(module_cache_ref);
-                    let mut module_cache_ref = module_cache.write().unwrap();
-                    module_cache_ref.insert(filename.clone(), m.clone());
-                    m
-                }
-            } else {
-                parse_file_with_session(self.sess.clone(), &filename, None)?
-            };
-
+        let files = pkg_info
+            .k_files
+            .iter()
+            .map(|filename| {
+                let code = pkg_info
+                    .k_codes
+                    .get(filename)
+                    .or_else(|| self.opts.sources.get(filename));
+                (filename.clone(), code.cloned())
+            })
+            .collect::<Vec<_>>();
+        for mut m in self.parse_files(&files, true)? {
             m.pkg = pkg_info.pkg_path.clone();
             m.name = "".to_string();
             fix_rel_import_path(&pkg_info.pkg_root, &mut m);
@@ -628,8 +900,21 @@ impl Loader {
             pkg_files.push(m);
         }
 
+        // The files declaring another package are loaded into the declared package.
+        let modules = pkg_files.iter().collect::<Vec<_>>();
+        if let Some(declared) = package::declared_dir_package(&self.sess, &modules) {
+            let fullpath = package::declared_pkg_fullpath(&pkg_info.pkg_name, &declared);
+            if fullpath != pkg_info.pkg_path {
+                let inferred = std::mem::replace(&mut pkg_info.pkg_path, fullpath);
+                for m in &mut pkg_files {
+                    m.pkg = pkg_info.pkg_path.clone();
+                }
+                self.declared_pkgs.insert(inferred, pkg_info.clone());
+            }
+        }
+
         // Insert an empty vec to determine whether there is a circular import.
-        pkgs.insert(pkg_info.pkg_path.clone(), vec![]);
+        pkgs.entry(pkg_info.pkg_path.clone()).or_default();
 
         self.load_import_package(
             &pkg_info.pkg_root.to_string(),
@@ -638,12 +923,205 @@ impl Loader {
             pkgs,
         )?;
 
-        // Insert the complete ast to replace the empty list.
-        pkgs.insert(pkg_info.pkg_path.clone(), pkg_files);
+        // Append the complete ast to the empty list, the package may be declared by
+        // the files of several directories.
+        pkgs.entry(pkg_info.pkg_path.clone())
+            .or_default()
+            .append(&mut pkg_files);
 
         Ok(Some(pkg_info))
     }
 
+    /// Parse the files with their optional source codes and return the modules in the
+    /// input order. When `read_cache` is true, the modules found in the module cache are
+    /// not parsed again, and all the parsed modules are written into the module cache.
+    fn parse_files(
+        &self,
+        files: &[(String, Option<String>)],
+        read_cache: bool,
+    ) -> Result<Vec<ast::Module>> {
+        let mut modules: Vec<Option<ast::Module>> = vec![None; files.len()];
+        if read_cache {
+            if let Some(module_cache) = self.module_cache.as_ref() {
+                let module_cache_ref = module_cache.read().unwrap();
+                for (i, (filename, _)) in files.iter().enumerate() {
+                    modules[i] = module_cache_ref.get(filename).cloned();
+                }
+            }
+        }
+        let pending = (0..files.len())
+            .filter(|i| modules[*i].is_none())
+            .collect::<Vec<usize>>();
+        let parsed = self.parse_pending_files(files, &pending);
+        for (i, m) in pending.into_iter().zip(parsed) {
+            let m = m?;
+            if let Some(module_cache) = self.module_cache.as_ref() {
+                let mut module_cache_ref = module_cache.write().unwrap();
+                module_cache_ref.insert(files[i].0.clone(), m.clone());
+            }
+            modules[i] = Some(m);
+        }
+        Ok(modules.into_iter().flatten().collect())
+    }
+
+    /// Parse the files at the `pending` indexes. When the option `parse_threads` is
+    /// greater than 1, the files are parsed in a pool of threads. Each file is parsed
+    /// with its own session, and the source files and the diagnostics are merged into
+    /// the loader session in the input order, so the results do not depend on the parse
+    /// completion order.
+    fn parse_pending_files(
+        &self,
+        files: &[(String, Option<String>)],
+        pending: &[usize],
+    ) -> Vec<Result<ast::Module>> {
+        let threads = self.opts.parse_threads.min(pending.len());
+        if threads <= 1 {
+            return pending
+                .iter()
+                .map(|i| self.parse_file(&files[*i].0, files[*i].1.clone()))
+                .collect();
+        }
+        let next = &AtomicUsize::new(0);
+        let opts = &self.opts;
+        let mut results = std::thread::scope(|s| {
+            let handles = (0..threads)
+                .map(|_| {
+                    s.spawn(move || {
+                        create_session_globals_then(|| {
+                            let mut results = vec![];
+                            loop {
+                                let n = next.fetch_add(1, Ordering::SeqCst);
+                                if n >= pending.len() {
+                                    break;
+                                }
+                                let (filename, code) = &files[pending[n]];
+                                let sess = Arc::new(ParseSession::default());
+                                let m = parse_file_with_opts(
+                                    sess.clone(),
+                                    filename,
+                                    code.clone(),
+                                    opts,
+                                );
+                                // The source map of the session holds only the parsed file.
+                                let src = m.is_ok().then(|| {
+                                    let sf = sess.0.sm.lookup_source_file(new_byte_pos(0));
+                                    sf.src.as_ref().map(|src| src.to_string())
+                                });
+                                let diagnostics = sess.1.borrow().diagnostics.clone();
+                                results.push((n, m, src.flatten(), diagnostics));
+                            }
+                            results
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| {
+                    h.join()
+                        .unwrap_or_else(|err| std::panic::resume_unwind(err))
+                })
+                .collect::<Vec<_>>()
+        });
+        results.sort_by_key(|(n, _, _, _)| *n);
+        results
+            .into_iter()
+            .map(|(n, m, src, diagnostics)| {
+                self.sess
+                    .merge_parsed_file(&files[pending[n]].0, src, diagnostics);
+                m
+            })
+            .collect()
+    }
+
+    /// Parse a KCL file with the loader options.
+    #[inline]
+    fn parse_file(&self, filename: &str, code: Option<String>) -> Result<ast::Module> {
+        parse_file_with_opts(self.sess.clone(), filename, code, &self.opts)
+    }
+}
+
+/// PackageFinder looks for the packages in the package root, the external package maps and
+/// the vendor directories. It only depends on the parse session and the load options, thus the
+/// loader and the [FileSystemImportResolver] share it without building another loader.
+pub(crate) struct PackageFinder<'a> {
+    pub(crate) sess: &'a ParseSessionRef,
+    pub(crate) opts: &'a LoadProgramOptions,
+}
+
+impl PackageFinder<'_> {
+    /// [`find_packages`] will find the kcl package.
+    /// If the package is found, the basic information of the package [`PkgInfo`] will be returned.
+    ///
+    /// # Errors
+    ///
+    ///  This method will return an error in the following two cases:
+    ///
+    /// 1. The package not found.
+    /// 2. The package was found both internal and external the current package.
+    pub(crate) fn find_packages(
+        &self,
+        range: Range,
+        pkg_name: &str,
+        pkg_root: &str,
+        pkg_path: &str,
+    ) -> Result<Option<PkgInfo>> {
+        // 1. Look for in the current package's directory.
+        let is_internal = self.is_internal_pkg(pkg_name, pkg_root, pkg_path)?;
+
+        // 2. Look for in the vendor path.
+        let is_external = self.is_external_pkg(pkg_path)?;
+
+        // 3. Internal and external packages cannot be duplicated
+        if is_external.is_some() && is_internal.is_some() {
+            self.sess.1.borrow_mut().add_error(
+                ErrorKind::CannotFindModule,
+                &[Message {
+                    range: range.clone(),
+                    style: Style::Line,
+                    message: format!(
+                        "the `{}` is found multiple times in the current package and vendor package",
+                        pkg_path
+                    ),
+                    note: None,
+                    suggested_replacement: None,
+                }],
+            );
+            return Ok(None);
+        }
+
+        // 4. Get package information based on whether the package is internal or external.
+        match is_internal.or(is_external) {
+            Some(pkg_info) => Ok(Some(pkg_info)),
+            None => {
+                self.sess.1.borrow_mut().add_error(
+                    ErrorKind::CannotFindModule,
+                    &[Message {
+                        range,
+                        style: Style::Line,
+                        message: format!("pkgpath {} not found in the program", pkg_path),
+                        note: None,
+                        suggested_replacement: None,
+                    }],
+                );
+                let mut suggestions =
+                    vec![format!("find more package on 'https://artifacthub.io'")];
+
+                if let Ok(pkg_name) = parse_external_pkg_name(pkg_path) {
+                    suggestions.insert(
+                        0,
+                        format!(
+                            "try 'kcl mod add {}' to download the package not found",
+                            pkg_name
+                        ),
+                    );
+                }
+                self.sess.1.borrow_mut().add_suggestions(suggestions);
+                Ok(None)
+            }
+        }
+    }
+
     fn get_pkg_kfile_list(&self, pkgroot: &str, pkgpath: &str) -> Result<Vec<String>> {
         // plugin pkgs
         if self.is_plugin_pkg(pkgpath) {
@@ -651,10 +1129,15 @@ impl Loader {
         }
 
         // builtin pkgs
-        if self.is_builtin_pkg(pkgpath) {
+        if self.is_builtin_pkg(pkgpath) && self.opts.stdlib_snapshot.is_none() {
             return Ok(Vec::new());
         }
 
+        let source_files = self.get_source_pkg_kfile_list(pkgroot, pkgpath);
+        if !source_files.is_empty() {
+            return Ok(source_files);
+        }
+
         if pkgroot.is_empty() {
             return Err(anyhow::anyhow!("pkgroot not found"));
         }
@@ -680,16 +1163,53 @@ impl Loader {
         Ok(Vec::new())
     }
 
+    /// Get the in-memory source files of the package, which is a `.k` file or the `.k`
+    /// files directly in the package directory.
+    fn get_source_pkg_kfile_list(&self, pkgroot: &str, pkgpath: &str) -> Vec<String> {
+        if self.opts.sources.is_empty() {
+            return Vec::new();
+        }
+        let mut pkg_dir = PathBuf::from(pkgroot);
+        pkgpath.split('.').for_each(|s| pkg_dir.push(s));
+        let pkg_file = pkg_dir.with_extension(KCL_FILE_EXTENSION);
+        let mut list = self
+            .opts
+            .sources
+            .keys()
+            .filter(|filename| {
+                let path = Path::new(filename);
+                if path == pkg_file {
+                    return true;
+                }
+                let name = match path.file_name().and_then(|n| n.to_str()) {
+                    Some(name) => name,
+                    None => return false,
+                };
+                path.parent() == Some(pkg_dir.as_path())
+                    && name.ends_with(KCL_FILE_SUFFIX)
+                    && !name.ends_with("_test.k")
+                    && !name.starts_with('_')
+            })
+            .cloned()
+            .collect::<Vec<String>>();
+        list.sort();
+        list
+    }
+
     /// Get file list in the directory.
     fn get_dir_files(&self, dir: &str) -> Result<Vec<String>> {
         if !std::path::Path::new(dir).exists() {
             return Ok(Vec::new());
         }
 
+        let ignore = KclIgnore::load_from_path(dir)?;
         let mut list = Vec::new();
 
         for path in std::fs::read_dir(dir).unwrap() {
             let path = path.unwrap();
+            if ignore.is_ignored(path.path()) {
+                continue;
+            }
             if !path
                 .file_name()
                 .to_str()
@@ -807,9 +1327,11 @@ impl Loader {
     /// Search for [`pkgpath`] under [`path`].
     /// It only returns [`true`] if [`path`]/[`pkgpath`] or [`path`]/[`kcl.mod`] exists.
     fn pkg_exists_in_path(&self, path: String, pkgpath: &str) -> bool {
-        let mut pathbuf = PathBuf::from(path);
+        let mut pathbuf = PathBuf::from(&path);
         pkgpath.split('.').for_each(|s| pathbuf.push(s));
-        pathbuf.exists() || pathbuf.with_extension(KCL_FILE_EXTENSION).exists()
+        pathbuf.exists()
+            || pathbuf.with_extension(KCL_FILE_EXTENSION).exists()
+            || !self.get_source_pkg_kfile_list(&path, pkgpath).is_empty()
     }
 }
 
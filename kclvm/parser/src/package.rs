@@ -0,0 +1,118 @@
+//! Per-file package declarations, e.g. `# kcl:package app.models`.
+//!
+//! A file may declare its logical package with a comment before its first
+//! statement, which overrides the package inferred from the file directory when
+//! the program is loaded. The files of a directory without a declaration follow
+//! the package declared by the other files of the directory, and the different
+//! declarations in the same directory are an error.
+
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use kclvm_ast::ast;
+use kclvm_error::diagnostic::Range;
+use kclvm_error::{ErrorKind, Message, Style};
+
+use crate::ParseSession;
+
+/// The comment prefix of the package declaration.
+pub(crate) const PACKAGE_DECLARATION_PREFIX: &str = "# kcl:package ";
+
+/// Returns the package declared by the module and the declaration comment. Only
+/// the comments before the first statement are declarations.
+pub(crate) fn declared_package(
+    module: &ast::Module,
+) -> Option<(String, &ast::NodeRef<ast::Comment>)> {
+    let first_line = module.body.first().map_or(u64::MAX, |stmt| stmt.line);
+    module
+        .comments
+        .iter()
+        .take_while(|comment| comment.line < first_line)
+        .find_map(|comment| {
+            let pkgpath = comment.node.text.strip_prefix(PACKAGE_DECLARATION_PREFIX)?;
+            Some((pkgpath.trim().to_string(), comment))
+        })
+}
+
+/// Returns the package declared by the modules which are in the same directory.
+/// The invalid and conflicting declarations are added into the parse session as
+/// errors, and the first valid declaration wins.
+pub(crate) fn declared_dir_package(
+    sess: &ParseSession,
+    modules: &[&ast::Module],
+) -> Option<String> {
+    let mut declared: Option<(String, String)> = None;
+    for module in modules {
+        let (pkgpath, comment) = match declared_package(module) {
+            Some(declaration) => declaration,
+            None => continue,
+        };
+        let message = if !is_pkgpath(&pkgpath) {
+            format!("invalid package declaration `{}`", pkgpath)
+        } else {
+            match &declared {
+                None => {
+                    declared = Some((pkgpath, module.filename.clone()));
+                    continue;
+                }
+                Some((expected, _)) if *expected == pkgpath => continue,
+                Some((expected, filename)) => format!(
+                    "the package `{}` conflicts with the package `{}` declared in {}",
+                    pkgpath, expected, filename
+                ),
+            }
+        };
+        sess.1.borrow_mut().add_error(
+            ErrorKind::CompileError,
+            &[Message {
+                range: Into::<Range>::into(comment.pos()),
+                style: Style::Line,
+                message,
+                note: None,
+                suggested_replacement: None,
+            }],
+        );
+    }
+    declared.map(|(pkgpath, _)| pkgpath)
+}
+
+/// Returns the packages declared by the directories of the modules, see
+/// [declared_dir_package].
+pub(crate) fn declared_dir_packages<'a>(
+    sess: &ParseSession,
+    modules: impl Iterator<Item = &'a ast::Module>,
+) -> IndexMap<PathBuf, String> {
+    let mut dirs: IndexMap<PathBuf, Vec<&ast::Module>> = IndexMap::new();
+    for module in modules {
+        dirs.entry(module_dir(module)).or_default().push(module);
+    }
+    dirs.into_iter()
+        .filter_map(|(dir, modules)| Some((dir, declared_dir_package(sess, &modules)?)))
+        .collect()
+}
+
+/// Returns the directory of the module file.
+pub(crate) fn module_dir(module: &ast::Module) -> PathBuf {
+    Path::new(&module.filename)
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default()
+}
+
+/// Returns the full package path of the declared package in the package `pkg_name`.
+pub(crate) fn declared_pkg_fullpath(pkg_name: &str, declared: &str) -> String {
+    if pkg_name == kclvm_ast::MAIN_PKG {
+        declared.to_string()
+    } else {
+        format!("{}.{}", pkg_name, declared)
+    }
+}
+
+/// Whether the package path is the dot-separated identifiers.
+fn is_pkgpath(pkgpath: &str) -> bool {
+    pkgpath.split('.').all(|name| {
+        let mut chars = name.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
@@ -580,6 +580,15 @@ impl<'a> Parser<'a> {
     /// Syntax:
     /// operand: identifier | number | string | constant | quant_expr | list_expr | list_comp | config_expr | dict_comp | identifier call_suffix | schema_expr | lambda_expr | paren_expr
     fn parse_operand_expr(&mut self) -> NodeRef<Expr> {
+        if !self.enter_nested() {
+            return self.missing_expr();
+        }
+        let expr = self.do_parse_operand_expr();
+        self.exit_nested();
+        expr
+    }
+
+    fn do_parse_operand_expr(&mut self) -> NodeRef<Expr> {
         let token = self.token;
 
         // try primary expr
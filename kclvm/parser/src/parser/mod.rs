@@ -45,8 +45,19 @@ pub struct Parser<'a> {
     comments: Vec<NodeRef<Comment>>,
     /// parse-time session
     pub sess: &'a ParseSession,
+    /// The current nesting depth of statements and expressions.
+    depth: usize,
+    /// The max nesting depth, the parser reports an error instead of
+    /// overflowing the stack when the depth is exceeded.
+    max_depth: usize,
 }
 
+/// The default max nesting depth of statements and expressions, which needs a
+/// large stack of the parsing thread, e.g., the stack of [`parse_file_safe`].
+///
+/// [`parse_file_safe`]: crate::parse_file_safe
+pub const DEFAULT_MAX_PARSE_DEPTH: usize = 1000;
+
 /// The DropMarker is used to mark whether to discard the token Mark whether to discard the token.
 /// The principle is to store the index of the token in the token stream. When there is no index
 /// change during the parse process, it is discarded and an error is output
@@ -63,6 +74,8 @@ impl<'a> Parser<'a> {
             cursor: TokenStream::new(non_comment_tokens).cursor(),
             comments,
             sess,
+            depth: 0,
+            max_depth: DEFAULT_MAX_PARSE_DEPTH,
         };
 
         // bump to the first token
@@ -71,6 +84,39 @@ impl<'a> Parser<'a> {
         parser
     }
 
+    /// Set the max nesting depth of statements and expressions.
+    #[inline]
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Enter a nested statement or expression. When the max nesting depth is
+    /// exceeded, it reports an error, skips all the remaining tokens and returns
+    /// `false`, then the caller should return without parsing deeper.
+    pub(crate) fn enter_nested(&mut self) -> bool {
+        if self.depth >= self.max_depth {
+            self.sess.struct_span_error(
+                &format!(
+                    "the max parse depth {} is exceeded, the statement or expression is nested too deeply",
+                    self.max_depth
+                ),
+                self.token.span,
+            );
+            while !matches!(self.token.kind, TokenKind::Eof) {
+                self.bump();
+            }
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    /// Exit a nested statement or expression entered by [`Parser::enter_nested`].
+    #[inline]
+    pub(crate) fn exit_nested(&mut self) {
+        self.depth -= 1;
+    }
+
     /// Get an AST position from the token pair (lo_tok, hi_tok).
     #[inline]
     pub(crate) fn token_span_pos(&mut self, lo_tok: Token, hi_tok: Token) -> PosTuple {
@@ -17,6 +17,7 @@ impl<'a> Parser<'a> {
             doc,
             comments: self.comments.clone(),
             body,
+            attached_comments: Default::default(),
         }
     }
 
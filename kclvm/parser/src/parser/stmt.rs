@@ -3,7 +3,7 @@
 
 use compiler_base_span::{span::new_byte_pos, BytePos, Span};
 use kclvm_ast::token::VALID_SPACES_LENGTH;
-use kclvm_ast::token::{CommentKind, DelimToken, LitKind, Token, TokenKind};
+use kclvm_ast::token::{BinOpToken, CommentKind, DelimToken, LitKind, Token, TokenKind};
 use kclvm_ast::{ast::*, expr_as, node_ref};
 use kclvm_span::symbol::kw;
 
@@ -19,6 +19,15 @@ impl<'a> Parser<'a> {
     /// simple_stmt: (assign_stmt | expr_stmt | assert_stmt | import_stmt | type_alias_stmt) NEWLINE
     /// compound_stmt: if_stmt | schema_stmt
     pub(crate) fn parse_stmt(&mut self) -> Option<NodeRef<Stmt>> {
+        if !self.enter_nested() {
+            return None;
+        }
+        let stmt = self.do_parse_stmt();
+        self.exit_nested();
+        stmt
+    }
+
+    fn do_parse_stmt(&mut self) -> Option<NodeRef<Stmt>> {
         // skip new lines
         if matches!(self.token.kind, TokenKind::Newline) {
             self.skip_newlines();
@@ -432,7 +441,21 @@ impl<'a> Parser<'a> {
             leading_dot.push(".".to_string());
             self.bump_token(TokenKind::Dot);
         }
-        let dot_name = self.parse_identifier().node;
+        // The package path from the module root alias, e.g., `@root/path/to/sub`.
+        let mut root_alias = None;
+        if let TokenKind::At = self.token.kind {
+            self.bump_token(TokenKind::At);
+            let alias = self.parse_identifier().node;
+            root_alias = Some(format!("@{}", alias.get_names().join(".")));
+            self.bump_token(TokenKind::BinOp(BinOpToken::Slash));
+        }
+        let mut dot_name = self.parse_identifier().node;
+        if root_alias.is_some() {
+            while let TokenKind::BinOp(BinOpToken::Slash) = self.token.kind {
+                self.bump_token(TokenKind::BinOp(BinOpToken::Slash));
+                dot_name.names.extend(self.parse_identifier().node.names);
+            }
+        }
         let dot_name_end_token = self.prev_token;
 
         let asname = if self.token.is_keyword(kw::As) {
@@ -450,7 +473,10 @@ impl<'a> Parser<'a> {
             None
         };
 
-        let mut path = leading_dot.join("");
+        let mut path = match &root_alias {
+            Some(alias) => format!("{}/", alias),
+            None => leading_dot.join(""),
+        };
         path.push_str(dot_name.get_names().join(".").as_str());
 
         let rawpath = path.clone();
@@ -3,9 +3,9 @@ use compiler_base_macros::bug;
 use compiler_base_session::Session;
 use indexmap::IndexSet;
 use kclvm_ast::token::Token;
-use kclvm_error::{Diagnostic, Handler, ParseError};
+use kclvm_error::{Diagnostic, Handler, Level, ParseError};
 use kclvm_span::{BytePos, Loc, Span};
-use std::{cell::RefCell, sync::Arc};
+use std::{cell::RefCell, path::PathBuf, sync::Arc};
 
 pub type ParseSessionRef = Arc<ParseSession>;
 
@@ -84,6 +84,33 @@ impl ParseSession {
         self
     }
 
+    /// Merge a file parsed with its own session into the parse session, i.e., add
+    /// the source code of the file into the source map and append the diagnostics.
+    pub(crate) fn merge_parsed_file(
+        &self,
+        filename: &str,
+        src: Option<String>,
+        diagnostics: IndexSet<Diagnostic>,
+    ) -> &Self {
+        if let Some(src) = src {
+            self.0
+                .sm
+                .new_source_file(PathBuf::from(filename).into(), src);
+        }
+        for diagnostic in diagnostics {
+            if diagnostic.level == Level::Error {
+                if let Err(err) = self.0.add_err(diagnostic.clone()) {
+                    bug!(
+                        "compiler session internal error occurs: {}",
+                        err.to_string()
+                    )
+                }
+            }
+            self.1.borrow_mut().add_diagnostic(diagnostic);
+        }
+        self
+    }
+
     /// Classify diagnostics into errors and warnings.
     pub fn classification(&self) -> (IndexSet<Diagnostic>, IndexSet<Diagnostic>) {
         self.1.borrow().classification()
@@ -0,0 +1,120 @@
+//! Copyright The KCL Authors. All rights reserved.
+//!
+//! Split a large KCL source into chunks of top-level statements. Each chunk can
+//! be lexed and parsed separately, so that only the tokens of one chunk are kept
+//! in memory at the same time.
+
+use std::ops::Range;
+
+/// The default chunk size in bytes used by the streaming parser.
+pub const DEFAULT_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Returns the byte ranges of the chunks in `src`. Each chunk holds at least
+/// `chunk_size` bytes except the last one, and every chunk except the first one
+/// starts at the beginning of a top-level statement.
+pub(crate) fn split_stmt_chunks(src: &str, chunk_size: usize) -> Vec<Range<usize>> {
+    let mut chunks = vec![];
+    let mut chunk_start = 0;
+    let mut scanner = Scanner::default();
+    let mut offset = 0;
+    for line in src.split_inclusive('\n') {
+        if offset > chunk_start && offset - chunk_start >= chunk_size && scanner.is_stmt_start(line)
+        {
+            chunks.push(chunk_start..offset);
+            chunk_start = offset;
+        }
+        scanner.scan_line(line);
+        offset += line.len();
+    }
+    chunks.push(chunk_start..src.len());
+    chunks
+}
+
+/// Scanner tracks the lexical states across lines which decide whether a line
+/// can start a new top-level statement.
+#[derive(Default)]
+struct Scanner {
+    /// The nesting depth of parentheses, brackets and braces.
+    depth: usize,
+    /// The quote byte and whether it is a triple-quoted string when in a string.
+    string: Option<(u8, bool)>,
+    /// Whether the previous line ends with a line continuation `\`.
+    continued: bool,
+    /// Whether the previous top-level line is a decorator.
+    decorated: bool,
+}
+
+impl Scanner {
+    /// Whether the line is the beginning of a top-level statement.
+    fn is_stmt_start(&self, line: &str) -> bool {
+        if self.depth > 0 || self.string.is_some() || self.continued || self.decorated {
+            return false;
+        }
+        let bytes = line.as_bytes();
+        match bytes.first() {
+            Some(b) if b.is_ascii_alphabetic() || *b == b'_' || *b == b'@' => {}
+            _ => return false,
+        }
+        let word_len = bytes
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count();
+        let word = &line[..word_len];
+        // `elif` and `else` continue the previous `if` statement, and a word followed by a
+        // quote is a string prefix which may be parsed as the module doc.
+        !matches!(word, "elif" | "else") && !matches!(bytes.get(word_len), Some(b'\'') | Some(b'"'))
+    }
+
+    fn scan_line(&mut self, line: &str) {
+        let bytes = line.as_bytes();
+        let content = line.trim_end_matches(['\n', '\r']);
+        if self.depth == 0 && self.string.is_none() && !self.continued {
+            let trimmed = content.trim_start();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                self.decorated = bytes.first() == Some(&b'@');
+            }
+        }
+        self.continued = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            match self.string {
+                Some((quote, triple)) => {
+                    if b == b'\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if triple {
+                        if bytes[i..].starts_with(&[quote, quote, quote]) {
+                            self.string = None;
+                            i += 3;
+                            continue;
+                        }
+                    } else if b == quote {
+                        self.string = None;
+                    }
+                }
+                None => match b {
+                    b'#' => break,
+                    b'\'' | b'"' => {
+                        let triple = bytes[i..].starts_with(&[b, b, b]);
+                        self.string = Some((b, triple));
+                        i += if triple { 3 } else { 1 };
+                        continue;
+                    }
+                    b'(' | b'[' | b'{' => self.depth += 1,
+                    b')' | b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                    b'\\' if i + 1 >= content.len() => self.continued = true,
+                    _ => {}
+                },
+            }
+            i += 1;
+        }
+        // Single-quoted strings can not span lines without a trailing `\`.
+        if let Some((_, false)) = self.string {
+            if !content.ends_with('\\') {
+                self.string = None;
+            }
+        }
+    }
+}
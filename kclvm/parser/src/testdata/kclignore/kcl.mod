@@ -0,0 +1,4 @@
+[package]
+name = "kclignore"
+edition = "0.0.1"
+version = "0.0.1"
@@ -0,0 +1,4 @@
+[package]
+name = "stdlib_snapshot"
+edition = "0.0.1"
+version = "0.0.1"
@@ -194,6 +194,130 @@ pub fn test_parse_file_invalid() {
     }
 }
 
+#[test]
+pub fn test_parse_file_safe_invalid() {
+    for case in PARSE_FILE_INVALID_TEST_CASES {
+        let result = parse_file_safe("test.k", Some((&case).to_string()));
+        assert!(
+            result.map_or(true, |r| !r.errors.is_empty()),
+            "case: {case}"
+        );
+    }
+}
+
+#[test]
+pub fn test_parse_file_max_depth() {
+    // Run in a thread with the large stack of the CLI to check the default limit
+    // is hit before the stack overflows.
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| {
+            let code = format!("a = {}1{}", "[".repeat(10000), "]".repeat(10000));
+            parse_file_force_errors("test.k", Some(code))
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    let err = result.unwrap_err().to_string();
+    assert!(
+        err.contains("the max parse depth 1000 is exceeded"),
+        "{err}"
+    );
+
+    let sess = Arc::new(ParseSession::default());
+    create_session_globals_then(|| {
+        parse_file_with_max_depth(sess.clone(), "test.k", Some("a = [[[1]]]".to_string()), 3)
+    })
+    .unwrap();
+    assert!(sess.0.diag_handler.has_errors().unwrap());
+    let sess = Arc::new(ParseSession::default());
+    create_session_globals_then(|| {
+        parse_file_with_max_depth(sess.clone(), "test.k", Some("a = [[1]]".to_string()), 4)
+    })
+    .unwrap();
+    assert!(!sess.0.diag_handler.has_errors().unwrap());
+}
+
+const FUZZ_SEED_CODE: &str = r#"import math
+schema Person[name: str](Base):
+    """Person doc"""
+    name: str = name
+    age?: int = 1
+    labels: {str:str} = {"a": "b"}
+    check:
+        age > 0 if age, "age must be positive"
+
+p = Person("Alice") {
+    age = math.floor(1.5)
+    items = [i * 2 for i in range(10) if i % 2 == 0]
+}
+s = "${p.name}: ${p.age:#json}"
+x = lambda a: int -> int { a + 1 }(1)
+"#;
+
+#[test]
+pub fn test_parse_file_safe_fuzz() {
+    // A deterministic xorshift generator keeps the fuzz cases reproducible.
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let alphabet = FUZZ_SEED_CODE.as_bytes();
+    let mut cases: Vec<Vec<u8>> = vec![];
+    // Truncated programs.
+    for i in 0..alphabet.len() {
+        cases.push(alphabet[..i].to_vec());
+    }
+    for _ in 0..256 {
+        // Random byte sequences.
+        let len = (next() % 64) as usize;
+        cases.push((0..len).map(|_| next() as u8).collect());
+        // Random sequences of KCL tokens and symbols.
+        let len = (next() % 128) as usize;
+        cases.push(
+            (0..len)
+                .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                .collect(),
+        );
+        // Programs with random mutated bytes.
+        let mut mutated = alphabet.to_vec();
+        for _ in 0..(next() % 8 + 1) {
+            let pos = (next() % mutated.len() as u64) as usize;
+            mutated[pos] = alphabet[(next() % alphabet.len() as u64) as usize];
+        }
+        cases.push(mutated);
+    }
+    for case in cases {
+        let code = String::from_utf8_lossy(&case).to_string();
+        let result = catch_unwind(|| parse_file_safe("fuzz.k", Some(code.clone())));
+        let result = match result {
+            Ok(result) => result,
+            Err(_) => panic!("parse_file_safe panics with the code: {code:?}"),
+        };
+        // The input which crashes the parser returns an error.
+        if catch_unwind(|| parse_file("fuzz.k", Some(code.clone()))).is_err() {
+            assert!(result.is_err(), "expect an error with the code: {code:?}");
+        }
+    }
+}
+
+#[test]
+pub fn test_parse_file_safe_deep_nesting() {
+    // The deep nesting is a parse error instead of a stack overflow even in the
+    // test thread with a small stack.
+    for (open, close) in [("[", "]"), ("(", ")"), ("{a: ", "}")] {
+        let code = format!("a = {}1{}", open.repeat(100000), close.repeat(100000));
+        let result = parse_file_safe("test.k", Some(code)).unwrap();
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| e.messages[0].message.contains("the max parse depth")));
+    }
+}
+
 pub fn test_vendor_home() {
     let vendor = &PathBuf::from(".")
         .join("testdata")
@@ -737,3 +861,451 @@ pub fn test_pkg_not_found_suggestion() {
         }
     }
 }
+
+#[test]
+fn test_load_program_data_import() {
+    let sess = Arc::new(ParseSession::default());
+    let dir = PathBuf::from("./src/testdata/data_import")
+        .canonicalize()
+        .unwrap();
+    let main = dir.join("main.k").display().to_string();
+    let program = load_program(sess.clone(), &[&main], None, None)
+        .unwrap()
+        .program;
+    let modules = &program.pkgs["data.yaml"];
+    assert_eq!(modules.len(), 1);
+    assert_eq!(
+        modules[0].filename,
+        dir.join("data.yaml").display().to_string()
+    );
+    let names: Vec<String> = modules[0]
+        .body
+        .iter()
+        .filter_map(|stmt| match &stmt.node {
+            kclvm_ast::ast::Stmt::Assign(assign) => Some(assign.targets[0].node.get_name()),
+            _ => None,
+        })
+        .collect();
+    // The whole document is bound besides the keys which are valid variable names.
+    assert_eq!(names, vec!["name", "replicas", "labels", "__data__"]);
+    // The malformed JSON file reports the syntax error at the offending location.
+    assert!(program.pkgs["broken.json"][0].body.is_empty());
+    let (errors, warnings) = sess.classification();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].messages[0]
+        .message
+        .starts_with("the key \"not-an-ident\" of the data file can not be bound"));
+    assert_eq!(errors.len(), 1);
+    let pos = &errors[0].messages[0].range.0;
+    assert_eq!(pos.filename, dir.join("broken.json").display().to_string());
+    assert_eq!((pos.line, pos.column), (3, Some(14)));
+}
+
+#[test]
+fn test_load_program_stdlib_snapshot() {
+    let sess = Arc::new(ParseSession::default());
+    let dir = PathBuf::from("./src/testdata/stdlib_snapshot")
+        .canonicalize()
+        .unwrap();
+    let snapshot = dir.join("snapshot");
+    let main = dir.join("main.k").display().to_string();
+    let opts = LoadProgramOptions {
+        stdlib_snapshot: Some(snapshot.display().to_string()),
+        ..Default::default()
+    };
+    let program = load_program(sess.clone(), &[&main], Some(opts), None)
+        .unwrap()
+        .program;
+    let modules = &program.pkgs["__stdlib__.math"];
+    assert_eq!(modules.len(), 1);
+    assert_eq!(
+        modules[0].filename,
+        snapshot.join("math").join("math.k").display().to_string()
+    );
+    // The import path is rewritten to the snapshot package.
+    if let kclvm_ast::ast::Stmt::Import(import_stmt) =
+        &program.pkgs[kclvm_ast::MAIN_PKG][0].body[0].node
+    {
+        assert_eq!(import_stmt.path.node, "__stdlib__.math");
+        assert_eq!(import_stmt.name, "math");
+    } else {
+        panic!("expected the import statement");
+    }
+    // The system module missing in the snapshot is an error.
+    let errors = sess.classification().0;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].messages[0]
+        .message
+        .contains("the system module regex is not found in the stdlib snapshot"));
+}
+
+#[test]
+fn test_load_program_package_declaration() {
+    let sess = Arc::new(ParseSession::default());
+    let dir = PathBuf::from("./src/testdata/package_declaration")
+        .canonicalize()
+        .unwrap();
+    let main = dir.join("main.k").display().to_string();
+    let program = load_program(sess.clone(), &[&main], None, None)
+        .unwrap()
+        .program;
+    // The files of the `models` directory are loaded into the declared package.
+    assert!(!program.pkgs.contains_key("models"));
+    let modules = &program.pkgs["app.models"];
+    let filenames: Vec<String> = modules.iter().map(|m| m.filename.clone()).collect();
+    assert_eq!(
+        filenames,
+        ["defaults.k", "person.k"]
+            .iter()
+            .map(|f| dir.join("models").join(f).display().to_string())
+            .collect::<Vec<String>>()
+    );
+    assert!(modules.iter().all(|m| m.pkg == "app.models"));
+    // The import path is rewritten to the declared package.
+    if let kclvm_ast::ast::Stmt::Import(import_stmt) =
+        &program.pkgs[kclvm_ast::MAIN_PKG][0].body[0].node
+    {
+        assert_eq!(import_stmt.path.node, "app.models");
+        assert_eq!(import_stmt.name, "models");
+    } else {
+        panic!("expected the import statement");
+    }
+    assert!(sess.classification().0.is_empty());
+
+    // The different declarations in the same directory are an error.
+    let sess = Arc::new(ParseSession::default());
+    let paths = ["a.k", "b.k"]
+        .iter()
+        .map(|f| dir.join("conflict").join(f).display().to_string())
+        .collect::<Vec<String>>();
+    let paths = paths.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+    let program = load_program(sess.clone(), &paths, None, None)
+        .unwrap()
+        .program;
+    assert_eq!(program.pkgs["app.a"].len(), 2);
+    assert!(program.pkgs[kclvm_ast::MAIN_PKG].is_empty());
+    let errors = sess.classification().0;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].messages[0]
+        .message
+        .contains("the package `app.b` conflicts with the package `app.a`"));
+}
+
+/// Resolves the imports from the in-memory packages keyed by the import paths.
+#[derive(Debug, Default)]
+struct MemoryImportResolver {
+    packages: HashMap<String, String>,
+    importers: std::sync::Mutex<Vec<(String, String)>>,
+}
+
+impl ImportResolver for MemoryImportResolver {
+    fn resolve(
+        &self,
+        sess: &ParseSessionRef,
+        request: &ImportRequest,
+    ) -> Result<Option<ResolvedPackage>> {
+        self.importers
+            .lock()
+            .unwrap()
+            .push((request.pkgpath.to_string(), request.importer().to_string()));
+        let code = match self.packages.get(request.pkgpath) {
+            Some(code) => code,
+            None => {
+                sess.1.borrow_mut().add_error(
+                    ErrorKind::CannotFindModule,
+                    &[Message {
+                        range: request.range.clone(),
+                        style: Style::Line,
+                        message: format!("pkgpath {} not found in the memory", request.pkgpath),
+                        note: None,
+                        suggested_replacement: None,
+                    }],
+                );
+                return Ok(None);
+            }
+        };
+        let filename = format!("memory/{}.k", request.pkgpath.replace('.', "/"));
+        Ok(Some(ResolvedPackage {
+            pkg_name: request.pkg_name.to_string(),
+            pkg_root: "".to_string(),
+            pkg_path: request.pkgpath.to_string(),
+            k_files: vec![filename.clone()],
+            sources: HashMap::from([(filename, code.to_string())]),
+        }))
+    }
+}
+
+#[test]
+fn test_load_program_import_resolver() {
+    let resolver = Arc::new(MemoryImportResolver {
+        packages: HashMap::from([
+            (
+                "base".to_string(),
+                "import base.labels\n\nname = \"base\"\nlabels = labels.app\n".to_string(),
+            ),
+            ("base.labels".to_string(), "app = \"nginx\"\n".to_string()),
+        ]),
+        ..Default::default()
+    });
+    let sess = Arc::new(ParseSession::default());
+    let opts = LoadProgramOptions {
+        k_code_list: vec!["import base\nimport missing\n\nname = base.name\n".to_string()],
+        import_resolver: Some(resolver.clone()),
+        ..Default::default()
+    };
+    let program = load_program(sess.clone(), &["main.k"], Some(opts), None)
+        .unwrap()
+        .program;
+    for (pkgpath, filename) in [
+        ("base", "memory/base.k"),
+        ("base.labels", "memory/base/labels.k"),
+    ] {
+        let modules = &program.pkgs[pkgpath];
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].filename, filename);
+        assert_eq!(modules[0].pkg, pkgpath);
+        assert_eq!(modules[0].body.len(), if pkgpath == "base" { 3 } else { 1 });
+    }
+    assert!(!program.pkgs.contains_key("missing"));
+    // The resolver is consulted with the importer locations.
+    let main = &program.pkgs[kclvm_ast::MAIN_PKG][0].filename;
+    let importers = resolver.importers.lock().unwrap().clone();
+    assert_eq!(
+        importers,
+        vec![
+            ("base".to_string(), main.to_string()),
+            ("base.labels".to_string(), "memory/base.k".to_string()),
+            ("missing".to_string(), main.to_string()),
+        ]
+    );
+    let errors = sess.classification().0;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].messages[0].message,
+        "pkgpath missing not found in the memory"
+    );
+}
+
+#[test]
+fn test_load_program_file_system_import_resolver() {
+    let dir = PathBuf::from("./src/testdata/package_declaration")
+        .canonicalize()
+        .unwrap();
+    let main = dir.join("main.k").display().to_string();
+    let load = |opts: LoadProgramOptions| {
+        let sess = Arc::new(ParseSession::default());
+        let program = load_program(sess.clone(), &[&main], Some(opts), None)
+            .unwrap()
+            .program;
+        assert!(sess.classification().0.is_empty());
+        serde_json::to_value(&program).unwrap()
+    };
+    // The explicit file system resolver loads the same program as the default one,
+    // including the packages declared by their files.
+    let opts = LoadProgramOptions::default();
+    let explicit = LoadProgramOptions {
+        import_resolver: Some(Arc::new(FileSystemImportResolver::new(opts.clone()))),
+        ..opts.clone()
+    };
+    let program = load(opts);
+    assert!(program["pkgs"].get("app.models").is_some());
+    assert_eq!(load(explicit), program);
+}
+
+#[test]
+fn test_get_dir_files_with_kclignore() {
+    let testpath = PathBuf::from("./src/testdata/kclignore")
+        .canonicalize()
+        .unwrap();
+    let files = crate::entry::get_dir_files(&testpath.display().to_string(), true).unwrap();
+    let expected: Vec<String> = ["keep/test_keep.k", "main.k", "sub/sub.k"]
+        .iter()
+        .map(|f| testpath.join(f).display().to_string())
+        .collect();
+    assert_eq!(files, expected);
+}
+
+#[test]
+fn test_split_stmt_chunks() {
+    let src = r#"a = 1
+@deco
+schema A:
+    """doc
+b = 2
+    """
+    c: int = 1
+if True:
+    d = [
+e
+    ]
+elif False:
+    f = 1
+g = 1 + \
+h
+i = 1
+"#;
+    let chunks = crate::stream::split_stmt_chunks(src, 0);
+    let starts: Vec<&str> = chunks
+        .iter()
+        .map(|range| src[range.clone()].lines().next().unwrap_or_default())
+        .collect();
+    assert_eq!(
+        starts,
+        vec!["a = 1", "@deco", "if True:", "g = 1 + \\", "i = 1"]
+    );
+    assert_eq!(chunks.last().unwrap().end, src.len());
+}
+
+#[test]
+fn test_load_program_keep_comments() {
+    let code = r#"# leading comment of a
+a = 1  # inline comment of a
+schema Person:
+    # leading comment of name
+    name: str
+    age: int  # inline comment of age
+    # trailing comment of age
+
+b = 2
+"#;
+    let opts = LoadProgramOptions {
+        k_code_list: vec![code.to_string()],
+        keep_comments: true,
+        ..Default::default()
+    };
+    let program = load_program(
+        Arc::new(ParseSession::default()),
+        &["test_keep_comments.k"],
+        Some(opts),
+        None,
+    )
+    .unwrap()
+    .program;
+    let module = program.get_main_package_first_module().unwrap();
+    let texts = |comments: &[kclvm_ast::ast::NodeRef<kclvm_ast::ast::Comment>]| -> Vec<String> {
+        comments.iter().map(|c| c.node.text.clone()).collect()
+    };
+
+    let a = &module.attached_comments[&module.body[0].id];
+    assert_eq!(texts(&a.leading), vec!["# leading comment of a"]);
+    assert_eq!(texts(&a.inline), vec!["# inline comment of a"]);
+
+    let schema_stmt = match &module.body[1].node {
+        kclvm_ast::ast::Stmt::Schema(schema_stmt) => schema_stmt,
+        _ => panic!("expect a schema statement"),
+    };
+    let name = &module.attached_comments[&schema_stmt.body[0].id];
+    assert_eq!(texts(&name.leading), vec!["# leading comment of name"]);
+    assert_eq!(name.leading[0].line, 4);
+    let age = &module.attached_comments[&schema_stmt.body[1].id];
+    assert_eq!(texts(&age.inline), vec!["# inline comment of age"]);
+    assert_eq!(texts(&age.trailing), vec!["# trailing comment of age"]);
+    assert!(!module.attached_comments.contains_key(&module.body[2].id));
+
+    // Comments are not attached without the `keep_comments` option.
+    let opts = LoadProgramOptions {
+        k_code_list: vec![code.to_string()],
+        ..Default::default()
+    };
+    let program = load_program(
+        Arc::new(ParseSession::default()),
+        &["test_keep_comments.k"],
+        Some(opts),
+        None,
+    )
+    .unwrap()
+    .program;
+    let module = program.get_main_package_first_module().unwrap();
+    assert!(module.attached_comments.is_empty());
+}
+
+#[test]
+fn test_load_program_parse_threads() {
+    let testpath = PathBuf::from("./src/testdata/parallel_parse")
+        .canonicalize()
+        .unwrap();
+    let paths = [
+        testpath.join("main.k").display().to_string(),
+        testpath.join("base.k").display().to_string(),
+    ];
+    let paths = paths.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+    let load = |parse_threads: usize| {
+        let opts = LoadProgramOptions {
+            parse_threads,
+            ..Default::default()
+        };
+        let program = load_program(Arc::new(ParseSession::default()), &paths, Some(opts), None)
+            .unwrap()
+            .program;
+        let mut pkgs = program.pkgs.iter().collect::<Vec<_>>();
+        pkgs.sort_by_key(|(pkgpath, _)| pkgpath.to_string());
+        serde_json::to_string(&pkgs).unwrap()
+    };
+    let expected = load(1);
+    assert!(expected.contains("default_service"));
+    for parse_threads in [2, 4, 8] {
+        assert_eq!(load(parse_threads), expected);
+    }
+}
+
+#[test]
+fn test_load_program_parse_threads_with_errors() {
+    let testpath = PathBuf::from("./src/testdata/parallel_parse")
+        .canonicalize()
+        .unwrap();
+    let main_file = testpath.join("main.k").display().to_string();
+    let base_file = testpath.join("base.k").display().to_string();
+    let main_src = std::fs::read_to_string(&main_file).unwrap();
+    let load = |parse_threads: usize| {
+        let opts = LoadProgramOptions {
+            parse_threads,
+            sources: HashMap::from([(base_file.clone(), "a = 1 +\n".to_string())]),
+            ..Default::default()
+        };
+        let sess = Arc::new(ParseSession::default());
+        let _ = load_program(
+            sess.clone(),
+            &[main_file.as_str(), base_file.as_str()],
+            Some(opts),
+            None,
+        );
+        sess
+    };
+    let expected = load(1);
+    let expected_diags = expected.1.borrow().diagnostics.clone();
+    assert!(!expected_diags.is_empty());
+    for parse_threads in [2, 4] {
+        let sess = load(parse_threads);
+        // The diagnostics and the source files of the files parsed in the threads are
+        // merged into the loader session.
+        assert_eq!(sess.1.borrow().diagnostics, expected_diags);
+        assert!(sess.0.diag_handler.has_errors().unwrap());
+        let sf = sess.0.sm.lookup_source_file(new_byte_pos(0));
+        assert_eq!(
+            sf.src.as_ref().map(|src| src.as_str()),
+            Some(main_src.as_str())
+        );
+    }
+}
+
+#[test]
+fn test_load_program_malformed_mod_file() {
+    let testpath = PathBuf::from("./src/testdata/malformed_mod")
+        .canonicalize()
+        .unwrap();
+    let main_file = testpath.join("main.k").display().to_string();
+    let sess = Arc::new(ParseSession::default());
+    let program = load_program(sess.clone(), &[main_file.as_str()], None, None)
+        .unwrap()
+        .program;
+    // The malformed `kcl.mod` is reported and the imports are resolved with the
+    // default root alias.
+    let errors = sess.classification().0;
+    assert_eq!(errors.len(), 1);
+    let message = &errors[0].messages[0].message;
+    assert!(
+        message.starts_with("failed to load the module root alias: failed to parse"),
+        "{message}"
+    );
+    assert!(program.pkgs.contains_key("sub"));
+}
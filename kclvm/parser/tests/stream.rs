@@ -0,0 +1,116 @@
+//! The streaming parse test lives in its own test binary because it installs
+//! a global allocation-counting allocator to measure the peak memory.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use kclvm_parser::{load_program, LoadProgramOptions, ParseSession};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Generate a large module with schemas, decorators, conditions, multi-line
+/// expressions and strings to cover the chunk boundaries.
+fn gen_large_module() -> String {
+    let mut code = String::from("\"\"\"Generated module doc.\"\"\"\nimport math\n\n");
+    for i in 0..2000 {
+        code.push_str(&format!(
+            r#"# Comment {i}
+@deprecated()
+schema Config{i}:
+    """Config{i} doc
+
+name = "config{i}"
+    """
+    name: str = "config{i}"
+    replicas: int = {i}
+    labels: {{str:str}} = {{
+        app = "app{i}"
+    }}
+
+    check:
+        replicas >= 0, "replicas must be non-negative"
+
+config{i} = Config{i} {{
+    labels = {{
+        "key{i}" = "value{i}"
+    }}
+}}
+if config{i}.replicas > 10:
+    large{i} = True
+elif config{i}.replicas > 5:
+    medium{i} = True
+else:
+    small{i} = True
+values{i} = [
+    1, 2, 3
+] + \
+    [4, 5]
+message{i} = '''multi
+line {i}
+'''
+"#
+        ));
+    }
+    code
+}
+
+fn parse_with_peak(filename: &str, streaming: bool) -> (String, usize) {
+    let opts = LoadProgramOptions {
+        streaming,
+        ..Default::default()
+    };
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let program = load_program(
+        Arc::new(ParseSession::default()),
+        &[filename],
+        Some(opts),
+        None,
+    )
+    .unwrap()
+    .program;
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+    let module = program.get_main_package_first_module().unwrap().clone();
+    (serde_json::to_string(&module).unwrap(), peak)
+}
+
+#[test]
+fn test_parse_large_file_streaming() {
+    let dir = std::env::temp_dir().join("kclvm_parser_stream_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("main.k");
+    std::fs::write(&file, gen_large_module()).unwrap();
+    let filename = file.to_str().unwrap();
+
+    let (expected, full_peak) = parse_with_peak(filename, false);
+    let (got, streaming_peak) = parse_with_peak(filename, true);
+    assert_eq!(got, expected);
+    assert!(
+        streaming_peak < full_peak,
+        "streaming peak {streaming_peak} should be lower than {full_peak}"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
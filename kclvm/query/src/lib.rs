@@ -18,7 +18,10 @@ use kclvm_ast_pretty::print_ast_module;
 use kclvm_parser::parse_file;
 
 pub use query::{get_schema_type, GetSchemaOption};
-pub use r#override::{apply_override_on_module, apply_overrides};
+pub use r#override::{
+    apply_override_on_module, apply_overrides, apply_overrides_with_outcomes,
+    check_stale_overrides, OverrideOutcome,
+};
 
 use self::r#override::parse_override_spec;
 
@@ -9,7 +9,7 @@ use kclvm_ast::path::get_key_path;
 use kclvm_ast::walk_list_mut;
 use kclvm_ast::walker::MutSelfMutWalker;
 use kclvm_ast::MAIN_PKG;
-use kclvm_ast_pretty::print_ast_module;
+use kclvm_ast_pretty::{print_ast_module, print_ast_node, print_schema_expr, ASTNode};
 use kclvm_parser::parse_expr;
 use kclvm_sema::pre_process::{fix_config_expr_nest_attr, transform_multi_assign};
 
@@ -44,22 +44,95 @@ pub fn apply_overrides(
     import_paths: &[String],
     print_ast: bool,
 ) -> Result<()> {
+    apply_overrides_with_outcomes(prog, overrides, import_paths, print_ast)?;
+    Ok(())
+}
+
+/// The outcome of an override specification on the program, which are ordered
+/// by how much the override takes effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OverrideOutcome {
+    /// The override selector matches nothing.
+    Unmatched,
+    /// The override matches, but the values it sets are equal to the existing
+    /// ones or the values it deletes do not exist.
+    NoOp,
+    /// The override changes the program.
+    Changed,
+}
+
+/// Apply overrides on the AST program like [apply_overrides], and returns the
+/// outcome of each override specification in order.
+pub fn apply_overrides_with_outcomes(
+    prog: &mut ast::Program,
+    overrides: &[ast::OverrideSpec],
+    import_paths: &[String],
+    print_ast: bool,
+) -> Result<Vec<OverrideOutcome>> {
+    let mut outcomes = vec![];
     for o in overrides {
         let pkgpath = if o.pkgpath.is_empty() {
             MAIN_PKG
         } else {
             &o.pkgpath
         };
+        let mut outcome = OverrideOutcome::Unmatched;
         if let Some(modules) = prog.pkgs.get_mut(pkgpath) {
             for m in modules.iter_mut() {
-                if apply_override_on_module(m, o, import_paths)? && print_ast {
+                let module_outcome = apply_override_on_module_with_outcome(m, o, import_paths)?;
+                if module_outcome != OverrideOutcome::Unmatched && print_ast {
                     let code_str = print_ast_module(m);
                     std::fs::write(&m.filename, &code_str)?
                 }
+                outcome = outcome.max(module_outcome);
             }
         }
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Returns an error listing the overrides which match nothing or are no-ops,
+/// which are usually the stale overrides.
+pub fn check_stale_overrides(
+    overrides: &[ast::OverrideSpec],
+    outcomes: &[OverrideOutcome],
+) -> Result<()> {
+    let stale = overrides
+        .iter()
+        .zip(outcomes)
+        .filter_map(|(o, outcome)| match outcome {
+            OverrideOutcome::Unmatched => Some(format!(
+                "the override '{}' matches nothing",
+                override_spec_string(o)
+            )),
+            OverrideOutcome::NoOp => Some(format!(
+                "the override '{}' is a no-op",
+                override_spec_string(o)
+            )),
+            OverrideOutcome::Changed => None,
+        })
+        .collect::<Vec<String>>();
+    if stale.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(stale.join("\n")))
+    }
+}
+
+/// Returns the command line form of the override spec, e.g., `pkg:config.id=1`.
+fn override_spec_string(o: &ast::OverrideSpec) -> String {
+    let pkgpath = if o.pkgpath.is_empty() {
+        String::new()
+    } else {
+        format!("{}:", o.pkgpath)
+    };
+    match o.action {
+        ast::OverrideAction::CreateOrUpdate => {
+            format!("{}{}={}", pkgpath, o.field_path, o.field_value)
+        }
+        ast::OverrideAction::Delete => format!("{}{}-", pkgpath, o.field_path),
     }
-    Ok(())
 }
 
 /// Build a expression from string.
@@ -106,6 +179,16 @@ pub fn apply_override_on_module(
     o: &ast::OverrideSpec,
     import_paths: &[String],
 ) -> Result<bool> {
+    Ok(apply_override_on_module_with_outcome(m, o, import_paths)? != OverrideOutcome::Unmatched)
+}
+
+/// Apply overrides on the AST module like [apply_override_on_module], and returns
+/// the outcome of the override specification on the module.
+pub fn apply_override_on_module_with_outcome(
+    m: &mut ast::Module,
+    o: &ast::OverrideSpec,
+    import_paths: &[String],
+) -> Result<OverrideOutcome> {
     // Apply import paths on AST module.
     apply_import_paths_on_module(m, import_paths)?;
     let ss = parse_attribute_path(&o.field_path)?;
@@ -137,10 +220,17 @@ pub fn apply_override_on_module(
         override_value: build_expr_from_string(value),
         override_target_count: 0,
         has_override: false,
+        has_change: false,
         action: o.action.clone(),
     };
     transformer.walk_module(m);
-    Ok(transformer.has_override)
+    Ok(if transformer.has_change {
+        OverrideOutcome::Changed
+    } else if transformer.has_override {
+        OverrideOutcome::NoOp
+    } else {
+        OverrideOutcome::Unmatched
+    })
 }
 
 /// Parse override spec string to override structure.
@@ -235,6 +325,8 @@ struct OverrideTransformer {
     pub override_value: Option<ast::NodeRef<ast::Expr>>,
     pub override_target_count: usize,
     pub has_override: bool,
+    /// Whether the override changes any value.
+    pub has_change: bool,
     pub action: ast::OverrideAction,
 }
 
@@ -260,6 +352,7 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
                                 let mut value = self.clone_override_value();
                                 // Use position information that needs to override the expression.
                                 value.set_pos(item.pos());
+                                self.has_change |= !expr_eq(&item, &value);
                                 // Override the node value.
                                 assign_stmt.value = value;
                                 self.has_override = true;
@@ -284,6 +377,8 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
                             // Unification is only support to override the schema expression.
                             if let ast::Expr::Schema(schema_expr) = value.node {
                                 self.has_override = true;
+                                self.has_change |= print_schema_expr(&item.node)
+                                    != print_schema_expr(&schema_expr);
                                 unification_stmt.value =
                                     Box::new(ast::Node::dummy_node(schema_expr));
                             }
@@ -303,6 +398,7 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
                             let target = get_key_path(target);
                             if target == self.target_id {
                                 self.has_override = true;
+                                self.has_change = true;
                                 return false;
                             }
                         }
@@ -317,6 +413,7 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
                         };
                         if target.node == self.target_id && self.field_paths.len() == 0 {
                             self.has_override = true;
+                            self.has_change = true;
                             return false;
                         }
                     }
@@ -383,6 +480,7 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
                                 operation: ast::ConfigEntryOperation::Override,
                                 insert_index: -1,
                             })));
+                        self.has_change = true;
                     }
                 }
             }
@@ -412,10 +510,10 @@ impl<'ctx> MutSelfMutWalker<'ctx> for OverrideTransformer {
 impl OverrideTransformer {
     /// Lookup schema config all fields and replace if it is matched with the override spec,
     /// return whether is found a replaced one.
-    fn lookup_config_and_replace(&self, config_expr: &mut ast::ConfigExpr) -> bool {
+    fn lookup_config_and_replace(&mut self, config_expr: &mut ast::ConfigExpr) -> bool {
         // Split a path into multiple parts. `a.b.c` -> ["a", "b", "c"]
-        let parts = self
-            .field_paths
+        let field_paths = self.field_paths.clone();
+        let parts = field_paths
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<&str>>();
@@ -425,7 +523,7 @@ impl OverrideTransformer {
     /// Replace AST config expr with one part of path. The implementation of this function
     /// uses recursive matching to find the config entry need to be modified.
     fn replace_config_with_path_parts(
-        &self,
+        &mut self,
         config_expr: &mut ast::ConfigExpr,
         parts: &[&str],
     ) -> bool {
@@ -456,6 +554,7 @@ impl OverrideTransformer {
                             let mut value = self.clone_override_value();
                             // Use position information that needs to override the expression.
                             value.set_pos(item.pos());
+                            self.has_change |= !expr_eq(&item.node.value, &value);
                             // Override the node value.
                             item.node.value = value;
                             changed = true;
@@ -465,6 +564,7 @@ impl OverrideTransformer {
                             // Because we can't delete the entry directly in the loop
                             delete_index_set.insert(i);
                             changed = true;
+                            self.has_change = true;
                         }
                     }
                 }
@@ -511,6 +611,7 @@ impl OverrideTransformer {
                         insert_index: -1,
                     })));
                 changed = true;
+                self.has_change = true;
             }
         }
         return changed;
@@ -525,3 +626,8 @@ impl OverrideTransformer {
         }
     }
 }
+
+/// Returns whether the expressions are printed as the same code.
+fn expr_eq(a: &ast::NodeRef<ast::Expr>, b: &ast::NodeRef<ast::Expr>) -> bool {
+    print_ast_node(ASTNode::Expr(a)) == print_ast_node(ASTNode::Expr(b))
+}
@@ -1,6 +1,9 @@
 use std::{fs, path::PathBuf};
 
-use super::{r#override::apply_override_on_module, *};
+use super::{
+    r#override::{apply_override_on_module, apply_override_on_module_with_outcome},
+    *,
+};
 use crate::path::parse_attribute_path;
 use kclvm_ast::ast;
 use kclvm_parser::parse_file_force_errors;
@@ -169,6 +172,50 @@ appConfigurationUnification: AppConfiguration {
     );
 }
 
+/// Test the outcomes of the overrides which match nothing or are no-ops.
+#[test]
+fn test_override_outcomes() {
+    let code = r#"config = {
+    image = "nginx:1.25"
+    replicas = 2
+}
+count = 1
+"#;
+    let cases = [
+        ("config.image=\"nginx:1.26\"", OverrideOutcome::Changed),
+        ("config.labels.app=\"web\"", OverrideOutcome::Changed),
+        ("count=2", OverrideOutcome::Changed),
+        ("config.replicas-", OverrideOutcome::Changed),
+        ("missing.image=\"nginx:1.26\"", OverrideOutcome::Unmatched),
+        ("missing=1", OverrideOutcome::Unmatched),
+        ("config.image=\"nginx:1.25\"", OverrideOutcome::NoOp),
+        ("count=1", OverrideOutcome::NoOp),
+        ("config.ports-", OverrideOutcome::NoOp),
+    ];
+    for (spec, expected) in cases {
+        let mut module = parse_file_force_errors("main.k", Some(code.to_string())).unwrap();
+        let o = parse_override_spec(spec).unwrap();
+        let outcome = apply_override_on_module_with_outcome(&mut module, &o, &[]).unwrap();
+        assert_eq!(outcome, expected, "{spec}");
+    }
+
+    let overrides = ["missing=1", "count=1", "count=2"]
+        .iter()
+        .map(|spec| parse_override_spec(spec).unwrap())
+        .collect::<Vec<ast::OverrideSpec>>();
+    let outcomes = [
+        OverrideOutcome::Unmatched,
+        OverrideOutcome::NoOp,
+        OverrideOutcome::Changed,
+    ];
+    let err = check_stale_overrides(&overrides, &outcomes).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the override 'missing=1' matches nothing\nthe override 'count=1' is a no-op"
+    );
+    assert!(check_stale_overrides(&overrides[2..], &outcomes[2..]).is_ok());
+}
+
 /// Test override spec parser.
 #[test]
 fn test_parse_override_spec_invalid() {
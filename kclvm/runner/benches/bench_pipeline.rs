@@ -0,0 +1,173 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use walkdir::WalkDir;
+
+use kclvm_config::cache::KCL_CACHE_PATH_ENV_VAR;
+use kclvm_parser::{load_program, ParseSession};
+use kclvm_runner::{build_program, exec_program, runner::ExecProgramArgs};
+use kclvm_sema::resolver::resolve_program;
+
+const TEST_DATA_PATH: &str = "./src/test_datas/";
+const EXEC_DATA_PATH: &str = "./src/exec_data/";
+const KONFIG_JOB_EXAMPLE_PATH: &str = "../../test/integration/konfig/base/examples/job-example/dev";
+
+/// Benchmark fixture with a stable name and the input KCL files.
+struct Fixture {
+    name: &'static str,
+    files: Vec<String>,
+}
+
+impl Fixture {
+    fn args(&self) -> ExecProgramArgs {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list = self.files.clone();
+        args
+    }
+
+    fn files(&self) -> Vec<&str> {
+        self.files.iter().map(|f| f.as_str()).collect()
+    }
+}
+
+/// Returns the representative fixtures. The konfig job example is only included
+/// when the konfig submodule is checked out.
+fn fixtures() -> Vec<Fixture> {
+    let mut fixtures = vec![
+        Fixture {
+            name: "single_file",
+            files: vec![format!("{}complex.k", EXEC_DATA_PATH)],
+        },
+        Fixture {
+            name: "import_abs_path",
+            files: vec![format!(
+                "{}multi_file_compilation/import_abs_path/app-main/main.k",
+                TEST_DATA_PATH
+            )],
+        },
+        Fixture {
+            name: "no_kcl_mod_file",
+            files: vec![format!(
+                "{}multi_file_compilation/no_kcl_mod_file/main.k",
+                TEST_DATA_PATH
+            )],
+        },
+    ];
+    if Path::new(KONFIG_JOB_EXAMPLE_PATH).is_dir() {
+        fixtures.push(Fixture {
+            name: "konfig_job_example",
+            files: get_files(KONFIG_JOB_EXAMPLE_PATH, ".k"),
+        });
+    }
+    fixtures
+}
+
+pub fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for fixture in fixtures() {
+        let opts = fixture.args().get_load_program_options();
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| {
+                load_program(
+                    Arc::new(ParseSession::default()),
+                    &fixture.files(),
+                    Some(opts.clone()),
+                    None,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+pub fn bench_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for fixture in fixtures() {
+        let opts = fixture.args().get_load_program_options();
+        let program = load_program(
+            Arc::new(ParseSession::default()),
+            &fixture.files(),
+            Some(opts),
+            None,
+        )
+        .unwrap()
+        .program;
+        group.bench_function(fixture.name, |b| {
+            b.iter_batched(
+                || program.clone(),
+                |mut program| resolve_program(&mut program),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Benchmark the native library generation through [build_program]. The cold
+/// benchmark clears the package cache before each iteration, and the warm one
+/// reuses the package libraries generated by the previous iterations.
+pub fn bench_gen_libs(c: &mut Criterion) {
+    let cache_dir = tempfile::tempdir().unwrap();
+    std::env::set_var(KCL_CACHE_PATH_ENV_VAR, cache_dir.path());
+    let clean_cache = || {
+        let _ = std::fs::remove_dir_all(cache_dir.path().join(".kclvm"));
+    };
+    let mut group = c.benchmark_group("gen_libs");
+    for fixture in fixtures() {
+        let args = fixture.args();
+        group.bench_function(format!("{}/cold_cache", fixture.name), |b| {
+            b.iter_batched(
+                clean_cache,
+                |_| build_program::<&str>(Arc::new(ParseSession::default()), &args, None).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+        clean_cache();
+        build_program::<&str>(Arc::new(ParseSession::default()), &args, None).unwrap();
+        group.bench_function(format!("{}/warm_cache", fixture.name), |b| {
+            b.iter(|| {
+                build_program::<&str>(Arc::new(ParseSession::default()), &args, None).unwrap()
+            })
+        });
+    }
+    group.finish();
+    std::env::remove_var(KCL_CACHE_PATH_ENV_VAR);
+}
+
+pub fn bench_exec_program(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exec_program");
+    for fixture in fixtures() {
+        let args = fixture.args();
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| exec_program(Arc::new(ParseSession::default()), &args).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_resolve,
+    bench_gen_libs,
+    bench_exec_program
+);
+criterion_main!(benches);
+
+/// Get the sorted kcl files in the directory.
+fn get_files<P: AsRef<Path>>(path: P, suffix: &str) -> Vec<String> {
+    let mut files = vec![];
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_file() {
+            let file = path.to_str().unwrap();
+            if file.ends_with(suffix) && entry.depth() == 1 {
+                files.push(file.to_string())
+            }
+        }
+    }
+    files.sort();
+    files
+}
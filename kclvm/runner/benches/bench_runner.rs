@@ -40,7 +40,7 @@ fn exec(file: &str) -> Result<String> {
         .unwrap()
         .program;
     // Resolve ATS, generate libs, link libs and execute.
-    execute(sess, program, &args).map(|r| r.yaml_result)
+    Ok(execute(sess, program, &args)?.yaml_result)
 }
 
 /// Get kcl files from path.
@@ -0,0 +1,177 @@
+//! Run the self-contained config packages distributed as the zip or tar.gz
+//! archives, e.g., `kcl run bundle.zip`.
+//!
+//! The archive holds a KCL module with the `kcl.mod` file at the archive root or
+//! in its single top-level directory. The archive is extracted into a temp dir,
+//! which is removed after the execution, and the input is replaced with the
+//! entries in the `[profile]` section of `kcl.mod`, or the module root when no
+//! entries are specified. Every directory under `vendor` of the module is an
+//! external package named by the directory name.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use kclvm_ast::ast::CmdExternalPkgSpec;
+use kclvm_config::modfile::{try_load_mod_file, KCL_MOD_FILE};
+use tempfile::TempDir;
+
+use crate::{create_temp_dir, ExecProgramArgs};
+
+/// The directory of the vendored dependencies in the archived module.
+pub const ARCHIVE_VENDOR_DIR: &str = "vendor";
+
+/// The supported archive kinds, which are detected by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Returns the archive kind of the file path, or [None] when the file is not an archive.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.to_lowercase();
+        if path.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// The extracted archive, the extracted files are removed on drop.
+#[derive(Debug)]
+pub struct ExtractedArchive {
+    dir: TempDir,
+    /// The root path of the KCL module in the archive.
+    pub root: PathBuf,
+}
+
+impl ExtractedArchive {
+    /// Extract the archive into a temp dir created with the temp dir options of the
+    /// arguments, which must be inside the sandbox root, and find the module root.
+    pub fn extract(path: &Path, kind: ArchiveKind, args: &ExecProgramArgs) -> Result<Self> {
+        let dir = create_temp_dir(args)?;
+        let file = File::open(path)
+            .with_context(|| format!("failed to open the archive {}", path.display()))?;
+        // Both the extractors reject the entries outside the extraction dir.
+        let extracted: Result<()> = match kind {
+            ArchiveKind::Zip => zip::ZipArchive::new(file)
+                .and_then(|mut archive| archive.extract(dir.path()))
+                .map_err(Into::into),
+            ArchiveKind::TarGz => tar::Archive::new(flate2::read::GzDecoder::new(file))
+                .unpack(dir.path())
+                .map_err(Into::into),
+        };
+        extracted.with_context(|| format!("failed to extract the archive {}", path.display()))?;
+        let root = find_mod_root(dir.path()).with_context(|| {
+            format!(
+                "the archive {} does not contain a {} file at its root",
+                path.display(),
+                KCL_MOD_FILE
+            )
+        })?;
+        Ok(Self { dir, root })
+    }
+
+    /// Returns the entry files of the module, or the module root when the
+    /// `[profile]` section of `kcl.mod` specifies no entries.
+    pub fn entries(&self) -> Result<Vec<String>> {
+        let root = self.root.to_string_lossy().to_string();
+        let entries = try_load_mod_file(&root)
+            .with_context(|| format!("failed to load the {} file of the archive", KCL_MOD_FILE))?
+            .profile
+            .and_then(|profile| profile.entries)
+            .unwrap_or_default();
+        if entries.is_empty() {
+            return Ok(vec![root]);
+        }
+        Ok(entries
+            .iter()
+            .map(|entry| self.root.join(entry).to_string_lossy().to_string())
+            .collect())
+    }
+
+    /// Returns the external packages vendored under the `vendor` dir of the module.
+    pub fn vendored_pkgs(&self) -> Result<Vec<CmdExternalPkgSpec>> {
+        let vendor_dir = self.root.join(ARCHIVE_VENDOR_DIR);
+        if !vendor_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut pkgs = vec![];
+        for entry in std::fs::read_dir(vendor_dir)? {
+            let path = entry?.path();
+            if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
+                pkgs.push(CmdExternalPkgSpec {
+                    pkg_name: name.to_string_lossy().to_string(),
+                    pkg_path: path.to_string_lossy().to_string(),
+                });
+            }
+        }
+        pkgs.sort_by(|a, b| a.pkg_name.cmp(&b.pkg_name));
+        Ok(pkgs)
+    }
+
+    /// Returns the temp dir path of the extracted files.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Find the module root with the `kcl.mod` file in the extraction dir or in
+/// its single top-level directory.
+fn find_mod_root(dir: &Path) -> Result<PathBuf> {
+    if dir.join(KCL_MOD_FILE).is_file() {
+        return Ok(dir.to_path_buf());
+    }
+    let mut subdirs = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        }
+    }
+    match subdirs.as_slice() {
+        [subdir] if subdir.join(KCL_MOD_FILE).is_file() => Ok(subdir.clone()),
+        _ => bail!("{} not found", KCL_MOD_FILE),
+    }
+}
+
+/// Extract the archive inputs and return the arguments which run the entries of
+/// the extracted modules. The archives must be kept alive during the execution,
+/// and the extracted files are removed when they are dropped.
+pub(crate) fn extract_archive_inputs(
+    args: &ExecProgramArgs,
+) -> Result<(Cow<'_, ExecProgramArgs>, Vec<ExtractedArchive>)> {
+    if !args
+        .k_filename_list
+        .iter()
+        .any(|file| ArchiveKind::from_path(file).is_some())
+    {
+        return Ok((Cow::Borrowed(args), vec![]));
+    }
+    if !args.k_code_list.is_empty() {
+        bail!("the archive inputs can not be used with the in-memory code list");
+    }
+    let work_dir = args.work_dir.clone().unwrap_or_default();
+    let mut archive_args = args.clone();
+    let mut archives = vec![];
+    archive_args.k_filename_list.clear();
+    for file in &args.k_filename_list {
+        match ArchiveKind::from_path(file) {
+            Some(kind) => {
+                let path = Path::new(&work_dir).join(file);
+                let archive = ExtractedArchive::extract(&path, kind, args)?;
+                archive_args.k_filename_list.extend(archive.entries()?);
+                archive_args.external_pkgs.extend(archive.vendored_pkgs()?);
+                archives.push(archive);
+            }
+            None => archive_args.k_filename_list.push(file.clone()),
+        }
+    }
+    Ok((Cow::Owned(archive_args), archives))
+}
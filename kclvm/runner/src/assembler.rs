@@ -1,14 +1,19 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use compiler_base_macros::bug;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use kclvm_ast::ast::{self, Program};
-use kclvm_compiler::codegen::{emit_code, EmitOptions, OBJECT_FILE_SUFFIX};
+use kclvm_compiler::codegen::{emit_code, EmitOptions, LL_FILE_SUFFIX, OBJECT_FILE_SUFFIX};
 use kclvm_config::cache::{load_pkg_cache, save_pkg_cache, CacheOption, KCL_CACHE_PATH_ENV_VAR};
+use kclvm_error::Diagnostic;
 use kclvm_sema::resolver::scope::ProgramScope;
 use std::{
     collections::HashMap,
-    env,
+    env, fmt,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use crate::ExecProgramArgs;
@@ -72,6 +77,41 @@ pub(crate) trait LibAssembler {
     }
 }
 
+/// Returns the cache dir of the libraries compiled for the default target under
+/// the program root path, which is overridden by [KCL_CACHE_PATH_ENV_VAR].
+#[inline]
+pub fn default_cache_dir(root: &str) -> PathBuf {
+    cache_dir(root, env!("KCLVM_DEFAULT_TARGET"))
+}
+
+fn cache_dir(root: &str, target: &str) -> PathBuf {
+    cache_root(root)
+        .join(kclvm_version::get_version_string())
+        .join(target)
+}
+
+/// Returns the cache root of the object files of all the versions and targets under
+/// the program root path, which is overridden by [KCL_CACHE_PATH_ENV_VAR].
+pub(crate) fn cache_root(root: &str) -> PathBuf {
+    let root = std::env::var(KCL_CACHE_PATH_ENV_VAR).unwrap_or(root.to_string());
+    Path::new(&root).join(".kclvm").join("cache")
+}
+
+/// The backend name of the fast evaluator.
+pub const EVALUATOR_BACKEND: &str = "evaluator";
+
+/// Returns the names of the backends compiled into the crate, the fast evaluator
+/// is always available.
+pub fn available_backends() -> Vec<&'static str> {
+    let mut backends = vec![EVALUATOR_BACKEND];
+    for assembler in [KclvmLibAssembler::LLVM] {
+        if assembler.is_available() {
+            backends.push(assembler.name());
+        }
+    }
+    backends
+}
+
 /// This enum lists all the intermediate code assemblers currently supported by kclvm.
 /// Currently only supports assemble llvm intermediate code into dynamic link library.
 #[derive(Clone)]
@@ -79,6 +119,25 @@ pub(crate) enum KclvmLibAssembler {
     LLVM,
 }
 
+impl KclvmLibAssembler {
+    /// Whether the backend of the assembler is compiled into the crate, the LLVM
+    /// backend is only available with the `llvm` feature.
+    #[inline]
+    pub(crate) fn is_available(&self) -> bool {
+        match self {
+            KclvmLibAssembler::LLVM => cfg!(feature = "llvm"),
+        }
+    }
+
+    /// Returns the backend name of the assembler.
+    #[inline]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            KclvmLibAssembler::LLVM => "llvm",
+        }
+    }
+}
+
 /// KclvmLibAssembler is a dispatcher, responsible for calling corresponding methods
 /// according to different types of intermediate codes.
 ///
@@ -153,6 +212,20 @@ impl LibAssembler for LlvmLibAssembler {
     ) -> Result<String> {
         // Clean the existed "*.o" object file.
         clean_path(object_file_path)?;
+        // The LLVM IR file is named by the package path.
+        let ir_path = match (&arg.llvm_ir_dir, compile_prog.pkgs.keys().next()) {
+            (Some(dir), Some(pkgpath)) => {
+                arg.check_sandbox_path(dir)?;
+                std::fs::create_dir_all(dir)?;
+                Some(
+                    Path::new(dir)
+                        .join(format!("{}{}", pkgpath, LL_FILE_SUFFIX))
+                        .display()
+                        .to_string(),
+                )
+            }
+            _ => None,
+        };
 
         // Compile KCL code into ".o" object file.
         emit_code(
@@ -163,6 +236,8 @@ impl LibAssembler for LlvmLibAssembler {
                 from_path: None,
                 emit_path: Some(code_file),
                 no_link: true,
+                target_triple: arg.target_triple.as_deref(),
+                ir_path: ir_path.as_deref(),
             },
         )
         .map_err(|e| {
@@ -186,6 +261,43 @@ impl LibAssembler for LlvmLibAssembler {
     }
 }
 
+/// The cache usage of the non-main packages when generating the libraries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    /// The number of the packages whose object files are read from the cache.
+    pub hits: usize,
+    /// The number of the packages which are compiled and written into the cache.
+    pub misses: usize,
+}
+
+/// CacheCounter counts the cache hits and misses among the codegen threads.
+#[derive(Debug, Default)]
+pub(crate) struct CacheCounter {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl CacheCounter {
+    #[inline]
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the cache usage counted so far.
+    #[inline]
+    pub(crate) fn usage(&self) -> CacheUsage {
+        CacheUsage {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// KclvmAssembler is mainly responsible for assembling the generated bytecode
 /// LLVM IR or other IR code into dynamic link libraries, for multi-file kcl programs,
 /// and take the result of kclvm-parser, kclvm-sema and kclvm-compiler as input.
@@ -200,13 +312,21 @@ pub(crate) struct KclvmAssembler {
     scope: ProgramScope,
     entry_file: String,
     single_file_assembler: KclvmLibAssembler,
-    target: String,
+    /// The name of the target which the object files are cached by.
+    pub(crate) target: String,
     external_pkgs: HashMap<String, String>,
+    /// The packages which fail to resolve with their resolve errors, which are not compiled.
+    failed_pkgs: IndexMap<String, Vec<Diagnostic>>,
+    /// The cache usage of the generated libraries.
+    cache_counter: Arc<CacheCounter>,
 }
 
 impl KclvmAssembler {
     /// Constructs an KclvmAssembler instance with a default value 4
     /// for the number of threads in multi-file compilation.
+    ///
+    /// It returns an error when the backend of the assembler is not compiled into
+    /// the crate, e.g., the LLVM backend without the `llvm` feature.
     #[inline]
     pub(crate) fn new(
         program: ast::Program,
@@ -214,15 +334,43 @@ impl KclvmAssembler {
         entry_file: String,
         single_file_assembler: KclvmLibAssembler,
         external_pkgs: HashMap<String, String>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        if !single_file_assembler.is_available() {
+            bail!(
+                "the {} backend is not available, rebuild the crate with the `{}` feature or use the fast evaluator",
+                single_file_assembler.name(),
+                single_file_assembler.name()
+            );
+        }
+        Ok(Self {
             program,
             scope,
             entry_file,
             single_file_assembler,
             target: env!("KCLVM_DEFAULT_TARGET").to_string(),
             external_pkgs,
-        }
+            failed_pkgs: IndexMap::default(),
+            cache_counter: Arc::default(),
+        })
+    }
+
+    /// Set the packages which fail to resolve with their resolve errors. These packages are
+    /// not compiled and the packages depending on them are skipped, while the independent
+    /// packages are still compiled, see [compile_pkgs_by_levels].
+    #[inline]
+    pub(crate) fn with_failed_pkgs(
+        mut self,
+        failed_pkgs: IndexMap<String, Vec<Diagnostic>>,
+    ) -> Self {
+        self.failed_pkgs = failed_pkgs;
+        self
+    }
+
+    /// Returns the counter of the cache usage, which is read after the libraries are
+    /// generated because [KclvmAssembler::gen_libs] consumes the assembler.
+    #[inline]
+    pub(crate) fn cache_counter(&self) -> Arc<CacheCounter> {
+        self.cache_counter.clone()
     }
 
     /// Clean up the path of the dynamic link libraries generated.
@@ -259,12 +407,7 @@ impl KclvmAssembler {
 
     #[inline]
     pub(crate) fn construct_cache_dir(&self, root: &str) -> PathBuf {
-        let root = std::env::var(KCL_CACHE_PATH_ENV_VAR).unwrap_or(root.to_string());
-        Path::new(&root)
-            .join(".kclvm")
-            .join("cache")
-            .join(kclvm_version::get_version_string())
-            .join(&self.target)
+        cache_dir(root, &self.target)
     }
 
     /// Generate the dynamic link libraries and return file paths.
@@ -276,12 +419,32 @@ impl KclvmAssembler {
     /// and ir code files, and return the file paths of the dynamic link library files in [Vec<String>].
     ///
     /// `gen_libs` will create multiple threads and call the method provided by [KclvmLibAssembler] in each thread
-    /// to generate the dynamic link library in parallel.
+    /// to generate the dynamic link library in parallel. Packages are compiled level by level in the import
+    /// order, and the packages that do not depend on each other in the same level are compiled concurrently.
+    ///
+    /// The object files of the non-main packages are cached by the source content, the KCL version
+    /// and the target only. The code generation does not depend on the top-level arguments, overrides
+    /// and path selectors, which are applied when running the libraries, so the object files are shared
+    /// among the runs with different arguments. The results of the runs are never cached.
     pub(crate) fn gen_libs(self, args: &ExecProgramArgs) -> Result<Vec<String>> {
+        self.gen_libs_with_parallel(args, true)
+    }
+
+    /// Generate the dynamic link libraries concurrently or sequentially.
+    pub(crate) fn gen_libs_with_parallel(
+        mut self,
+        args: &ExecProgramArgs,
+        parallel: bool,
+    ) -> Result<Vec<String>> {
+        // The object files of different targets are cached separately.
+        if let Some(triple) = &args.target_triple {
+            self.target = triple.clone();
+        }
         self.clean_path_for_genlibs(
             DEFAULT_IR_FILE,
             &self.single_file_assembler.get_code_file_suffix(),
         )?;
+        args.check_sandbox_path(self.construct_cache_dir(&self.program.root))?;
         let cache_dir = self.load_cache_dir(&self.program.root)?;
         let mut compile_progs: IndexMap<
             String,
@@ -302,111 +465,369 @@ impl KclvmAssembler {
                 pkgpath,
                 (
                     compile_prog,
-                    self.scope.import_names.clone(),
+                    self.scope.imports().clone(),
                     cache_dir.clone(),
                 ),
             );
         }
-        let mut lib_paths = vec![];
-        for (pkgpath, (compile_prog, import_names, cache_dir)) in compile_progs {
-            // Clone a single file assembler for one thread.
-            let assembler = self.single_file_assembler.clone();
-            // Generate paths for some intermediate files (*.o, *.lock).
-            let entry_file = self.entry_file.clone();
-            let is_main_pkg = pkgpath == kclvm_ast::MAIN_PKG;
-            let file = if is_main_pkg {
-                // The path to the generated files(*.o or *.lock) when the main package is compiled.
-                PathBuf::from(entry_file)
+        let generator = PkgLibGenerator {
+            assembler: &self.single_file_assembler,
+            entry_file: &self.entry_file,
+            target: &self.target,
+            external_pkgs: &self.external_pkgs,
+            cache_counter: &self.cache_counter,
+        };
+        let lib_paths =
+            compile_pkgs_by_levels(&compile_progs, &self.failed_pkgs, parallel, |pkgpath| {
+                let (compile_prog, import_names, cache_dir) = &compile_progs[pkgpath];
+                generator.gen_lib(pkgpath, compile_prog, import_names, cache_dir, args)
+            })?;
+        // Keep the same library order as the package order of the program.
+        let lib_paths = compile_progs
+            .keys()
+            .filter_map(|pkgpath| lib_paths.get(pkgpath).cloned())
+            .collect::<Vec<String>>();
+        self.single_file_assembler
+            .clean_lock_file(&self.entry_file)?;
+        Ok(lib_paths)
+    }
+}
+
+/// PkgLibGenerator holds the shared states to generate the object file of a single package,
+/// and it can be shared among the codegen threads.
+pub(crate) struct PkgLibGenerator<'a> {
+    pub(crate) assembler: &'a KclvmLibAssembler,
+    pub(crate) entry_file: &'a str,
+    pub(crate) target: &'a str,
+    pub(crate) external_pkgs: &'a HashMap<String, String>,
+    pub(crate) cache_counter: &'a CacheCounter,
+}
+
+impl<'a> PkgLibGenerator<'a> {
+    /// Generate the object file of a single package and return the file path.
+    pub(crate) fn gen_lib(
+        &self,
+        pkgpath: &str,
+        compile_prog: &ast::Program,
+        import_names: &IndexMap<String, IndexMap<String, String>>,
+        cache_dir: &Path,
+        args: &ExecProgramArgs,
+    ) -> Result<String> {
+        // Clone a single file assembler for one thread.
+        let assembler = self.assembler.clone();
+        // Generate paths for some intermediate files (*.o, *.lock).
+        let entry_file = self.entry_file.to_string();
+        let is_main_pkg = pkgpath == kclvm_ast::MAIN_PKG;
+        let file = if is_main_pkg {
+            // The path to the generated files(*.o or *.lock) when the main package is compiled.
+            PathBuf::from(entry_file)
+        } else {
+            // The path to the generated files(*.o or *.lock) when the non-main package is compiled.
+            cache_dir.join(pkgpath)
+        };
+        let code_file = file
+            .to_str()
+            .ok_or(anyhow::anyhow!("Internal error: get cache file failed"))?
+            .to_string();
+        let code_file_path = assembler.add_code_file_suffix(&code_file);
+        let lock_file_path = format!("{}.lock", code_file_path);
+        let target = self.target.to_string();
+        {
+            // Locking file for parallel code generation.
+            let mut file_lock = fslock::LockFile::open(&lock_file_path)?;
+            file_lock.lock()?;
+
+            let root = &compile_prog.root;
+            // The main package does not perform cache reading and writing,
+            // and other packages perform read and write caching. Because
+            // KCL supports multi-file compilation, it is impossible to
+            // specify a standard entry for these multi-files and cannot
+            // be shared, so the cache of the main package is not read and
+            // written.
+            // The cached object files are not used when emitting the LLVM IR of all the packages.
+            let file_path = if is_main_pkg || args.llvm_ir_dir.is_some() {
+                // generate dynamic link library for single file kcl program
+                assembler.assemble(
+                    compile_prog,
+                    import_names.clone(),
+                    &code_file,
+                    &code_file_path,
+                    args,
+                )?
             } else {
-                // The path to the generated files(*.o or *.lock) when the non-main package is compiled.
-                cache_dir.join(&pkgpath)
-            };
-            let code_file = file
-                .to_str()
-                .ok_or(anyhow::anyhow!("Internal error: get cache file failed"))?
-                .to_string();
-            let code_file_path = assembler.add_code_file_suffix(&code_file);
-            let lock_file_path = format!("{}.lock", code_file_path);
-            let target = self.target.clone();
-            {
-                // Locking file for parallel code generation.
-                let mut file_lock = fslock::LockFile::open(&lock_file_path)?;
-                file_lock.lock()?;
-
-                let root = &compile_prog.root;
-                // The main package does not perform cache reading and writing,
-                // and other packages perform read and write caching. Because
-                // KCL supports multi-file compilation, it is impossible to
-                // specify a standard entry for these multi-files and cannot
-                // be shared, so the cache of the main package is not read and
-                // written.
-                let file_path = if is_main_pkg {
-                    // generate dynamic link library for single file kcl program
-                    assembler.assemble(
-                        &compile_prog,
-                        import_names,
-                        &code_file,
-                        &code_file_path,
-                        args,
-                    )?
-                } else {
-                    // Read the lib path cache
-                    let file_relative_path: Option<String> = load_pkg_cache(
-                        root,
-                        &target,
-                        &pkgpath,
-                        CacheOption::default(),
-                        &self.external_pkgs,
-                    );
-                    let file_abs_path = match file_relative_path {
-                        Some(file_relative_path) => {
-                            let path = if file_relative_path.starts_with('.') {
-                                file_relative_path.replacen('.', root, 1)
-                            } else {
-                                file_relative_path
-                            };
-                            if Path::new(&path).exists() {
-                                Some(path)
-                            } else {
-                                None
-                            }
-                        }
-                        None => None,
-                    };
-                    match file_abs_path {
-                        Some(path) => path,
-                        None => {
-                            // Generate the object file for single file kcl program.
-                            let file_path = assembler.assemble(
-                                &compile_prog,
-                                import_names,
-                                &code_file,
-                                &code_file_path,
-                                args,
-                            )?;
-                            let lib_relative_path = file_path.replacen(root, ".", 1);
-                            let _ = save_pkg_cache(
-                                root,
-                                &target,
-                                &pkgpath,
-                                lib_relative_path,
-                                CacheOption::default(),
-                                &self.external_pkgs,
-                            );
-                            file_path
-                        }
+                // Read the lib path cache
+                match cached_lib_path(
+                    root,
+                    &target,
+                    pkgpath,
+                    self.external_pkgs,
+                    args.shared_cache_dir.as_deref(),
+                ) {
+                    Some(path) => {
+                        self.cache_counter.hit();
+                        path
+                    }
+                    None => {
+                        self.cache_counter.miss();
+                        // Generate the object file for single file kcl program.
+                        let file_path = assembler.assemble(
+                            compile_prog,
+                            import_names.clone(),
+                            &code_file,
+                            &code_file_path,
+                            args,
+                        )?;
+                        let lib_relative_path = file_path.replacen(root, ".", 1);
+                        let _ = save_pkg_cache(
+                            root,
+                            &target,
+                            pkgpath,
+                            lib_relative_path,
+                            CacheOption::default(),
+                            self.external_pkgs,
+                        );
+                        file_path
                     }
-                };
-                file_lock.unlock()?;
-                lib_paths.push(file_path);
+                }
             };
+            file_lock.unlock()?;
+            Ok(file_path)
         }
-        self.single_file_assembler
-            .clean_lock_file(&self.entry_file)?;
-        Ok(lib_paths)
     }
 }
 
+/// Returns the object file path of the package from the cache, which is valid
+/// only when the package sources are unchanged and the cached file exists. The
+/// read-only shared cache is consulted when the package is not cached under the
+/// program root, see [ExecProgramArgs::shared_cache_dir].
+pub(crate) fn cached_lib_path(
+    root: &str,
+    target: &str,
+    pkgpath: &str,
+    external_pkgs: &HashMap<String, String>,
+    shared_cache_dir: Option<&Path>,
+) -> Option<String> {
+    layer_lib_path(
+        root,
+        root,
+        target,
+        pkgpath,
+        CacheOption::default(),
+        external_pkgs,
+    )
+    .or_else(|| {
+        let shared_cache_dir = match shared_cache_dir? {
+            dir if dir.is_absolute() => dir.to_path_buf(),
+            dir => std::env::current_dir().ok()?.join(dir),
+        };
+        layer_lib_path(
+            root,
+            &shared_cache_dir.display().to_string(),
+            target,
+            pkgpath,
+            CacheOption::with_cache_root(&shared_cache_dir),
+            external_pkgs,
+        )
+    })
+}
+
+/// Returns the object file path of the package from a cache layer, where the
+/// relative object file paths are relative to `cache_root`.
+fn layer_lib_path(
+    root: &str,
+    cache_root: &str,
+    target: &str,
+    pkgpath: &str,
+    option: CacheOption,
+    external_pkgs: &HashMap<String, String>,
+) -> Option<String> {
+    let file_relative_path: String = load_pkg_cache(root, target, pkgpath, option, external_pkgs)?;
+    let path = if file_relative_path.starts_with('.') {
+        file_relative_path.replacen('.', cache_root, 1)
+    } else {
+        file_relative_path
+    };
+    if Path::new(&path).exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// The errors of the packages which fail to compile, which is returned by
+/// [compile_pkgs_by_levels] and can be downcast from the errors of building the
+/// program, e.g., [crate::build_program].
+#[derive(Debug, Default, Clone)]
+pub struct PkgCompileErrors {
+    /// The resolve errors of the packages which fail to resolve.
+    pub resolve_errors: IndexMap<String, Vec<Diagnostic>>,
+    /// The errors of the packages which fail to generate the code.
+    pub codegen_errors: IndexMap<String, String>,
+    /// The packages which are skipped, mapping to the failed packages they depend on.
+    pub skipped: IndexMap<String, String>,
+    /// The object files of the packages which are compiled.
+    pub lib_paths: IndexMap<String, String>,
+}
+
+impl fmt::Display for PkgCompileErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![];
+        for (pkgpath, diags) in &self.resolve_errors {
+            for msg in diags.iter().flat_map(|diag| &diag.messages) {
+                lines.push(format!(
+                    "failed to resolve the package {}: {}:{}:{}: {}",
+                    pkgpath,
+                    msg.range.0.filename,
+                    msg.range.0.line,
+                    msg.range.0.column.unwrap_or_default(),
+                    msg.message
+                ));
+            }
+        }
+        for (pkgpath, err) in &self.codegen_errors {
+            lines.push(format!(
+                "failed to compile the package {}: {}",
+                pkgpath, err
+            ));
+        }
+        for (pkgpath, dep) in &self.skipped {
+            lines.push(format!(
+                "note: the package {} is skipped because it depends on the failed package {}",
+                pkgpath, dep
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for PkgCompileErrors {}
+
+/// Compile the packages level by level in the dependency order with `compile`, which
+/// returns the object file path of a package. The independent packages in the same level
+/// are compiled concurrently when `parallel` is set.
+///
+/// A failed package does not stop compiling the other packages, and the packages which
+/// depend on a failed package are skipped with a note, so the returned [PkgCompileErrors]
+/// holds the errors of all the failed packages. The packages in `failed_pkgs` have failed
+/// to resolve, so they are not compiled.
+pub(crate) fn compile_pkgs_by_levels<T, F>(
+    compile_progs: &IndexMap<String, (ast::Program, IndexMap<String, IndexMap<String, String>>, T)>,
+    failed_pkgs: &IndexMap<String, Vec<Diagnostic>>,
+    parallel: bool,
+    compile: F,
+) -> Result<IndexMap<String, String>>
+where
+    F: Fn(&str) -> Result<String> + Sync,
+{
+    let deps = pkg_dependencies(compile_progs);
+    let mut errors = PkgCompileErrors::default();
+    let mut failed: IndexSet<String> = IndexSet::default();
+    for level in pkg_dependency_levels(compile_progs) {
+        let mut compiled = vec![];
+        for pkgpath in level {
+            if let Some(diags) = failed_pkgs.get(&pkgpath) {
+                errors.resolve_errors.insert(pkgpath.clone(), diags.clone());
+                failed.insert(pkgpath);
+                continue;
+            }
+            match deps[&pkgpath].iter().find(|dep| failed.contains(*dep)) {
+                Some(dep) => {
+                    errors.skipped.insert(pkgpath.clone(), dep.clone());
+                    failed.insert(pkgpath);
+                }
+                None => compiled.push(pkgpath),
+            }
+        }
+        let results = if parallel && compiled.len() > 1 {
+            std::thread::scope(|s| {
+                let handles = compiled
+                    .iter()
+                    .map(|pkgpath| {
+                        let compile = &compile;
+                        s.spawn(move || compile(pkgpath))
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|h| {
+                        h.join().unwrap_or_else(|_| {
+                            Err(anyhow::anyhow!("Internal error: codegen thread panicked"))
+                        })
+                    })
+                    .collect::<Vec<Result<String>>>()
+            })
+        } else {
+            compiled
+                .iter()
+                .map(|pkgpath| compile(pkgpath))
+                .collect::<Vec<Result<String>>>()
+        };
+        for (pkgpath, result) in compiled.into_iter().zip(results) {
+            match result {
+                Ok(lib_path) => {
+                    errors.lib_paths.insert(pkgpath, lib_path);
+                }
+                Err(err) => {
+                    errors
+                        .codegen_errors
+                        .insert(pkgpath.clone(), err.to_string());
+                    failed.insert(pkgpath);
+                }
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(errors.into());
+    }
+    Ok(errors.lib_paths)
+}
+
+/// Returns the packages which each package imports.
+fn pkg_dependencies<T>(
+    compile_progs: &IndexMap<String, (ast::Program, IndexMap<String, IndexMap<String, String>>, T)>,
+) -> IndexMap<String, IndexSet<String>> {
+    let mut deps = IndexMap::default();
+    for (pkgpath, (compile_prog, import_names, _)) in compile_progs {
+        let mut pkg_deps = IndexSet::default();
+        for module in compile_prog.pkgs.values().flatten() {
+            if let Some(imports) = import_names.get(&module.filename) {
+                for import_path in imports.values() {
+                    if compile_progs.contains_key(import_path) && import_path != pkgpath {
+                        pkg_deps.insert(import_path.clone());
+                    }
+                }
+            }
+        }
+        deps.insert(pkgpath.clone(), pkg_deps);
+    }
+    deps
+}
+
+/// Group the packages into levels by the import dependencies. Packages in a level only
+/// depend on the packages in the previous levels, so they can be compiled concurrently.
+pub(crate) fn pkg_dependency_levels<T>(
+    compile_progs: &IndexMap<String, (ast::Program, IndexMap<String, IndexMap<String, String>>, T)>,
+) -> Vec<Vec<String>> {
+    let mut deps = pkg_dependencies(compile_progs);
+    let mut levels = vec![];
+    while !deps.is_empty() {
+        let mut level: Vec<String> = deps
+            .iter()
+            .filter(|(_, pkg_deps)| pkg_deps.iter().all(|d| !deps.contains_key(d)))
+            .map(|(pkgpath, _)| pkgpath.to_string())
+            .collect();
+        // Circular imports are reported by the resolver, here just compile them together.
+        if level.is_empty() {
+            level = deps.keys().map(|pkgpath| pkgpath.to_string()).collect();
+        }
+        for pkgpath in &level {
+            deps.shift_remove(pkgpath);
+        }
+        levels.push(level);
+    }
+    levels
+}
+
 #[inline]
 pub(crate) fn clean_path(path: &str) -> Result<()> {
     if Path::new(path).exists() {
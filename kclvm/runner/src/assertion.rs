@@ -0,0 +1,118 @@
+//! Check the predicates over the result of the program, e.g.,
+//! `kcl run main.k --assert 'spec.replicas >= 1'`, which gates the output without
+//! a separate schema, see [ExecProgramResult::check_assertion].
+//!
+//! The predicate is a single KCL expression evaluated by the fast evaluator, where
+//! the top-level keys of the result are bound as the variables. The keys which are
+//! not the KCL identifiers or are the KCL keywords, e.g., `type`, are not bound. The
+//! predicate is evaluated in the sandbox and under the limits of the run.
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use kclvm_ast::ast::{CmdArgSpec, Stmt};
+use kclvm_parser::{parse_file_force_errors, ParseSession};
+use kclvm_span::symbol::reserved::is_reserved_word;
+
+use crate::format::json_documents;
+use crate::{exec_program, ExecProgramArgs, ExecProgramResult};
+
+/// The top-level argument holding the JSON document the predicate is evaluated on.
+const ASSERT_DOCUMENT_ARG: &str = "kcl_assert_document";
+/// The top-level key of the predicate value in the output of the assertion program.
+const ASSERT_RESULT_KEY: &str = "kcl_assert_result";
+
+impl ExecProgramResult {
+    /// Check the predicate holds for every document of the result, which is
+    /// evaluated on an empty object when the result is empty. The error reports
+    /// the failed predicate and the index of the document in a stream result.
+    /// The sandbox and the limits of the arguments apply to the predicate.
+    pub fn check_assertion(&self, predicate: &str, args: &ExecProgramArgs) -> Result<()> {
+        let mut documents: Vec<&str> = json_documents(&self.json_result).collect();
+        if documents.is_empty() {
+            documents.push("{}");
+        }
+        check_predicate(predicate)
+            .map_err(|err| anyhow!("failed to evaluate the assertion '{}': {}", predicate, err))?;
+        let stream = documents.len() > 1;
+        for (i, document) in documents.iter().enumerate() {
+            let holds = eval_predicate(predicate, document, args).map_err(|err| {
+                anyhow!("failed to evaluate the assertion '{}': {}", predicate, err)
+            })?;
+            match (holds, stream) {
+                (true, _) => {}
+                (false, true) => {
+                    bail!("the assertion '{}' failed on the document {}", predicate, i)
+                }
+                (false, false) => bail!("the assertion '{}' failed", predicate),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the predicate is a single expression on a single line, which is spliced
+/// into the lambda body of the assertion program.
+fn check_predicate(predicate: &str) -> Result<()> {
+    if predicate.contains(|c| c == '\n' || c == '\r') {
+        bail!("the predicate must be on a single line");
+    }
+    let module = parse_file_force_errors("assert.k", Some(predicate.to_string()))?;
+    match module.body.as_slice() {
+        [stmt] if matches!(&stmt.node, Stmt::Expr(expr) if expr.exprs.len() == 1) => Ok(()),
+        _ => bail!("the predicate must be a single expression"),
+    }
+}
+
+/// Evaluate the predicate on the JSON document and returns its truth value.
+fn eval_predicate(predicate: &str, document: &str, run_args: &ExecProgramArgs) -> Result<bool> {
+    let names: Vec<String> = match serde_json::from_str(document)? {
+        serde_json::Value::Object(fields) => fields
+            .keys()
+            .filter(|key| is_identifier(key))
+            .cloned()
+            .collect(),
+        _ => vec![],
+    };
+    let params = names.join(", ");
+    let values = names
+        .iter()
+        .map(|name| format!("_document[\"{}\"]", name))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let code = format!(
+        "_document = option(\"{ASSERT_DOCUMENT_ARG}\")\n\
+         _predicate = lambda {params} {{\n    {predicate}\n}}\n\
+         {ASSERT_RESULT_KEY} = bool(_predicate({values}))\n"
+    );
+    let args = ExecProgramArgs {
+        k_filename_list: vec!["assert.k".to_string()],
+        k_code_list: vec![code],
+        args: vec![CmdArgSpec {
+            name: ASSERT_DOCUMENT_ARG.to_string(),
+            value: document.to_string(),
+        }],
+        fast_eval: true,
+        sandbox_root: run_args.sandbox_root.clone(),
+        offline: run_args.offline,
+        max_parse_depth: run_args.max_parse_depth,
+        max_eval_depth: run_args.max_eval_depth,
+        max_eval_memory_bytes: run_args.max_eval_memory_bytes,
+        max_output_bytes: run_args.max_output_bytes,
+        cancel_flag: run_args.cancel_flag.clone(),
+        ..Default::default()
+    };
+    let result = exec_program(Arc::new(ParseSession::default()), &args)?;
+    if !result.err_message.is_empty() {
+        bail!("{}", result.err_message);
+    }
+    let output: serde_json::Value = serde_json::from_str(&result.json_result)?;
+    Ok(output[ASSERT_RESULT_KEY].as_bool() == Some(true))
+}
+
+/// Returns true when the key can be a KCL variable name.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !is_reserved_word(key)
+}
@@ -0,0 +1,196 @@
+//! Snapshot and restore the object cache of a program root as a tar archive,
+//! e.g., `kcl cache export cache.tar` and `kcl cache import cache.tar`, which
+//! carries a warm cache between the ephemeral CI machines, see [export_cache]
+//! and [import_cache].
+//!
+//! Only the cache of the current KCL version is archived, i.e., the object files,
+//! the cache info files holding the content hashes of the package sources and the
+//! cached object file paths of all the targets. The cache info files are under the
+//! program root and the object files are under the cache path environment variable
+//! if it is set, thus the two cache directories are archived under the
+//! [PROGRAM_CACHE_ENTRY] and [OBJECT_CACHE_ENTRY] directories respectively. The
+//! archive starts with the [CACHE_MANIFEST_FILE] which records the KCL version, and
+//! the cache built by another version is rejected on import.
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+use crate::assembler::cache_root;
+
+/// The manifest file name at the archive root.
+pub const CACHE_MANIFEST_FILE: &str = "kcl-cache.json";
+/// The archive directory of the cache under the program root.
+pub const PROGRAM_CACHE_ENTRY: &str = "program";
+/// The archive directory of the object cache, which is only archived when the
+/// cache path environment variable moves it out of the program root.
+pub const OBJECT_CACHE_ENTRY: &str = "objects";
+
+/// The manifest of the cache archive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CacheManifest {
+    /// The full version string of the KCL which built the cache.
+    pub version: String,
+}
+
+impl Default for CacheManifest {
+    fn default() -> Self {
+        Self {
+            version: kclvm_version::get_version_string(),
+        }
+    }
+}
+
+/// Returns the cache directories of the program root keyed by their archive
+/// directories, and the object cache is omitted when it is under the program root.
+fn cache_dirs(root: &str) -> Vec<(&'static str, PathBuf)> {
+    let program_cache_dir = Path::new(root).join(".kclvm").join("cache");
+    let object_cache_dir = cache_root(root);
+    let mut dirs = vec![(PROGRAM_CACHE_ENTRY, program_cache_dir.clone())];
+    if object_cache_dir != program_cache_dir {
+        dirs.push((OBJECT_CACHE_ENTRY, object_cache_dir));
+    }
+    dirs
+}
+
+/// Archive the cache of the current KCL version under the program root and the
+/// cache path into the tar file, and returns the number of the archived files.
+/// The lock files are not archived.
+pub fn export_cache<P: AsRef<Path>>(root: &str, output: P) -> Result<usize> {
+    let output = output.as_ref();
+    let manifest = CacheManifest::default();
+    let dirs = cache_dirs(root)
+        .into_iter()
+        .map(|(entry, dir)| (entry, dir.join(&manifest.version)))
+        .filter(|(_, dir)| dir.is_dir())
+        .collect::<Vec<_>>();
+    if dirs.is_empty() {
+        bail!(
+            "no cache found in {}",
+            cache_root(root).join(&manifest.version).display()
+        );
+    }
+    let file = File::create(output)
+        .with_context(|| format!("failed to create the cache archive {}", output.display()))?;
+    let mut builder = tar::Builder::new(file);
+    let content = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, CACHE_MANIFEST_FILE, content.as_slice())?;
+    let mut files = 0;
+    for (archive_dir, cache_dir) in &dirs {
+        for entry in WalkDir::new(cache_dir).sort_by_file_name() {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type().is_file() || path.extension().map_or(false, |ext| ext == "lock") {
+                continue;
+            }
+            let name = Path::new(archive_dir)
+                .join(&manifest.version)
+                .join(path.strip_prefix(cache_dir)?);
+            builder.append_path_with_name(path, name)?;
+            files += 1;
+        }
+    }
+    builder.into_inner()?;
+    Ok(files)
+}
+
+/// Restore the cache archived by [export_cache] under the program root and the
+/// cache path, which replaces the existing cache of the current KCL version, and
+/// returns the number of the restored files. The entries are unpacked into the
+/// temp directories beside the caches and moved into place after all of them are
+/// validated, thus the archive built by another KCL version or holding unexpected
+/// entries is rejected before the existing cache is touched.
+pub fn import_cache<P: AsRef<Path>>(root: &str, input: P) -> Result<usize> {
+    let input = input.as_ref();
+    let file = File::open(input)
+        .with_context(|| format!("failed to open the cache archive {}", input.display()))?;
+    let mut archive = tar::Archive::new(file);
+    let mut entries = archive.entries()?;
+    let manifest: CacheManifest = match entries.next() {
+        Some(entry) => {
+            let mut entry = entry?;
+            if entry.path()?.as_os_str() != CACHE_MANIFEST_FILE {
+                bail!(
+                    "{} is not a KCL cache archive, the {} file is missing",
+                    input.display(),
+                    CACHE_MANIFEST_FILE
+                );
+            }
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("invalid cache archive manifest in {}", input.display()))?
+        }
+        None => bail!("the cache archive {} is empty", input.display()),
+    };
+    let expected = CacheManifest::default();
+    if manifest.version != expected.version {
+        bail!(
+            "the cache archive {} is built by KCL {}, which is incompatible with KCL {}",
+            input.display(),
+            manifest.version,
+            expected.version
+        );
+    }
+    // The temp directories are created in the cache roots to be renamed into place.
+    let mut staging: Vec<(&'static str, PathBuf, TempDir)> = vec![];
+    for (archive_dir, cache_dir) in cache_dirs(root) {
+        std::fs::create_dir_all(&cache_dir)?;
+        let temp_dir = tempfile::Builder::new()
+            .prefix(".import")
+            .tempdir_in(&cache_dir)?;
+        staging.push((archive_dir, cache_dir, temp_dir));
+    }
+    let mut files = 0;
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        // The entry path is the archive directory of a cache followed by the version.
+        let mut components = path.components().map(|c| c.as_os_str());
+        let archive_dir = components.next();
+        let version = components.next();
+        let staging_dir = staging
+            .iter()
+            .find(|(dir, _, _)| archive_dir == Some(OsStr::new(dir)))
+            .filter(|_| version == Some(OsStr::new(&expected.version)))
+            .map(|(_, _, temp_dir)| temp_dir.path());
+        let staging_dir = match staging_dir {
+            Some(staging_dir) => staging_dir,
+            None => bail!(
+                "unexpected entry {} in the cache archive {}",
+                path.display(),
+                input.display()
+            ),
+        };
+        // The entries outside the temp directory are rejected by the unpacking.
+        if !entry.unpack_in(staging_dir)? {
+            bail!(
+                "the entry {} is outside the cache directory",
+                path.display()
+            );
+        }
+        files += 1;
+    }
+    // All the entries are valid, replace the existing caches.
+    for (archive_dir, cache_dir, temp_dir) in &staging {
+        let unpacked = temp_dir.path().join(archive_dir).join(&expected.version);
+        if !unpacked.is_dir() {
+            continue;
+        }
+        let target = cache_dir.join(&expected.version);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::rename(&unpacked, &target)?;
+    }
+    Ok(files)
+}
@@ -0,0 +1,188 @@
+//! Measure how effective the object cache is across the configs of a workspace,
+//! see [cache_stats].
+//!
+//! Each config is compiled and run, which counts the packages whose object files
+//! are read from the cache and fills the cache for the next configs and the next
+//! runs. The config is also planned with [explain_plan] to get its backend and
+//! program root.
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use kclvm_parser::ParseSession;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::assembler::cache_root;
+use crate::plan::explain_plan;
+use crate::{exec_program, ExecProgramArgs};
+
+/// The entry file name of a config in the workspace.
+pub const CONFIG_ENTRY_FILE: &str = "main.k";
+
+/// The output format of the cache stats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheStatsFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl TryFrom<&str> for CacheStatsFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_lowercase().as_str() {
+            "table" => Ok(CacheStatsFormat::Table),
+            "json" => Ok(CacheStatsFormat::Json),
+            _ => Err(anyhow::anyhow!(
+                "unsupported cache stats format '{format}', expected one of table and json"
+            )),
+        }
+    }
+}
+
+/// The cache hits and misses of the packages of a config.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigCacheStats {
+    /// The entry file of the config.
+    pub config: String,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// The cache stats of all the configs in a workspace. The main packages are
+/// never cached, thus they are not counted, and nothing is counted when the
+/// programs are run by the evaluator without the compilation.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    pub backend: &'static str,
+    pub configs: Vec<ConfigCacheStats>,
+    pub packages: usize,
+    pub hits: usize,
+    pub misses: usize,
+    /// The ratio of the cache hits in the packages, which is 0 without packages.
+    pub hit_ratio: f64,
+    /// The total bytes of the cache directories of the configs on the disk.
+    pub cache_size: u64,
+}
+
+impl CacheStats {
+    pub fn render(&self, format: CacheStatsFormat) -> Result<String> {
+        match format {
+            CacheStatsFormat::Table => Ok(self.to_table()),
+            CacheStatsFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    /// Render the stats to the markdown table.
+    pub fn to_table(&self) -> String {
+        let mut table = format!(
+            "{} package(s) in {} config(s) with {}: {} hit(s), {} miss(es), {:.1}% hit ratio, \
+             {} byte(s) cached\n\n",
+            self.packages,
+            self.configs.len(),
+            self.backend,
+            self.hits,
+            self.misses,
+            self.hit_ratio * 100.0,
+            self.cache_size
+        );
+        table.push_str("| config | hits | misses |\n");
+        table.push_str("| --- | --- | --- |\n");
+        for config in &self.configs {
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                config.config, config.hits, config.misses
+            ));
+        }
+        table
+    }
+}
+
+/// Compile every config in the directory, i.e., every [CONFIG_ENTRY_FILE] outside
+/// the hidden directories, with the arguments in the path order, and returns the
+/// cache hits and misses of their packages and the cache size after compiling them.
+pub fn cache_stats(dir: &str, args: &ExecProgramArgs) -> Result<CacheStats> {
+    let mut stats = CacheStats::default();
+    let mut roots = BTreeSet::new();
+    for entry in config_entries(dir)? {
+        let entry_file = entry.display().to_string();
+        let mut entry_args = args.clone();
+        entry_args.k_filename_list = vec![entry_file.clone()];
+        entry_args.work_dir = entry.parent().map(|dir| dir.display().to_string());
+        let plan = explain_plan(Arc::new(ParseSession::default()), &entry_args)
+            .with_context(|| format!("failed to load the config {}", entry_file))?;
+        let result = exec_program(Arc::new(ParseSession::default()), &entry_args)
+            .with_context(|| format!("failed to compile the config {}", entry_file))?;
+        let usage = result.cache_usage.unwrap_or_default();
+        let config = ConfigCacheStats {
+            config: entry_file,
+            hits: usage.hits,
+            misses: usage.misses,
+        };
+        stats.hits += config.hits;
+        stats.misses += config.misses;
+        stats.configs.push(config);
+        stats.backend = plan.backend;
+        if !plan.root.is_empty() {
+            roots.insert(plan.root);
+        }
+    }
+    stats.packages = stats.hits + stats.misses;
+    if stats.packages > 0 {
+        stats.hit_ratio = stats.hits as f64 / stats.packages as f64;
+    }
+    // The cache info files are always under the program root, and the object files
+    // are under the cache path environment variable if it is set.
+    let cache_dirs: BTreeSet<PathBuf> = roots
+        .iter()
+        .flat_map(|root| {
+            [
+                Path::new(root).join(".kclvm").join("cache"),
+                cache_root(root),
+            ]
+        })
+        .collect();
+    stats.cache_size = cache_dirs.iter().map(|dir| dir_size(dir)).sum();
+    Ok(stats)
+}
+
+/// Returns the config entry files under the directory in the path order.
+fn config_entries(dir: &str) -> Result<Vec<PathBuf>> {
+    let mut entries = vec![];
+    let walker = WalkDir::new(dir).sort_by_file_name().into_iter();
+    for entry in walker.filter_entry(|e| e.depth() == 0 || !is_hidden(e.path())) {
+        let entry = entry?;
+        if entry.file_type().is_file() && entry.file_name() == CONFIG_ENTRY_FILE {
+            entries.push(entry.path().canonicalize()?);
+        }
+    }
+    if entries.is_empty() {
+        anyhow::bail!(
+            "no config entry file {} found in {}",
+            CONFIG_ENTRY_FILE,
+            dir
+        );
+    }
+    Ok(entries)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map_or(false, |name| name.starts_with('.'))
+}
+
+/// Returns the total bytes of the files under the directory, which is 0 when it
+/// does not exist.
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
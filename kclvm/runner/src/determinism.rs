@@ -0,0 +1,72 @@
+//! Detect the non-deterministic evaluation, e.g., the output depends on the time
+//! or the iteration order of the hash maps, see [check_determinism].
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use kclvm_parser::{ParseSession, ParseSessionRef};
+
+use crate::{exec_program, ExecProgramArgs, ExecProgramResult};
+
+/// Run the program twice with the arguments and compare the canonical outputs of
+/// the two runs, the differing outputs are reported as an error with a line diff.
+/// The result of the first run is returned when the outputs are identical.
+pub fn check_determinism(
+    sess: ParseSessionRef,
+    args: &ExecProgramArgs,
+) -> Result<ExecProgramResult> {
+    let first = exec_program(sess, args)?;
+    let second = exec_program(Arc::new(ParseSession::default()), args)?;
+    let (expected, actual) = (canonical_output(&first), canonical_output(&second));
+    if expected != actual {
+        bail!(
+            "the evaluation is not deterministic, the outputs of two runs differ\n{}",
+            diff_lines(&expected, &actual)
+        );
+    }
+    Ok(first)
+}
+
+/// Returns the output of the run to compare, which is the error message of the
+/// failed run, or the YAML output with `\n` line endings.
+fn canonical_output(result: &ExecProgramResult) -> String {
+    let output = if result.err_message.is_empty() {
+        &result.yaml_result
+    } else {
+        &result.err_message
+    };
+    output.replace("\r\n", "\n")
+}
+
+/// Returns the line diff from `expected` to `actual`, where the removed lines
+/// start with `-` and the added lines start with `+`.
+pub(crate) fn diff_lines(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+    // The length of the longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            diff.push_str(&format!(" {}\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", a[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", b[j]));
+            j += 1;
+        }
+    }
+    diff
+}
@@ -0,0 +1,101 @@
+//! Diagnose the installation problems, e.g., the missing linker and the wrong
+//! LLVM backend, by running the self checks with the assembler, linker and runner
+//! internals. The main API function is `run_checks`.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use kclvm_parser::ParseSession;
+
+use crate::assembler::{available_backends, default_cache_dir, KclvmLibAssembler};
+use crate::linker::KclvmLinker;
+use crate::{exec_program, ExecProgramArgs};
+
+/// The result of a self check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    /// Whether the installation is broken when the check fails.
+    pub critical: bool,
+    /// The detail when the check passes or the error message when it fails.
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &str, critical: bool, result: Result<String>) -> Self {
+        let (passed, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(err) => (false, err.to_string()),
+        };
+        Self {
+            name: name.to_string(),
+            passed,
+            critical,
+            detail,
+        }
+    }
+}
+
+/// Run all the self checks under the program root path. The linker and the cache
+/// dir are only critical when the LLVM backend is available, because the fast
+/// evaluator does not use them.
+pub fn run_checks(root: &str) -> Vec<CheckResult> {
+    let llvm = KclvmLibAssembler::LLVM.is_available();
+    let mut checks = vec![
+        CheckResult::new("backends", false, Ok(available_backends().join(", "))),
+        CheckResult::new("linker", llvm, KclvmLinker::check_linker()),
+        CheckResult::new("cache", llvm, check_cache_dir(root)),
+        CheckResult::new("compile", true, check_compile(true)),
+    ];
+    if llvm {
+        checks.push(CheckResult::new(
+            "compile (llvm)",
+            true,
+            check_compile(false),
+        ));
+    }
+    checks
+}
+
+/// Check the cache dir under the root path is writable, and returns the cache dir.
+/// The cache dir is not created, the nearest existing ancestor is checked instead.
+pub fn check_cache_dir(root: &str) -> Result<String> {
+    let cache_dir = default_cache_dir(root);
+    let mut dir: &Path = &cache_dir;
+    while !dir.exists() {
+        dir = match dir.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+    }
+    if let Err(err) = tempfile::NamedTempFile::new_in(dir) {
+        bail!("the cache dir {} is not writable: {}", dir.display(), err);
+    }
+    Ok(cache_dir.display().to_string())
+}
+
+/// Check a trivial KCL program compiles and runs with the fast evaluator or the
+/// native backend, and returns the backend name.
+pub fn check_compile(fast_eval: bool) -> Result<String> {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("doctor.k".to_string());
+    args.k_code_list.push("a = 1 + 1".to_string());
+    args.fast_eval = fast_eval;
+    let result = exec_program(Arc::new(ParseSession::default()), &args)?;
+    if !result.err_message.is_empty() {
+        bail!("{}", result.err_message);
+    }
+    if result.yaml_result != "a: 2" {
+        bail!(
+            "unexpected output '{}', expected 'a: 2'",
+            result.yaml_result
+        );
+    }
+    Ok(if fast_eval {
+        "the program runs with the evaluator".to_string()
+    } else {
+        "the program runs with the llvm backend".to_string()
+    })
+}
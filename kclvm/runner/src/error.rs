@@ -0,0 +1,91 @@
+use std::fmt;
+use std::time::Duration;
+
+/// RunnerError denotes the errors in each stage of loading, compiling and
+/// running a KCL program. The message of the error is the same as the message
+/// of its source error.
+#[derive(Debug)]
+pub enum RunnerError {
+    /// Errors of the invalid execution arguments, e.g., the conflicting options.
+    Args(anyhow::Error),
+    /// Errors when loading, parsing and overriding the KCL program.
+    Parse(anyhow::Error),
+    /// Errors when resolving the KCL program, e.g., type errors.
+    Resolve(anyhow::Error),
+    /// Errors when generating the native code of the KCL program.
+    Codegen(anyhow::Error),
+    /// Errors when linking the generated libraries.
+    Link(anyhow::Error),
+    /// Errors when executing the KCL program.
+    Runtime(anyhow::Error),
+    /// Errors when reading or writing files.
+    Io(anyhow::Error),
+    /// Errors when deserializing the result into the Rust value, see [crate::execute_into].
+    Deserialize(anyhow::Error),
+    /// The execution is cancelled.
+    Cancelled,
+    /// The execution exceeds the time limit.
+    Timeout(Duration),
+}
+
+/// The result type with the [RunnerError].
+pub type RunnerResult<T> = std::result::Result<T, RunnerError>;
+
+impl RunnerError {
+    /// Returns the stage name of the error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RunnerError::Args(_) => "args",
+            RunnerError::Parse(_) => "parse",
+            RunnerError::Resolve(_) => "resolve",
+            RunnerError::Codegen(_) => "codegen",
+            RunnerError::Link(_) => "link",
+            RunnerError::Runtime(_) => "runtime",
+            RunnerError::Io(_) => "io",
+            RunnerError::Deserialize(_) => "deserialize",
+            RunnerError::Cancelled => "cancelled",
+            RunnerError::Timeout(_) => "timeout",
+        }
+    }
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunnerError::Args(err)
+            | RunnerError::Parse(err)
+            | RunnerError::Resolve(err)
+            | RunnerError::Codegen(err)
+            | RunnerError::Link(err)
+            | RunnerError::Runtime(err)
+            | RunnerError::Io(err)
+            | RunnerError::Deserialize(err) => write!(f, "{}", err),
+            RunnerError::Cancelled => write!(f, "the execution is cancelled"),
+            RunnerError::Timeout(timeout) => {
+                write!(f, "the execution timed out after {:?}", timeout)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunnerError::Args(err)
+            | RunnerError::Parse(err)
+            | RunnerError::Resolve(err)
+            | RunnerError::Codegen(err)
+            | RunnerError::Link(err)
+            | RunnerError::Runtime(err)
+            | RunnerError::Io(err)
+            | RunnerError::Deserialize(err) => Some(err.as_ref()),
+            RunnerError::Cancelled | RunnerError::Timeout(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RunnerError {
+    fn from(err: std::io::Error) -> Self {
+        RunnerError::Io(err.into())
+    }
+}
@@ -0,0 +1,493 @@
+//! Serialize the [ExecProgramResult] in the output formats.
+//!
+//! The `tfjson` format outputs the Terraform JSON configuration syntax (`.tf.json`)
+//! with the following convention:
+//!
+//! - The top-level Terraform block keys, e.g., `terraform`, `provider`, `variable`,
+//!   `output`, `locals`, `module`, `resource` and `data`, are output as they are,
+//!   so the output which is already in the Terraform shape is passed through.
+//! - Every other top-level object with a string attribute `resource_type` is a
+//!   resource block named by the top-level key, and every top-level object with a
+//!   string attribute `data_type` is a data block. The type attribute is removed
+//!   from the block body.
+//!
+//! ```text
+//! web = {                                  {
+//!     resource_type = "aws_instance"           "resource": {
+//!     ami = "ami-123"             ->               "aws_instance": {
+//!     instance_type = "t2.micro"                       "web": {"ami": "ami-123", "instance_type": "t2.micro"}
+//! }                                                }
+//!                                              }
+//!                                          }
+//! ```
+//!
+//! Other top-level keys do not follow the convention and are reported as errors.
+//!
+//! The `ndjson` format outputs the newline-delimited JSON, which is one single-line
+//! JSON value per line. It parallels the YAML stream: the elements of a top-level
+//! list and the documents of a stream result are output as separate lines, and the
+//! other results are output as a single line.
+//!
+//! The result can also be rendered through a custom handlebars template, see
+//! [ExecProgramResult::render_template], and the documents of a stream result can
+//! be selected before the serialization, see [ExecProgramResult::select_documents],
+//! or sorted by their references, see [ExecProgramResult::topo_sort_documents].
+
+use anyhow::{bail, Result};
+use handlebars::{no_escape, Handlebars};
+use kclvm_runtime::{Context, JsonEncodeOptions, ValueRef, JSON_STREAM_SEP, YAML_STREAM_SEP};
+use sha2::{Digest, Sha256};
+
+use crate::ExecProgramResult;
+
+/// The top-level block keys of the Terraform JSON configuration syntax.
+pub const TERRAFORM_BLOCK_KEYS: &[&str] = &[
+    "terraform",
+    "provider",
+    "variable",
+    "output",
+    "locals",
+    "module",
+    "resource",
+    "data",
+    "check",
+    "import",
+    "moved",
+];
+/// The attribute which denotes the type of a resource block.
+pub const TERRAFORM_RESOURCE_TYPE_ATTR: &str = "resource_type";
+/// The attribute which denotes the type of a data block.
+pub const TERRAFORM_DATA_TYPE_ATTR: &str = "data_type";
+
+/// The output format of the KCL program result.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Yaml,
+    Json,
+    /// The Terraform JSON configuration syntax.
+    TfJson,
+    /// The newline-delimited JSON.
+    NdJson,
+}
+
+impl TryFrom<&str> for OutputFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "json" => Ok(OutputFormat::Json),
+            "tfjson" | "tf.json" => Ok(OutputFormat::TfJson),
+            "ndjson" | "jsonl" => Ok(OutputFormat::NdJson),
+            _ => bail!(
+                "unsupported output format '{}', expected yaml, json, tfjson or ndjson",
+                value
+            ),
+        }
+    }
+}
+
+impl ExecProgramResult {
+    /// Serialize the result in the output format.
+    pub fn to_format(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Yaml => Ok(self.yaml_result.clone()),
+            OutputFormat::Json => Ok(self.json_result.clone()),
+            OutputFormat::TfJson => to_terraform_json(&self.json_result),
+            OutputFormat::NdJson => to_ndjson(&self.json_result),
+        }
+    }
+
+    /// Returns the SHA-256 hex digest of the canonical JSON result with the sorted
+    /// keys, thus the semantically equal results with different key orders have the
+    /// same digest. The documents of a stream result are digested in order.
+    pub fn digest(&self) -> Result<String> {
+        let mut ctx = Context::new();
+        let opts = JsonEncodeOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        let mut hasher = Sha256::new();
+        for (i, document) in json_documents(&self.json_result).enumerate() {
+            if i > 0 {
+                hasher.update(JSON_STREAM_SEP.as_bytes());
+            }
+            let value = ValueRef::from_json(&mut ctx, document)?;
+            hasher.update(value.to_json_string_with_options(&opts).as_bytes());
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    /// Render the result through the handlebars template, e.g., `name={{app.name}}`. The
+    /// template context is the result value, which is the list of the documents for a stream
+    /// result, and the object keys are iterated in the sorted order. The values are not HTML
+    /// escaped, and an undefined template variable is an error.
+    pub fn render_template(&self, template: &str) -> Result<String> {
+        let documents = json_documents(&self.json_result)
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let data = match documents.len() {
+            0 => serde_json::Value::Object(Default::default()),
+            1 => documents.into_iter().next().unwrap_or_default(),
+            _ => serde_json::Value::Array(documents),
+        };
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        handlebars.register_escape_fn(no_escape);
+        Ok(handlebars.render_template(template, &data)?)
+    }
+
+    /// Returns the result of the documents matching the selector in order, the
+    /// documents are the YAML stream documents, i.e., the ones produced by
+    /// `manifests.yaml_stream` and the lines of the `ndjson` format. The selector
+    /// matching nothing, e.g., an out-of-range index, is an error.
+    pub fn select_documents(&self, selector: &DocumentSelector) -> Result<ExecProgramResult> {
+        let (json_documents, yaml_documents) = self.stream_documents()?;
+        let indices = match selector {
+            DocumentSelector::Index(index) if *index < json_documents.len() => vec![*index],
+            DocumentSelector::Index(index) => bail!(
+                "the document index {} is out of range, the result has {} document(s)",
+                index,
+                json_documents.len()
+            ),
+            DocumentSelector::Field { .. } => {
+                let mut indices = vec![];
+                for (i, document) in json_documents.iter().enumerate() {
+                    if selector.matches(&serde_json::from_str(document)?) {
+                        indices.push(i);
+                    }
+                }
+                if indices.is_empty() {
+                    bail!("no document matches the selector {}", selector);
+                }
+                indices
+            }
+        };
+        Ok(self.with_documents(&json_documents, &yaml_documents, &indices))
+    }
+
+    /// Returns the result of the documents in the dependency order, i.e., every
+    /// document is output after the documents it references. The references of a
+    /// document are the names at the dotted path `ref_field`, which is a string or
+    /// a list of strings, and the name of a document is its `metadata.name` or
+    /// `name` field. The independent documents keep their original order, and the
+    /// reference cycles and the references to the unknown names are errors.
+    pub fn topo_sort_documents(&self, ref_field: &str) -> Result<ExecProgramResult> {
+        let (json_documents, yaml_documents) = self.stream_documents()?;
+        let path: Vec<&str> = ref_field.split('.').map(|key| key.trim()).collect();
+        if path.iter().any(|key| key.is_empty()) {
+            bail!("invalid reference field '{}'", ref_field);
+        }
+        let mut names = vec![];
+        let mut refs = vec![];
+        for document in &json_documents {
+            let document: serde_json::Value = serde_json::from_str(document)?;
+            names.push(document_name(&document));
+            refs.push(match json_field(&document, &path) {
+                None | Some(serde_json::Value::Null) => vec![],
+                Some(serde_json::Value::String(name)) => vec![name.to_string()],
+                Some(serde_json::Value::Array(items)) => items
+                    .iter()
+                    .map(|item| match item {
+                        serde_json::Value::String(name) => Ok(name.to_string()),
+                        _ => bail!("the reference {} is not a string", item),
+                    })
+                    .collect::<Result<Vec<String>>>()?,
+                Some(field) => bail!(
+                    "the reference field {} is {}, expected a string or a list of strings",
+                    ref_field,
+                    field
+                ),
+            });
+        }
+        // The documents referenced by each document, which share the name.
+        let mut deps = vec![];
+        for (i, doc_refs) in refs.iter().enumerate() {
+            let mut doc_deps = vec![];
+            for name in doc_refs {
+                let referenced: Vec<usize> = (0..names.len())
+                    .filter(|j| names[*j].as_deref() == Some(name.as_str()))
+                    .collect();
+                if referenced.is_empty() {
+                    bail!(
+                        "the document {} references the unknown document {}",
+                        names[i].clone().unwrap_or_else(|| i.to_string()),
+                        name
+                    );
+                }
+                doc_deps.extend(referenced);
+            }
+            deps.push(doc_deps);
+        }
+        let mut sorter = TopoSorter {
+            deps: &deps,
+            names: &names,
+            visited: vec![false; deps.len()],
+            stack: vec![],
+            order: vec![],
+        };
+        for i in 0..deps.len() {
+            sorter.visit(i)?;
+        }
+        Ok(self.with_documents(&json_documents, &yaml_documents, &sorter.order))
+    }
+
+    /// Returns the JSON and YAML documents of the result, see [json_documents].
+    fn stream_documents(&self) -> Result<(Vec<&str>, Vec<&str>)> {
+        let json_documents: Vec<&str> = json_documents(&self.json_result).collect();
+        let yaml_documents: Vec<&str> = if json_documents.is_empty() {
+            vec![]
+        } else {
+            self.yaml_result.split(YAML_STREAM_SEP).collect()
+        };
+        if json_documents.len() != yaml_documents.len() {
+            bail!("the documents of the YAML and JSON results are inconsistent");
+        }
+        Ok((json_documents, yaml_documents))
+    }
+
+    /// Returns the result of the documents at the indices in order.
+    fn with_documents(
+        &self,
+        json_documents: &[&str],
+        yaml_documents: &[&str],
+        indices: &[usize],
+    ) -> ExecProgramResult {
+        let select = |documents: &[&str], sep: &str| {
+            indices
+                .iter()
+                .map(|i| documents[*i])
+                .collect::<Vec<&str>>()
+                .join(sep)
+        };
+        ExecProgramResult {
+            json_result: select(json_documents, JSON_STREAM_SEP),
+            yaml_result: select(yaml_documents, YAML_STREAM_SEP),
+            ..self.clone()
+        }
+    }
+}
+
+/// The depth-first topological sort of the documents, which outputs a document
+/// after all its dependencies.
+struct TopoSorter<'a> {
+    deps: &'a [Vec<usize>],
+    names: &'a [Option<String>],
+    visited: Vec<bool>,
+    /// The documents being visited, which is the current reference path.
+    stack: Vec<usize>,
+    order: Vec<usize>,
+}
+
+impl<'a> TopoSorter<'a> {
+    fn visit(&mut self, i: usize) -> Result<()> {
+        if let Some(start) = self.stack.iter().position(|j| *j == i) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&i))
+                .map(|j| self.names[*j].clone().unwrap_or_else(|| j.to_string()))
+                .collect::<Vec<String>>()
+                .join(" -> ");
+            bail!("the documents have a reference cycle {}", cycle);
+        }
+        if self.visited[i] {
+            return Ok(());
+        }
+        self.stack.push(i);
+        let deps = self.deps;
+        for dep in &deps[i] {
+            self.visit(*dep)?;
+        }
+        self.stack.pop();
+        self.visited[i] = true;
+        self.order.push(i);
+        Ok(())
+    }
+}
+
+/// Returns the field of the JSON document at the path.
+fn json_field<'a>(document: &'a serde_json::Value, path: &[&str]) -> Option<&'a serde_json::Value> {
+    path.iter()
+        .try_fold(document, |document, key| document.get(key))
+}
+
+/// Returns the name of the document, i.e., its `metadata.name` or `name` field.
+fn document_name(document: &serde_json::Value) -> Option<String> {
+    json_field(document, &["metadata", "name"])
+        .or_else(|| document.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+}
+
+/// The selector of the documents in a stream result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentSelector {
+    /// The document at the index, which starts from 0.
+    Index(usize),
+    /// The documents whose field at the dotted path equals the value, e.g.,
+    /// `kind==Service` and `metadata.name==web`. The non-string fields are
+    /// compared in their JSON form, e.g., `replicas==3`.
+    Field { path: Vec<String>, value: String },
+}
+
+impl TryFrom<&str> for DocumentSelector {
+    type Error = anyhow::Error;
+
+    fn try_from(selector: &str) -> Result<Self> {
+        if let Ok(index) = selector.trim().parse::<usize>() {
+            return Ok(DocumentSelector::Index(index));
+        }
+        match selector.split_once("==") {
+            Some((path, value)) if path.split('.').all(|key| !key.trim().is_empty()) => {
+                Ok(DocumentSelector::Field {
+                    path: path.split('.').map(|key| key.trim().to_string()).collect(),
+                    value: value.trim().to_string(),
+                })
+            }
+            _ => bail!(
+                "invalid document selector '{}', expected an index, e.g., 0, or a field predicate, e.g., kind==Service",
+                selector
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for DocumentSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentSelector::Index(index) => write!(f, "{}", index),
+            DocumentSelector::Field { path, value } => write!(f, "{}=={}", path.join("."), value),
+        }
+    }
+}
+
+impl DocumentSelector {
+    /// Whether the JSON document matches the field predicate.
+    fn matches(&self, document: &serde_json::Value) -> bool {
+        match self {
+            DocumentSelector::Index(_) => false,
+            DocumentSelector::Field { path, value } => {
+                let field = path
+                    .iter()
+                    .try_fold(document, |document, key| document.get(key));
+                match field {
+                    Some(serde_json::Value::String(field)) => field == value,
+                    Some(field) => field.to_string() == *value,
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+/// Returns the documents of the JSON result, which are joined by [JSON_STREAM_SEP].
+pub(crate) fn json_documents(json_result: &str) -> impl Iterator<Item = &str> {
+    json_result
+        .split(JSON_STREAM_SEP)
+        .filter(|document| !document.trim().is_empty())
+}
+
+/// Output the JSON result of the KCL program as the newline-delimited JSON, see
+/// the module document for the convention.
+pub fn to_ndjson(json_result: &str) -> Result<String> {
+    let mut ctx = Context::new();
+    let opts = JsonEncodeOptions::default();
+    let mut lines = vec![];
+    // Every document of the JSON result is encoded in a single line.
+    for document in json_documents(json_result) {
+        let value = ValueRef::from_json(&mut ctx, document)?;
+        if value.is_list() {
+            for item in value.as_list_ref().values.iter() {
+                lines.push(item.to_json_string_with_options(&opts));
+            }
+        } else {
+            lines.push(value.to_json_string_with_options(&opts));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Structure the JSON result of the KCL program in the Terraform JSON syntax,
+/// see the module document for the convention.
+pub fn to_terraform_json(json_result: &str) -> Result<String> {
+    let mut ctx = Context::new();
+    let value = ValueRef::from_json(&mut ctx, json_result)?;
+    if !value.is_dict() {
+        bail!("the Terraform JSON output requires a top-level object");
+    }
+    let config = ValueRef::dict(None);
+    for (key, block) in value.as_dict_ref().values.iter() {
+        if TERRAFORM_BLOCK_KEYS.contains(&key.as_str()) {
+            merge_blocks(
+                &config,
+                &ValueRef::dict(Some(&[(key.as_str(), block)])),
+                &[],
+            )?;
+            continue;
+        }
+        let type_attr = [TERRAFORM_RESOURCE_TYPE_ATTR, TERRAFORM_DATA_TYPE_ATTR]
+            .into_iter()
+            .find(|attr| block.is_dict() && block.dict_get_value(attr).is_some());
+        let attr = match type_attr {
+            Some(attr) => attr,
+            None => bail!(
+                "the top-level key '{}' is not a Terraform block, set its '{}' or '{}'",
+                key,
+                TERRAFORM_RESOURCE_TYPE_ATTR,
+                TERRAFORM_DATA_TYPE_ATTR
+            ),
+        };
+        let ty = block.dict_get_value(attr).unwrap_or_default();
+        if !ty.is_str() {
+            bail!("the '{}' of the block '{}' must be a string", attr, key);
+        }
+        let ty = ty.as_str();
+        let mut body = block.deep_copy();
+        body.dict_remove(attr);
+        let block_key = if attr == TERRAFORM_RESOURCE_TYPE_ATTR {
+            "resource"
+        } else {
+            "data"
+        };
+        let body = ValueRef::dict(Some(&[(key.as_str(), &body)]));
+        let body = ValueRef::dict(Some(&[(ty.as_str(), &body)]));
+        merge_blocks(&config, &ValueRef::dict(Some(&[(block_key, &body)])), &[])?;
+    }
+    let opts = JsonEncodeOptions {
+        indent: 2,
+        ..Default::default()
+    };
+    Ok(config.to_json_string_with_options(&opts))
+}
+
+/// Merge the blocks into the config, the blocks with the same labels, e.g., the
+/// resource blocks with the same type and name, are reported as errors.
+fn merge_blocks(config: &ValueRef, blocks: &ValueRef, path: &[&str]) -> Result<()> {
+    for (name, block) in blocks.as_dict_ref().values.iter() {
+        let mut path = path.to_vec();
+        path.push(name);
+        // The resource and data blocks have two labels, and the other blocks have one label.
+        let labels = match path[0] {
+            "resource" | "data" => 2,
+            _ => 1,
+        };
+        match config.dict_get_value(name) {
+            Some(existed) if existed.is_dict() && block.is_dict() && path.len() <= labels => {
+                merge_blocks(&existed, block, &path)?
+            }
+            Some(_) => bail!(
+                "the Terraform block '{}' is defined more than once",
+                path.join(".")
+            ),
+            None => config
+                .clone()
+                .dict_update_key_value(name, block.deep_copy()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! Compare the YAML outputs of the KCL programs with the golden files. The outputs
+//! and the golden files are normalized by sorting the keys and using `\n` line
+//! endings before the comparison.
+//!
+//! The golden files are only read here, see the `testing` feature for updating
+//! them with the outputs in the tests.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _, Result};
+use kclvm_parser::ParseSession;
+use kclvm_runtime::{Context, ValueRef, YamlEncodeOptions, YAML_STREAM_SEP};
+
+use crate::determinism::diff_lines;
+use crate::{exec_program, ExecProgramArgs};
+
+/// Run the KCL program with the args and compare its YAML output with the golden
+/// file, the mismatch is returned as an error with a line diff of the normalized
+/// outputs.
+pub fn compare_kcl_output<Q: AsRef<Path>>(args: &ExecProgramArgs, golden_path: Q) -> Result<()> {
+    let golden_path = golden_path.as_ref();
+    let actual = kcl_output(args)?;
+    let expected = std::fs::read_to_string(golden_path)
+        .with_context(|| format!("failed to read the golden file {}", golden_path.display()))?;
+    if let Some(diff) = diff_output(&expected, &actual) {
+        bail!(
+            "the output does not match the golden file {}\n{}",
+            golden_path.display(),
+            diff
+        );
+    }
+    Ok(())
+}
+
+/// Run the KCL program with the args and returns its normalized YAML output.
+pub fn kcl_output(args: &ExecProgramArgs) -> Result<String> {
+    let result = exec_program(Arc::new(ParseSession::default()), args)?;
+    if !result.err_message.is_empty() {
+        bail!(result.err_message);
+    }
+    Ok(normalize_output(&result.yaml_result))
+}
+
+/// Returns the line diff of the expected output and the normalized actual output
+/// when they are different.
+pub(crate) fn diff_output(expected: &str, actual: &str) -> Option<String> {
+    let expected = normalize_output(expected);
+    if actual == expected {
+        None
+    } else {
+        Some(diff_lines(&expected, actual))
+    }
+}
+
+/// Normalize the YAML stream by sorting the keys and using `\n` line endings. The
+/// content which is not a valid YAML stream is only normalized by the line endings.
+pub fn normalize_output(output: &str) -> String {
+    let output = output.replace("\r\n", "\n");
+    let mut ctx = Context::new();
+    let mut normalized = match ValueRef::from_yaml_documents(&mut ctx, &output) {
+        Ok(documents) => {
+            let opts = YamlEncodeOptions {
+                sort_keys: true,
+                ..Default::default()
+            };
+            documents
+                .iter()
+                .map(|d| {
+                    let yaml = d.to_yaml_string_with_options(&opts);
+                    yaml.trim_end_matches('\n').to_string()
+                })
+                .collect::<Vec<String>>()
+                .join(YAML_STREAM_SEP)
+        }
+        Err(_) => output.trim_end().to_string(),
+    };
+    normalized.push('\n');
+    normalized
+}
@@ -1,41 +1,76 @@
-use std::{collections::HashMap, ffi::OsStr, path::Path};
+use std::{collections::HashMap, ffi::OsStr, path::Path, sync::Arc};
 
 use anyhow::{anyhow, bail, Result};
-use assembler::KclvmLibAssembler;
+use assembler::{KclvmLibAssembler, EVALUATOR_BACKEND};
+pub use error::{RunnerError, RunnerResult};
+pub use format::OutputFormat;
+use indexmap::IndexMap;
 use kclvm_ast::{
     ast::{Module, Program},
     MAIN_PKG,
 };
-use kclvm_driver::{canonicalize_input_files, expand_input_files};
+use kclvm_driver::{canonicalize_input_files, expand_input_files, kpm::check_offline_dependencies};
+use kclvm_error::{
+    render_rich_diagnostics, sort_diagnostics, Diagnostic, DiagnosticFormat, Handler, Level,
+};
 use kclvm_parser::{load_program, KCLModuleCache, ParseSessionRef};
-use kclvm_query::apply_overrides;
+use kclvm_query::{apply_overrides_with_outcomes, check_stale_overrides};
+use kclvm_sema::fold::fold_constants;
 use kclvm_sema::resolver::{
-    resolve_program, resolve_program_with_opts, scope::ProgramScope, Options,
+    resolve_program, resolve_program_with_emitter, resolve_program_with_opts, scope::ProgramScope,
+    Options,
 };
 use linker::Command;
+use patch::render_patch;
+use provenance::{collect_provenance, render_provenance};
 #[cfg(feature = "llvm")]
 use runner::LibRunner;
 pub use runner::{Artifact, ExecProgramArgs, ExecProgramResult, MapErrorResult};
 use runner::{FastRunner, ProgramRunner, RunnerOptions};
-use tempfile::tempdir;
+use serde::de::DeserializeOwned;
+use tempfile::TempDir;
+use version::render_version;
 
+pub mod archive;
 pub mod assembler;
+pub mod assertion;
+pub mod cache_archive;
+pub mod cache_stats;
+pub mod determinism;
+pub mod doctor;
+pub mod error;
+pub mod format;
+pub mod golden;
 pub mod linker;
+pub mod metrics;
+pub mod patch;
+pub mod plan;
+pub mod provenance;
 pub mod runner;
+pub mod sandbox;
+mod schema;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod version;
 
 #[cfg(test)]
 pub mod tests;
 
 const KCL_FAST_EVAL_ENV_VAR: &str = "KCL_FAST_EVAL";
 
+/// The stack size of the thread which runs the program, the recursion of the parser
+/// and the evaluator bounded by the default depth limits never overflows it.
+pub const EXEC_STACK_SIZE: usize = 256 * 1024 * 1024;
+
 /// After the kcl program passed through kclvm-parser in the compiler frontend,
 /// KCL needs to resolve ast, generate corresponding LLVM IR, dynamic link library or
 /// executable file for kcl program in the compiler backend.
 ///
 /// Method “execute” is the entry point for the compiler backend.
 ///
-/// It returns the KCL program executing result as Result<a_json_string, an_err_string>,
-/// and mainly takes "program" (ast.Program returned by kclvm-parser) as input.
+/// It returns the KCL program executing result as Result<[ExecProgramResult], [RunnerError]>,
+/// where the error denotes the failed stage, and mainly takes "program" (ast.Program returned
+/// by kclvm-parser) as input.
 ///
 /// "args" is the items selected by the user in the KCL CLI.
 ///
@@ -73,25 +108,119 @@ const KCL_FAST_EVAL_ENV_VAR: &str = "KCL_FAST_EVAL";
 /// // Result is the kcl in json format.
 /// let result = exec_program(sess, &args).unwrap();
 /// ```
-pub fn exec_program(sess: ParseSessionRef, args: &ExecProgramArgs) -> Result<ExecProgramResult> {
+pub fn exec_program(
+    sess: ParseSessionRef,
+    args: &ExecProgramArgs,
+) -> RunnerResult<ExecProgramResult> {
+    args.validate().map_err(RunnerError::Args)?;
+    // Run the entries of the archived modules, the extracted files are removed on return.
+    let (args, _archives) = archive::extract_archive_inputs(args).map_err(RunnerError::Io)?;
+    let args = args.as_ref();
     // parse args from json string
     let opts = args.get_load_program_options();
-    let kcl_paths = expand_files(args)?;
+    let kcl_paths = expand_files(args).map_err(RunnerError::Parse)?;
     let kcl_paths_str = kcl_paths.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+    // The dependencies are never fetched in the offline mode.
+    if let Some(path) = kcl_paths.first() {
+        check_offline_dependencies(Path::new(path), &opts).map_err(RunnerError::Parse)?;
+    }
     let module_cache = KCLModuleCache::default();
     let mut program = load_program(
         sess.clone(),
         kcl_paths_str.as_slice(),
         Some(opts),
         Some(module_cache),
-    )?
+    )
+    .map_err(RunnerError::Parse)?
     .program;
-    apply_overrides(
+    check_cancelled(args)?;
+    let outcomes = apply_overrides_with_outcomes(
         &mut program,
         &args.overrides,
         &[],
         args.print_override_ast || args.debug > 0,
-    )?;
+    )
+    .map_err(RunnerError::Parse)?;
+    if args.strict_overrides {
+        check_stale_overrides(&args.overrides, &outcomes).map_err(RunnerError::Parse)?;
+    }
+    let provenance = args.trace_provenance.then(|| collect_provenance(&program));
+    let mut result = execute(sess, program, args)?;
+    if !args.json_patch.is_empty() && result.err_message.is_empty() {
+        (result.yaml_result, result.json_result) = render_patch(
+            &result.json_result,
+            &args.json_patch,
+            args.sort_keys,
+            args.yaml_spec,
+        )
+        .map_err(RunnerError::Runtime)?;
+    }
+    if let Some(provenance) = provenance {
+        if result.err_message.is_empty() && !result.json_result.is_empty() {
+            (result.yaml_result, result.json_result) =
+                render_provenance(&provenance, &result.json_result)
+                    .map_err(RunnerError::Runtime)?;
+        }
+    }
+    if args.emit_version && result.err_message.is_empty() {
+        (result.yaml_result, result.json_result) =
+            render_version(&result.yaml_result, &result.json_result, backend_name(args))
+                .map_err(RunnerError::Runtime)?;
+    }
+    Ok(result)
+}
+
+/// Returns the name of the backend which runs the program with the arguments,
+/// the fast evaluator is used without the `llvm` feature.
+pub fn backend_name(args: &ExecProgramArgs) -> &'static str {
+    if args.uses_llvm_backend() {
+        KclvmLibAssembler::LLVM.name()
+    } else {
+        EVALUATOR_BACKEND
+    }
+}
+
+/// Execute the KCL program built from the in-memory sources, which map the file
+/// paths to the source codes, and `entry` is the path of the main file in the sources.
+/// The import packages are looked up in the sources first and then on the disk.
+///
+/// # Examples
+///
+/// ```
+/// use kclvm_runner::{execute_from_sources, ExecProgramArgs};
+/// use kclvm_parser::ParseSession;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// let sources = HashMap::from([
+///     ("main.k".to_string(), "import pkg\n\na = pkg.b".to_string()),
+///     ("pkg.k".to_string(), "b = 1".to_string()),
+/// ]);
+/// let mut args = ExecProgramArgs::default();
+/// args.fast_eval = true;
+/// let sess = Arc::new(ParseSession::default());
+/// let result = execute_from_sources(sess, sources, "main.k", &args).unwrap();
+/// assert_eq!(result.yaml_result, "a: 1");
+/// ```
+pub fn execute_from_sources(
+    sess: ParseSessionRef,
+    sources: HashMap<String, String>,
+    entry: &str,
+    args: &ExecProgramArgs,
+) -> RunnerResult<ExecProgramResult> {
+    args.validate().map_err(RunnerError::Args)?;
+    let code = sources.get(entry).cloned().ok_or_else(|| {
+        RunnerError::Args(anyhow!(
+            "the entry file '{}' is not found in the sources",
+            entry
+        ))
+    })?;
+    let mut opts = args.get_load_program_options();
+    opts.k_code_list = vec![code];
+    opts.sources = sources;
+    let program = load_program(sess.clone(), &[entry], Some(opts), None)
+        .map_err(RunnerError::Parse)?
+        .program;
     execute(sess, program, args)
 }
 
@@ -109,8 +238,9 @@ pub fn exec_artifact<P: AsRef<OsStr>>(
 ///
 /// Method “execute” is the entry point for the compiler backend.
 ///
-/// It returns the KCL program executing result as Result<a_json_string, an_err_string>,
-/// and mainly takes "program" (ast.Program returned by kclvm-parser) as input.
+/// It returns the KCL program executing result as Result<[ExecProgramResult], [RunnerError]>,
+/// where the error denotes the failed stage, and mainly takes "program" (ast.Program returned
+/// by kclvm-parser) as input.
 ///
 /// "args" is the items selected by the user in the KCL CLI.
 ///
@@ -157,62 +287,83 @@ pub fn execute(
     sess: ParseSessionRef,
     mut program: Program,
     args: &ExecProgramArgs,
-) -> Result<ExecProgramResult> {
+) -> RunnerResult<ExecProgramResult> {
+    args.validate().map_err(RunnerError::Args)?;
+    // The objects built for another target can not be loaded and executed on the host.
+    if let Some(triple) = &args.target_triple {
+        if !args.compile_only {
+            return Err(RunnerError::Args(anyhow!(
+                "the target triple '{}' can only be set when building the program instead of executing it",
+                triple
+            )));
+        }
+    }
     // If the user only wants to compile the kcl program, the following code will only resolve ast.
     if args.compile_only {
         let mut resolve_opts = Options::default();
         resolve_opts.merge_program = false;
         // Resolve ast
-        let scope = resolve_program_with_opts(&mut program, resolve_opts, None);
-        emit_compile_diag_to_string(sess, &scope, args.compile_only)?;
+        let scope = resolve_program_with_args(&sess, &mut program, resolve_opts, args);
+        emit_compile_diag(sess, &scope, args.compile_only, args)?;
         return Ok(ExecProgramResult::default());
     }
     // Resolve ast
-    let scope = resolve_program(&mut program);
-    // Emit parse and resolve errors if exists.
-    emit_compile_diag_to_string(sess, &scope, false)?;
-    Ok(
+    let scope = resolve_program_with_args(&sess, &mut program, Options::default(), args);
+    // Emit parse and resolve errors if exists, and the warnings are errors when denied.
+    emit_compile_diag(sess, &scope, args.deny_warnings, args)?;
+    let warnings: Vec<Diagnostic> = scope
+        .diagnostics()
+        .iter()
+        .filter(|diag| matches!(diag.level, Level::Warning))
+        .cloned()
+        .collect();
+    if args.fold_constants {
+        fold_constants(&mut program);
+    }
+    check_cancelled(args)?;
+    let mut result =
         // Use the fast evaluator to run the kcl program.
-        if args.fast_eval || std::env::var(KCL_FAST_EVAL_ENV_VAR).is_ok() {
+        if !args.uses_llvm_backend() {
             FastRunner::new(Some(RunnerOptions {
                 plugin_agent_ptr: args.plugin_agent,
             }))
-            .run(&program, args)?
+            .run(&program, args)
+            .map_err(RunnerError::Runtime)?
         } else {
             // Compile the kcl program to native lib and run it.
             #[cfg(feature = "llvm")]
             {
                 // Create a temp entry file and the temp dir will be delete automatically
-                let temp_dir = tempdir()?;
-                let temp_dir_path = temp_dir.path().to_str().ok_or(anyhow!(
-                    "Internal error: {}: No such file or directory",
-                    temp_dir.path().display()
-                ))?;
-                let temp_entry_file = temp_file(temp_dir_path)?;
+                let (_temp_dir, temp_entry_file) =
+                    temp_entry_file(args).map_err(RunnerError::Io)?;
 
                 // Generate libs
-                let lib_paths = assembler::KclvmAssembler::new(
+                let assembler = assembler::KclvmAssembler::new(
                     program,
                     scope,
                     temp_entry_file.clone(),
                     KclvmLibAssembler::LLVM,
                     args.get_package_maps_from_external_pkg(),
                 )
-                .gen_libs(args)?;
+                .map_err(RunnerError::Codegen)?;
+                let cache_counter = assembler.cache_counter();
+                let lib_paths = assembler.gen_libs(args).map_err(RunnerError::Codegen)?;
 
                 // Link libs into one library
                 let lib_suffix = Command::get_lib_suffix();
                 let temp_out_lib_file = format!("{}{}", temp_entry_file, lib_suffix);
-                let lib_path = linker::KclvmLinker::link_all_libs(lib_paths, temp_out_lib_file)?;
+                let lib_path = linker::KclvmLinker::link_all_libs(lib_paths, temp_out_lib_file)
+                    .map_err(RunnerError::Link)?;
 
                 // Run the library
                 let runner = LibRunner::new(Some(RunnerOptions {
                     plugin_agent_ptr: args.plugin_agent,
                 }));
-                let result = runner.run(&lib_path, args)?;
+                let mut result = runner.run(&lib_path, args).map_err(RunnerError::Runtime)?;
+                result.cache_usage = Some(cache_counter.usage());
 
-                remove_file(&lib_path)?;
-                clean_tmp_files(&temp_entry_file, &lib_suffix)?;
+                remove_file(&lib_path).map_err(RunnerError::Io)?;
+                clean_tmp_files(&temp_entry_file, &lib_suffix).map_err(RunnerError::Io)?;
                 result
             }
             // If we don't enable llvm feature, the default running path is through the evaluator.
@@ -221,10 +372,179 @@ pub fn execute(
                 FastRunner::new(Some(RunnerOptions {
                     plugin_agent_ptr: args.plugin_agent,
                 }))
-                .run(&program, args)?
+                .run(&program, args)
+                .map_err(RunnerError::Runtime)?
             }
-        },
-    )
+        };
+    // The runtime error of the cancelled evaluation is the cancelled error.
+    check_cancelled(args)?;
+    result.warnings = warnings;
+    Ok(result)
+}
+
+/// Returns the cancelled error when the cancel flag of the arguments is set.
+#[inline]
+fn check_cancelled(args: &ExecProgramArgs) -> RunnerResult<()> {
+    if args.cancel_flag.is_cancelled() {
+        Err(RunnerError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Execute the KCL program as far as possible and return the result together with
+/// all the parse, resolve and runtime diagnostics, which is used by IDEs.
+///
+/// Unlike the fail-fast [execute], it does not stop at the compile errors and always
+/// evaluates the program with the fast evaluator. When the evaluation fails, the result
+/// holds the partial output planned from the global variables evaluated before the
+/// error, and the result is `None` when there is no output at all.
+pub fn execute_with_diagnostics(
+    sess: ParseSessionRef,
+    mut program: Program,
+    args: &ExecProgramArgs,
+) -> (Option<ExecProgramResult>, Vec<Diagnostic>) {
+    let scope = resolve_program(&mut program);
+    let mut diagnostics: Vec<Diagnostic> = sess.1.borrow().diagnostics.iter().cloned().collect();
+    diagnostics.extend(scope.diagnostics().iter().cloned());
+    let (result, runtime_diagnostic) = FastRunner::new(Some(RunnerOptions {
+        plugin_agent_ptr: args.plugin_agent,
+    }))
+    .run_partial(&program, args);
+    diagnostics.extend(runtime_diagnostic);
+    let has_output = result.err_message.is_empty() || !result.json_result.is_empty();
+    (has_output.then_some(result), diagnostics)
+}
+
+/// Validate the JSON `data` against the schema `schema_name` defined in the main
+/// package of the KCL program.
+///
+/// The data is bound to the schema as its config, e.g., `Schema {"key": "value"}`,
+/// and the program is resolved and evaluated with the fast evaluator. The result
+/// only holds the validated schema instance. The failed schema checks are returned
+/// as the [RunnerError::Runtime] error with the diagnostic message.
+///
+/// # Examples
+///
+/// ```
+/// use kclvm_runner::{execute_schema, ExecProgramArgs};
+/// use kclvm_parser::{load_program, ParseSession};
+/// use std::sync::Arc;
+///
+/// let sess = Arc::new(ParseSession::default());
+/// let args = ExecProgramArgs::default();
+/// let opts = args.get_load_program_options();
+/// let kcl_path = "./src/test_datas/execute_schema/main.k";
+/// let prog = load_program(sess.clone(), &[kcl_path], Some(opts), None).unwrap().program;
+///
+/// let data = serde_json::json!({"name": "alice", "age": 18});
+/// let result = execute_schema(sess, prog, "Person", data, &args).unwrap();
+/// assert!(result.yaml_result.contains("name: alice"));
+/// ```
+pub fn execute_schema(
+    sess: ParseSessionRef,
+    mut program: Program,
+    schema_name: &str,
+    data: serde_json::Value,
+    args: &ExecProgramArgs,
+) -> RunnerResult<ExecProgramResult> {
+    let stmt =
+        schema::build_schema_instance_stmt(schema_name, &data).map_err(RunnerError::Parse)?;
+    match program
+        .pkgs
+        .get_mut(MAIN_PKG)
+        .and_then(|modules| modules.last_mut())
+    {
+        Some(module) => module.body.push(stmt),
+        None => {
+            return Err(RunnerError::Parse(anyhow!(
+                "no main package found in the KCL program"
+            )))
+        }
+    }
+    // Resolve ast and emit parse and resolve errors if exists.
+    let scope = resolve_program_with_args(&sess, &mut program, Options::default(), args);
+    emit_compile_diag(sess, &scope, false, args)?;
+    // Only output the schema instance.
+    let mut args = args.clone();
+    args.path_selector = vec![schema::SCHEMA_INSTANCE_NAME.to_string()];
+    let result = FastRunner::new(Some(RunnerOptions {
+        plugin_agent_ptr: args.plugin_agent,
+    }))
+    .run(&program, &args)
+    .map_err(RunnerError::Runtime)?;
+    if result.err_message.is_empty() {
+        Ok(result)
+    } else {
+        Err(RunnerError::Runtime(anyhow!(result.err_message)))
+    }
+}
+
+/// Execute the KCL program and deserialize the result into the Rust value of the
+/// type `T`, which is deserialized from the JSON result directly. The documents of
+/// a stream result are deserialized as a sequence.
+///
+/// The evaluation errors are returned as the [RunnerError::Runtime] error, and the
+/// mismatch between the result and `T` is returned as the [RunnerError::Deserialize]
+/// error with the path of the mismatched field, e.g., `app.replicas`.
+///
+/// # Examples
+///
+/// ```
+/// use kclvm_runner::{execute_into, ExecProgramArgs};
+/// use kclvm_parser::{load_program, ParseSession};
+/// use std::sync::Arc;
+///
+/// #[derive(serde::Deserialize)]
+/// struct App {
+///     name: String,
+///     replicas: u32,
+/// }
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     app: App,
+/// }
+///
+/// let sess = Arc::new(ParseSession::default());
+/// let mut args = ExecProgramArgs::default();
+/// args.fast_eval = true;
+/// let opts = args.get_load_program_options();
+/// let kcl_path = "./src/test_datas/execute_into/main.k";
+/// let prog = load_program(sess.clone(), &[kcl_path], Some(opts), None).unwrap().program;
+/// let config: Config = execute_into(sess, prog, &args).unwrap();
+/// assert_eq!(config.app.name, "web");
+/// assert_eq!(config.app.replicas, 3);
+/// ```
+pub fn execute_into<T: DeserializeOwned>(
+    sess: ParseSessionRef,
+    program: Program,
+    args: &ExecProgramArgs,
+) -> RunnerResult<T> {
+    let result = execute(sess, program, args)?;
+    if !result.err_message.is_empty() {
+        return Err(RunnerError::Runtime(anyhow!(result.err_message)));
+    }
+    let documents = format::json_documents(&result.json_result).collect::<Vec<&str>>();
+    let json = match documents.len() {
+        0 => "{}".to_string(),
+        1 => documents[0].to_string(),
+        _ => format!("[{}]", documents.join(",")),
+    };
+    // The path of the failed field is tracked while deserializing, e.g., `app.ports[1]`,
+    // and `.` denotes the whole value.
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+        RunnerError::Deserialize(anyhow!(
+            "failed to deserialize the result at {}: {}",
+            err.path(),
+            err.inner()
+        ))
+    })?;
+    deserializer
+        .end()
+        .map_err(|err| RunnerError::Deserialize(err.into()))?;
+    Ok(value)
 }
 
 /// `execute_module` can directly execute the ast `Module`.
@@ -233,7 +553,7 @@ pub fn execute(
 /// For more information, see doc above method `execute`.
 ///
 /// **Note that it is not thread safe.**
-pub fn execute_module(mut m: Module) -> Result<ExecProgramResult> {
+pub fn execute_module(mut m: Module) -> RunnerResult<ExecProgramResult> {
     m.pkg = MAIN_PKG.to_string();
 
     let mut pkgs = HashMap::new();
@@ -257,6 +577,10 @@ pub fn build_program<P: AsRef<Path>>(
     args: &ExecProgramArgs,
     output: Option<P>,
 ) -> Result<Artifact> {
+    args.validate()?;
+    if let Some(output) = &output {
+        args.check_sandbox_path(output)?;
+    }
     // Parse program.
     let opts = args.get_load_program_options();
     let kcl_paths = expand_files(args)?;
@@ -265,15 +589,14 @@ pub fn build_program<P: AsRef<Path>>(
         load_program(sess.clone(), kcl_paths_str.as_slice(), Some(opts), None)?.program;
     // Resolve program.
     let scope = resolve_program(&mut program);
-    // Emit parse and resolve errors if exists.
-    emit_compile_diag_to_string(sess, &scope, false)?;
+    // Emit parse errors and the resolve errors out of the packages if exists. The packages
+    // which fail to resolve do not stop compiling the independent packages.
+    let failed_pkgs = pkg_resolve_errors(&sess, &program, &scope).unwrap_or_default();
+    if failed_pkgs.is_empty() {
+        emit_compile_diag_to_string(sess, &scope, false, args.diagnostic_format)?;
+    }
     // Create a temp entry file and the temp dir will be delete automatically.
-    let temp_dir = tempdir()?;
-    let temp_dir_path = temp_dir.path().to_str().ok_or(anyhow!(
-        "Internal error: {}: No such file or directory",
-        temp_dir.path().display()
-    ))?;
-    let temp_entry_file = temp_file(temp_dir_path)?;
+    let (_temp_dir, temp_entry_file) = temp_entry_file(args)?;
     // Generate native libs.
     let lib_paths = assembler::KclvmAssembler::new(
         program,
@@ -281,7 +604,8 @@ pub fn build_program<P: AsRef<Path>>(
         temp_entry_file.clone(),
         KclvmLibAssembler::LLVM,
         args.get_package_maps_from_external_pkg(),
-    )
+    )?
+    .with_failed_pkgs(failed_pkgs)
     .gen_libs(args)?;
 
     // Link libs into one library.
@@ -301,6 +625,37 @@ pub fn build_program<P: AsRef<Path>>(
     Artifact::from_path(lib_path)
 }
 
+/// Returns the resolve errors of the program grouped by the packages of the files where
+/// they occur, or `None` when there are any parse errors or resolve errors out of the
+/// program files, which abort the compilation instead.
+pub(crate) fn pkg_resolve_errors(
+    sess: &ParseSessionRef,
+    program: &Program,
+    scope: &ProgramScope,
+) -> Option<IndexMap<String, Vec<Diagnostic>>> {
+    if sess.1.borrow().has_errors() {
+        return None;
+    }
+    let file_pkgs: HashMap<&str, &str> = program
+        .pkgs
+        .iter()
+        .flat_map(|(pkgpath, modules)| {
+            modules
+                .iter()
+                .map(move |m| (m.filename.as_str(), pkgpath.as_str()))
+        })
+        .collect();
+    let mut failed_pkgs: IndexMap<String, Vec<Diagnostic>> = IndexMap::default();
+    for diag in scope.diagnostics().iter().filter(|diag| diag.is_error()) {
+        let pkgpath = file_pkgs.get(diag.messages.first()?.range.0.filename.as_str())?;
+        failed_pkgs
+            .entry(pkgpath.to_string())
+            .or_default()
+            .push(diag.clone());
+    }
+    Some(failed_pkgs)
+}
+
 /// Expand and return the normalized file paths for the input file list.
 pub fn expand_files(args: &ExecProgramArgs) -> Result<Vec<String>> {
     let k_files = &args.k_filename_list;
@@ -328,13 +683,37 @@ fn remove_file(file: &str) -> Result<()> {
     Ok(())
 }
 
-/// Returns a temporary file name consisting of timestamp and process id.
-fn temp_file(dir: &str) -> Result<String> {
+/// Create a temp dir under [ExecProgramArgs::temp_dir] with [ExecProgramArgs::temp_prefix],
+/// which must be inside the sandbox root, and the temp dir is removed on drop.
+pub(crate) fn create_temp_dir(args: &ExecProgramArgs) -> Result<TempDir> {
+    let parent = args.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+    args.check_sandbox_path(&parent)?;
+    std::fs::create_dir_all(&parent)?;
+    Ok(tempfile::Builder::new()
+        .prefix(args.temp_prefix.as_deref().unwrap_or(".tmp"))
+        .tempdir_in(&parent)?)
+}
+
+/// Create a temp dir under the [ExecProgramArgs::temp_dir] or the OS temp dir and
+/// returns it with the temp entry file name in it. The dir is removed on drop.
+fn temp_entry_file(args: &ExecProgramArgs) -> Result<(TempDir, String)> {
+    let temp_dir = create_temp_dir(args)?;
+    let prefix = args.temp_prefix.as_deref();
+    let temp_dir_path = temp_dir.path().to_str().ok_or(anyhow!(
+        "Internal error: {}: No such file or directory",
+        temp_dir.path().display()
+    ))?;
+    let temp_entry_file = temp_file(temp_dir_path, prefix)?;
+    Ok((temp_dir, temp_entry_file))
+}
+
+/// Returns a temporary file name consisting of the prefix, timestamp and process id.
+fn temp_file(dir: &str, prefix: Option<&str>) -> Result<String> {
     let timestamp = chrono::Local::now()
         .timestamp_nanos_opt()
         .unwrap_or_default();
     let id = std::process::id();
-    let file = format!("{}_{}", id, timestamp);
+    let file = format!("{}{}_{}", prefix.unwrap_or_default(), id, timestamp);
     std::fs::create_dir_all(dir)?;
     Ok(Path::new(dir)
         .join(file)
@@ -348,7 +727,11 @@ fn emit_compile_diag_to_string(
     sess: ParseSessionRef,
     scope: &ProgramScope,
     include_warnings: bool,
+    format: DiagnosticFormat,
 ) -> Result<()> {
+    if format == DiagnosticFormat::Rich {
+        return emit_rich_compile_diag_to_string(sess, scope, include_warnings);
+    }
     let mut res_str = sess.1.borrow_mut().emit_to_string()?;
     let sema_err = scope.emit_diagnostics_to_string(sess.0.clone(), include_warnings);
     if let Err(err) = &sema_err {
@@ -364,3 +747,122 @@ fn emit_compile_diag_to_string(
         .then(|| Ok(()))
         .unwrap_or_else(|| bail!(res_str))
 }
+
+// [`emit_rich_compile_diag_to_string`] emits the same diagnostics as [`emit_compile_diag_to_string`]
+// in the rich format with the source snippets.
+fn emit_rich_compile_diag_to_string(
+    sess: ParseSessionRef,
+    scope: &ProgramScope,
+    include_warnings: bool,
+) -> Result<()> {
+    let parse_diags = sess.1.borrow().diagnostics.clone();
+    let sema_diags = scope.diagnostics().iter().filter(|diag| match diag.level {
+        Level::Error | Level::Suggestions => true,
+        Level::Warning => include_warnings,
+        Level::Note => false,
+    });
+    let diags: Vec<&Diagnostic> = sort_diagnostics(&parse_diags)
+        .into_iter()
+        .chain(sort_diagnostics(sema_diags))
+        .collect();
+    // The included warnings fail like the plain format.
+    let has_errors = diags
+        .iter()
+        .any(|diag| diag.is_error() || matches!(diag.level, Level::Warning));
+    if parse_diags.is_empty() && !has_errors {
+        Ok(())
+    } else {
+        bail!(render_rich_diagnostics(diags))
+    }
+}
+
+// [`emit_compile_diag`] emits compile diagnostics like [`emit_compile_diag_to_string`], and the
+// error is a parse error when there are any parse errors, otherwise it is a resolve error.
+// The streamed diagnostics are not emitted again, and the error only counts them.
+fn emit_compile_diag(
+    sess: ParseSessionRef,
+    scope: &ProgramScope,
+    include_warnings: bool,
+    args: &ExecProgramArgs,
+) -> RunnerResult<()> {
+    let has_parse_errors = sess.1.borrow().has_errors();
+    let emitted = if args.stream_diagnostics {
+        streamed_compile_diag_errors(&sess, scope, include_warnings)
+    } else {
+        emit_compile_diag_to_string(sess, scope, include_warnings, args.diagnostic_format)
+    };
+    emitted.map_err(|err| {
+        if has_parse_errors {
+            RunnerError::Parse(err)
+        } else {
+            RunnerError::Resolve(err)
+        }
+    })
+}
+
+// [`resolve_program_with_args`] resolves the program, and the parse diagnostics and the
+// diagnostics of each package are emitted to stderr as soon as they are produced when
+// [`ExecProgramArgs::stream_diagnostics`] is set.
+fn resolve_program_with_args(
+    sess: &ParseSessionRef,
+    program: &mut Program,
+    opts: Options,
+    args: &ExecProgramArgs,
+) -> ProgramScope {
+    if !args.stream_diagnostics {
+        return resolve_program_with_opts(program, opts, None);
+    }
+    let format = args.diagnostic_format;
+    emit_diagnostics_to_stderr(&sess.1.borrow().diagnostics, format);
+    resolve_program_with_emitter(
+        program,
+        opts,
+        Arc::new(move |_, diags| emit_diagnostics_to_stderr(diags, format)),
+    )
+}
+
+fn emit_diagnostics_to_stderr<'a>(
+    diags: impl IntoIterator<Item = &'a Diagnostic>,
+    format: DiagnosticFormat,
+) {
+    let diags: Vec<&Diagnostic> = sort_diagnostics(diags)
+        .into_iter()
+        .filter(|diag| !matches!(diag.level, Level::Note))
+        .collect();
+    if diags.is_empty() {
+        return;
+    }
+    match format {
+        DiagnosticFormat::Rich => eprintln!("{}", render_rich_diagnostics(diags)),
+        DiagnosticFormat::Plain => {
+            let mut handler = Handler::default();
+            for diag in diags {
+                handler.add_diagnostic(diag.clone());
+            }
+            let _ = handler.emit();
+        }
+    }
+}
+
+// [`streamed_compile_diag_errors`] fails like [`emit_compile_diag_to_string`] on the
+// diagnostics which are already streamed, and the error only counts them.
+fn streamed_compile_diag_errors(
+    sess: &ParseSessionRef,
+    scope: &ProgramScope,
+    include_warnings: bool,
+) -> Result<()> {
+    let parse_errors = sess.1.borrow().diagnostics.len();
+    let sema_errors = scope
+        .diagnostics()
+        .iter()
+        .filter(|diag| match diag.level {
+            Level::Error => true,
+            Level::Warning => include_warnings,
+            Level::Suggestions | Level::Note => false,
+        })
+        .count();
+    match parse_errors + sema_errors {
+        0 => Ok(()),
+        errors => bail!("aborting due to {} previous error(s)", errors),
+    }
+}
@@ -1,12 +1,93 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use kclvm_utils::path::PathPrefix;
+use once_cell::sync::Lazy;
 use std::env::consts::DLL_SUFFIX;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
 
 const KCLVM_CLI_BIN_PATH_ENV_VAR: &str = "KCLVM_CLI_BIN_PATH";
 const KCLVM_LIB_LINK_PATH_ENV_VAR: &str = "KCLVM_LIB_LINK_PATH";
 const KCLVM_LIB_SHORT_NAME: &str = "kclvm_cli_cdylib";
 const EXEC_ROOT_NOT_FOUND_MSG: &str = "Internal error: the executable root is not found";
+/// The environment variable which bounds the number of concurrent link invocations.
+pub const KCL_MAX_PARALLEL_LINK_ENV_VAR: &str = "KCL_MAX_PARALLEL_LINK";
+
+/// The global semaphore shared by all the link invocations in the process.
+static LINK_SEMAPHORE: Lazy<LinkSemaphore> = Lazy::new(|| {
+    let permits = std::env::var(KCL_MAX_PARALLEL_LINK_ENV_VAR)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or_else(default_max_parallel_link);
+    LinkSemaphore::new(permits)
+});
+
+/// Returns the default number of concurrent link invocations, which is the CPU count.
+pub fn default_max_parallel_link() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Set the maximum number of concurrent link invocations of the process, it
+/// overrides the [KCL_MAX_PARALLEL_LINK_ENV_VAR] and `0` is treated as `1`.
+pub fn set_max_parallel_link(permits: usize) {
+    LINK_SEMAPHORE.set_permits(permits);
+}
+
+/// Returns the maximum number of concurrent link invocations of the process.
+pub fn max_parallel_link() -> usize {
+    LINK_SEMAPHORE.permits()
+}
+
+/// LinkSemaphore bounds the number of the linker processes which run at the same time.
+#[derive(Debug)]
+pub(crate) struct LinkSemaphore {
+    /// The permit count and the number of acquired permits.
+    state: Mutex<(usize, usize)>,
+    cond: Condvar,
+}
+
+impl LinkSemaphore {
+    pub(crate) fn new(permits: usize) -> Self {
+        Self {
+            state: Mutex::new((permits.max(1), 0)),
+            cond: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn permits(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).0
+    }
+
+    pub(crate) fn set_permits(&self, permits: usize) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).0 = permits.max(1);
+        // More waiters can run when the permit count is increased.
+        self.cond.notify_all();
+    }
+
+    /// Run the link function after acquiring a permit, the permit is released
+    /// when the function returns.
+    pub(crate) fn run<T, F: FnOnce() -> T>(&self, link: F) -> T {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            while state.1 >= state.0 {
+                state = self.cond.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            state.1 += 1;
+        }
+        let _permit = LinkPermit(self);
+        link()
+    }
+}
+
+/// Release the acquired permit on drop, which also covers the panicking linker.
+struct LinkPermit<'a>(&'a LinkSemaphore);
+
+impl Drop for LinkPermit<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap_or_else(|e| e.into_inner()).1 -= 1;
+        self.0.cond.notify_one();
+    }
+}
 
 /// KclvmLinker is mainly responsible for linking the libs generated by KclvmAssembler.
 pub struct KclvmLinker;
@@ -15,7 +96,12 @@ impl KclvmLinker {
     pub fn link_all_libs(lib_paths: Vec<String>, lib_path: String) -> Result<String> {
         // In the final stage of link, we can't ignore any undefined symbols and do
         // not allow external mounting of the implementation.
-        Command::new()?.link_libs_with_cc(&lib_paths, &lib_path)
+        LINK_SEMAPHORE.run(|| Command::new()?.link_libs_with_cc(&lib_paths, &lib_path))
+    }
+
+    /// Check the linker is present and runnable, and returns the linker path.
+    pub fn check_linker() -> Result<String> {
+        Command::new()?.check_cc()
     }
 }
 
@@ -42,25 +128,8 @@ impl Command {
             lib_path.to_string()
         };
 
-        #[cfg(not(target_os = "windows"))]
-        let target = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
-
-        #[cfg(target_os = "windows")]
-        let target = format!("{}-{}", std::env::consts::ARCH, Self::cc_env_windows());
-
-        let mut build = cc::Build::new();
-
-        build
-            .cargo_metadata(false)
-            .no_default_flags(false)
-            .pic(true)
-            .shared_flag(true)
-            .opt_level(0)
-            .target(&target)
-            .host(&target)
-            .flag("-o")
-            .flag(&lib_path);
-
+        let mut build = Self::cc_build();
+        build.flag("-o").flag(&lib_path);
         build.files(libs);
 
         // Run command with cc.
@@ -79,6 +148,55 @@ impl Command {
         Ok(path.adjust_canonicalization())
     }
 
+    /// Check the C compiler used to link the libraries is present and runnable,
+    /// and returns the compiler path.
+    pub(crate) fn check_cc(&self) -> Result<String> {
+        let lib_path = Path::new(&self.get_lib_link_path()?).join(Self::get_lib_name());
+        if !lib_path.exists() {
+            bail!(
+                "the KCL runtime library {} is not found",
+                lib_path.display()
+            );
+        }
+        let compiler = Self::cc_build().try_get_compiler()?;
+        let mut cmd = compiler.to_command();
+        // MSVC prints the usage without the version option.
+        if !compiler.is_like_msvc() {
+            cmd.arg("--version");
+        }
+        let result = cmd
+            .output()
+            .map_err(|e| anyhow!("run linker {} failed: {}", compiler.path().display(), e))?;
+        if !compiler.is_like_msvc() && !result.status.success() {
+            bail!(
+                "run linker {} failed: stderr: {}",
+                compiler.path().display(),
+                String::from_utf8_lossy(&result.stderr)
+            );
+        }
+        Ok(compiler.path().display().to_string())
+    }
+
+    /// The cc-rs build shared by linking and checking the linker.
+    fn cc_build() -> cc::Build {
+        #[cfg(not(target_os = "windows"))]
+        let target = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+
+        #[cfg(target_os = "windows")]
+        let target = format!("{}-{}", std::env::consts::ARCH, Self::cc_env_windows());
+
+        let mut build = cc::Build::new();
+        build
+            .cargo_metadata(false)
+            .no_default_flags(false)
+            .pic(true)
+            .shared_flag(true)
+            .opt_level(0)
+            .target(&target)
+            .host(&target);
+        build
+    }
+
     /// Add args for cc.
     pub(crate) fn add_args(
         &self,
@@ -0,0 +1,81 @@
+//! Report the complexity metrics of a program for the capacity planning, which
+//! are enabled by [crate::ExecProgramArgs::collect_metrics].
+//!
+//! The schema instance, evaluation step and call depth counters are gathered by
+//! the evaluator, and the output nesting depth and bytes are measured on the
+//! result after the execution.
+use anyhow::Result;
+use kclvm_runtime::{Context, ValueRef};
+use serde::Serialize;
+
+use crate::format::json_documents;
+use crate::ExecProgramResult;
+
+/// The complexity metrics of a program.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramMetrics {
+    /// The number of the created schema instances.
+    pub schema_instances: usize,
+    /// The number of the evaluated statements and expressions.
+    pub eval_steps: usize,
+    /// The max depth of the function, schema and rule calls.
+    pub max_call_depth: usize,
+    /// The max nesting depth of the lists and dicts in the output, a scalar
+    /// document has the depth 0.
+    pub max_nesting_depth: usize,
+    /// The bytes of the YAML output.
+    pub output_bytes: usize,
+}
+
+impl ProgramMetrics {
+    /// Serialize the metrics to the single-line JSON object.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl ExecProgramResult {
+    /// Returns the metrics of the program, or [None] when the metrics are not collected.
+    pub fn metrics(&self) -> Result<Option<ProgramMetrics>> {
+        let eval_metrics = match &self.eval_metrics {
+            Some(eval_metrics) => eval_metrics,
+            None => return Ok(None),
+        };
+        let mut ctx = Context::new();
+        let mut max_nesting_depth = 0;
+        for document in json_documents(&self.json_result) {
+            let value = ValueRef::from_json(&mut ctx, document)?;
+            max_nesting_depth = max_nesting_depth.max(nesting_depth(&value));
+        }
+        Ok(Some(ProgramMetrics {
+            schema_instances: eval_metrics.schema_instances,
+            eval_steps: eval_metrics.eval_steps,
+            max_call_depth: eval_metrics.max_call_depth,
+            max_nesting_depth,
+            output_bytes: self.yaml_result.len(),
+        }))
+    }
+}
+
+/// Returns the max nesting depth of the lists and dicts in the value.
+fn nesting_depth(value: &ValueRef) -> usize {
+    if value.is_list() {
+        1 + value
+            .as_list_ref()
+            .values
+            .iter()
+            .map(nesting_depth)
+            .max()
+            .unwrap_or_default()
+    } else if value.is_dict() {
+        1 + value
+            .as_dict_ref()
+            .values
+            .values()
+            .map(nesting_depth)
+            .max()
+            .unwrap_or_default()
+    } else {
+        0
+    }
+}
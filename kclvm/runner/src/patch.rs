@@ -0,0 +1,274 @@
+//! Apply the JSON Patch (RFC 6902) documents to the result of the program before
+//! it is serialized, which is enabled by [ExecProgramArgs::json_patch], see
+//! [render_patch].
+//!
+//! Unlike the `-O` overrides applied to the source code, the patch operations
+//! address the output values with the JSON pointers (RFC 6901), e.g.,
+//! `/spec/containers/0/image`, and follow the standard JSON Patch semantics.
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use kclvm_runtime::{
+    Context, JsonEncodeOptions, ValueRef, YamlEncodeOptions, YamlSpec, JSON_STREAM_SEP,
+};
+use serde::{Deserialize, Serialize};
+
+/// An operation of the JSON Patch document.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Add the value into the object or insert it into the array, `-` appends it.
+    Add {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// Remove the existing value.
+    Remove { path: String },
+    /// Replace the existing value.
+    Replace {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// Remove the value at `from` and add it at `path`.
+    Move { from: String, path: String },
+    /// Add the copy of the value at `from` at `path`.
+    Copy { from: String, path: String },
+    /// Check the value at `path` equals the value.
+    Test {
+        path: String,
+        value: serde_json::Value,
+    },
+}
+
+impl PatchOperation {
+    /// Returns the operation name and the target path.
+    fn describe(&self) -> String {
+        match self {
+            PatchOperation::Add { path, .. } => format!("add {}", path),
+            PatchOperation::Remove { path } => format!("remove {}", path),
+            PatchOperation::Replace { path, .. } => format!("replace {}", path),
+            PatchOperation::Move { from, path } => format!("move {} to {}", from, path),
+            PatchOperation::Copy { from, path } => format!("copy {} to {}", from, path),
+            PatchOperation::Test { path, .. } => format!("test {}", path),
+        }
+    }
+}
+
+/// Load the JSON Patch document, i.e., a JSON array of the patch operations.
+pub fn load_patch_file<P: AsRef<Path>>(path: P) -> Result<Vec<PatchOperation>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read the patch file {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("invalid JSON Patch document {}", path.display()))
+}
+
+/// Apply the patch operations in order to the value. The patch stops at the first
+/// failed operation, and the error reports the index and the path of it.
+pub fn apply_patch(
+    ctx: &mut Context,
+    value: &mut ValueRef,
+    patch: &[PatchOperation],
+) -> Result<()> {
+    for (i, op) in patch.iter().enumerate() {
+        apply_operation(ctx, value, op).map_err(|err| {
+            anyhow!(
+                "failed to apply the JSON Patch operation {} ({}): {}",
+                i,
+                op.describe(),
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Apply the patch to the JSON result and returns the YAML and JSON results of the
+/// patched value, which are encoded with the same options as the program results.
+pub fn render_patch(
+    json_result: &str,
+    patch: &[PatchOperation],
+    sort_keys: bool,
+    yaml_spec: YamlSpec,
+) -> Result<(String, String)> {
+    if json_result.contains(JSON_STREAM_SEP) {
+        bail!("the JSON Patch can only be applied to the output of a single document");
+    }
+    let mut ctx = Context::new();
+    let mut value = if json_result.trim().is_empty() {
+        ValueRef::dict(None)
+    } else {
+        ValueRef::from_json(&mut ctx, json_result)?
+    };
+    apply_patch(&mut ctx, &mut value, patch)?;
+    let json_opts = JsonEncodeOptions {
+        sort_keys,
+        ..Default::default()
+    };
+    let yaml_opts = YamlEncodeOptions {
+        sort_keys,
+        yaml_spec,
+        ..Default::default()
+    };
+    let yaml = value.to_yaml_string_with_options(&yaml_opts);
+    Ok((
+        yaml.strip_suffix('\n').unwrap_or(&yaml).to_string(),
+        value.to_json_string_with_options(&json_opts),
+    ))
+}
+
+fn apply_operation(ctx: &mut Context, root: &mut ValueRef, op: &PatchOperation) -> Result<()> {
+    match op {
+        PatchOperation::Add { path, value } => add(root, path, to_value(ctx, value)?),
+        PatchOperation::Remove { path } => remove(root, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => replace(root, path, to_value(ctx, value)?),
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                bail!("the value can not be moved into its own child");
+            }
+            let value = remove(root, from)?;
+            add(root, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = get(root, &parse_pointer(from)?)?.deep_copy();
+            add(root, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            let actual = get(root, &parse_pointer(path)?)?;
+            if !actual.cmp_equal(&to_value(ctx, value)?) {
+                bail!(
+                    "the value {} does not equal the expected value {}",
+                    actual.to_json_string(),
+                    value
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn to_value(ctx: &mut Context, value: &serde_json::Value) -> Result<ValueRef> {
+    Ok(ValueRef::from_json(ctx, &value.to_string())?)
+}
+
+/// Parse the JSON pointer into the reference tokens, where `~1` is unescaped to
+/// `/` and `~0` is unescaped to `~`. The empty pointer denotes the whole value.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    match pointer.strip_prefix('/') {
+        Some(rest) => Ok(rest
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect()),
+        None => bail!(
+            "invalid JSON pointer '{}', expected to start with '/'",
+            pointer
+        ),
+    }
+}
+
+/// Parse the array index token which is less than the bound, the leading zeros
+/// are not allowed.
+fn parse_index(token: &str, bound: usize) -> Result<usize> {
+    match token.parse::<usize>() {
+        Ok(index) if index < bound && (token == "0" || !token.starts_with('0')) => Ok(index),
+        _ => bail!(
+            "the array index '{}' is invalid or out of the range [0, {})",
+            token,
+            bound
+        ),
+    }
+}
+
+/// Returns the value at the tokens, which shares the underlying value.
+fn get(root: &ValueRef, tokens: &[String]) -> Result<ValueRef> {
+    let mut value = root.clone();
+    for (i, token) in tokens.iter().enumerate() {
+        let child = if value.is_config() {
+            value.dict_get_value(token)
+        } else if value.is_list() {
+            value.list_get(parse_index(token, value.len())? as isize)
+        } else {
+            None
+        };
+        value = child.ok_or_else(|| {
+            anyhow!(
+                "the path /{} does not exist",
+                tokens[..=i]
+                    .iter()
+                    .map(|t| t.replace('~', "~0").replace('/', "~1"))
+                    .collect::<Vec<String>>()
+                    .join("/")
+            )
+        })?;
+    }
+    Ok(value)
+}
+
+fn add(root: &mut ValueRef, path: &str, value: ValueRef) -> Result<()> {
+    let tokens = parse_pointer(path)?;
+    let (last, parent_tokens) = match tokens.split_last() {
+        Some(split) => split,
+        None => {
+            *root = value;
+            return Ok(());
+        }
+    };
+    let mut parent = get(root, parent_tokens)?;
+    if parent.is_config() {
+        parent.dict_update_key_value(last, value);
+    } else if parent.is_list() {
+        if last == "-" {
+            parent.list_append(&value);
+        } else {
+            // The index equal to the length appends the value.
+            let index = parse_index(last, parent.len() + 1)?;
+            parent.list_insert_at(index, &value);
+        }
+    } else {
+        bail!("the parent of the path {} is not an object or array", path);
+    }
+    Ok(())
+}
+
+/// Replace the existing value at the path in place, which keeps the key order.
+fn replace(root: &mut ValueRef, path: &str, value: ValueRef) -> Result<()> {
+    let tokens = parse_pointer(path)?;
+    let (last, parent_tokens) = match tokens.split_last() {
+        Some(split) => split,
+        None => {
+            *root = value;
+            return Ok(());
+        }
+    };
+    let mut parent = get(root, parent_tokens)?;
+    get(&parent, std::slice::from_ref(last))
+        .map_err(|_| anyhow!("the path {} does not exist", path))?;
+    if parent.is_config() {
+        parent.dict_update_key_value(last, value);
+    } else {
+        parent.list_set(parse_index(last, parent.len())?, &value);
+    }
+    Ok(())
+}
+
+/// Remove the value at the path and returns it.
+fn remove(root: &mut ValueRef, path: &str) -> Result<ValueRef> {
+    let tokens = parse_pointer(path)?;
+    let (last, parent_tokens) = match tokens.split_last() {
+        Some(split) => split,
+        None => bail!("the whole value can not be removed"),
+    };
+    let mut parent = get(root, parent_tokens)?;
+    let value = get(&parent, std::slice::from_ref(last))
+        .map_err(|_| anyhow!("the path {} does not exist", path))?;
+    if parent.is_config() {
+        // Keep the order of the other keys.
+        parent.as_dict_mut_ref().values.shift_remove(last);
+    } else {
+        parent.list_remove_at(parse_index(last, parent.len())?);
+    }
+    Ok(value)
+}
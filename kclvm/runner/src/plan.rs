@@ -0,0 +1,195 @@
+//! Explain the build plan of a run without executing it, which lists the files
+//! to load, the packages to compile with their cache status, the link step and
+//! the execution, see [explain_plan].
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use kclvm_ast::{ast, MAIN_PKG};
+use kclvm_parser::{load_program, ParseSessionRef};
+use kclvm_sema::resolver::resolve_program;
+use serde::Serialize;
+
+use crate::assembler::{cached_lib_path, pkg_dependency_levels, EVALUATOR_BACKEND};
+use crate::{backend_name, expand_files, ExecProgramArgs};
+
+/// The status of a package in the build plan.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageStatus {
+    /// The cached object file of the package is valid and reused.
+    CacheHit,
+    /// The package is compiled, the main package is always compiled fresh.
+    Fresh,
+    /// The package is run by the evaluator without the compilation.
+    Evaluated,
+}
+
+impl PackageStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageStatus::CacheHit => "cache hit",
+            PackageStatus::Fresh => "fresh",
+            PackageStatus::Evaluated => "evaluated",
+        }
+    }
+}
+
+/// A package in the build plan.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlannedPackage {
+    pub pkgpath: String,
+    /// The dependency level, packages in a level only import the packages in
+    /// the previous levels.
+    pub level: usize,
+    pub status: PackageStatus,
+}
+
+/// The ordered build plan of a run.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExecPlan {
+    /// The backend which runs the program, see [backend_name].
+    pub backend: &'static str,
+    /// The program root under which the packages are cached.
+    pub root: String,
+    /// The files to load in the import order.
+    pub files: Vec<String>,
+    /// The packages to compile in the dependency order.
+    pub packages: Vec<PlannedPackage>,
+}
+
+impl ExecPlan {
+    /// Whether the packages are compiled and linked before the execution.
+    pub fn is_compiled(&self) -> bool {
+        self.backend != EVALUATOR_BACKEND
+    }
+
+    /// Render the plan as the numbered steps.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut step = 0;
+        let mut next_step = |out: &mut String, title: String| {
+            step += 1;
+            out.push_str(&format!("{}. {}\n", step, title));
+        };
+        next_step(&mut out, format!("load {} file(s)", self.files.len()));
+        for file in &self.files {
+            out.push_str(&format!("   - {}\n", file));
+        }
+        let title = if self.is_compiled() {
+            format!("compile {} package(s)", self.packages.len())
+        } else {
+            format!(
+                "evaluate {} package(s) without compiling",
+                self.packages.len()
+            )
+        };
+        next_step(&mut out, title);
+        for pkg in &self.packages {
+            out.push_str(&format!(
+                "   - [level {}] {}: {}\n",
+                pkg.level,
+                pkg.pkgpath,
+                pkg.status.name()
+            ));
+        }
+        if self.is_compiled() {
+            next_step(&mut out, format!("link {} library(s)", self.packages.len()));
+        }
+        next_step(
+            &mut out,
+            format!("execute {} with {}", MAIN_PKG, self.backend),
+        );
+        out
+    }
+}
+
+/// Returns the build plan of running the program with the arguments, the files
+/// are loaded and resolved to get the dependency graph, and nothing is compiled
+/// or executed.
+pub fn explain_plan(sess: ParseSessionRef, args: &ExecProgramArgs) -> Result<ExecPlan> {
+    args.validate()?;
+    let opts = args.get_load_program_options();
+    let kcl_paths = expand_files(args)?;
+    let kcl_paths_str = kcl_paths.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+    let result = load_program(sess, kcl_paths_str.as_slice(), Some(opts), None)?;
+    let mut program = result.program;
+    let scope = resolve_program(&mut program);
+    let root = program.root.clone();
+    let files = result
+        .paths
+        .iter()
+        .map(|path| match path.strip_prefix(&root) {
+            Ok(rel) if !root.is_empty() => rel.display().to_string(),
+            _ => path.display().to_string(),
+        })
+        .collect();
+
+    let backend = backend_name(args);
+    let target = args
+        .target_triple
+        .clone()
+        .unwrap_or_else(|| env!("KCLVM_DEFAULT_TARGET").to_string());
+    let external_pkgs = args.get_package_maps_from_external_pkg();
+    let mut compile_progs: IndexMap<
+        String,
+        (ast::Program, IndexMap<String, IndexMap<String, String>>, ()),
+    > = IndexMap::default();
+    let mut pkgpaths: Vec<&String> = program.pkgs.keys().collect();
+    pkgpaths.sort();
+    for pkgpath in pkgpaths {
+        let compile_prog = ast::Program {
+            root: root.clone(),
+            pkgs: HashMap::from([(pkgpath.clone(), program.pkgs[pkgpath].clone())]),
+        };
+        compile_progs.insert(pkgpath.clone(), (compile_prog, scope.imports().clone(), ()));
+    }
+    let mut packages = vec![];
+    for (level, pkgpaths) in pkg_dependency_levels(&compile_progs)
+        .into_iter()
+        .enumerate()
+    {
+        for pkgpath in pkgpaths {
+            let status = if backend == EVALUATOR_BACKEND {
+                PackageStatus::Evaluated
+            } else if pkgpath == MAIN_PKG || args.llvm_ir_dir.is_some() {
+                PackageStatus::Fresh
+            } else {
+                package_cache_status(
+                    &root,
+                    &target,
+                    &pkgpath,
+                    &external_pkgs,
+                    args.shared_cache_dir.as_deref(),
+                )
+            };
+            packages.push(PlannedPackage {
+                pkgpath,
+                level,
+                status,
+            });
+        }
+    }
+    Ok(ExecPlan {
+        backend,
+        root,
+        files,
+        packages,
+    })
+}
+
+/// Returns the cache status of the non-main package compiled for the target, the
+/// package cached in the shared cache is also a cache hit.
+pub fn package_cache_status(
+    root: &str,
+    target: &str,
+    pkgpath: &str,
+    external_pkgs: &HashMap<String, String>,
+    shared_cache_dir: Option<&Path>,
+) -> PackageStatus {
+    match cached_lib_path(root, target, pkgpath, external_pkgs, shared_cache_dir) {
+        Some(_) => PackageStatus::CacheHit,
+        None => PackageStatus::Fresh,
+    }
+}
@@ -0,0 +1,85 @@
+//! Provenance tracing of the top-level output keys, which records the source
+//! file and line of the statement that last set each top-level key. It is a
+//! debugging aid enabled by `trace_provenance` and is off by default.
+use indexmap::IndexMap;
+use kclvm_ast::{ast, MAIN_PKG};
+use kclvm_error::Position;
+
+/// Collect the position of the statement that last set each top-level key
+/// of the main package in the program.
+pub fn collect_provenance(program: &ast::Program) -> IndexMap<String, Position> {
+    let mut provenance = IndexMap::new();
+    if let Some(modules) = program.pkgs.get(MAIN_PKG) {
+        for module in modules {
+            collect_stmts_provenance(&module.body, &mut provenance);
+        }
+    }
+    provenance
+}
+
+fn collect_stmts_provenance(
+    stmts: &[ast::NodeRef<ast::Stmt>],
+    provenance: &mut IndexMap<String, Position>,
+) {
+    for stmt in stmts {
+        match &stmt.node {
+            ast::Stmt::Assign(assign_stmt) => {
+                for target in &assign_stmt.targets {
+                    set_provenance(&target.node, stmt, provenance);
+                }
+            }
+            ast::Stmt::AugAssign(aug_assign_stmt) => {
+                set_provenance(&aug_assign_stmt.target.node, stmt, provenance)
+            }
+            ast::Stmt::Unification(unification_stmt) => {
+                set_provenance(&unification_stmt.target.node, stmt, provenance)
+            }
+            ast::Stmt::If(if_stmt) => {
+                collect_stmts_provenance(&if_stmt.body, provenance);
+                collect_stmts_provenance(&if_stmt.orelse, provenance);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_provenance(
+    target: &ast::Identifier,
+    stmt: &ast::NodeRef<ast::Stmt>,
+    provenance: &mut IndexMap<String, Position>,
+) {
+    if let Some(name) = target.names.first() {
+        // Remove the key first to keep the order of the last assignment.
+        provenance.shift_remove(&name.node);
+        provenance.insert(
+            name.node.clone(),
+            Position {
+                filename: stmt.filename.clone(),
+                line: stmt.line,
+                column: Some(stmt.column),
+            },
+        );
+    }
+}
+
+/// Render the provenance of the top-level keys that appear in the JSON result
+/// as the YAML and JSON string with the format `key: filename:line`.
+pub fn render_provenance(
+    provenance: &IndexMap<String, Position>,
+    json_result: &str,
+) -> anyhow::Result<(String, String)> {
+    let value: serde_json::Value = serde_json::from_str(json_result)?;
+    let mut yaml_lines = vec![];
+    let mut json_map = serde_json::Map::new();
+    for (key, pos) in provenance {
+        if value.get(key).is_some() {
+            let location = format!("{}:{}", pos.filename, pos.line);
+            yaml_lines.push(format!("{}: {}", key, location));
+            json_map.insert(key.clone(), serde_json::Value::String(location));
+        }
+    }
+    Ok((
+        yaml_lines.join("\n"),
+        serde_json::to_string(&serde_json::Value::Object(json_map))?,
+    ))
+}
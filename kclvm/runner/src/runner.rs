@@ -1,22 +1,37 @@
+use crate::assembler::CacheUsage;
+use crate::error::RunnerError;
+use crate::patch::PatchOperation;
+use crate::sandbox::resolve_in_sandbox;
 use anyhow::{anyhow, Result};
 use kclvm_evaluator::Evaluator;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::{cell::RefCell, rc::Rc};
 
 use kclvm_ast::ast;
 use kclvm_config::{
     modfile::get_vendor_home,
+    offline::is_offline,
     settings::{SettingsFile, SettingsPathBuf},
 };
-use kclvm_error::{Diagnostic, Handler};
+use kclvm_driver::canonicalize_input_files;
+use kclvm_error::{Diagnostic, DiagnosticFormat, Handler};
 use kclvm_query::r#override::parse_override_spec;
-use kclvm_runtime::{kclvm_plugin_init, Context, FFIRunOptions, PanicInfo, RuntimePanicRecord};
+use kclvm_runtime::{
+    kclvm_plugin_init, CancelFlag, Context, DuplicateKeyPolicy, EvalMetrics, FFIRunOptions,
+    MergeKey, PanicInfo, RuntimePanicRecord, UnitsFormat, ValueSerializers, YamlSpec,
+};
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::os::raw::c_char;
+use std::path::Path;
 
 const RESULT_SIZE: usize = 2048 * 2048;
 const KCL_DEBUG_ERROR_ENV_VAR: &str = "KCL_DEBUG_ERROR";
+/// The top-level argument which the enabled features are bound to, e.g.,
+/// `"prod" in option("features", default=[])`. It is only bound when any feature
+/// is enabled, thus the programs without features keep the argument to themselves.
+pub const FEATURES_OPTION_KEY: &str = "features";
 
 #[allow(non_camel_case_types)]
 pub type kclvm_char_t = c_char;
@@ -28,7 +43,12 @@ pub type kclvm_context_t = std::ffi::c_void;
 pub type kclvm_value_ref_t = std::ffi::c_void;
 
 /// ExecProgramArgs denotes the configuration required to execute the KCL program.
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+///
+/// Every field except the process-local ones, e.g., `plugin_agent`, survives the
+/// JSON round trip through [ExecProgramArgs::to_json] and [ExecProgramArgs::from_str],
+/// and the missing fields in the JSON text take their default values.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
 pub struct ExecProgramArgs {
     pub work_dir: Option<String>,
     pub k_filename_list: Vec<String>,
@@ -39,6 +59,9 @@ pub struct ExecProgramArgs {
     pub args: Vec<ast::CmdArgSpec>,
     /// -O override_spec
     pub overrides: Vec<ast::OverrideSpec>,
+    /// strict_overrides denotes reporting the overrides which match nothing or are
+    /// no-ops as errors, see [kclvm_query::check_stale_overrides].
+    pub strict_overrides: bool,
     /// -S path_selector
     pub path_selector: Vec<String>,
     pub disable_yaml_result: bool,
@@ -60,13 +83,138 @@ pub struct ExecProgramArgs {
     pub include_schema_type_path: bool,
     /// Whether to compile only.
     pub compile_only: bool,
-    /// plugin_agent is the address of plugin.
+    /// The max nesting depth of statements and expressions in the parser,
+    /// 0 denotes the default depth limit, which fits in [crate::EXEC_STACK_SIZE].
+    pub max_parse_depth: usize,
+    /// The max depth of function, schema and rule calls in the evaluator,
+    /// 0 denotes the default depth limit, which fits in [crate::EXEC_STACK_SIZE].
+    pub max_eval_depth: usize,
+    /// The approximate max memory bytes of values built by the evaluator,
+    /// 0 denotes no limit.
+    pub max_eval_memory_bytes: usize,
+    /// The YAML specification which the YAML result is unambiguous under,
+    /// defaults to YAML 1.2, which quotes the YAML 1.1 boolean words as well.
+    pub yaml_spec: YamlSpec,
+    /// --include: only keep these top-level keys in the result.
+    pub include_keys: Vec<String>,
+    /// --exclude: drop these top-level keys in the result.
+    pub exclude_keys: Vec<String>,
+    /// The output format of the parse and resolve diagnostics.
+    pub diagnostic_format: DiagnosticFormat,
+    /// The target triple of the object files emitted by the LLVM backend, e.g.,
+    /// `aarch64-unknown-linux-gnu`, defaults to the host triple.
+    pub target_triple: Option<String>,
+    /// The directory to write the textual LLVM IR of each package into, which is
+    /// named by the package path, e.g., `__main__.ll`. It is only emitted by the
+    /// LLVM backend.
+    pub llvm_ir_dir: Option<String>,
+    /// plugin_agent is the address of plugin. It is only valid in the current
+    /// process, thus it is never serialized.
     #[serde(skip)]
     pub plugin_agent: u64,
     /// fast_eval denotes directly executing at the AST level to obtain
     /// the result without any form of compilation.
-    #[serde(skip)]
     pub fast_eval: bool,
+    /// trace_provenance denotes outputting the source file and line of the
+    /// statement that last set each top-level key instead of the plain result.
+    pub trace_provenance: bool,
+    /// emit_version denotes adding the top-level key `__kcl_version__` holding
+    /// the crate version and the backend name into the output.
+    pub emit_version: bool,
+    /// collect_check_failures denotes collecting all the failed schema checks and
+    /// asserts instead of stopping at the first one. It is only supported by the
+    /// fast evaluator.
+    pub collect_check_failures: bool,
+    /// continue_on_error denotes recording the failed schema checks and asserts
+    /// and continuing the evaluation, the failures are returned in the result
+    /// diagnostics instead of the error. It is only supported by the fast evaluator.
+    pub continue_on_error: bool,
+    /// offline denotes forbidding the network access, e.g., fetching the module
+    /// dependencies, which is also enabled by the `KCL_OFFLINE` environment variable.
+    pub offline: bool,
+    /// temp_dir denotes the directory to create the intermediate files of the
+    /// native compilation in, which defaults to the OS temp dir.
+    pub temp_dir: Option<PathBuf>,
+    /// temp_prefix denotes the name prefix of the intermediate files and their dir.
+    pub temp_prefix: Option<String>,
+    /// sandbox_root denotes the directory which all the files written by the run,
+    /// e.g., the cache, temp and output files, must be inside.
+    pub sandbox_root: Option<PathBuf>,
+    /// features denotes the enabled build-time feature flags, e.g., `-F prod`, which
+    /// are bound to the top-level argument [FEATURES_OPTION_KEY] as a list when
+    /// they are not empty.
+    pub features: Vec<String>,
+    /// duplicate_key_policy denotes what happens when a config sets the same key
+    /// twice. The policies except the default last-wins are only supported by the
+    /// fast evaluator.
+    pub duplicate_key_policy: DuplicateKeyPolicy,
+    /// collect_metrics denotes gathering the evaluation metrics into the result,
+    /// see [ExecProgramResult::metrics]. It is only supported by the fast evaluator.
+    pub collect_metrics: bool,
+    /// merge_keys denotes the lists which are unioned by matching the elements with
+    /// the key field instead of the position, e.g., `containers=name`, see
+    /// [parse_merge_key_spec]. It is only supported by the fast evaluator.
+    pub merge_keys: Vec<MergeKey>,
+    /// fold_constants denotes precomputing the pure constant expressions of the
+    /// resolved program before the execution, which never changes the output.
+    pub fold_constants: bool,
+    /// deny_warnings denotes failing on the resolve warnings, e.g., setting the
+    /// deprecated schema attributes, instead of returning them in the result.
+    pub deny_warnings: bool,
+    /// json_patch denotes the JSON Patch (RFC 6902) operations applied in order to
+    /// the result before it is serialized, see [crate::patch::render_patch].
+    pub json_patch: Vec<PatchOperation>,
+    /// include_schema_meta denotes adding the attribute `__schema__` holding the
+    /// schema name and the source location into every schema instance in the result.
+    pub include_schema_meta: bool,
+    /// shared_cache_dir denotes the read-only cache consulted for the object files
+    /// of the packages which are not cached under the program root, i.e., the
+    /// program root or the `KCL_CACHE_PATH` of the run which populated the cache.
+    /// The packages not found in it are compiled and cached as usual.
+    pub shared_cache_dir: Option<PathBuf>,
+    /// units denotes how the number multiplier values, e.g., `1Mi`, are output,
+    /// which defaults to the expanded numbers.
+    pub units: UnitsFormat,
+    /// stream_diagnostics denotes emitting the parse diagnostics and the resolve
+    /// diagnostics of each package to stderr as soon as the package is resolved,
+    /// instead of returning them in the error at the end. The run still fails on
+    /// the errors after all the packages are resolved.
+    pub stream_diagnostics: bool,
+    /// max_output_bytes denotes the max bytes of each of the YAML and JSON results,
+    /// the serialization is aborted with an error once the result exceeds it, and
+    /// 0 denotes no limit.
+    pub max_output_bytes: usize,
+    /// value_serializers denotes the custom serializers of the schema values in
+    /// the result keyed by the schema type name. They are only valid in the
+    /// current process, thus they are never serialized.
+    #[serde(skip)]
+    pub value_serializers: ValueSerializers,
+    /// cancel_flag denotes the flag which cancels the execution from another thread.
+    /// It is checked between the stages of the execution and at every statement and
+    /// expression of the fast evaluator, thus the LLVM backend is only cancelled
+    /// between the stages. It is never serialized.
+    #[serde(skip)]
+    pub cancel_flag: CancelFlag,
+}
+
+/// Parse the merge key spec `path=keyfield`, e.g., `spec.containers=name`.
+pub fn parse_merge_key_spec(spec: &str) -> Result<MergeKey> {
+    match spec.split_once('=') {
+        Some((path, key))
+            if !path.trim().is_empty()
+                && !key.trim().is_empty()
+                && path.split('.').all(|k| !k.trim().is_empty()) =>
+        {
+            Ok(MergeKey {
+                path: path.trim().to_string(),
+                key: key.trim().to_string(),
+            })
+        }
+        _ => Err(anyhow!(
+            "invalid merge key spec '{}', expected the form path=keyfield, e.g., containers=name",
+            spec
+        )),
+    }
 }
 
 impl ExecProgramArgs {
@@ -98,6 +246,19 @@ pub struct ExecProgramResult {
     pub yaml_result: String,
     pub log_message: String,
     pub err_message: String,
+    /// The failed schema checks and asserts recorded when continuing on errors.
+    #[serde(skip)]
+    pub diagnostics: Vec<Diagnostic>,
+    /// The evaluation metrics gathered when [ExecProgramArgs::collect_metrics] is set.
+    #[serde(skip)]
+    pub eval_metrics: Option<EvalMetrics>,
+    /// The resolve warnings of the program, e.g., setting the deprecated schema attributes.
+    #[serde(skip)]
+    pub warnings: Vec<Diagnostic>,
+    /// The cache usage of the compiled packages, which is `None` when the program is
+    /// run by the evaluator without the compilation.
+    #[serde(skip)]
+    pub cache_usage: Option<CacheUsage>,
 }
 
 pub trait MapErrorResult {
@@ -134,6 +295,19 @@ impl MapErrorResult for Result<ExecProgramResult> {
     }
 }
 
+impl MapErrorResult for Result<ExecProgramResult, RunnerError> {
+    /// Map execute error message into the [`Result::Err`]
+    fn map_err_to_result(self) -> Result<ExecProgramResult>
+    where
+        Self: Sized,
+    {
+        match self {
+            Ok(result) => result.map_err_to_result(),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
 impl ExecProgramArgs {
     /// Deserialize an instance of type [ExecProgramArgs] from a string of JSON text.
     pub fn from_str(s: &str) -> Self {
@@ -148,6 +322,119 @@ impl ExecProgramArgs {
         serde_json::ser::to_string(self).unwrap()
     }
 
+    /// Whether the program is executed by the LLVM backend, i.e., the llvm feature is
+    /// enabled and the fast evaluator is not selected by the fast_eval option or the
+    /// `KCL_FAST_EVAL` environment variable.
+    pub fn uses_llvm_backend(&self) -> bool {
+        cfg!(feature = "llvm")
+            && !self.fast_eval
+            && std::env::var(crate::KCL_FAST_EVAL_ENV_VAR).is_err()
+    }
+
+    /// Check the conflicting arguments.
+    pub fn validate(&self) -> Result<()> {
+        if !self.include_keys.is_empty() && !self.exclude_keys.is_empty() {
+            return Err(anyhow!(
+                "the include keys and the exclude keys can not be used together"
+            ));
+        }
+        if let Some(triple) = &self.target_triple {
+            kclvm_compiler::codegen::check_target_triple(triple).map_err(|err| anyhow!(err))?;
+            if !self.uses_llvm_backend() {
+                return Err(anyhow!(
+                    "the target triple can only be set with the LLVM backend, which requires the llvm feature and can not be used with the fast evaluator"
+                ));
+            }
+        }
+        if self.max_eval_depth > 0 && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the max evaluation depth can only be enforced by the fast evaluator"
+            ));
+        }
+        if self.max_eval_memory_bytes > 0 && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the max evaluation memory can only be enforced by the fast evaluator"
+            ));
+        }
+        if self.llvm_ir_dir.is_some() && !self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the LLVM IR can only be emitted by the LLVM backend, which requires the llvm feature and can not be used with the fast evaluator"
+            ));
+        }
+        if self.collect_check_failures && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the check failures can only be collected by the fast evaluator"
+            ));
+        }
+        if self.continue_on_error && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the evaluation can only continue on errors with the fast evaluator"
+            ));
+        }
+        if self.duplicate_key_policy != DuplicateKeyPolicy::LastWins && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the duplicate key policy can only be set with the fast evaluator"
+            ));
+        }
+        if self.collect_metrics && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the metrics can only be collected by the fast evaluator"
+            ));
+        }
+        if !self.merge_keys.is_empty() && self.uses_llvm_backend() {
+            return Err(anyhow!(
+                "the merge keys can only be set with the fast evaluator"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the top-level arguments with the sorted enabled features bound to
+    /// [FEATURES_OPTION_KEY], which is overridden by the `-D` argument of the same name.
+    /// The features are not bound when no feature is enabled.
+    pub fn option_args(&self) -> Vec<ast::CmdArgSpec> {
+        let mut args = vec![];
+        if !self.features.is_empty() {
+            let mut features = self.features.clone();
+            features.sort();
+            features.dedup();
+            args.push(ast::CmdArgSpec {
+                name: FEATURES_OPTION_KEY.to_string(),
+                value: serde_json::to_string(&features).unwrap_or_default(),
+            });
+        }
+        args.extend(self.args.iter().cloned());
+        args
+    }
+
+    /// Returns the evaluation-time bindings as JSON, i.e., the top-level arguments
+    /// which the program sees after the later arguments override the earlier ones,
+    /// including the features, and the overrides and selectors of the output.
+    pub fn eval_env(&self) -> serde_json::Value {
+        let mut args = serde_json::Map::new();
+        for arg in self.option_args() {
+            // The argument values which are not JSON are bound as strings.
+            let value = serde_json::from_str(&arg.value)
+                .unwrap_or_else(|_| serde_json::Value::String(arg.value.clone()));
+            args.insert(arg.name, value);
+        }
+        serde_json::json!({
+            "args": args,
+            "overrides": self.overrides,
+            "path_selector": self.path_selector,
+            "include_keys": self.include_keys,
+            "exclude_keys": self.exclude_keys,
+        })
+    }
+
+    /// Check the file path written by the run is inside the sandbox root when it is set.
+    pub fn check_sandbox_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(root) = &self.sandbox_root {
+            resolve_in_sandbox(root, path.as_ref())?;
+        }
+        Ok(())
+    }
+
     /// Get the input file list.
     pub fn get_files(&self) -> Vec<&str> {
         self.k_filename_list.iter().map(|s| s.as_str()).collect()
@@ -161,6 +448,12 @@ impl ExecProgramArgs {
             package_maps: self.get_package_maps_from_external_pkg(),
             k_code_list: self.k_code_list.clone(),
             load_plugins: self.plugin_agent > 0,
+            max_parse_depth: if self.max_parse_depth > 0 {
+                self.max_parse_depth
+            } else {
+                kclvm_parser::DEFAULT_MAX_PARSE_DEPTH
+            },
+            offline: self.offline || is_offline(),
             ..Default::default()
         }
     }
@@ -210,6 +503,21 @@ impl TryFrom<SettingsPathBuf> for ExecProgramArgs {
     fn try_from(s: SettingsPathBuf) -> Result<Self, Self::Error> {
         let mut args: ExecProgramArgs = s.settings().clone().try_into()?;
         args.work_dir = s.path().clone().map(|p| p.to_string_lossy().to_string());
+        // Relative input files in the settings file are relative to the settings file
+        // location instead of the current working directory.
+        if let Some(path) = s.path() {
+            let base = if path.is_absolute() {
+                path.clone()
+            } else {
+                std::env::current_dir()?.join(path)
+            };
+            args.k_filename_list = canonicalize_input_files(
+                &args.k_filename_list,
+                base.to_string_lossy().to_string(),
+                false,
+            )
+            .map_err(|err| anyhow!(err))?;
+        }
         Ok(args)
     }
 }
@@ -320,6 +628,8 @@ impl LibRunner {
                 option_values: *const *const kclvm_char_t,
                 opts: FFIRunOptions,
                 path_selector: *const *const kclvm_char_t,
+                include_keys: *const *const kclvm_char_t,
+                exclude_keys: *const *const kclvm_char_t,
                 json_result_buffer_len: *mut kclvm_size_t,
                 json_result_buffer: *mut kclvm_char_t,
                 yaml_result_buffer_len: *mut kclvm_size_t,
@@ -336,10 +646,10 @@ impl LibRunner {
         let kclvm_main_ptr = kclvm_main.into_raw().into_raw() as u64;
 
         // CLI configs option len
-        let option_len = args.args.len() as kclvm_size_t;
+        let option_args = args.option_args();
+        let option_len = option_args.len() as kclvm_size_t;
         // CLI configs option keys
-        let cstr_argv: Vec<_> = args
-            .args
+        let cstr_argv: Vec<_> = option_args
             .iter()
             .map(|arg| std::ffi::CString::new(arg.name.as_str()).unwrap())
             .collect();
@@ -350,8 +660,7 @@ impl LibRunner {
         p_argv.push(std::ptr::null());
         let option_keys = p_argv.as_ptr();
         // CLI configs option values
-        let cstr_argv: Vec<_> = args
-            .args
+        let cstr_argv: Vec<_> = option_args
             .iter()
             .map(|arg| std::ffi::CString::new(arg.value.as_str()).unwrap())
             .collect();
@@ -373,6 +682,35 @@ impl LibRunner {
             .collect();
         p_argv.push(std::ptr::null());
         let path_selector = p_argv.as_ptr();
+        // Included top-level keys
+        let cstr_argv: Vec<_> = args
+            .include_keys
+            .iter()
+            .map(|arg| std::ffi::CString::new(arg.as_str()).unwrap())
+            .collect();
+        let mut p_argv: Vec<_> = cstr_argv
+            .iter() // do NOT into_iter()
+            .map(|arg| arg.as_ptr())
+            .collect();
+        p_argv.push(std::ptr::null());
+        let include_keys = p_argv.as_ptr();
+        // Excluded top-level keys
+        let cstr_argv: Vec<_> = args
+            .exclude_keys
+            .iter()
+            .map(|arg| std::ffi::CString::new(arg.as_str()).unwrap())
+            .collect();
+        let mut p_argv: Vec<_> = cstr_argv
+            .iter() // do NOT into_iter()
+            .map(|arg| arg.as_ptr())
+            .collect();
+        p_argv.push(std::ptr::null());
+        let exclude_keys = p_argv.as_ptr();
+        // The sandbox root must outlive the run.
+        let sandbox_root = args
+            .sandbox_root
+            .as_ref()
+            .map(|root| std::ffi::CString::new(root.display().to_string()).unwrap());
 
         let opts = FFIRunOptions {
             strict_range_check: args.strict_range_check as i32,
@@ -383,6 +721,13 @@ impl LibRunner {
             show_hidden: args.show_hidden as i32,
             debug_mode: args.debug,
             include_schema_type_path: args.include_schema_type_path as i32,
+            yaml_spec: (args.yaml_spec == YamlSpec::Yaml11) as i32,
+            include_schema_meta: args.include_schema_meta as i32,
+            units: (args.units == UnitsFormat::Suffixed) as i32,
+            max_output_bytes: args.max_output_bytes as u64,
+            sandbox_root: sandbox_root
+                .as_ref()
+                .map_or(std::ptr::null(), |root| root.as_ptr()),
         };
         let mut json_buffer = Buffer::make();
         let mut yaml_buffer = Buffer::make();
@@ -397,6 +742,8 @@ impl LibRunner {
             option_values,
             opts,
             path_selector,
+            include_keys,
+            exclude_keys,
             json_buffer.mut_len(),
             json_buffer.mut_ptr(),
             yaml_buffer.mut_len(),
@@ -412,6 +759,7 @@ impl LibRunner {
             json_result: json_buffer.to_string()?,
             log_message: log_buffer.to_string()?,
             err_message: err_buffer.to_string()?,
+            ..Default::default()
         };
         // Wrap runtime JSON Panic error string into diagnostic style string.
         if !result.err_message.is_empty() && std::env::var(KCL_DEBUG_ERROR_ENV_VAR).is_err() {
@@ -447,6 +795,52 @@ impl FastRunner {
 
     /// Run kcl library with exec arguments.
     pub fn run(&self, program: &ast::Program, args: &ExecProgramArgs) -> Result<ExecProgramResult> {
+        let mut result = self.eval(program, args, false);
+        // Wrap runtime JSON Panic error string into diagnostic style string.
+        if !result.err_message.is_empty() && std::env::var(KCL_DEBUG_ERROR_ENV_VAR).is_err() {
+            result.err_message = match Handler::default()
+                .add_diagnostic(<PanicInfo as Into<Diagnostic>>::into(PanicInfo::from(
+                    result.err_message.as_str(),
+                )))
+                .emit_to_string()
+            {
+                Ok(msg) => msg,
+                Err(err) => err.to_string(),
+            };
+        }
+        Ok(result)
+    }
+
+    /// Run the kcl program with exec arguments and keep the partial result planned
+    /// from the global variables evaluated before the runtime error. The runtime
+    /// error is returned as a diagnostic.
+    pub fn run_partial(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+    ) -> (ExecProgramResult, Option<Diagnostic>) {
+        let mut result = self.eval(program, args, true);
+        if result.err_message.is_empty() {
+            return (result, None);
+        }
+        let diagnostic: Diagnostic = PanicInfo::from(result.err_message.as_str()).into();
+        result.err_message = match Handler::default()
+            .add_diagnostic(diagnostic.clone())
+            .emit_to_string()
+        {
+            Ok(msg) => msg,
+            Err(err) => err.to_string(),
+        };
+        (result, Some(diagnostic))
+    }
+
+    /// Evaluate the kcl program and return the raw runtime error message in the result.
+    pub(crate) fn eval(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        keep_partial: bool,
+    ) -> ExecProgramResult {
         let ctx = Rc::new(RefCell::new(args_to_ctx(program, args)));
         let evaluator = Evaluator::new_with_runtime_ctx(program, ctx.clone());
         let prev_hook = std::panic::take_hook();
@@ -483,7 +877,6 @@ impl FastRunner {
             }
             evaluator.run()
         });
-        std::panic::set_hook(prev_hook);
         KCL_RUNTIME_PANIC_RECORD.with(|record| {
             let record = record.borrow();
             ctx.borrow_mut().set_panic_info(&record);
@@ -498,6 +891,17 @@ impl FastRunner {
                 Ok((json, yaml)) => {
                     result.json_result = json;
                     result.yaml_result = yaml;
+                    if args.collect_metrics {
+                        result.eval_metrics = Some(ctx.borrow().eval_metrics.clone());
+                    }
+                    result.diagnostics = ctx
+                        .borrow()
+                        .panic_info
+                        .check_failures
+                        .iter()
+                        .cloned()
+                        .map(Diagnostic::from)
+                        .collect();
                 }
                 Err(err) => {
                     result.err_message = err.to_string();
@@ -513,19 +917,19 @@ impl FastRunner {
                 };
             }
         }
-        // Wrap runtime JSON Panic error string into diagnostic style string.
-        if !result.err_message.is_empty() && std::env::var(KCL_DEBUG_ERROR_ENV_VAR).is_err() {
-            result.err_message = match Handler::default()
-                .add_diagnostic(<PanicInfo as Into<Diagnostic>>::into(PanicInfo::from(
-                    result.err_message.as_str(),
-                )))
-                .emit_to_string()
-            {
-                Ok(msg) => msg,
-                Err(err) => err.to_string(),
-            };
+        if keep_partial && !result.err_message.is_empty() {
+            // The evaluator state may be broken by the error, so ignore the panic when planning,
+            // which is recorded by the panic hook of the run instead of being printed.
+            let planned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                evaluator.plan_globals_to_string()
+            }));
+            if let Ok((json, yaml)) = planned {
+                result.json_result = json;
+                result.yaml_result = yaml;
+            }
         }
-        Ok(result)
+        std::panic::set_hook(prev_hook);
+        result
     }
 }
 
@@ -533,12 +937,28 @@ pub(crate) fn args_to_ctx(program: &ast::Program, args: &ExecProgramArgs) -> Con
     let mut ctx = Context::new();
     ctx.cfg.strict_range_check = args.strict_range_check;
     ctx.cfg.debug_mode = args.debug != 0;
+    ctx.cfg.max_eval_depth = args.max_eval_depth;
+    ctx.cfg.max_eval_memory_bytes = args.max_eval_memory_bytes;
+    ctx.cfg.collect_check_failures = args.collect_check_failures || args.continue_on_error;
+    ctx.cfg.continue_on_error = args.continue_on_error;
+    ctx.cfg.duplicate_key_policy = args.duplicate_key_policy;
+    ctx.cfg.collect_metrics = args.collect_metrics;
+    ctx.cfg.merge_keys = args.merge_keys.clone();
+    ctx.cfg.sandbox_root = args.sandbox_root.clone();
+    ctx.cfg.cancel_flag = args.cancel_flag.clone();
     ctx.plan_opts.disable_none = args.disable_none;
     ctx.plan_opts.show_hidden = args.show_hidden;
     ctx.plan_opts.sort_keys = args.sort_keys;
     ctx.plan_opts.include_schema_type_path = args.include_schema_type_path;
+    ctx.plan_opts.include_schema_meta = args.include_schema_meta;
+    ctx.plan_opts.serializers = args.value_serializers.clone();
     ctx.plan_opts.query_paths = args.path_selector.clone();
-    for arg in &args.args {
+    ctx.plan_opts.yaml_spec = args.yaml_spec;
+    ctx.plan_opts.units = args.units;
+    ctx.plan_opts.max_output_bytes = args.max_output_bytes;
+    ctx.plan_opts.include_keys = args.include_keys.clone();
+    ctx.plan_opts.exclude_keys = args.exclude_keys.clone();
+    for arg in &args.option_args() {
         ctx.builtin_option_init(&arg.name, &arg.value);
     }
     ctx.set_kcl_workdir(&args.work_dir.clone().unwrap_or_default());
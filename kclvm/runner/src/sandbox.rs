@@ -0,0 +1,18 @@
+//! Confine the files written by a run, i.e., the compiled library cache, the
+//! temp files, the LLVM IR files, the outputs and the files written by the
+//! builtins, e.g., `yaml.dump_to_file`, to a sandbox root for the multi-tenant
+//! servers. It is enabled by [crate::ExecProgramArgs::sandbox_root].
+//!
+//! The written path is resolved component by component, the symlinks of the
+//! existing components are followed and the `..` components are applied, so
+//! the crafted paths can not escape the root.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// Resolve the path against the current dir and returns it when it is inside the
+/// sandbox root, otherwise returns an error. The path may not exist yet.
+pub fn resolve_in_sandbox(root: &Path, path: &Path) -> Result<PathBuf> {
+    kclvm_runtime::resolve_in_sandbox(root, path).map_err(anyhow::Error::msg)
+}
@@ -0,0 +1,111 @@
+//! Build the AST which binds the JSON data to a schema, which is used by
+//! [crate::execute_schema] to validate the data against a schema entry point.
+
+use anyhow::{bail, Result};
+use kclvm_ast::{
+    ast::{
+        AssignStmt, ConfigEntry, ConfigEntryOperation, ConfigExpr, Expr, ExprContext, Identifier,
+        ListExpr, NameConstant, NameConstantLit, Node, NodeRef, NumberLit, NumberLitValue,
+        SchemaExpr, Stmt, StringLit,
+    },
+    node_ref,
+};
+
+/// The private global variable name which holds the schema instance. The private
+/// variable is not output unless it is selected by the path selector.
+pub(crate) const SCHEMA_INSTANCE_NAME: &str = "_kcl_schema_instance";
+
+/// Build the statement `_kcl_schema_instance = <schema_name> {<data>}`.
+pub(crate) fn build_schema_instance_stmt(
+    schema_name: &str,
+    data: &serde_json::Value,
+) -> Result<NodeRef<Stmt>> {
+    if schema_name.is_empty() {
+        bail!("the schema name is empty");
+    }
+    let config = match data {
+        serde_json::Value::Object(_) => json_to_expr(data)?,
+        _ => bail!(
+            "the data bound to the schema '{}' must be a JSON object",
+            schema_name
+        ),
+    };
+    let value = node_ref!(Expr::Schema(SchemaExpr {
+        name: identifier(schema_name, ExprContext::Load),
+        args: vec![],
+        kwargs: vec![],
+        config,
+    }));
+    Ok(node_ref!(Stmt::Assign(AssignStmt {
+        targets: vec![identifier(SCHEMA_INSTANCE_NAME, ExprContext::Store)],
+        value,
+        ty: None,
+    })))
+}
+
+/// Convert the JSON value to the KCL literal expression, objects are converted
+/// to config expressions.
+fn json_to_expr(value: &serde_json::Value) -> Result<NodeRef<Expr>> {
+    Ok(match value {
+        serde_json::Value::Null => node_ref!(Expr::NameConstantLit(NameConstantLit {
+            value: NameConstant::None,
+        })),
+        serde_json::Value::Bool(b) => node_ref!(Expr::NameConstantLit(NameConstantLit {
+            value: if *b {
+                NameConstant::True
+            } else {
+                NameConstant::False
+            },
+        })),
+        serde_json::Value::Number(n) => {
+            let value = if let Some(i) = n.as_i64() {
+                NumberLitValue::Int(i)
+            } else if let Some(f) = n.as_f64() {
+                NumberLitValue::Float(f)
+            } else {
+                bail!("unsupported number {}", n)
+            };
+            node_ref!(Expr::NumberLit(NumberLit {
+                binary_suffix: None,
+                value,
+            }))
+        }
+        serde_json::Value::String(s) => node_ref!(Expr::StringLit(string_lit(s))),
+        serde_json::Value::Array(items) => node_ref!(Expr::List(ListExpr {
+            elts: items.iter().map(json_to_expr).collect::<Result<_>>()?,
+            ctx: ExprContext::Load,
+        })),
+        serde_json::Value::Object(entries) => {
+            let mut items = vec![];
+            for (key, value) in entries {
+                items.push(node_ref!(ConfigEntry {
+                    key: Some(node_ref!(Expr::StringLit(string_lit(key)))),
+                    value: json_to_expr(value)?,
+                    operation: ConfigEntryOperation::Union,
+                    insert_index: -1,
+                }));
+            }
+            node_ref!(Expr::Config(ConfigExpr { items }))
+        }
+    })
+}
+
+fn string_lit(value: &str) -> StringLit {
+    StringLit {
+        value: value.to_string(),
+        raw_value: format!("{:?}", value),
+        is_long_string: false,
+    }
+}
+
+/// Build the identifier from the dotted name, e.g., `pkg.Schema`.
+fn identifier(name: &str, ctx: ExprContext) -> NodeRef<Identifier> {
+    node_ref!(Identifier {
+        names: name
+            .split('.')
+            .map(|n| Node::dummy_node(n.to_string()))
+            .collect(),
+        pkgpath: String::new(),
+        ctx,
+    })
+}
@@ -0,0 +1,7 @@
+[package]
+name = "bundle"
+edition = "0.0.1"
+version = "0.0.1"
+
+[profile]
+entries = ["main.k"]
@@ -0,0 +1,2 @@
+[package]
+name = "args_result"
@@ -0,0 +1,7 @@
+[package]
+name = "offline"
+edition = "0.0.1"
+version = "0.0.1"
+
+[dependencies]
+helper = { oci = "oci://ghcr.io/kcl-lang/helper", tag = "0.1.0" }
@@ -0,0 +1,93 @@
+//! Golden testing helpers for the crates embedding KCL, which are enabled by the
+//! `testing` feature.
+//!
+//! ```no_run
+//! use kclvm_runner::testing::assert_kcl_output;
+//!
+//! assert_kcl_output("config/main.k", "config/main.golden.yaml");
+//! ```
+//!
+//! Run the tests with `KCL_BLESS=1` to write the outputs into the golden files.
+
+use std::path::Path;
+
+use anyhow::{bail, Context as _, Result};
+
+use crate::golden::{diff_output, kcl_output};
+use crate::ExecProgramArgs;
+
+/// The environment variable which updates the golden files with the outputs
+/// instead of comparing them, when it is set to a value other than `0`.
+pub const KCL_BLESS_ENV_VAR: &str = "KCL_BLESS";
+
+/// Run the KCL file and assert its YAML output equals the golden file, the
+/// mismatch panics with a line diff of the normalized outputs.
+pub fn assert_kcl_output<P: AsRef<Path>, Q: AsRef<Path>>(kcl_path: P, golden_path: Q) {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(kcl_path.as_ref().display().to_string());
+    if let Err(err) = check_kcl_output(&args, golden_path) {
+        panic!("{err}")
+    }
+}
+
+/// Run the KCL program with the args and compare its YAML output with the golden
+/// file, or update the golden file when [KCL_BLESS_ENV_VAR] is set. See
+/// [crate::golden::compare_kcl_output] for the comparison which never updates it.
+pub fn check_kcl_output<Q: AsRef<Path>>(args: &ExecProgramArgs, golden_path: Q) -> Result<()> {
+    check_kcl_output_with_bless(args, golden_path.as_ref(), is_bless_enabled())
+}
+
+/// Returns whether the golden files are updated with the outputs, see [KCL_BLESS_ENV_VAR].
+pub fn is_bless_enabled() -> bool {
+    std::env::var(KCL_BLESS_ENV_VAR).map_or(false, |v| !v.is_empty() && v != "0")
+}
+
+/// Read the golden file, the missing golden file is created with the actual
+/// output in the bless mode, otherwise an error suggesting the bless mode is
+/// returned.
+pub fn read_golden_file<P: AsRef<Path>>(
+    golden_path: P,
+    actual: &str,
+    bless: bool,
+) -> Result<String> {
+    let golden_path = golden_path.as_ref();
+    if !golden_path.exists() {
+        if !bless {
+            bail!(
+                "the golden file {} does not exist, run the tests with {}=1 to create it",
+                golden_path.display(),
+                KCL_BLESS_ENV_VAR
+            );
+        }
+        std::fs::write(golden_path, actual).with_context(|| {
+            format!("failed to write the golden file {}", golden_path.display())
+        })?;
+    }
+    std::fs::read_to_string(golden_path)
+        .with_context(|| format!("failed to read the golden file {}", golden_path.display()))
+}
+
+pub(crate) fn check_kcl_output_with_bless(
+    args: &ExecProgramArgs,
+    golden_path: &Path,
+    bless: bool,
+) -> Result<()> {
+    let actual = kcl_output(args)?;
+    if bless {
+        std::fs::write(golden_path, &actual).with_context(|| {
+            format!("failed to write the golden file {}", golden_path.display())
+        })?;
+        return Ok(());
+    }
+    let expected = read_golden_file(golden_path, &actual, bless)?;
+    if let Some(diff) = diff_output(&expected, &actual) {
+        bail!(
+            "the output does not match the golden file {}, set {}=1 to update it\n{}",
+            golden_path.display(),
+            KCL_BLESS_ENV_VAR,
+            diff
+        );
+    }
+    Ok(())
+}
@@ -1,38 +1,61 @@
+use crate::archive::{ArchiveKind, ExtractedArchive};
 #[cfg(feature = "llvm")]
 use crate::assembler::clean_path;
-#[cfg(feature = "llvm")]
 use crate::assembler::KclvmAssembler;
-#[cfg(feature = "llvm")]
 use crate::assembler::KclvmLibAssembler;
 #[cfg(feature = "llvm")]
 use crate::assembler::LibAssembler;
+use crate::assembler::{compile_pkgs_by_levels, PkgCompileErrors};
+use crate::assembler::{CacheCounter, CacheUsage, PkgLibGenerator};
+use crate::cache_archive::{
+    export_cache, import_cache, CacheManifest, CACHE_MANIFEST_FILE, PROGRAM_CACHE_ENTRY,
+};
+use crate::cache_stats::{cache_stats, CacheStatsFormat};
+use crate::determinism::check_determinism;
 use crate::exec_program;
+use crate::format::{to_ndjson, to_terraform_json, DocumentSelector, OutputFormat};
+use crate::linker::LinkSemaphore;
+use crate::metrics::ProgramMetrics;
+use crate::patch::{load_patch_file, PatchOperation};
+use crate::plan::{explain_plan, package_cache_status, PackageStatus};
+use crate::testing::{is_bless_enabled, read_golden_file};
+use crate::version::KCL_VERSION_KEY;
+use crate::RunnerError;
+use crate::{
+    execute, execute_from_sources, execute_into, execute_schema, execute_with_diagnostics,
+    runner::{
+        parse_merge_key_spec, ExecProgramArgs, ExecProgramResult, FastRunner, FEATURES_OPTION_KEY,
+    },
+    EXEC_STACK_SIZE,
+};
 #[cfg(feature = "llvm")]
-use crate::temp_file;
-use crate::{execute, runner::ExecProgramArgs};
+use crate::{temp_entry_file, temp_file};
 #[cfg(feature = "llvm")]
 use anyhow::Context;
 use anyhow::Result;
-use kclvm_ast::ast::{Module, Program};
+use indexmap::IndexMap;
+use kclvm_ast::ast::{self, Module, Program};
 #[cfg(feature = "llvm")]
 use kclvm_compiler::codegen::OBJECT_FILE_SUFFIX;
-use kclvm_config::settings::load_file;
+use kclvm_config::cache::{save_pkg_cache, CacheOption};
+use kclvm_config::settings::{load_file, SettingsPathBuf};
+use kclvm_error::{DiagnosticFormat, Level};
 use kclvm_parser::load_program;
 use kclvm_parser::ParseSession;
-#[cfg(feature = "llvm")]
-use kclvm_sema::resolver::resolve_program;
+use kclvm_runtime::{
+    CheckFailure, DuplicateKeyPolicy, MergeKey, UnitsFormat, ValueRef, YamlSpec, SCHEMA_META_ATTR,
+};
+use kclvm_sema::resolver::{resolve_program, scope::ProgramScope};
+use serde::Deserialize;
 use serde_json::Value;
-#[cfg(feature = "llvm")]
 use std::fs::create_dir_all;
+#[cfg(feature = "llvm")]
+use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-#[cfg(feature = "llvm")]
 use std::thread;
-use std::{
-    collections::HashMap,
-    fs::{self, File},
-};
-#[cfg(feature = "llvm")]
+use std::{collections::HashMap, fs};
 use tempfile::tempdir;
 use uuid::Uuid;
 use walkdir::WalkDir;
@@ -140,9 +163,26 @@ const CARGO_PATH: &str = env!("CARGO_MANIFEST_DIR");
 
 #[derive(serde::Deserialize, serde::Serialize)]
 struct SimplePanicInfo {
+    #[serde(alias = "kcl_line")]
     line: i32,
+    #[serde(alias = "kcl_col")]
     col: i32,
     message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    check_failures: Vec<CheckFailure>,
+}
+
+/// Evaluate the program with the fast evaluator and keep the raw runtime error
+/// JSON in the result.
+fn eval_with_raw_error(args: &ExecProgramArgs) -> ExecProgramResult {
+    let sess = Arc::new(ParseSession::default());
+    let opts = args.get_load_program_options();
+    let files = args.get_files();
+    let mut program = load_program(sess, &files, Some(opts), None)
+        .unwrap()
+        .program;
+    resolve_program(&mut program);
+    FastRunner::new(None).eval(&program, args, false)
 }
 
 #[cfg(feature = "llvm")]
@@ -208,10 +248,12 @@ fn construct_pkg_lib_path(
     result
 }
 
-/// Load the expect result from stdout.golden.json
-fn load_expect_file(filename: String) -> String {
-    let f = File::open(filename).unwrap();
-    let v: serde_json::Value = serde_json::from_reader(f).unwrap();
+/// Load the expect result from stdout.golden.json, the missing golden file is
+/// created with the actual result in the bless mode.
+fn load_expect_file(filename: String, actual: &str) -> String {
+    let content = read_golden_file(filename, actual, is_bless_enabled())
+        .unwrap_or_else(|err| panic!("{err}"));
+    let v: serde_json::Value = serde_json::from_str(&content).unwrap();
     v.to_string()
 }
 
@@ -242,6 +284,7 @@ fn gen_assembler(entry_file: &str, test_kcl_case_path: &str) -> KclvmAssembler {
         KclvmLibAssembler::LLVM,
         HashMap::new(),
     )
+    .unwrap()
 }
 
 #[cfg(feature = "llvm")]
@@ -298,7 +341,7 @@ fn assemble_lib_for_test(
     assembler
         .assemble(
             &program,
-            scope.import_names,
+            scope.imports().clone(),
             entry_file,
             temp_entry_file_path,
             &ExecProgramArgs::default(),
@@ -319,7 +362,7 @@ fn test_kclvm_runner_execute() {
             .display()
             .to_string();
         let result = execute_for_test(kcl_path);
-        let expected_result = load_expect_file(expected_path.to_string());
+        let expected_result = load_expect_file(expected_path.to_string(), &result);
         assert_eq!(expected_result, format_str_by_json(result));
     }
 }
@@ -330,7 +373,7 @@ fn test_assemble_lib_llvm() {
     for case in TEST_CASES {
         let temp_dir = tempdir().unwrap();
         let temp_dir_path = temp_dir.path().to_str().unwrap();
-        let temp_entry_file = temp_file(temp_dir_path).unwrap();
+        let temp_entry_file = temp_file(temp_dir_path, None).unwrap();
         let kcl_path = &Path::new(&test_case_path())
             .join(case)
             .join(KCL_FILE_NAME)
@@ -357,7 +400,7 @@ fn test_gen_libs() {
     for case in multi_file_test_cases() {
         let temp_dir = tempdir().unwrap();
         let temp_dir_path = temp_dir.path().to_str().unwrap();
-        let temp_entry_file = temp_file(temp_dir_path).unwrap();
+        let temp_entry_file = temp_file(temp_dir_path, None).unwrap();
 
         let kcl_path = gen_full_path(
             Path::new(&test_case_path())
@@ -371,6 +414,68 @@ fn test_gen_libs() {
     }
 }
 
+#[test]
+#[cfg(feature = "llvm")]
+fn test_gen_libs_parallel() {
+    for (i, case) in multi_file_test_cases().iter().enumerate() {
+        let temp_dir = tempdir().unwrap();
+        let temp_dir_path = temp_dir.path().to_str().unwrap();
+        let temp_entry_file = temp_file(temp_dir_path, None).unwrap();
+        let entry_file = format!("{}{}", temp_entry_file, "4gen_libs_parallel");
+
+        let kcl_path = gen_full_path(
+            Path::new(&test_case_path())
+                .join(case)
+                .join(KCL_FILE_NAME)
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        let args = ExecProgramArgs::default();
+        let mut lib_paths = vec![];
+        for parallel in [false, true] {
+            // Each build uses a clean cache dir of its own target name, thus all the
+            // packages are compiled instead of being read from the cache.
+            let mut assembler = gen_assembler(&entry_file, &kcl_path);
+            assembler.target = format!(
+                "{}-gen-libs-parallel-{}-{}-{}",
+                env!("KCLVM_DEFAULT_TARGET"),
+                std::process::id(),
+                i,
+                parallel
+            );
+            let cache_dir = assembler.construct_cache_dir(&assembler_root(&kcl_path));
+            let _ = fs::remove_dir_all(&cache_dir);
+            let paths = assembler.gen_libs_with_parallel(&args, parallel).unwrap();
+            // Every package is built into the clean cache dir, except the main package.
+            for lib_path in &paths {
+                assert!(Path::new(lib_path).exists());
+                if !lib_path.starts_with(&entry_file) {
+                    assert!(Path::new(lib_path).starts_with(&cache_dir), "{lib_path}");
+                }
+            }
+            lib_paths.push(
+                paths
+                    .iter()
+                    .map(|p| Path::new(p).file_name().unwrap().to_owned())
+                    .collect::<Vec<_>>(),
+            );
+            let _ = fs::remove_dir_all(&cache_dir);
+        }
+        assert_eq!(lib_paths[0], lib_paths[1]);
+
+        let tmp_main_lib_path =
+            fs::canonicalize(format!("{}{}", entry_file, OBJECT_FILE_SUFFIX)).unwrap();
+        clean_path(tmp_main_lib_path.to_str().unwrap()).unwrap();
+    }
+}
+
+/// Returns the program root of the KCL file, which holds the cache of the packages.
+#[cfg(feature = "llvm")]
+fn assembler_root(kcl_path: &str) -> String {
+    parse_program(kcl_path).root
+}
+
 #[test]
 #[cfg(feature = "llvm")]
 fn test_clean_path_for_genlibs() {
@@ -392,11 +497,12 @@ fn test_clean_path_for_genlibs() {
         String::new(),
         KclvmLibAssembler::LLVM,
         HashMap::new(),
-    );
+    )
+    .unwrap();
 
     let temp_dir = tempdir().unwrap();
     let temp_dir_path = temp_dir.path().to_str().unwrap();
-    let tmp_file_path = &temp_file(temp_dir_path).unwrap();
+    let tmp_file_path = &temp_file(temp_dir_path, None).unwrap();
 
     create_dir_all(tmp_file_path).unwrap();
 
@@ -455,6 +561,158 @@ fn test_from_str_program_arg() {
     }
 }
 
+/// A xorshift generator to build reproducible random test inputs.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bool(&mut self) -> bool {
+        self.next() % 2 == 0
+    }
+
+    fn string(&mut self) -> String {
+        const CHARS: &[char] = &[
+            'a', 'Z', '0', '_', '.', '/', '"', '\\', ' ', '\n', '中', '😀',
+        ];
+        let len = self.next() % 8;
+        (0..len)
+            .map(|_| CHARS[(self.next() % CHARS.len() as u64) as usize])
+            .collect()
+    }
+
+    fn strings(&mut self) -> Vec<String> {
+        let len = self.next() % 4;
+        (0..len).map(|_| self.string()).collect()
+    }
+
+    fn vec<T>(&mut self, f: impl Fn(&mut Self) -> T) -> Vec<T> {
+        let len = self.next() % 4;
+        (0..len).map(|_| f(self)).collect()
+    }
+}
+
+fn random_exec_program_args(rng: &mut XorShift) -> ExecProgramArgs {
+    ExecProgramArgs {
+        work_dir: rng.bool().then(|| rng.string()),
+        k_filename_list: rng.strings(),
+        external_pkgs: rng.vec(|rng| ast::CmdExternalPkgSpec {
+            pkg_name: rng.string(),
+            pkg_path: rng.string(),
+        }),
+        k_code_list: rng.strings(),
+        args: rng.vec(|rng| ast::CmdArgSpec {
+            name: rng.string(),
+            value: rng.string(),
+        }),
+        overrides: rng.vec(|rng| ast::OverrideSpec {
+            pkgpath: rng.string(),
+            field_path: rng.string(),
+            field_value: rng.string(),
+            action: if rng.bool() {
+                ast::OverrideAction::Delete
+            } else {
+                ast::OverrideAction::CreateOrUpdate
+            },
+        }),
+        strict_overrides: rng.bool(),
+        path_selector: rng.strings(),
+        disable_yaml_result: rng.bool(),
+        print_override_ast: rng.bool(),
+        strict_range_check: rng.bool(),
+        disable_none: rng.bool(),
+        verbose: rng.next() as i32,
+        debug: rng.next() as i32,
+        sort_keys: rng.bool(),
+        show_hidden: rng.bool(),
+        include_schema_type_path: rng.bool(),
+        compile_only: rng.bool(),
+        max_parse_depth: rng.next() as usize,
+        max_eval_depth: rng.next() as usize,
+        max_eval_memory_bytes: rng.next() as usize,
+        yaml_spec: if rng.bool() {
+            YamlSpec::Yaml11
+        } else {
+            YamlSpec::Yaml12
+        },
+        include_keys: rng.strings(),
+        exclude_keys: rng.strings(),
+        diagnostic_format: if rng.bool() {
+            DiagnosticFormat::Rich
+        } else {
+            DiagnosticFormat::Plain
+        },
+        target_triple: rng.bool().then(|| rng.string()),
+        llvm_ir_dir: rng.bool().then(|| rng.string()),
+        // The plugin agent address is never serialized.
+        plugin_agent: 0,
+        fast_eval: rng.bool(),
+        trace_provenance: rng.bool(),
+        emit_version: rng.bool(),
+        collect_check_failures: rng.bool(),
+        continue_on_error: rng.bool(),
+        offline: rng.bool(),
+        temp_dir: rng.bool().then(|| PathBuf::from(rng.string())),
+        temp_prefix: rng.bool().then(|| rng.string()),
+        sandbox_root: rng.bool().then(|| PathBuf::from(rng.string())),
+        features: rng.strings(),
+        duplicate_key_policy: match rng.next() % 3 {
+            0 => DuplicateKeyPolicy::Error,
+            1 => DuplicateKeyPolicy::LastWins,
+            _ => DuplicateKeyPolicy::Merge,
+        },
+        collect_metrics: rng.bool(),
+        merge_keys: rng.vec(|rng| MergeKey {
+            path: rng.string(),
+            key: rng.string(),
+        }),
+        fold_constants: rng.bool(),
+        deny_warnings: rng.bool(),
+        json_patch: rng.vec(|rng| match rng.next() % 3 {
+            0 => PatchOperation::Add {
+                path: rng.string(),
+                value: Value::String(rng.string()),
+            },
+            1 => PatchOperation::Remove { path: rng.string() },
+            _ => PatchOperation::Move {
+                from: rng.string(),
+                path: rng.string(),
+            },
+        }),
+        include_schema_meta: rng.bool(),
+        shared_cache_dir: rng.bool().then(|| PathBuf::from(rng.string())),
+        units: if rng.bool() {
+            UnitsFormat::Suffixed
+        } else {
+            UnitsFormat::Expanded
+        },
+        stream_diagnostics: rng.bool(),
+        max_output_bytes: rng.next() as usize,
+        // The value serializers and the cancel flag are never serialized.
+        value_serializers: Default::default(),
+        cancel_flag: Default::default(),
+    }
+}
+
+#[test]
+fn test_program_arg_json_round_trip() {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    for _ in 0..1000 {
+        let args = random_exec_program_args(&mut rng);
+        assert_eq!(ExecProgramArgs::from_str(&args.to_json()), args);
+    }
+    // Missing fields take the default values.
+    assert_eq!(ExecProgramArgs::from_str("{}"), ExecProgramArgs::default());
+    let args = ExecProgramArgs::from_str(r#"{"k_filename_list":["main.k"],"fast_eval":true}"#);
+    assert_eq!(args.k_filename_list, vec!["main.k".to_string()]);
+    assert!(args.fast_eval);
+}
+
 #[test]
 fn test_from_setting_file_program_arg() {
     for (case_yaml, case_json) in settings_file_test_case() {
@@ -475,6 +733,37 @@ fn test_from_setting_file_program_arg() {
     }
 }
 
+#[test]
+fn test_from_setting_path_buf_rebase_files() {
+    let settings_dir = Path::new(&test_case_path()).join("settings_file");
+    let mut settings =
+        load_file(&settings_dir.join("settings.yaml").display().to_string()).unwrap();
+    let abs_file = std::env::current_dir()
+        .unwrap()
+        .join("abs.k")
+        .display()
+        .to_string();
+    if let Some(configs) = settings.kcl_cli_configs.as_mut() {
+        if let Some(files) = configs.files.as_mut() {
+            files.push(abs_file.clone());
+            files.push("${KCL_MOD}/mod.k".to_string());
+        }
+    }
+    let args =
+        ExecProgramArgs::try_from(SettingsPathBuf::new(Some(settings_dir.clone()), settings))
+            .unwrap();
+    let base = std::env::current_dir().unwrap().join(&settings_dir);
+    let mut expected: Vec<String> = ["../main.k", "./before/base.k", "./main.k", "./sub/sub.k"]
+        .iter()
+        .map(|f| base.join(f).display().to_string())
+        .collect();
+    expected.push(abs_file);
+    // The module relative path is replaced with the module root, which is empty
+    // for the missing files.
+    expected.push("/mod.k".to_string());
+    assert_eq!(args.k_filename_list, expected);
+}
+
 fn test_exec_file() {
     let result = std::panic::catch_unwind(|| {
         for file in get_files(exec_data_path(), false, true, ".k") {
@@ -581,8 +870,8 @@ fn exec(file: &str) -> Result<String, String> {
 /// Run all kcl files at path and compare the exec result with the expect output.
 fn exec_with_result_at(path: &str) {
     let kcl_files = get_files(path, false, true, ".k");
-    let output_files = get_files(path, false, true, ".stdout.golden");
-    for (kcl_file, output_file) in kcl_files.iter().zip(&output_files) {
+    for kcl_file in &kcl_files {
+        let output_file = format!("{}.stdout.golden", kcl_file.strip_suffix(".k").unwrap());
         let mut args = ExecProgramArgs::default();
         args.k_filename_list.push(kcl_file.to_string());
         let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
@@ -592,8 +881,9 @@ fn exec_with_result_at(path: &str) {
         #[cfg(target_os = "windows")]
         let newline = "\r\n";
 
-        let expected = std::fs::read_to_string(output_file)
-            .unwrap()
+        let actual = format!("{}{}", result.yaml_result.replace('\n', newline), newline);
+        let expected = read_golden_file(&output_file, &actual, is_bless_enabled())
+            .unwrap_or_else(|err| panic!("{err}"))
             .strip_suffix(newline)
             .unwrap()
             .to_string();
@@ -690,3 +980,2578 @@ fn test_uuid() {
         assert!(Uuid::parse_str(uuid_str).is_ok());
     }
 }
+
+#[test]
+fn test_exec_with_trace_provenance() {
+    let file = Path::new(&test_case_path())
+        .join("provenance")
+        .join(KCL_FILE_NAME)
+        .canonicalize()
+        .unwrap()
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file.clone());
+    args.trace_provenance = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(
+        result.yaml_result,
+        format!("a: {file}:1\nb: {file}:3\nc: {file}:4")
+    );
+}
+
+#[test]
+fn test_exec_with_max_eval_depth() {
+    let file = Path::new(&test_case_path())
+        .join("recursive_schema")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file);
+    args.fast_eval = true;
+    args.max_eval_depth = 16;
+    let result = exec_program(Arc::new(ParseSession::default()), &args);
+    let err = match result {
+        Ok(result) => result.err_message,
+        Err(err) => err.to_string(),
+    };
+    assert!(
+        err.contains("the max evaluation depth 16 is exceeded"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_default_max_eval_depth() {
+    let file = Path::new(&test_case_path())
+        .join("recursive_schema")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    // The default limit is hit before the stack of the run overflows.
+    let err = thread::Builder::new()
+        .stack_size(EXEC_STACK_SIZE)
+        .spawn(move || {
+            let mut args = ExecProgramArgs::default();
+            args.k_filename_list.push(file);
+            args.fast_eval = true;
+            match exec_program(Arc::new(ParseSession::default()), &args) {
+                Ok(result) => result.err_message,
+                Err(err) => err.to_string(),
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(
+        err.contains("the max evaluation depth 1000 is exceeded"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_max_parse_depth() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = [[[[1]]]]".to_string());
+    args.max_parse_depth = 4;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Parse(_)), "{err:?}");
+    let err = err.to_string();
+    assert!(err.contains("the max parse depth 4 is exceeded"), "{err}");
+}
+
+#[test]
+fn test_exec_runner_error_kind() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a: int = \"s\"".to_string());
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Resolve(_)), "{err:?}");
+    assert_eq!(err.kind(), "resolve");
+    // The source of the error is the wrapped stage error.
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(source.to_string(), err.to_string());
+    assert!(std::error::Error::source(&RunnerError::Cancelled).is_none());
+}
+
+#[test]
+fn test_exec_with_max_eval_memory_bytes() {
+    let file = Path::new(&test_case_path())
+        .join("large_list")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file);
+    args.fast_eval = true;
+    args.max_eval_memory_bytes = 64 * 1024;
+    let result = exec_program(Arc::new(ParseSession::default()), &args);
+    let err = match result {
+        Ok(result) => result.err_message,
+        Err(err) => err.to_string(),
+    };
+    assert!(
+        err.contains("the max evaluation memory 65536 bytes is exceeded"),
+        "{err}"
+    );
+    // The memory limit is only enforced by the fast evaluator.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        args.fast_eval = false;
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains("max evaluation memory"), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_with_max_output_bytes() {
+    let file = Path::new(&test_case_path())
+        .join("large_list")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file);
+    args.fast_eval = true;
+    args.max_output_bytes = 4 * 1024;
+    // The evaluator aborts the serialization with a runtime error in the result.
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(
+        result
+            .err_message
+            .contains("the max output size 4096 bytes is exceeded"),
+        "{}",
+        result.err_message
+    );
+    assert_eq!(result.yaml_result, "");
+    // The output within the limit is not affected.
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = [1, 2, 3]".to_string());
+    args.fast_eval = true;
+    args.max_output_bytes = 64;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(result.yaml_result, "a:\n- 1\n- 2\n- 3");
+}
+
+#[test]
+fn test_exec_with_cancel_flag() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    // The ranges are small, the steps of the nested comprehension are many.
+    args.k_code_list
+        .push("data = [i * j for i in range(10000) for j in range(10000)]".to_string());
+    args.fast_eval = true;
+    // The execution is cancelled at the first step after the flag is set.
+    let cancel_flag = args.cancel_flag.clone();
+    let canceller = thread::spawn(move || {
+        thread::sleep(std::time::Duration::from_millis(10));
+        cancel_flag.cancel();
+    });
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    canceller.join().unwrap();
+    assert!(matches!(err, RunnerError::Cancelled), "{err:?}");
+    // The cancelled flag stops the execution before the evaluation.
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Cancelled), "{err:?}");
+}
+
+#[test]
+fn test_execute_with_diagnostics() {
+    let kcl_path = Path::new(&test_case_path())
+        .join("partial_result")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let args = ExecProgramArgs::default();
+    let sess = Arc::new(ParseSession::default());
+    let program = load_program(
+        sess.clone(),
+        &[&kcl_path],
+        Some(args.get_load_program_options()),
+        None,
+    )
+    .unwrap()
+    .program;
+    let (result, diagnostics) = execute_with_diagnostics(sess, program, &args);
+    let result = result.expect("the partial result should be returned");
+    assert!(
+        result.yaml_result.contains("a: 1"),
+        "{}",
+        result.yaml_result
+    );
+    assert!(
+        diagnostics.iter().any(|diag| diag.level == Level::Error
+            && diag.messages.iter().any(|msg| msg.message.contains("int"))),
+        "{diagnostics:?}"
+    );
+}
+
+#[test]
+fn test_execute_schema() {
+    let kcl_path = Path::new(&test_case_path())
+        .join("execute_schema")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let args = ExecProgramArgs::default();
+    let load = || {
+        let sess = Arc::new(ParseSession::default());
+        let program = load_program(
+            sess.clone(),
+            &[&kcl_path],
+            Some(args.get_load_program_options()),
+            None,
+        )
+        .unwrap()
+        .program;
+        (sess, program)
+    };
+    // Valid data
+    let (sess, program) = load();
+    let data = serde_json::json!({"name": "bob", "age": 18});
+    let result = execute_schema(sess, program, "Person", data, &args).unwrap();
+    let value: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(value, serde_json::json!({"name": "bob", "age": 18}));
+    // Invalid data which fails the schema check
+    let (sess, program) = load();
+    let data = serde_json::json!({"name": "bob", "age": -1});
+    let err = execute_schema(sess, program, "Person", data, &args).unwrap_err();
+    assert_eq!(err.kind(), "runtime");
+    assert!(
+        err.to_string().contains("age must be non-negative"),
+        "{err}"
+    );
+    // Invalid data with the wrong attribute type
+    let (sess, program) = load();
+    let data = serde_json::json!({"name": 1, "age": 18});
+    assert!(execute_schema(sess, program, "Person", data, &args).is_err());
+}
+
+#[test]
+fn test_execute_into() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct App {
+        name: String,
+        replicas: u32,
+        ports: Vec<u16>,
+        labels: HashMap<String, String>,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        app: App,
+    }
+    let kcl_path = Path::new(&test_case_path())
+        .join("execute_into")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.fast_eval = true;
+    let load = || {
+        let sess = Arc::new(ParseSession::default());
+        let program = load_program(
+            sess.clone(),
+            &[&kcl_path],
+            Some(args.get_load_program_options()),
+            None,
+        )
+        .unwrap()
+        .program;
+        (sess, program)
+    };
+    let (sess, program) = load();
+    let config: Config = execute_into(sess, program, &args).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            app: App {
+                name: "web".to_string(),
+                replicas: 3,
+                ports: vec![80, 443],
+                labels: HashMap::from([("tier".to_string(), "frontend".to_string())]),
+            }
+        }
+    );
+    // The mismatched fields are reported with their paths.
+    #[derive(Deserialize, Debug)]
+    struct WrongType {
+        #[allow(dead_code)]
+        app: HashMap<String, String>,
+    }
+    let (sess, program) = load();
+    let err = execute_into::<WrongType>(sess, program, &args).unwrap_err();
+    assert_eq!(err.kind(), "deserialize");
+    assert!(
+        err.to_string()
+            .starts_with("failed to deserialize the result at app.replicas: invalid type"),
+        "{err}"
+    );
+    #[derive(Deserialize, Debug)]
+    struct WrongItem {
+        #[allow(dead_code)]
+        app: HashMap<String, Vec<String>>,
+    }
+    let (sess, program) = load();
+    let err = execute_into::<WrongItem>(sess, program, &args).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("failed to deserialize the result at app.name: invalid type"),
+        "{err}"
+    );
+    #[derive(Deserialize, Debug)]
+    struct MissingField {
+        #[allow(dead_code)]
+        app: MissingImage,
+    }
+    #[derive(Deserialize, Debug)]
+    struct MissingImage {
+        #[allow(dead_code)]
+        image: String,
+    }
+    let (sess, program) = load();
+    let err = execute_into::<MissingField>(sess, program, &args).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("failed to deserialize the result at app: missing field `image`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_yaml_spec() {
+    let file = Path::new(&test_case_path())
+        .join("yaml_spec")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file);
+    args.fast_eval = true;
+    // The ambiguous strings are quoted under YAML 1.1.
+    args.yaml_spec = YamlSpec::Yaml11;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    for expected in [
+        "  'on': push",
+        "  enabled: 'yes'",
+        "  disabled: 'off'",
+        "  short: 'y'",
+        "  mode: '0777'",
+        "  duration: '1:20'",
+        "  flag: 'true'",
+        "  name: kcl",
+    ] {
+        assert!(
+            result.yaml_result.contains(expected),
+            "{}",
+            result.yaml_result
+        );
+    }
+    // The strings stay strings under the default YAML 1.2.
+    args.yaml_spec = YamlSpec::default();
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    let mut ctx = kclvm_runtime::Context::new();
+    let yaml = kclvm_runtime::ValueRef::from_yaml(&mut ctx, &result.yaml_result).unwrap();
+    let yaml: Value = serde_json::from_str(&yaml.to_json_string()).unwrap();
+    let json: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(yaml, json);
+    assert_eq!(json["trigger"]["on"], Value::String("push".to_string()));
+    // The YAML 1.1 boolean words are quoted under YAML 1.2 as well.
+    for expected in [
+        "  'on': push",
+        "  enabled: 'yes'",
+        "  disabled: 'off'",
+        "  short: y",
+        "  duration: 1:20",
+    ] {
+        assert!(
+            result.yaml_result.contains(expected),
+            "{}",
+            result.yaml_result
+        );
+    }
+}
+
+#[test]
+fn test_exec_with_include_exclude_keys() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list
+        .push("a = 1\nb = {c = 2}\nd = [3]".to_string());
+    args.fast_eval = true;
+    // Include only
+    args.include_keys = vec!["a".to_string(), "d".to_string()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.yaml_result, "a: 1\nd:\n- 3");
+    // Exclude only
+    args.include_keys = vec![];
+    args.exclude_keys = vec!["a".to_string()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.yaml_result, "b:\n  c: 2\nd:\n- 3");
+    // Include and exclude together
+    args.include_keys = vec!["a".to_string()];
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+    assert!(
+        err.to_string().contains("can not be used together"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_rich_diagnostic_format() {
+    let file = Path::new(&test_case_path())
+        .join("diagnostic_format")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(file.clone());
+    args.fast_eval = true;
+    // The plain format is the default format.
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(!err.to_string().contains("2 | b = c + a"), "{err}");
+    // The caret is under the column of the undefined name.
+    args.diagnostic_format = DiagnosticFormat::Rich;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Resolve(_)), "{err:?}");
+    let err = err.to_string();
+    assert!(err.contains("main.k:2:5\n"), "{err}");
+    assert!(
+        err.contains("2 | b = c + a\n  |     ^ name 'c' is not defined"),
+        "{err}"
+    );
+    // Multi-line spans underline each line of the range.
+    let diag = kclvm_error::Diagnostic::new(
+        Level::Error,
+        "multi-line",
+        (
+            kclvm_error::Position {
+                filename: file.clone(),
+                line: 3,
+                column: Some(4),
+            },
+            kclvm_error::Position {
+                filename: file,
+                line: 5,
+                column: Some(1),
+            },
+        ),
+    );
+    let rendered = kclvm_error::render_rich_diagnostic(&diag);
+    assert!(
+        rendered.ends_with(
+            "3 | d = {\n  |     ^\n4 |     e = 1\n  |     ^^^^^\n5 | }\n  | ^ multi-line\n"
+        ),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn test_execute_from_sources() {
+    let sources = HashMap::from([
+        (
+            "main.k".to_string(),
+            "import sub.lib\n\nperson = lib.Person {name = \"kcl\"}".to_string(),
+        ),
+        (
+            "sub/lib.k".to_string(),
+            "schema Person:\n    name: str\n    age: int = 1".to_string(),
+        ),
+    ]);
+    let mut args = ExecProgramArgs::default();
+    args.fast_eval = true;
+    let result = execute_from_sources(
+        Arc::new(ParseSession::default()),
+        sources.clone(),
+        "main.k",
+        &args,
+    )
+    .unwrap();
+    assert_eq!(result.yaml_result, "person:\n  name: kcl\n  age: 1");
+    // The entry must be one of the sources.
+    let err = execute_from_sources(Arc::new(ParseSession::default()), sources, "app.k", &args)
+        .unwrap_err();
+    assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+}
+
+#[test]
+fn test_check_kcl_output() {
+    let dir = tempdir().unwrap();
+    let golden = dir.path().join("main.golden.yaml");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list
+        .push("b = {d = 2, c = 1}\na = [1, 2]".to_string());
+    args.fast_eval = true;
+    // Bless writes the golden file.
+    crate::testing::check_kcl_output_with_bless(&args, &golden, true).unwrap();
+    // The key order and line endings are normalized before the comparison.
+    std::fs::write(&golden, "a:\r\n- 1\r\n- 2\r\nb:\r\n  c: 1\r\n  d: 2\r\n").unwrap();
+    crate::testing::check_kcl_output_with_bless(&args, &golden, false).unwrap();
+    // The mismatch is reported with a line diff.
+    std::fs::write(&golden, "a:\n- 1\n- 3\nb:\n  c: 1\n  d: 2\n").unwrap();
+    let err = crate::testing::check_kcl_output_with_bless(&args, &golden, false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("does not match the golden file"), "{err}");
+    assert!(err.contains(" - 1\n-- 3\n+- 2\n b:\n"), "{err}");
+}
+
+#[test]
+fn test_compare_kcl_output() {
+    let dir = tempdir().unwrap();
+    let golden = dir.path().join("main.golden.yaml");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("b = 2\na = 1".to_string());
+    args.fast_eval = true;
+    // The missing golden file is an error and it is not created.
+    let err = crate::golden::compare_kcl_output(&args, &golden)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("failed to read the golden file"), "{err}");
+    assert!(!golden.exists());
+    std::fs::write(&golden, "a: 1\r\nb: 2\r\n").unwrap();
+    crate::golden::compare_kcl_output(&args, &golden).unwrap();
+    // The mismatch is reported without updating the golden file.
+    std::fs::write(&golden, "a: 1\nb: 3\n").unwrap();
+    let err = crate::golden::compare_kcl_output(&args, &golden)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("does not match the golden file"), "{err}");
+    assert!(!err.contains("KCL_BLESS"), "{err}");
+    assert_eq!(fs::read_to_string(&golden).unwrap(), "a: 1\nb: 3\n");
+}
+
+#[test]
+fn test_missing_golden_file() {
+    let dir = tempdir().unwrap();
+    let golden = dir.path().join("missing.stdout.golden");
+    // The missing golden file is reported with the hint to create it.
+    let err = read_golden_file(&golden, "a: 1\n", false)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("does not exist"), "{err}");
+    assert!(err.contains("KCL_BLESS=1"), "{err}");
+    assert!(!golden.exists());
+    // Bless creates the missing golden file with the actual output.
+    assert_eq!(read_golden_file(&golden, "a: 1\n", true).unwrap(), "a: 1\n");
+    assert_eq!(fs::read_to_string(&golden).unwrap(), "a: 1\n");
+    // The existing golden file is not overwritten by the reader.
+    assert_eq!(read_golden_file(&golden, "a: 2\n", true).unwrap(), "a: 1\n");
+}
+
+#[test]
+#[cfg(not(feature = "llvm"))]
+fn test_runner_without_llvm() {
+    // The LLVM assembler is not available without the llvm feature.
+    let mut program = load_test_program(
+        Path::new(&test_case_path())
+            .join("normal_2")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    let scope = resolve_program(&mut program);
+    let err = KclvmAssembler::new(
+        program.clone(),
+        scope,
+        String::new(),
+        KclvmLibAssembler::LLVM,
+        HashMap::new(),
+    )
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string()
+            .contains("the llvm backend is not available"),
+        "{err}"
+    );
+    // The runner falls back to the interpreter backend.
+    let args = ExecProgramArgs::default();
+    let result = execute(Arc::new(ParseSession::default()), program, &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    assert!(!result.json_result.is_empty());
+}
+
+#[test]
+#[cfg(all(feature = "llvm", target_os = "linux"))]
+fn test_gen_libs_with_target_triple() {
+    // The ELF machine types of the requested triples.
+    for (triple, machine) in [
+        ("aarch64-unknown-linux-gnu", 183u16),
+        ("x86_64-unknown-linux-gnu", 62u16),
+    ] {
+        if !kclvm_compiler::codegen::llvm::is_target_available(triple) {
+            continue;
+        }
+        let temp_dir = tempdir().unwrap();
+        let temp_dir_path = temp_dir.path().to_str().unwrap();
+        let entry_file = format!("{}4target_triple", temp_file(temp_dir_path, None).unwrap());
+        let kcl_path = gen_full_path(
+            Path::new(&test_case_path())
+                .join("normal_2")
+                .join(KCL_FILE_NAME)
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        let mut args = ExecProgramArgs::default();
+        args.target_triple = Some(triple.to_string());
+        gen_assembler(&entry_file, &kcl_path)
+            .gen_libs(&args)
+            .unwrap();
+        let object_file = format!("{}{}", entry_file, OBJECT_FILE_SUFFIX);
+        let bytes = fs::read(&object_file).unwrap();
+        assert_eq!(&bytes[..4], b"\x7fELF");
+        assert_eq!(u16::from_le_bytes([bytes[18], bytes[19]]), machine);
+        clean_path(&object_file).unwrap();
+    }
+}
+
+#[test]
+fn test_exec_with_invalid_target_triple() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = 1".to_string());
+    for (triple, msg) in [
+        ("x86_64", "invalid target triple 'x86_64'"),
+        ("mips-unknown-linux-gnu", "unsupported architecture 'mips'"),
+    ] {
+        args.target_triple = Some(triple.to_string());
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains(msg), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_with_target_triple_on_unsupported_backend() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = 1".to_string());
+    // The objects of the target triple are only emitted by the LLVM backend.
+    args.fast_eval = true;
+    args.target_triple = Some("x86_64-unknown-linux-gnu".to_string());
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+    assert!(
+        err.to_string()
+            .contains("only be set with the LLVM backend"),
+        "{err}"
+    );
+    // The program built for a target triple is not executed.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        args.fast_eval = false;
+        args.target_triple = Some(env!("KCLVM_DEFAULT_TARGET").to_string());
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains("instead of executing it"), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_with_unsupported_backend_options() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = 1".to_string());
+    // The evaluation depth limit is only enforced by the fast evaluator.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        args.max_eval_depth = 16;
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains("max evaluation depth"), "{err}");
+    }
+}
+
+#[test]
+#[cfg(feature = "llvm")]
+fn test_gen_libs_with_llvm_ir() {
+    let temp_dir = tempdir().unwrap();
+    let temp_dir_path = temp_dir.path().to_str().unwrap();
+    let entry_file = format!("{}4llvm_ir", temp_file(temp_dir_path, None).unwrap());
+    let kcl_path = gen_full_path(
+        Path::new(&test_case_path())
+            .join("normal_2")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    )
+    .unwrap();
+    let ir_dir = temp_dir.path().join("ir");
+    let mut args = ExecProgramArgs::default();
+    args.llvm_ir_dir = Some(ir_dir.display().to_string());
+    gen_assembler(&entry_file, &kcl_path)
+        .gen_libs(&args)
+        .unwrap();
+    let ir = fs::read_to_string(ir_dir.join(format!("{}.ll", kclvm_ast::MAIN_PKG))).unwrap();
+    assert!(!ir.is_empty());
+    assert!(
+        ir.contains(&format!("@{}", kclvm_compiler::codegen::MODULE_NAME)),
+        "{ir}"
+    );
+    clean_path(&format!("{}{}", entry_file, OBJECT_FILE_SUFFIX)).unwrap();
+}
+
+#[test]
+#[cfg(feature = "llvm")]
+fn test_gen_libs_deterministic() {
+    let temp_dir = tempdir().unwrap();
+    let temp_dir_path = temp_dir.path().to_str().unwrap();
+    let kcl_path = gen_full_path(
+        Path::new(&test_case_path())
+            .join("init_check_order_1")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    )
+    .unwrap();
+    // Compile the same source twice without the cache and compare the outputs.
+    let mut outputs = vec![];
+    for i in 0..2 {
+        let entry_file = format!(
+            "{}4deterministic_{}",
+            temp_file(temp_dir_path, None).unwrap(),
+            i
+        );
+        let ir_dir = temp_dir.path().join(format!("ir_{}", i));
+        let mut args = ExecProgramArgs::default();
+        args.llvm_ir_dir = Some(ir_dir.display().to_string());
+        gen_assembler(&entry_file, &kcl_path)
+            .gen_libs(&args)
+            .unwrap();
+        let object_file = format!("{}{}", entry_file, OBJECT_FILE_SUFFIX);
+        let ir = fs::read_to_string(ir_dir.join(format!("{}.ll", kclvm_ast::MAIN_PKG))).unwrap();
+        outputs.push((ir, fs::read(&object_file).unwrap()));
+        clean_path(&object_file).unwrap();
+    }
+    assert_eq!(outputs[0].0, outputs[1].0);
+    assert!(
+        outputs[0].1 == outputs[1].1,
+        "the object files are different"
+    );
+}
+
+#[test]
+fn test_exec_llvm_ir_with_fast_eval() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = 1".to_string());
+    args.fast_eval = true;
+    args.llvm_ir_dir = Some("ir".to_string());
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+    assert!(
+        err.to_string()
+            .contains("can only be emitted by the LLVM backend"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_link_semaphore_bounds_concurrency() {
+    let semaphore = LinkSemaphore::new(2);
+    let running = AtomicUsize::new(0);
+    let max_running = AtomicUsize::new(0);
+    let linked = AtomicUsize::new(0);
+    // The counting linker records the max number of the concurrent invocations.
+    let counting_linker = || {
+        let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+        max_running.fetch_max(n, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        running.fetch_sub(1, Ordering::SeqCst);
+        linked.fetch_add(1, Ordering::SeqCst);
+    };
+    std::thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| semaphore.run(counting_linker));
+        }
+    });
+    assert_eq!(linked.load(Ordering::SeqCst), 8);
+    let max_running = max_running.load(Ordering::SeqCst);
+    assert!((1..=2).contains(&max_running), "{max_running}");
+    assert_eq!(semaphore.permits(), 2);
+    semaphore.set_permits(0);
+    assert_eq!(semaphore.permits(), 1);
+}
+
+#[test]
+fn test_exec_with_tfjson_format() {
+    let dir = Path::new(&test_case_path()).join("tfjson");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    let tfjson = result.to_format(OutputFormat::TfJson).unwrap();
+    let expected = fs::read_to_string(dir.join("main.tf.json")).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&tfjson).unwrap(),
+        serde_json::from_str::<Value>(&expected).unwrap(),
+        "{tfjson}"
+    );
+    // The output in the Terraform shape is passed through.
+    let passed = to_terraform_json(&tfjson).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&passed).unwrap(),
+        serde_json::from_str::<Value>(&expected).unwrap()
+    );
+    // The keys which do not follow the convention are reported.
+    let err = to_terraform_json(r#"{"web": {"ami": "ami-123"}}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("'web' is not a Terraform block"), "{err}");
+    let err = to_terraform_json(
+        r#"{"resource": {"aws_instance": {"web": {}}}, "web": {"resource_type": "aws_instance"}}"#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.contains("'resource.aws_instance.web' is defined more than once"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_ndjson_format() {
+    let dir = Path::new(&test_case_path()).join("ndjson");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    let ndjson = result.to_format(OutputFormat::NdJson).unwrap();
+    // Every line is an independent JSON value, and the lines round-trip to the stream.
+    let items = ndjson
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<Value>>();
+    assert_eq!(
+        Value::Array(items),
+        serde_json::json!([
+            {"name": "web", "replicas": 2, "labels": {"app": "web"}},
+            {"name": "db", "replicas": 1, "command": ["postgres", "-c", "max_connections=100"]},
+            {"name": "cache", "description": "multi-line\ndescription"},
+        ]),
+        "{ndjson}"
+    );
+    // The elements of a top-level list are output as separate lines.
+    let list = r#"[{"a": 1}, [1, 2], "b", null]"#;
+    let ndjson = to_ndjson(list).unwrap();
+    let items = ndjson
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<Value>>();
+    assert_eq!(
+        Value::Array(items),
+        serde_json::from_str::<Value>(list).unwrap()
+    );
+    // The scalar and object results are output as a single line.
+    let object = r#"{"a": {"b": [1, 2]}, "c": "d"}"#;
+    let ndjson = to_ndjson(object).unwrap();
+    assert_eq!(ndjson.lines().count(), 1, "{ndjson}");
+    assert_eq!(
+        serde_json::from_str::<Value>(&ndjson).unwrap(),
+        serde_json::from_str::<Value>(object).unwrap()
+    );
+    assert_eq!(to_ndjson("1").unwrap(), "1");
+}
+
+#[test]
+fn test_exec_with_root_alias_import() {
+    let dir = Path::new(&test_case_path()).join("root_alias");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        dir.join("app")
+            .join("sub")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    // The root alias resolves identically to the equivalent relative import.
+    let value = serde_json::from_str::<Value>(&result.json_result).unwrap();
+    assert_eq!(value["alias"], value["relative"]);
+    assert_eq!(
+        value["alias"],
+        serde_json::json!({"name": "common", "replicas": 3, "labels": {"app": "nested"}})
+    );
+    // The unknown aliases are reported.
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        dir.join("app")
+            .join("unknown")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("unknown import alias `@mono`, the module root alias is `@root`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_stable_diagnostic_order() {
+    let dir = Path::new(&test_case_path()).join("diagnostic_order");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let errors = (0..5)
+        .map(|_| {
+            exec_program(Arc::new(ParseSession::default()), &args)
+                .unwrap_err()
+                .to_string()
+        })
+        .collect::<Vec<String>>();
+    // The diagnostics are emitted in the same order across runs.
+    assert!(errors.iter().all(|err| err == &errors[0]), "{errors:?}");
+    // The diagnostics are ordered by the file then the position.
+    let positions = [
+        Path::new("diagnostic_order").join(KCL_FILE_NAME),
+        Path::new("pkg_a").join("a.k"),
+        Path::new("pkg_b").join("b.k"),
+    ]
+    .iter()
+    .map(|file| {
+        errors[0]
+            .find(&format!("{}:", file.display()))
+            .unwrap_or_else(|| panic!("{} not found in {}", file.display(), errors[0]))
+    })
+    .collect::<Vec<usize>>();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", errors[0]);
+}
+
+#[test]
+fn test_exec_with_template() {
+    let dir = Path::new(&test_case_path()).join("template");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let template = fs::read_to_string(dir.join("env.hbs")).unwrap();
+    assert_eq!(
+        result.render_template(&template).unwrap(),
+        "# generated by kcl\nAPP_NAME=web & api\nDEBUG=false\nREPLICAS=3\n"
+    );
+    // The undefined template variables are errors.
+    let err = result.render_template("PORT={{env.PORT}}").unwrap_err();
+    assert!(err.to_string().contains("PORT"), "{err}");
+}
+
+#[test]
+fn test_check_determinism() {
+    let dir = Path::new(&test_case_path()).join("determinism");
+    let args = |name: &str| {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list
+            .push(dir.join(name).join(KCL_FILE_NAME).display().to_string());
+        args.fast_eval = true;
+        args
+    };
+    let result =
+        check_determinism(Arc::new(ParseSession::default()), &args("deterministic")).unwrap();
+    assert_eq!(result.err_message, "");
+    assert!(result.yaml_result.contains("tier: frontend"));
+    // The current time differs between the two runs.
+    let err = check_determinism(Arc::new(ParseSession::default()), &args("nondeterministic"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("the evaluation is not deterministic"), "{err}");
+    assert!(err.contains("-generated_at: "), "{err}");
+    assert!(err.contains("+generated_at: "), "{err}");
+}
+
+#[test]
+fn test_exec_with_json_patch() {
+    let dir = Path::new(&test_case_path()).join("json_patch");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    args.json_patch = load_patch_file(dir.join("patch.json")).unwrap();
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        "app:\n  name: web\n  replicas: 3\n  ports:\n  - 8080\n  - 8081\n  - 9090\n  labels:\n    team: platform"
+    );
+    // The invalid patch paths error with the failed operation.
+    for (patch, expected) in [
+        (
+            r#"[{"op": "remove", "path": "/app/image"}]"#,
+            "operation 0 (remove /app/image)",
+        ),
+        (
+            r#"[{"op": "replace", "path": "/app/ports/2", "value": 1}]"#,
+            "the array index '2' is invalid or out of the range [0, 2)",
+        ),
+        (
+            r#"[{"op": "add", "path": "/svc/port", "value": 80}]"#,
+            "the path /svc does not exist",
+        ),
+        (
+            r#"[{"op": "test", "path": "/app/name", "value": "api"}]"#,
+            "does not equal the expected value \"api\"",
+        ),
+    ] {
+        args.json_patch = serde_json::from_str(patch).unwrap();
+        let err = exec_program(Arc::new(ParseSession::default()), &args)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_select_documents() {
+    let dir = Path::new(&test_case_path()).join("select_doc");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let select =
+        |selector: &str| result.select_documents(&DocumentSelector::try_from(selector).unwrap());
+    let selected = select("1").unwrap();
+    assert_eq!(
+        selected.yaml_result,
+        "apiVersion: v1\nkind: Service\nmetadata:\n  name: web"
+    );
+    let document: Value = serde_json::from_str(&selected.json_result).unwrap();
+    assert_eq!(document["kind"], "Service");
+    assert_eq!(document["metadata"]["name"], "web");
+    let selected = select("kind==Service").unwrap();
+    assert_eq!(selected.yaml_result.matches("kind: Service").count(), 2);
+    assert_eq!(selected.json_result.lines().count(), 2);
+    assert!(!selected.yaml_result.contains("Deployment"));
+    let selected = select("spec.replicas==2").unwrap();
+    assert!(selected.yaml_result.starts_with("apiVersion: apps/v1"));
+    // The selector matching nothing is an error.
+    let err = select("3").unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "the document index 3 is out of range, the result has 3 document(s)"
+    );
+    let err = select("kind==Ingress").unwrap_err().to_string();
+    assert_eq!(err, "no document matches the selector kind==Ingress");
+    assert!(DocumentSelector::try_from("kind").is_err());
+    assert!(DocumentSelector::try_from("==Service").is_err());
+}
+
+#[test]
+fn test_exec_check_assertion() {
+    let dir = Path::new(&test_case_path()).join("assert");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    for predicate in [
+        "spec.replicas >= 1",
+        "metadata.name == \"app\" and spec.image.startswith(\"nginx\")",
+        "len(spec) == 2",
+    ] {
+        result.check_assertion(predicate, &args).unwrap();
+    }
+    let err = result
+        .check_assertion("spec.replicas > 3", &args)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(err, "the assertion 'spec.replicas > 3' failed");
+    // The keywords are not bound as the variables.
+    let typed = ExecProgramResult {
+        json_result: "{\"type\": \"Deployment\"}".to_string(),
+        ..Default::default()
+    };
+    let err = typed
+        .check_assertion("type == \"Deployment\"", &args)
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("failed to evaluate the assertion"), "{err}");
+    // Every document of a stream result is checked.
+    let stream = ExecProgramResult {
+        json_result: "{\"replicas\": 1}\n{\"replicas\": 0}".to_string(),
+        ..Default::default()
+    };
+    let err = stream
+        .check_assertion("replicas >= 1", &args)
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "the assertion 'replicas >= 1' failed on the document 1"
+    );
+    // The predicate can not escape the lambda body of the assertion program.
+    for predicate in ["true\n}\nx = 1", "true; x = 1", "true }", "x = 1"] {
+        let err = result
+            .check_assertion(predicate, &args)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("failed to evaluate the assertion"), "{err}");
+    }
+    // The limits of the run apply to the predicate.
+    let mut limited = args.clone();
+    limited.max_output_bytes = 1;
+    let err = result
+        .check_assertion("spec.replicas >= 1", &limited)
+        .unwrap_err()
+        .to_string();
+    assert!(err.starts_with("failed to evaluate the assertion"), "{err}");
+}
+
+#[test]
+fn test_exec_topo_sort_documents() {
+    let dir = Path::new(&test_case_path()).join("topo_sort");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(dir.join(KCL_FILE_NAME).display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let sorted = result.topo_sort_documents("dependsOn").unwrap();
+    let names = |result: &ExecProgramResult| {
+        result
+            .json_result
+            .lines()
+            .map(|document| serde_json::from_str::<Value>(document).unwrap()["name"].clone())
+            .collect::<Vec<Value>>()
+    };
+    // The referenced documents are output before the referencing ones.
+    assert_eq!(names(&sorted), vec!["db", "api", "config", "app"]);
+    assert_eq!(
+        sorted.yaml_result.split("\n---\n").next().unwrap(),
+        "name: db"
+    );
+    // The documents without references keep their order.
+    let sorted = result.topo_sort_documents("notFound").unwrap();
+    assert_eq!(names(&sorted), vec!["app", "api", "db", "config"]);
+    // The reference cycle is reported with the cycle path.
+    let cyclic = ExecProgramResult {
+        json_result: [
+            r#"{"name": "a", "deps": ["b"]}"#,
+            r#"{"name": "b", "deps": "c"}"#,
+            r#"{"name": "c", "deps": "a"}"#,
+        ]
+        .join("\n"),
+        yaml_result: ["name: a", "name: b", "name: c"].join("\n---\n"),
+        ..Default::default()
+    };
+    let err = cyclic.topo_sort_documents("deps").unwrap_err().to_string();
+    assert_eq!(err, "the documents have a reference cycle a -> b -> c -> a");
+    let err = result.topo_sort_documents("name").unwrap_err().to_string();
+    assert_eq!(err, "the documents have a reference cycle app -> app");
+}
+
+#[test]
+fn test_exec_with_units() {
+    let kcl_path = Path::new(&test_case_path())
+        .join("units")
+        .join(KCL_FILE_NAME);
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(kcl_path.display().to_string());
+    args.fast_eval = true;
+    // The units are expanded by default.
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let value: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(value["resources"]["memory"].as_f64(), Some(1048576.0));
+    assert_eq!(value["resources"]["cpu"].as_f64(), Some(0.5));
+    assert_eq!(
+        value["resources"]["storage"][0].as_f64(),
+        Some(10737418240.0)
+    );
+    args.units = UnitsFormat::Suffixed;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        "resources:\n  memory: 1Mi\n  cpu: 500m\n  storage:\n  - 10Gi\nreplicas: 2"
+    );
+    let value: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(value["resources"]["memory"], "1Mi");
+    // The other numbers are not changed.
+    assert_eq!(value["replicas"], 2);
+}
+
+#[test]
+fn test_exec_with_schema_meta() {
+    let kcl_path = Path::new(&test_case_path())
+        .join("schema_meta")
+        .join(KCL_FILE_NAME);
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(kcl_path.display().to_string());
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(!result.yaml_result.contains(SCHEMA_META_ATTR));
+    args.include_schema_meta = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let value: Value = serde_json::from_str(&result.json_result).unwrap();
+    let meta = &value["web"][SCHEMA_META_ATTR];
+    assert_eq!(meta["name"], "__main__.Service");
+    assert!(
+        meta["filename"].as_str().unwrap().ends_with(KCL_FILE_NAME),
+        "{meta}"
+    );
+    assert_eq!(meta["line"], 5);
+    // Only the schema instances have the metadata.
+    assert!(value["labels"].get(SCHEMA_META_ATTR).is_none());
+}
+
+#[test]
+fn test_exec_result_digest() {
+    let result = |json_result: &str| ExecProgramResult {
+        json_result: json_result.to_string(),
+        ..Default::default()
+    };
+    let digest = result(r#"{"a": 1, "b": {"c": [1, 2], "d": "e"}}"#)
+        .digest()
+        .unwrap();
+    assert_eq!(digest.len(), 64);
+    // The reordered but equal outputs have the same digest.
+    assert_eq!(
+        result(r#"{"b": {"d": "e", "c": [1, 2]}, "a": 1}"#)
+            .digest()
+            .unwrap(),
+        digest
+    );
+    // The list order and the values change the digest.
+    assert_ne!(
+        result(r#"{"a": 1, "b": {"c": [2, 1], "d": "e"}}"#)
+            .digest()
+            .unwrap(),
+        digest
+    );
+    assert_ne!(
+        result(r#"{"a": 2, "b": {"c": [1, 2], "d": "e"}}"#)
+            .digest()
+            .unwrap(),
+        digest
+    );
+    // The digest of the evaluated output is stable across runs.
+    let mut args = ExecProgramArgs::default();
+    args.k_code_list = vec!["b = {d = 1, c = 2}\na = 1".to_string()];
+    args.k_filename_list = vec![KCL_FILE_NAME.to_string()];
+    args.fast_eval = true;
+    let evaluated = exec_program(Arc::new(ParseSession::default()), &args)
+        .unwrap()
+        .digest()
+        .unwrap();
+    assert_eq!(
+        evaluated,
+        result(r#"{"a": 1, "b": {"c": 2, "d": 1}}"#)
+            .digest()
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_temp_entry_file_in_custom_temp_dir() {
+    let custom_dir = tempdir().unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.temp_dir = Some(custom_dir.path().join("intermediates"));
+    args.temp_prefix = Some("kcl_test_".to_string());
+    let (temp_dir, entry_file) = temp_entry_file(&args).unwrap();
+    let entry_file = Path::new(&entry_file);
+    assert_eq!(entry_file.parent().unwrap(), temp_dir.path());
+    assert_eq!(
+        temp_dir.path().parent().unwrap(),
+        custom_dir.path().join("intermediates")
+    );
+    for path in [temp_dir.path(), entry_file] {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("kcl_test_"), "{name}");
+    }
+    assert!(temp_dir.path().is_dir());
+    let temp_dir_path = temp_dir.path().to_path_buf();
+    drop(temp_dir);
+    assert!(!temp_dir_path.exists());
+}
+
+#[test]
+fn test_check_sandbox_path() {
+    let sandbox = tempdir().unwrap();
+    let mut args = ExecProgramArgs::default();
+    // No path is checked without the sandbox root.
+    args.check_sandbox_path("../escaped").unwrap();
+    args.sandbox_root = Some(sandbox.path().to_path_buf());
+    for path in [
+        sandbox.path().join("out.yaml"),
+        sandbox.path().join("a").join("..").join("cache"),
+    ] {
+        args.check_sandbox_path(&path).unwrap();
+    }
+    for path in [
+        sandbox.path().join("..").join("out.yaml"),
+        sandbox.path().join("a").join("..").join("..").join("cache"),
+        std::env::temp_dir(),
+    ] {
+        let err = args.check_sandbox_path(&path).unwrap_err().to_string();
+        assert!(err.contains("outside the sandbox root"), "{err}");
+    }
+    // The temp files are also confined to the sandbox root.
+    assert!(temp_entry_file(&args).is_err());
+    args.temp_dir = Some(sandbox.path().join("tmp"));
+    temp_entry_file(&args).unwrap();
+}
+
+#[test]
+fn test_exec_with_sandbox_root_dump_to_file() {
+    let sandbox = tempdir().unwrap();
+    let exec = |code: String| {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list.push("main.k".to_string());
+        args.k_code_list.push(code);
+        args.fast_eval = true;
+        args.sandbox_root = Some(sandbox.path().to_path_buf());
+        match exec_program(Arc::new(ParseSession::default()), &args) {
+            Ok(result) => result.err_message,
+            Err(err) => err.to_string(),
+        }
+    };
+    // The files inside the sandbox root are written by the builtins.
+    let inside = sandbox.path().join("out.yaml").display().to_string();
+    let err = exec(format!(
+        "import yaml\n_a = yaml.dump_to_file({{a = 1}}, r\"{}\")",
+        inside
+    ));
+    assert_eq!(err, "");
+    assert!(std::fs::read_to_string(&inside).unwrap().contains("a: 1"));
+    // The files outside the sandbox root are rejected.
+    let escaped = sandbox.path().join("..").join("escape.json");
+    for code in [
+        "import yaml\n_a = yaml.dump_to_file({a = 1}, \"../escape.yaml\")".to_string(),
+        format!(
+            "import json\n_a = json.dump_to_file({{a = 1}}, r\"{}\")",
+            escaped.display()
+        ),
+    ] {
+        let err = exec(code);
+        assert!(err.contains("is outside the sandbox root"), "{err}");
+    }
+    assert!(!Path::new("../escape.yaml").exists());
+    assert!(!escaped.exists());
+}
+
+#[test]
+fn test_exec_with_features() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("features")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(result.yaml_result, "env: dev\nreplicas: 1\nfeatures: []");
+    // The features are not bound without the enabled features, thus the program
+    // is free to use the argument of the same name.
+    assert!(args.option_args().is_empty());
+    args.args.push(ast::CmdArgSpec {
+        name: FEATURES_OPTION_KEY.to_string(),
+        value: "\"custom\"".to_string(),
+    });
+    assert_eq!(args.option_args(), args.args);
+    args.args.clear();
+    args.features = vec!["tracing".to_string(), "prod".to_string()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        "env: prod\nreplicas: 3\nfeatures:\n- prod\n- tracing"
+    );
+}
+
+#[test]
+fn test_exec_with_duplicate_key_policy() {
+    let exec = |case: &str, policy: DuplicateKeyPolicy| {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list.push(
+            Path::new(&test_case_path())
+                .join(case)
+                .join(KCL_FILE_NAME)
+                .display()
+                .to_string(),
+        );
+        args.fast_eval = true;
+        args.duplicate_key_policy = policy;
+        match exec_program(Arc::new(ParseSession::default()), &args) {
+            Ok(result) if result.err_message.is_empty() => Ok(result.yaml_result),
+            Ok(result) => Err(result.err_message),
+            Err(err) => Err(err.to_string()),
+        }
+    };
+    // The latter entry overrides the former one by default.
+    assert_eq!(
+        exec("duplicate_key", DuplicateKeyPolicy::default()).unwrap(),
+        "config:\n  labels:\n    tier: frontend\n  replicas: 1\n  ports:\n  - 80\n  - 443"
+    );
+    assert_eq!(
+        exec("duplicate_key", DuplicateKeyPolicy::Merge).unwrap(),
+        "config:\n  labels:\n    app: web\n    tier: frontend\n  replicas: 1\n  ports:\n  - 80\n  - 443"
+    );
+    // The list insertions with `+=` are not conflicts.
+    let err = exec("duplicate_key", DuplicateKeyPolicy::Error).unwrap_err();
+    assert!(err.contains("duplicate key 'labels' is set at"), "{err}");
+    assert!(err.contains("main.k:2:5 and"), "{err}");
+    assert!(err.contains("main.k:5:5"), "{err}");
+    // The keys set by the entries of different configs are found when the configs
+    // are merged, and the former config is not changed.
+    let merged = exec("duplicate_key_union", DuplicateKeyPolicy::Merge).unwrap();
+    assert!(
+        merged.contains("config:\n  labels:\n    app: web\n    tier: frontend\n"),
+        "{merged}"
+    );
+    assert!(
+        merged.starts_with("base:\n  labels:\n    app: web\n  replicas: 1\n"),
+        "{merged}"
+    );
+    let err = exec("duplicate_key_union", DuplicateKeyPolicy::Error).unwrap_err();
+    assert!(err.contains("duplicate key 'labels' is set at"), "{err}");
+    assert!(err.contains("main.k:1:9 and"), "{err}");
+    assert!(err.contains("main.k:2:18"), "{err}");
+    assert!(exec("duplicate_key_union", DuplicateKeyPolicy::default()).is_ok());
+    // The policy other than the default one is only applied by the fast evaluator.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list.push("main.k".to_string());
+        args.k_code_list.push("a = 1".to_string());
+        args.duplicate_key_policy = DuplicateKeyPolicy::Error;
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains("duplicate key policy"), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_with_metrics() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("metrics")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.metrics().unwrap(), None);
+    args.collect_metrics = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    let metrics = result.metrics().unwrap().unwrap();
+    assert_eq!(metrics.schema_instances, 3);
+    // The top-level dict, `app`, `app.ports` and the port dicts.
+    assert_eq!(metrics.max_nesting_depth, 4);
+    assert_eq!(metrics.output_bytes, result.yaml_result.len());
+    // The counters are deterministic.
+    let rerun = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(rerun.metrics().unwrap(), Some(metrics));
+    // The steps and calls of a program without schemas.
+    args.k_filename_list = vec![Path::new(&test_case_path())
+        .join("metrics_calls")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.yaml_result, "a: 1\nb: 2");
+    assert_eq!(
+        result.metrics().unwrap(),
+        Some(ProgramMetrics {
+            schema_instances: 0,
+            // `add` and `twice`: the statement and the lambda, `a`: the statement and
+            // the number, `b`: the statement, the call, `twice` and `a`, the body of
+            // `twice`: the statement, the call, `add`, `x` and `x`, and the body of
+            // `add`: the statement, the binary expression, `x` and `y`.
+            eval_steps: 19,
+            // The lambda `twice` calls the lambda `add`.
+            max_call_depth: 2,
+            max_nesting_depth: 1,
+            output_bytes: 9,
+        })
+    );
+    // The metrics are only collected by the fast evaluator.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        args.fast_eval = false;
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(
+            err.to_string().contains("metrics can only be collected"),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn test_exec_with_merge_keys() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("merge_key")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    // The lists are unioned by position without the merge keys.
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        concat!(
+            "app:\n",
+            "  containers:\n",
+            "  - name: sidecar\n",
+            "    image: envoy:1.29\n",
+            "    ports:\n",
+            "    - 80\n",
+            "  - name: logger\n",
+            "    image: fluentd:1.16",
+        )
+    );
+    // The containers are matched by name, the unmatched ones are appended.
+    args.merge_keys = vec![parse_merge_key_spec("containers=name").unwrap()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        concat!(
+            "app:\n",
+            "  containers:\n",
+            "  - name: nginx\n",
+            "    image: nginx:1.25\n",
+            "    ports:\n",
+            "    - 80\n",
+            "  - name: sidecar\n",
+            "    image: envoy:1.29\n",
+            "  - name: logger\n",
+            "    image: fluentd:1.16",
+        )
+    );
+    // The path is matched against the trailing keys.
+    args.merge_keys = vec![parse_merge_key_spec("spec.containers=name").unwrap()];
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result
+        .yaml_result
+        .starts_with("app:\n  containers:\n  - name: sidecar\n"));
+    assert!(parse_merge_key_spec("containers").is_err());
+    assert!(parse_merge_key_spec("spec..containers=name").is_err());
+    // The merge keys are only applied by the fast evaluator.
+    if cfg!(feature = "llvm") && std::env::var("KCL_FAST_EVAL").is_err() {
+        args.fast_eval = false;
+        let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+        assert!(matches!(err, RunnerError::Args(_)), "{err:?}");
+        assert!(err.to_string().contains("merge keys"), "{err}");
+    }
+}
+
+#[test]
+fn test_exec_with_value_serializers() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("value_serializer")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    // The schema values are planned as configs by default.
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert!(result
+        .yaml_result
+        .contains("  memory:\n    value: 512\n    unit: Mi\n"));
+    args.value_serializers.register("Quantity", |value| {
+        let amount = value.get_by_key("value").unwrap().as_int();
+        let unit = value.get_by_key("unit").unwrap().as_str();
+        ValueRef::str(&format!("{amount}{unit}"))
+    });
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        "limits:\n  memory: 512Mi\n  cpu: 500m\n  replicas: 2"
+    );
+    assert_eq!(
+        result.json_result,
+        r#"{"limits": {"memory": "512Mi", "cpu": "500m", "replicas": 2}}"#
+    );
+}
+
+#[test]
+fn test_exec_with_data_import() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("data_import")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        concat!(
+            "app:\n",
+            "  name: web\n",
+            "  replicas: 4\n",
+            "  labels:\n",
+            "    tier: frontend\n",
+            "owner: platform",
+        )
+    );
+}
+
+#[test]
+fn test_exec_with_fold_constants() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("fold_constants")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    args.collect_metrics = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    args.fold_constants = true;
+    let folded = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(folded.err_message, "");
+    // The folded program outputs the same result with fewer evaluation steps.
+    assert_eq!(folded.yaml_result, result.yaml_result);
+    assert_eq!(folded.json_result, result.json_result);
+    let steps = result.metrics().unwrap().unwrap().eval_steps;
+    let folded_steps = folded.metrics().unwrap().unwrap().eval_steps;
+    assert!(folded_steps < steps, "{folded_steps} >= {steps}");
+}
+
+#[test]
+fn test_exec_with_deprecated_attr() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("deprecated_attr")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(
+        result.yaml_result,
+        "alice:\n  name: Alice\n  fullName: Alice Smith"
+    );
+    let messages: Vec<&str> = result
+        .warnings
+        .iter()
+        .map(|warning| warning.messages[0].message.as_str())
+        .collect();
+    assert_eq!(messages, vec!["name is deprecated: use fullName instead"]);
+    // The warnings fail the run when they are denied.
+    args.deny_warnings = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("name is deprecated: use fullName instead"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_with_stream_diagnostics() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list
+        .push("a: int = \"s\"\nb: str = 1\nc = d\n".to_string());
+    args.fast_eval = true;
+    let batched = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    // The streamed diagnostics are not repeated in the error, and the run still fails.
+    args.stream_diagnostics = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(matches!(err, RunnerError::Resolve(_)), "{err:?}");
+    assert_eq!(err.to_string(), "aborting due to 3 previous error(s)");
+    assert!(batched.to_string().contains("name 'd' is not defined"));
+    // The warnings are streamed and still returned in the result.
+    args.k_filename_list = vec![Path::new(&test_case_path())
+        .join("deprecated_attr")
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string()];
+    args.k_code_list.clear();
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.err_message, "");
+    assert_eq!(result.warnings.len(), 1);
+    args.deny_warnings = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert_eq!(err.to_string(), "aborting due to 1 previous error(s)");
+}
+
+#[test]
+fn test_explain_plan() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    fs::write(root.join("kcl.mod"), "[package]\nname = \"plan\"\n").unwrap();
+    create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("sub").join("sub.k"), "port = 80\n").unwrap();
+    fs::write(root.join("main.k"), "import sub\n\nport = sub.port\n").unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(root.join("main.k").display().to_string());
+    let plan = explain_plan(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(plan.files, vec!["sub/sub.k", "main.k"]);
+    let pkgs: Vec<(&str, usize)> = plan
+        .packages
+        .iter()
+        .map(|pkg| (pkg.pkgpath.as_str(), pkg.level))
+        .collect();
+    assert_eq!(pkgs, vec![("sub", 0), ("__main__", 1)]);
+    // Nothing is cached yet for the LLVM backend, and the evaluator compiles nothing.
+    let status = if plan.is_compiled() {
+        PackageStatus::Fresh
+    } else {
+        PackageStatus::Evaluated
+    };
+    assert!(plan.packages.iter().all(|pkg| pkg.status == status));
+    assert!(
+        plan.render().contains("[level 0] sub: "),
+        "{}",
+        plan.render()
+    );
+    // The package is a cache hit once its object file is cached, until its sources change.
+    let root = root.display().to_string();
+    let target = env!("KCLVM_DEFAULT_TARGET");
+    let external_pkgs = HashMap::new();
+    let status = || package_cache_status(&root, target, "sub", &external_pkgs, None);
+    assert_eq!(status(), PackageStatus::Fresh);
+    fs::write(Path::new(&root).join("sub.o"), "").unwrap();
+    save_pkg_cache(
+        &root,
+        target,
+        "sub",
+        "./sub.o".to_string(),
+        CacheOption::default(),
+        &external_pkgs,
+    )
+    .unwrap();
+    assert_eq!(status(), PackageStatus::CacheHit);
+    fs::write(Path::new(&root).join("sub").join("sub.k"), "port = 8080\n").unwrap();
+    assert_eq!(status(), PackageStatus::Fresh);
+}
+
+/// Write a program importing the package `sub` into the directory.
+fn write_shared_cache_program(root: &Path) {
+    fs::write(root.join("kcl.mod"), "[package]\nname = \"shared\"\n").unwrap();
+    create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("sub").join("sub.k"), "port = 80\n").unwrap();
+    fs::write(root.join("main.k"), "import sub\n\nport = sub.port\n").unwrap();
+}
+
+#[test]
+fn test_shared_cache_status() {
+    let shared_dir = tempdir().unwrap();
+    let shared_root = shared_dir.path().canonicalize().unwrap();
+    let job_dir = tempdir().unwrap();
+    let job_root = job_dir.path().canonicalize().unwrap();
+    write_shared_cache_program(&shared_root);
+    write_shared_cache_program(&job_root);
+    // Populate the shared cache by the run under its root.
+    let target = env!("KCLVM_DEFAULT_TARGET");
+    let external_pkgs = HashMap::new();
+    fs::write(shared_root.join("sub.o"), "").unwrap();
+    save_pkg_cache(
+        &shared_root.display().to_string(),
+        target,
+        "sub",
+        "./sub.o".to_string(),
+        CacheOption::default(),
+        &external_pkgs,
+    )
+    .unwrap();
+    let job_root = job_root.display().to_string();
+    let status = |shared_cache_dir: Option<&Path>| {
+        package_cache_status(&job_root, target, "sub", &external_pkgs, shared_cache_dir)
+    };
+    assert_eq!(status(None), PackageStatus::Fresh);
+    assert_eq!(status(Some(&shared_root)), PackageStatus::CacheHit);
+    // The shared cache is only valid for the same sources.
+    fs::write(
+        Path::new(&job_root).join("sub").join("sub.k"),
+        "port = 8080\n",
+    )
+    .unwrap();
+    assert_eq!(status(Some(&shared_root)), PackageStatus::Fresh);
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_exec_with_shared_cache() {
+    let shared_dir = tempdir().unwrap();
+    let shared_root = shared_dir.path().canonicalize().unwrap();
+    let job_dir = tempdir().unwrap();
+    let job_root = job_dir.path().canonicalize().unwrap();
+    write_shared_cache_program(&shared_root);
+    write_shared_cache_program(&job_root);
+    let run = |root: &Path, shared_cache_dir: Option<PathBuf>| {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list
+            .push(root.join("main.k").display().to_string());
+        args.shared_cache_dir = shared_cache_dir;
+        exec_program(Arc::new(ParseSession::default()), &args).unwrap()
+    };
+    // The first job compiles the package into the shared cache.
+    assert_eq!(run(&shared_root, None).yaml_result, "port: 80");
+    let result = run(&job_root, Some(shared_root.clone()));
+    assert_eq!(result.yaml_result, "port: 80");
+    // The package is not compiled into the cache of the second job.
+    let cache_dir = crate::assembler::default_cache_dir(&job_root.display().to_string());
+    let compiled: Vec<PathBuf> = fs::read_dir(cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(true, |ext| ext != "lock"))
+        .collect();
+    assert!(compiled.is_empty(), "{compiled:?}");
+}
+
+/// Write a workspace of two configs sharing the package `sub` into the directory.
+fn write_cache_stats_workspace(root: &Path) {
+    fs::write(root.join("kcl.mod"), "[package]\nname = \"stats\"\n").unwrap();
+    create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("sub").join("sub.k"), "port = 80\n").unwrap();
+    for app in ["app1", "app2"] {
+        create_dir_all(root.join(app)).unwrap();
+        fs::write(
+            root.join(app).join("main.k"),
+            "import sub\n\nport = sub.port\n",
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_cache_stats_with_evaluator() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write_cache_stats_workspace(&root);
+    let mut args = ExecProgramArgs::default();
+    args.fast_eval = true;
+    let stats = cache_stats(&root.display().to_string(), &args).unwrap();
+    // The evaluator compiles and caches nothing.
+    assert_eq!(stats.configs.len(), 2);
+    assert_eq!((stats.packages, stats.hits, stats.misses), (0, 0, 0));
+    assert_eq!(stats.hit_ratio, 0.0);
+    let table = stats.render(CacheStatsFormat::Table).unwrap();
+    assert!(
+        table.starts_with("0 package(s) in 2 config(s) with evaluator"),
+        "{table}"
+    );
+    let json: Value = serde_json::from_str(&stats.render(CacheStatsFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["configs"].as_array().unwrap().len(), 2);
+    assert!(cache_stats(&root.join("sub").display().to_string(), &args).is_err());
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_cache_stats() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write_cache_stats_workspace(&root);
+    let root = root.display().to_string();
+    let args = ExecProgramArgs::default();
+    // The package is compiled by the first config and reused by the second one.
+    let first = cache_stats(&root, &args).unwrap();
+    assert_eq!((first.packages, first.hits, first.misses), (2, 1, 1));
+    assert!(first.cache_size > 0);
+    // All the packages are cache hits in the second run.
+    let second = cache_stats(&root, &args).unwrap();
+    assert_eq!((second.packages, second.hits, second.misses), (2, 2, 0));
+    assert_eq!(second.hit_ratio, 1.0);
+}
+
+#[test]
+fn test_gen_lib_counts_cache_hits() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write_shared_cache_program(&root);
+    let root = root.display().to_string();
+    // Populate the cache with an object file of the package.
+    let target = env!("KCLVM_DEFAULT_TARGET");
+    let external_pkgs = HashMap::new();
+    let cache_dir = crate::assembler::default_cache_dir(&root);
+    create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("sub.o"), "").unwrap();
+    let lib_path = cache_dir.join("sub.o").display().to_string();
+    save_pkg_cache(
+        &root,
+        target,
+        "sub",
+        lib_path.replacen(&root, ".", 1),
+        CacheOption::default(),
+        &external_pkgs,
+    )
+    .unwrap();
+    let cache_counter = CacheCounter::default();
+    let generator = PkgLibGenerator {
+        assembler: &KclvmLibAssembler::LLVM,
+        entry_file: "main",
+        target,
+        external_pkgs: &external_pkgs,
+        cache_counter: &cache_counter,
+    };
+    let compile_prog = Program {
+        root: root.clone(),
+        pkgs: HashMap::new(),
+    };
+    // The cached object file is used without compiling the package.
+    let path = generator
+        .gen_lib(
+            "sub",
+            &compile_prog,
+            &IndexMap::new(),
+            &cache_dir,
+            &ExecProgramArgs::default(),
+        )
+        .unwrap();
+    assert_eq!(path, lib_path);
+    assert_eq!(cache_counter.usage(), CacheUsage { hits: 1, misses: 0 });
+}
+
+#[test]
+fn test_cache_export_import() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write_shared_cache_program(&root);
+    let root = root.display().to_string();
+    // Populate the cache with an object file under the cache dir.
+    let target = env!("KCLVM_DEFAULT_TARGET");
+    let external_pkgs = HashMap::new();
+    let cache_dir = crate::assembler::default_cache_dir(&root);
+    create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("sub.o"), "").unwrap();
+    let lib_path = cache_dir.join("sub.o").display().to_string();
+    save_pkg_cache(
+        &root,
+        target,
+        "sub",
+        lib_path.replacen(&root, ".", 1),
+        CacheOption::default(),
+        &external_pkgs,
+    )
+    .unwrap();
+    let status = || package_cache_status(&root, target, "sub", &external_pkgs, None);
+    assert_eq!(status(), PackageStatus::CacheHit);
+    // Export, clean and import the cache.
+    let archive_dir = tempdir().unwrap();
+    let archive = archive_dir.path().join("cache.tar");
+    assert!(export_cache(&root, &archive).unwrap() > 0);
+    // The cache under the program root is archived with the version.
+    let mut entries = tar::Archive::new(File::open(&archive).unwrap());
+    let prefix = Path::new(PROGRAM_CACHE_ENTRY).join(CacheManifest::default().version);
+    for entry in entries.entries().unwrap().skip(1) {
+        let path = entry.unwrap().path().unwrap().to_path_buf();
+        assert!(path.starts_with(&prefix), "{}", path.display());
+    }
+    fs::remove_dir_all(Path::new(&root).join(".kclvm")).unwrap();
+    assert_eq!(status(), PackageStatus::Fresh);
+    assert!(import_cache(&root, &archive).unwrap() > 0);
+    assert_eq!(status(), PackageStatus::CacheHit);
+}
+
+#[test]
+fn test_cache_import_incompatible_version() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().display().to_string();
+    let archive = dir.path().join("cache.tar");
+    let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+    let manifest = serde_json::to_vec(&CacheManifest {
+        version: "0.0.1-0".to_string(),
+    })
+    .unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, CACHE_MANIFEST_FILE, manifest.as_slice())
+        .unwrap();
+    builder.into_inner().unwrap();
+    let err = import_cache(&root, &archive).unwrap_err().to_string();
+    assert!(err.contains("is built by KCL 0.0.1-0"), "{err}");
+    assert!(!Path::new(&root).join(".kclvm").exists());
+}
+
+#[test]
+fn test_cache_import_invalid_entry() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap().display().to_string();
+    let cache_dir = crate::assembler::default_cache_dir(&root);
+    create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("sub.o"), "cached").unwrap();
+    let archive = dir.path().join("cache.tar");
+    let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+    let manifest = CacheManifest::default();
+    let mut append = |name: String, content: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, content).unwrap();
+    };
+    append(
+        CACHE_MANIFEST_FILE.to_string(),
+        &serde_json::to_vec(&manifest).unwrap(),
+    );
+    append(
+        format!("{}/{}/sub.o", PROGRAM_CACHE_ENTRY, manifest.version),
+        b"",
+    );
+    append("unexpected/sub.o".to_string(), b"");
+    builder.into_inner().unwrap();
+    let err = import_cache(&root, &archive).unwrap_err().to_string();
+    assert!(err.contains("unexpected entry unexpected/sub.o"), "{err}");
+    // The existing cache is untouched and the unpacked entries are cleaned up.
+    assert_eq!(
+        fs::read_to_string(cache_dir.join("sub.o")).unwrap(),
+        "cached"
+    );
+    let cache_root = Path::new(&root).join(".kclvm").join("cache");
+    let names: Vec<_> = fs::read_dir(&cache_root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec![std::ffi::OsString::from(&manifest.version)]);
+}
+
+#[cfg(feature = "llvm")]
+#[test]
+fn test_cache_export_import_compiled() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write_shared_cache_program(&root);
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(root.join("main.k").display().to_string());
+    let root = root.display().to_string();
+    exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    let archive_dir = tempdir().unwrap();
+    let archive = archive_dir.path().join("cache.tar");
+    export_cache(&root, &archive).unwrap();
+    fs::remove_dir_all(Path::new(&root).join(".kclvm")).unwrap();
+    import_cache(&root, &archive).unwrap();
+    // All the imported packages are cache hits in the next compilation.
+    let plan = explain_plan(Arc::new(ParseSession::default()), &args).unwrap();
+    let packages: Vec<_> = plan
+        .packages
+        .iter()
+        .filter(|pkg| pkg.pkgpath != kclvm_ast::MAIN_PKG)
+        .collect();
+    assert!(!packages.is_empty());
+    for pkg in packages {
+        assert_eq!(pkg.status, PackageStatus::CacheHit, "{}", pkg.pkgpath);
+    }
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.yaml_result, "port: 80");
+}
+
+#[test]
+fn test_exec_archive() {
+    let dir = Path::new(&test_case_path()).join("archive");
+    let golden = dir.join("bundle.stdout.golden");
+    // The zip archive holds the module at its root and the tarball holds the
+    // module in its top-level directory.
+    for archive in ["bundle.zip", "bundle.tar.gz"] {
+        let mut args = ExecProgramArgs::default();
+        args.k_filename_list
+            .push(dir.join(archive).display().to_string());
+        args.fast_eval = true;
+        let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+        assert!(result.err_message.is_empty(), "{}", result.err_message);
+        let actual = format!("{}\n", result.yaml_result);
+        let expected = read_golden_file(&golden, &actual, is_bless_enabled()).unwrap();
+        assert_eq!(actual, expected, "test case {archive} failed");
+    }
+    // The extracted files are removed on drop.
+    let args = ExecProgramArgs::default();
+    let archive =
+        ExtractedArchive::extract(&dir.join("bundle.zip"), ArchiveKind::Zip, &args).unwrap();
+    let extracted = archive.path().to_path_buf();
+    assert!(extracted.join("kcl.mod").exists());
+    assert_eq!(archive.vendored_pkgs().unwrap()[0].pkg_name, "helper");
+    drop(archive);
+    assert!(!extracted.exists());
+}
+
+#[test]
+fn test_exec_archive_with_invalid_mod_file() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("broken.zip");
+    let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    let options = zip::write::FileOptions::default();
+    writer.start_file("kcl.mod", options).unwrap();
+    writer.write_all(b"[profile\nentries = ").unwrap();
+    writer.start_file("main.k", options).unwrap();
+    writer.write_all(b"a = 1\n").unwrap();
+    writer.finish().unwrap();
+    // The malformed kcl.mod file is reported as an error rather than a panic.
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(path.display().to_string());
+    args.fast_eval = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("failed to load the kcl.mod file of the archive"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_exec_archive_with_sandbox_root() {
+    let sandbox = tempdir().unwrap();
+    let archive = sandbox.path().join("bundle.zip");
+    fs::copy(
+        Path::new(&test_case_path())
+            .join("archive")
+            .join("bundle.zip"),
+        &archive,
+    )
+    .unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(archive.display().to_string());
+    args.fast_eval = true;
+    args.sandbox_root = Some(sandbox.path().to_path_buf());
+    // The archive is not extracted into the temp dir outside the sandbox root.
+    let err = exec_program(Arc::new(ParseSession::default()), &args).unwrap_err();
+    assert!(
+        err.to_string().contains("outside the sandbox root"),
+        "{err}"
+    );
+    // The archive is extracted into the temp dir inside the sandbox root.
+    let temp_dir = sandbox.path().join("tmp");
+    args.temp_dir = Some(temp_dir.clone());
+    args.temp_prefix = Some("kcl_archive".to_string());
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    assert!(!result.yaml_result.is_empty());
+    // The extracted files are removed after the execution.
+    assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_exec_with_emit_version() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push("a = 1\nb = {c = 2}".to_string());
+    args.fast_eval = true;
+    args.emit_version = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    let version = env!("CARGO_PKG_VERSION");
+    assert_eq!(
+        result.yaml_result,
+        format!("{KCL_VERSION_KEY}:\n  version: {version}\n  backend: evaluator\na: 1\nb:\n  c: 2")
+    );
+    let json: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(json[KCL_VERSION_KEY]["version"], version);
+    assert_eq!(json[KCL_VERSION_KEY]["backend"], "evaluator");
+    assert_eq!(json["b"]["c"], 2);
+    // The version is not emitted by default.
+    args.emit_version = false;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(!result.yaml_result.contains(KCL_VERSION_KEY));
+}
+
+#[test]
+fn test_exec_with_collect_check_failures() {
+    let code = r#"
+schema Person:
+    name: str
+    age: int
+
+    check:
+        len(name) > 0, "name must not be empty"
+        age >= 0, "age must not be negative"
+
+alice = Person {name = "", age = -1}
+"#;
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push("main.k".to_string());
+    args.k_code_list.push(code.to_string());
+    args.fast_eval = true;
+    args.collect_check_failures = true;
+    // The raw runtime error holds the structured check failures.
+    let result = eval_with_raw_error(&args);
+    let panic_info: SimplePanicInfo = serde_json::from_str(&result.err_message).unwrap();
+    let failures = panic_info
+        .check_failures
+        .iter()
+        .map(|f| {
+            (
+                f.schema.as_str(),
+                f.attribute.as_str(),
+                f.message.as_str(),
+                f.line,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        vec![
+            ("Person", "name", "name must not be empty", 7),
+            ("Person", "age", "age must not be negative", 8),
+        ]
+    );
+    assert_eq!(panic_info.line, 7);
+    // All the failures are rendered in the error message.
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(
+        result.err_message.contains("name must not be empty"),
+        "{}",
+        result.err_message
+    );
+    assert!(
+        result.err_message.contains("age must not be negative"),
+        "{}",
+        result.err_message
+    );
+    // Stop at the first failed check by default.
+    args.collect_check_failures = false;
+    let result = eval_with_raw_error(&args);
+    let panic_info: SimplePanicInfo = serde_json::from_str(&result.err_message).unwrap();
+    assert!(panic_info.check_failures.is_empty());
+    assert_eq!(panic_info.message, "name must not be empty");
+}
+
+#[test]
+fn test_exec_with_continue_on_error() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("continue_on_error")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.fast_eval = true;
+    args.continue_on_error = true;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    // The failures of all the three instances are reported.
+    let failures = result
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            assert_eq!(diag.level, Level::Error);
+            let msg = &diag.messages[0];
+            (msg.message.as_str(), msg.range.0.line)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        vec![
+            (
+                "Check failed on the condition: replicas must be positive",
+                6
+            );
+            3
+        ]
+    );
+    // The evaluation goes on and the output holds all the instances.
+    let json: Value = serde_json::from_str(&result.json_result).unwrap();
+    assert_eq!(json["web"]["replicas"], 0);
+    assert_eq!(json["api"]["replicas"], -1);
+    assert_eq!(json["worker"]["replicas"], -2);
+    // Abort on the first failed check by default.
+    args.continue_on_error = false;
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert!(result.diagnostics.is_empty());
+    assert!(
+        result.err_message.contains("replicas must be positive"),
+        "{}",
+        result.err_message
+    );
+    assert!(result.json_result.is_empty());
+}
+
+#[test]
+fn test_exec_offline_with_remote_dependency() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("offline")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    args.offline = true;
+    let err = exec_program(Arc::new(ParseSession::default()), &args)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("offline mode"), "{err}");
+    assert!(
+        err.contains("helper (oci://ghcr.io/kcl-lang/helper)"),
+        "{err}"
+    );
+    // The dependency mapped to a local path is not fetched.
+    args.external_pkgs.push(ast::CmdExternalPkgSpec {
+        pkg_name: "helper".to_string(),
+        pkg_path: Path::new(&test_case_path())
+            .join("offline_vendor")
+            .join("helper")
+            .display()
+            .to_string(),
+    });
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(result.yaml_result, "a: helper");
+}
+
+#[test]
+fn test_exec_with_different_args() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("args_result")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    let mut results = vec![];
+    for env in ["dev", "prod"] {
+        args.args = vec![ast::CmdArgSpec {
+            name: "env".to_string(),
+            value: format!("\"{env}\""),
+        }];
+        // The object file of the imported package is cached and shared by the runs.
+        let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+        assert!(result.err_message.is_empty(), "{}", result.err_message);
+        results.push(result.yaml_result);
+    }
+    assert_eq!(results, vec!["env: dev", "env: prod"]);
+}
+
+#[test]
+fn test_compile_pkgs_concurrently() {
+    // The main package imports the independent packages `a`, `b` and `c`.
+    let imports: &[(&str, &[&str])] = &[
+        ("__main__", &["a", "b", "c"]),
+        ("a", &[]),
+        ("b", &[]),
+        ("c", &[]),
+    ];
+    let mut import_names = IndexMap::default();
+    for (pkgpath, pkg_imports) in imports {
+        import_names.insert(
+            format!("{pkgpath}.k"),
+            pkg_imports
+                .iter()
+                .map(|name| (name.to_string(), name.to_string()))
+                .collect::<IndexMap<String, String>>(),
+        );
+    }
+    let mut compile_progs = IndexMap::default();
+    for (pkgpath, _) in imports {
+        let module = Module {
+            filename: format!("{pkgpath}.k"),
+            pkg: pkgpath.to_string(),
+            ..Default::default()
+        };
+        let program = Program {
+            root: String::new(),
+            pkgs: HashMap::from([(pkgpath.to_string(), vec![module])]),
+        };
+        compile_progs.insert(pkgpath.to_string(), (program, import_names.clone(), ()));
+    }
+    for (parallel, expected) in [(false, 1), (true, 3)] {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let lib_paths =
+            compile_pkgs_by_levels(&compile_progs, &IndexMap::default(), parallel, |pkgpath| {
+                let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(n, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(format!("{pkgpath}.o"))
+            })
+            .unwrap();
+        assert_eq!(lib_paths.len(), imports.len());
+        // The independent packages are compiled at the same time.
+        assert_eq!(max_running.into_inner(), expected);
+    }
+}
+
+/// Load and resolve the program of the test case.
+fn resolve_test_case(case: &str) -> (Arc<ParseSession>, Program, ProgramScope) {
+    let file = Path::new(&test_case_path())
+        .join(case)
+        .join(KCL_FILE_NAME)
+        .display()
+        .to_string();
+    let sess = Arc::new(ParseSession::default());
+    let mut program = load_program(sess.clone(), &[&file], None, None)
+        .unwrap()
+        .program;
+    let scope = resolve_program(&mut program);
+    (sess, program, scope)
+}
+
+#[test]
+fn test_compile_pkgs_with_resolve_errors() {
+    // The main package imports the independent packages `broken` and `good`.
+    let (sess, program, scope) = resolve_test_case("pkg_errors");
+    // The resolve errors are grouped by the packages where they occur.
+    let failed_pkgs = crate::pkg_resolve_errors(&sess, &program, &scope).unwrap();
+    assert_eq!(failed_pkgs.keys().collect::<Vec<_>>(), vec!["broken"]);
+    let mut compile_progs = IndexMap::default();
+    for (pkgpath, modules) in &program.pkgs {
+        let compile_prog = Program {
+            root: program.root.clone(),
+            pkgs: HashMap::from([(pkgpath.clone(), modules.clone())]),
+        };
+        compile_progs.insert(pkgpath.clone(), (compile_prog, scope.imports().clone(), ()));
+    }
+    for parallel in [false, true] {
+        let compiled = std::sync::Mutex::new(vec![]);
+        let err = compile_pkgs_by_levels(&compile_progs, &failed_pkgs, parallel, |pkgpath| {
+            compiled.lock().unwrap().push(pkgpath.to_string());
+            Ok(format!("{pkgpath}.o"))
+        })
+        .unwrap_err();
+        // The independent package is compiled, and the broken package reports its
+        // resolve diagnostics instead of aborting the compilation.
+        assert_eq!(compiled.into_inner().unwrap(), vec!["good"]);
+        let errors = err.downcast_ref::<PkgCompileErrors>().unwrap();
+        assert_eq!(errors.lib_paths.keys().collect::<Vec<_>>(), vec!["good"]);
+        let diags = &errors.resolve_errors["broken"];
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].is_error());
+        assert!(diags[0].messages[0].range.0.filename.ends_with("broken.k"));
+        assert!(errors.codegen_errors.is_empty());
+        // The main package depending on the broken package is skipped.
+        assert_eq!(errors.skipped["__main__"], "broken");
+        let err = err.to_string();
+        assert!(
+            err.contains("failed to resolve the package broken: "),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "llvm")]
+fn test_build_program_with_resolve_errors() {
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list.push(
+        Path::new(&test_case_path())
+            .join("pkg_errors")
+            .join(KCL_FILE_NAME)
+            .display()
+            .to_string(),
+    );
+    let err =
+        crate::build_program(Arc::new(ParseSession::default()), &args, None::<&str>).unwrap_err();
+    let errors = err.downcast_ref::<PkgCompileErrors>().unwrap();
+    // The object file of the independent package is built.
+    assert!(Path::new(&errors.lib_paths["good"]).exists());
+    assert!(errors.resolve_errors.contains_key("broken"));
+    assert_eq!(errors.skipped["__main__"], "broken");
+}
+
+#[test]
+fn test_compile_pkgs_with_failed_pkgs() {
+    // The main package imports `a`, `b` and `c`, and `c` imports `a`.
+    let imports: &[(&str, &[&str])] = &[
+        ("__main__", &["a", "b", "c"]),
+        ("a", &[]),
+        ("b", &[]),
+        ("c", &["a"]),
+    ];
+    let mut import_names = IndexMap::default();
+    for (pkgpath, pkg_imports) in imports {
+        import_names.insert(
+            format!("{pkgpath}.k"),
+            pkg_imports
+                .iter()
+                .map(|name| (name.to_string(), name.to_string()))
+                .collect::<IndexMap<String, String>>(),
+        );
+    }
+    let mut compile_progs = IndexMap::default();
+    for (pkgpath, _) in imports {
+        let module = Module {
+            filename: format!("{pkgpath}.k"),
+            pkg: pkgpath.to_string(),
+            ..Default::default()
+        };
+        let program = Program {
+            root: String::new(),
+            pkgs: HashMap::from([(pkgpath.to_string(), vec![module])]),
+        };
+        compile_progs.insert(pkgpath.to_string(), (program, import_names.clone(), ()));
+    }
+    for parallel in [false, true] {
+        let compiled = std::sync::Mutex::new(vec![]);
+        let err =
+            compile_pkgs_by_levels(&compile_progs, &IndexMap::default(), parallel, |pkgpath| {
+                compiled.lock().unwrap().push(pkgpath.to_string());
+                match pkgpath {
+                    "a" => Err(anyhow::anyhow!("invalid code in a")),
+                    "b" => Err(anyhow::anyhow!("invalid code in b")),
+                    _ => Ok(format!("{pkgpath}.o")),
+                }
+            })
+            .unwrap_err()
+            .to_string();
+        // The independent packages are compiled and report their own errors.
+        let mut compiled = compiled.into_inner().unwrap();
+        compiled.sort();
+        assert_eq!(compiled, vec!["a", "b"]);
+        assert!(
+            err.contains("failed to compile the package a: invalid code in a"),
+            "{err}"
+        );
+        assert!(
+            err.contains("failed to compile the package b: invalid code in b"),
+            "{err}"
+        );
+        // The packages depending on the failed packages are skipped.
+        assert!(
+            err.contains(
+                "note: the package c is skipped because it depends on the failed package a"
+            ),
+            "{err}"
+        );
+        assert!(
+            err.contains(
+                "note: the package __main__ is skipped because it depends on the failed package a"
+            ),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Record the KCL version and the backend that produced the output for the
+//! reproducibility audits. It adds the top-level key `__kcl_version__` into the
+//! output, which is enabled by `emit_version` and is off by default.
+use anyhow::{bail, Result};
+use kclvm_runtime::{Context, ValueRef, JSON_STREAM_SEP};
+
+/// The top-level key of the version annotation.
+pub const KCL_VERSION_KEY: &str = "__kcl_version__";
+
+/// Returns the version annotation holding the crate version and the backend name.
+pub fn version_annotation(backend: &str) -> ValueRef {
+    ValueRef::dict(Some(&[
+        ("version", &ValueRef::str(env!("CARGO_PKG_VERSION"))),
+        ("backend", &ValueRef::str(backend)),
+    ]))
+}
+
+/// Add the version annotation into the YAML and JSON results. The annotation is
+/// the first key of the first document when the results are streams.
+pub fn render_version(
+    yaml_result: &str,
+    json_result: &str,
+    backend: &str,
+) -> Result<(String, String)> {
+    let annotation = ValueRef::dict(Some(&[(KCL_VERSION_KEY, &version_annotation(backend))]));
+    let annotation_yaml = annotation
+        .to_yaml_string()
+        .trim_end_matches('\n')
+        .to_string();
+    if json_result.trim().is_empty() {
+        return Ok((annotation_yaml, annotation.to_json_string()));
+    }
+    let (first, rest) = match json_result.split_once(JSON_STREAM_SEP) {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (json_result, None),
+    };
+    let mut ctx = Context::new();
+    let value = ValueRef::from_json(&mut ctx, first)?;
+    if !value.is_dict() {
+        bail!("the version can only be emitted into the output of a top-level object");
+    }
+    let mut dict = annotation.deep_copy();
+    for (key, item) in value.as_dict_ref().values.iter() {
+        dict.dict_update_key_value(key, item.clone());
+    }
+    let mut json = dict.to_json_string();
+    if let Some(rest) = rest {
+        json.push_str(JSON_STREAM_SEP);
+        json.push_str(rest);
+    }
+    // Keep the original YAML encoding of the first document, the empty object
+    // is replaced by the annotation.
+    let yaml = if value.as_dict_ref().values.is_empty() {
+        format!(
+            "{}{}",
+            annotation_yaml,
+            yaml_result.strip_prefix("{}").unwrap_or(yaml_result)
+        )
+    } else {
+        format!("{}\n{}", annotation_yaml, yaml_result)
+    };
+    Ok((yaml, json))
+}
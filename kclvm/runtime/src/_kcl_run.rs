@@ -44,7 +44,7 @@ pub struct RuntimePanicRecord {
     pub rust_col: i32,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct FFIRunOptions {
     pub strict_range_check: i32,
@@ -55,13 +55,27 @@ pub struct FFIRunOptions {
     pub sort_keys: i32,
     pub include_schema_type_path: i32,
     pub disable_empty_list: i32,
+    /// 1 denotes YAML 1.1 and 0 denotes YAML 1.2.
+    pub yaml_spec: i32,
+    pub include_schema_meta: i32,
+    /// 1 denotes the suffixed units and 0 denotes the expanded units.
+    pub units: i32,
+    /// The max bytes of each of the JSON and YAML results, 0 denotes no limit.
+    pub max_output_bytes: u64,
+    /// The sandbox root of the files written by the builtins, null denotes no sandbox.
+    pub sandbox_root: *const kclvm_char_t,
 }
 
 thread_local! {
     static KCL_RUNTIME_PANIC_RECORD: std::cell::RefCell<RuntimePanicRecord>  = std::cell::RefCell::new(RuntimePanicRecord::default())
 }
 
-fn new_ctx_with_opts(opts: FFIRunOptions, path_selector: &[String]) -> Context {
+fn new_ctx_with_opts(
+    opts: FFIRunOptions,
+    path_selector: &[String],
+    include_keys: &[String],
+    exclude_keys: &[String],
+) -> Context {
     let mut ctx = Context::new();
     // Config
     ctx.cfg.strict_range_check = opts.strict_range_check != 0;
@@ -72,8 +86,25 @@ fn new_ctx_with_opts(opts: FFIRunOptions, path_selector: &[String]) -> Context {
     ctx.plan_opts.show_hidden = opts.show_hidden != 0;
     ctx.plan_opts.sort_keys = opts.sort_keys != 0;
     ctx.plan_opts.include_schema_type_path = opts.include_schema_type_path != 0;
+    ctx.plan_opts.include_schema_meta = opts.include_schema_meta != 0;
     ctx.plan_opts.disable_empty_list = opts.disable_empty_list != 0;
     ctx.plan_opts.query_paths = path_selector.to_vec();
+    ctx.plan_opts.include_keys = include_keys.to_vec();
+    ctx.plan_opts.exclude_keys = exclude_keys.to_vec();
+    ctx.plan_opts.yaml_spec = if opts.yaml_spec == 1 {
+        YamlSpec::Yaml11
+    } else {
+        YamlSpec::Yaml12
+    };
+    ctx.plan_opts.units = if opts.units == 1 {
+        UnitsFormat::Suffixed
+    } else {
+        UnitsFormat::Expanded
+    };
+    ctx.plan_opts.max_output_bytes = opts.max_output_bytes as usize;
+    if !opts.sandbox_root.is_null() {
+        ctx.cfg.sandbox_root = Some(c2str(opts.sandbox_root).into());
+    }
     ctx
 }
 
@@ -86,6 +117,8 @@ pub unsafe extern "C" fn _kcl_run(
     option_values: *const *const kclvm_char_t,
     opts: FFIRunOptions,
     path_selector: *const *const kclvm_char_t,
+    include_keys: *const *const kclvm_char_t,
+    exclude_keys: *const *const kclvm_char_t,
     json_result_buffer_len: *mut kclvm_size_t,
     json_result_buffer: *mut kclvm_char_t,
     yaml_result_buffer_len: *mut kclvm_size_t,
@@ -96,7 +129,13 @@ pub unsafe extern "C" fn _kcl_run(
     log_buffer: *mut kclvm_char_t,
 ) -> kclvm_size_t {
     // Init runtime context with options
-    let ctx = Box::new(new_ctx_with_opts(opts, &c2str_vec(path_selector))).into_raw();
+    let ctx = Box::new(new_ctx_with_opts(
+        opts,
+        &c2str_vec(path_selector),
+        &c2str_vec(include_keys),
+        &c2str_vec(exclude_keys),
+    ))
+    .into_raw();
     let option_keys = std::slice::from_raw_parts(option_keys, option_len as usize);
     let option_values = std::slice::from_raw_parts(option_values, option_len as usize);
     for i in 0..(option_len as usize) {
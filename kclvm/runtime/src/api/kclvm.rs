@@ -224,6 +224,9 @@ pub struct ListValue {
     pub values: Vec<ValueRef>,
 }
 
+/// The file name, line and column of a config entry.
+pub type ConfigEntryLocation = (String, u64, u64);
+
 #[derive(PartialEq, Eq, Clone, Default, Debug)]
 pub struct DictValue {
     pub values: IndexMap<String, ValueRef>,
@@ -232,6 +235,10 @@ pub struct DictValue {
     pub attr_map: IndexMap<String, String>,
     // The runtime dict to schema reflect type string.
     pub potential_schema: Option<String>,
+    /// The locations of the config entries which set the keys first, which are
+    /// only recorded by the evaluator with the duplicate key policies except
+    /// [DuplicateKeyPolicy::LastWins].
+    pub key_locations: IndexMap<String, ConfigEntryLocation>,
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
@@ -303,13 +310,149 @@ pub struct PanicInfo {
     pub message: String,
     pub err_type_code: i32,
     pub is_warning: bool,
+
+    // Only for the failed checks collected in the collect all mode
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub check_failures: Vec<CheckFailure>,
+}
+
+/// A failed schema check or assert statement, which is collected instead of
+/// stopping the evaluation when the check failures are collected.
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct CheckFailure {
+    /// The schema or rule name of the check, empty for the assert statements
+    /// outside the schemas and rules.
+    pub schema: String,
+    /// The schema attribute referenced by the check expression, or the first
+    /// name of the expression when it references no attributes.
+    pub attribute: String,
+    pub message: String,
+    pub filename: String,
+    pub line: i32,
+    pub col: i32,
+}
+
+/// The policy when an entry of a config sets the key which is already set by
+/// another entry, e.g., `{a = 1, a = 2}` or `{a = 1} | {a = 2}`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicateKeyPolicy {
+    /// Report the locations of the conflicting entries, the list insertions
+    /// with `+=` are not conflicts.
+    Error,
+    /// Apply the entries in order with their operators, e.g., the latter `=`
+    /// entry overrides the former value.
+    #[default]
+    LastWins,
+    /// Deep-merge the dict values, and the other values are applied in order.
+    Merge,
+}
+
+/// The merge key of the lists at a config path, the elements of the unioned
+/// lists are matched by the value of the key field instead of the position,
+/// e.g., the containers matched by `name`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeKey {
+    /// The dotted attribute path of the list, e.g., `spec.containers`, which is
+    /// matched against the trailing keys of the union path.
+    pub path: String,
+    /// The key field of the list elements, e.g., `name`.
+    pub key: String,
+}
+
+impl MergeKey {
+    /// Returns whether the path of the merge key is the suffix of the union path.
+    pub fn matches(&self, path: &[String]) -> bool {
+        let keys = self.path.split('.').collect::<Vec<&str>>();
+        keys.len() <= path.len()
+            && path[path.len() - keys.len()..]
+                .iter()
+                .zip(keys)
+                .all(|(k, key)| k == key)
+    }
+}
+
+/// The flag which cancels the evaluation from another thread, e.g., when the
+/// time limit of the command is exceeded, and the clones share the same flag.
+#[derive(Clone, Default)]
+pub struct CancelFlag(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancelFlag {
+    /// Cancel the evaluations which share the flag.
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Returns whether the flag is cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for CancelFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CancelFlag")
+            .field(&self.is_cancelled())
+            .finish()
+    }
 }
 
+/// The flags are compared by their states instead of the shared flag.
+impl PartialEq for CancelFlag {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_cancelled() == other.is_cancelled()
+    }
+}
+
+impl Eq for CancelFlag {}
+
 #[derive(PartialEq, Eq, Clone, Default, Debug)]
 pub struct ContextConfig {
     pub debug_mode: bool,
     pub strict_range_check: bool,
     pub disable_schema_check: bool,
+    /// The max depth of function, schema and rule calls, 0 denotes the default limit.
+    pub max_eval_depth: usize,
+    /// The approximate max memory bytes of evaluation values, 0 denotes no limit.
+    pub max_eval_memory_bytes: usize,
+    /// Collect all the failed checks and asserts into the panic info instead of
+    /// stopping at the first one.
+    pub collect_check_failures: bool,
+    /// Keep the collected check failures in the panic info and return the
+    /// evaluation result instead of the error.
+    pub continue_on_error: bool,
+    /// The policy of the duplicate keys in a config.
+    pub duplicate_key_policy: DuplicateKeyPolicy,
+    /// Gather the evaluation metrics into [Context::eval_metrics].
+    pub collect_metrics: bool,
+    /// The merge keys of the lists which are unioned element by element.
+    pub merge_keys: Vec<MergeKey>,
+    /// The directory which the files written by the builtins, e.g.,
+    /// `yaml.dump_to_file`, are confined to.
+    pub sandbox_root: Option<std::path::PathBuf>,
+    /// The flag which aborts the evaluation at the next statement or expression.
+    pub cancel_flag: CancelFlag,
+}
+
+impl ContextConfig {
+    /// Returns the key field of the first merge key matching the union path.
+    pub fn merge_key(&self, path: &[String]) -> Option<String> {
+        self.merge_keys
+            .iter()
+            .find(|merge_key| merge_key.matches(path))
+            .map(|merge_key| merge_key.key.clone())
+    }
+}
+
+/// The counters gathered during the evaluation when the metrics are collected.
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct EvalMetrics {
+    /// The number of the created schema instances.
+    pub schema_instances: usize,
+    /// The number of the evaluated statements and expressions.
+    pub eval_steps: usize,
+    /// The max depth of the function, schema and rule calls.
+    pub max_call_depth: usize,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -347,6 +490,8 @@ pub struct Context {
     pub instances: IndexMap<String, IndexMap<String, Vec<ValueRef>>>,
     /// All schema types
     pub all_schemas: HashMap<String, SchemaType>,
+    /// The evaluation metrics, which are gathered when [ContextConfig::collect_metrics] is set.
+    pub eval_metrics: EvalMetrics,
     /// Import graph
     pub import_names: IndexMap<String, IndexMap<String, String>>,
 
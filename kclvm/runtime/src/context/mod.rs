@@ -2,6 +2,8 @@
 
 pub mod api;
 pub use api::*;
+pub mod sandbox;
+pub use sandbox::*;
 use std::fmt;
 
 use crate::{BacktraceFrame, PanicInfo, RuntimePanicRecord};
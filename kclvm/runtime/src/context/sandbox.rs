@@ -0,0 +1,60 @@
+//! Confine the files written by the builtins, e.g., `yaml.dump_to_file`, to the
+//! sandbox root of the context, see [crate::ContextConfig::sandbox_root].
+//!
+//! The written path is resolved component by component, the symlinks of the
+//! existing components are followed and the `..` components are applied, so
+//! the crafted paths can not escape the root.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::Context;
+
+/// Resolve the path against the current dir and returns it when it is inside the
+/// sandbox root, otherwise returns the error message. The path may not exist yet.
+pub fn resolve_in_sandbox(root: &Path, path: &Path) -> Result<PathBuf, String> {
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|_| format!("the sandbox root {} is not found", root.display()))?;
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|err| err.to_string())?
+            .join(path)
+    };
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => {
+                resolved.push(component);
+                if let Ok(canonical) = resolved.canonicalize() {
+                    resolved = canonical;
+                }
+            }
+        }
+    }
+    if !resolved.starts_with(&canonical_root) {
+        return Err(format!(
+            "the path {} is outside the sandbox root {}",
+            path.display(),
+            root.display()
+        ));
+    }
+    Ok(resolved)
+}
+
+impl Context {
+    /// Panic when the file path written by a builtin is outside the sandbox root
+    /// of the context.
+    pub fn check_sandbox_path(&self, path: &str) {
+        if let Some(root) = &self.cfg.sandbox_root {
+            if let Err(err) = resolve_in_sandbox(root, Path::new(path)) {
+                panic!("Unable to write file '{}': {}", path, err);
+            }
+        }
+    }
+}
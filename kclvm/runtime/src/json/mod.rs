@@ -77,6 +77,7 @@ pub extern "C" fn kclvm_json_dump_to_file(
     match (data, filename) {
         (Some(data), Some(filename)) => {
             let filename = filename.as_str();
+            ptr_as_ref(ctx).check_sandbox_path(&filename);
             let json = data.to_json_string_with_options(&kwargs_to_opts(kwargs));
             std::fs::write(&filename, json)
                 .unwrap_or_else(|e| panic!("Unable to write file '{}': {}", filename, e));
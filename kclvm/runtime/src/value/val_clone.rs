@@ -65,6 +65,7 @@ impl ValueRef {
                     );
                 }
                 dict.set_potential_schema_type(&v.potential_schema.clone().unwrap_or_default());
+                dict.as_dict_mut_ref().key_locations = v.key_locations.clone();
                 dict
             }
             Value::schema_value(ref v) => {
@@ -89,6 +90,7 @@ impl ValueRef {
                         dict.update_attr_map(key, type_str);
                     }
                 }
+                dict.as_dict_mut_ref().key_locations = v.config.key_locations.clone();
                 return ValueRef {
                     rc: Rc::new(RefCell::new(Value::schema_value(Box::new(SchemaValue {
                         name: v.name.clone(),
@@ -26,6 +26,52 @@ pub struct JsonEncodeOptions {
     pub ignore_none: bool,
 }
 
+/// The error of the serialized output exceeding the max output bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputSizeError {
+    pub max_bytes: usize,
+}
+
+impl std::fmt::Display for OutputSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the max output size {} bytes is exceeded",
+            self.max_bytes
+        )
+    }
+}
+
+impl std::error::Error for OutputSizeError {}
+
+/// The writer which fails once more than `max_bytes` bytes are written, which
+/// aborts the serialization, and 0 denotes no limit.
+struct LimitedWriter {
+    buf: Vec<u8>,
+    max_bytes: usize,
+    exceeded: bool,
+}
+
+impl std::io::Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if self.max_bytes > 0 && self.buf.len() + data.len() > self.max_bytes {
+            self.exceeded = true;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                OutputSizeError {
+                    max_bytes: self.max_bytes,
+                },
+            ));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 struct JsonFormatter {
     current_indent: usize,
     has_value: bool,
@@ -420,12 +466,33 @@ impl ValueRef {
     }
 
     pub fn to_json_string_with_options(&self, opt: &JsonEncodeOptions) -> String {
+        self.to_json_string_with_limit(opt, 0)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Serialize the value like [ValueRef::to_json_string_with_options], and the
+    /// serialization is aborted once the output exceeds `max_bytes` bytes instead
+    /// of materializing the whole output, where 0 denotes no limit.
+    pub fn to_json_string_with_limit(
+        &self,
+        opt: &JsonEncodeOptions,
+        max_bytes: usize,
+    ) -> Result<String, OutputSizeError> {
         let json = self.build_json(opt);
         let formatter = JsonFormatter::with_indent(opt.indent);
-        let mut writer = Vec::with_capacity(128);
+        let mut writer = LimitedWriter {
+            buf: Vec::with_capacity(128),
+            max_bytes,
+            exceeded: false,
+        };
         let mut serializer = serde_json::Serializer::with_formatter(&mut writer, formatter);
-        json.serialize(&mut serializer).unwrap();
-        writer.to_str().unwrap().to_string()
+        if let Err(err) = json.serialize(&mut serializer) {
+            if writer.exceeded {
+                return Err(OutputSizeError { max_bytes });
+            }
+            panic!("{}", err);
+        }
+        Ok(writer.buf.to_str().unwrap().to_string())
     }
 
     pub fn to_json_string_with_null(&self) -> String {
@@ -1,10 +1,18 @@
 //! Copyright The KCL Authors. All rights reserved.
 
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
 use crate::*;
 
 pub const KCL_PRIVATE_VAR_PREFIX: &str = "_";
 const LIST_DICT_TEMP_KEY: &str = "$";
 const SCHEMA_TYPE_META_ATTR: &str = "_type";
+/// The attribute holding the schema metadata of the schema instance, i.e., the
+/// fully-qualified schema name and the source location of the instance.
+pub const SCHEMA_META_ATTR: &str = "__schema__";
 
 /// PlanOptions denotes the configuration required to execute the KCL
 /// program and the JSON/YAML planning.
@@ -14,6 +22,8 @@ pub struct PlanOptions {
     pub sort_keys: bool,
     /// Emit the `_type` attribute in the schema instance.
     pub include_schema_type_path: bool,
+    /// Emit the [SCHEMA_META_ATTR] attribute in the schema instance.
+    pub include_schema_meta: bool,
     /// Whether to emit hidden attributes that start with `_`
     pub show_hidden: bool,
     /// Whether to emit none value in the plan process.
@@ -22,6 +32,86 @@ pub struct PlanOptions {
     pub disable_empty_list: bool,
     /// Filter planned value with the path selector.
     pub query_paths: Vec<String>,
+    /// The YAML specification which the YAML result is unambiguous under.
+    pub yaml_spec: YamlSpec,
+    /// How the number multiplier values, e.g., `1Mi`, are planned.
+    pub units: UnitsFormat,
+    /// Only keep these top-level keys in the planned value.
+    pub include_keys: Vec<String>,
+    /// Drop these top-level keys in the planned value.
+    pub exclude_keys: Vec<String>,
+    /// The custom serializers of the schema values.
+    pub serializers: ValueSerializers,
+    /// The max bytes of each of the JSON and YAML results, the planning fails
+    /// once the serialized result exceeds it, and 0 denotes no limit.
+    pub max_output_bytes: usize,
+}
+
+/// The output form of the number multiplier values, e.g., `1Mi` and `500m`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitsFormat {
+    /// The expanded numbers, e.g., `1048576` for `1Mi`.
+    #[default]
+    Expanded,
+    /// The strings of the number and the unit suffix, e.g., `1Mi`.
+    Suffixed,
+}
+
+/// The custom serializer of a schema value, which returns the planned value,
+/// e.g., the string `512Mi` of a quantity schema.
+pub type ValueSerializer = Arc<dyn Fn(&ValueRef) -> ValueRef + Send + Sync>;
+
+/// The custom serializers keyed by the schema type name, the schema values
+/// without a registered serializer are planned as configs.
+#[derive(Clone, Default)]
+pub struct ValueSerializers {
+    serializers: IndexMap<String, ValueSerializer>,
+}
+
+impl ValueSerializers {
+    /// Register the serializer of the schema type name, which is the schema name,
+    /// e.g., `Quantity`, or the name qualified by the package path, e.g.,
+    /// `units.Quantity`. The serializer of the qualified name takes precedence.
+    pub fn register<F>(&mut self, name: &str, serializer: F)
+    where
+        F: Fn(&ValueRef) -> ValueRef + Send + Sync + 'static,
+    {
+        self.serializers
+            .insert(name.to_string(), Arc::new(serializer));
+    }
+
+    /// Returns the serializer of the schema value.
+    pub fn get(&self, value: &ValueRef) -> Option<&ValueSerializer> {
+        if self.serializers.is_empty() {
+            return None;
+        }
+        self.serializers
+            .get(&value_type_path(value, true))
+            .or_else(|| self.serializers.get(&value_type_path(value, false)))
+    }
+
+    /// Returns whether no serializer is registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.serializers.is_empty()
+    }
+}
+
+impl fmt::Debug for ValueSerializers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.serializers.keys()).finish()
+    }
+}
+
+impl PartialEq for ValueSerializers {
+    fn eq(&self, other: &Self) -> bool {
+        self.serializers.len() == other.serializers.len()
+            && self
+                .serializers
+                .iter()
+                .zip(&other.serializers)
+                .all(|((k1, f1), (k2, f2))| k1 == k2 && Arc::ptr_eq(f1, f2))
+    }
 }
 
 /// Filter list or config results with context options.
@@ -34,7 +124,7 @@ fn filter_results(ctx: &Context, key_values: &ValueRef) -> Vec<ValueRef> {
             if key_values.is_list_or_config() {
                 results.append(&mut filter_results(ctx, key_values));
             } else if key_values.is_scalar() {
-                results.push(key_values.clone());
+                results.push(plan_scalar(ctx, key_values));
             }
         }
         results
@@ -135,7 +225,7 @@ fn filter_results(ctx: &Context, key_values: &ValueRef) -> Vec<ValueRef> {
                 }
             } else {
                 let result = results.get_mut(0).unwrap();
-                result.dict_update_key_value(key.as_str(), value.clone());
+                result.dict_update_key_value(key.as_str(), plan_scalar(ctx, value));
             }
         }
         results.iter().enumerate().map(|v| v.1).cloned().collect()
@@ -144,7 +234,21 @@ fn filter_results(ctx: &Context, key_values: &ValueRef) -> Vec<ValueRef> {
     }
 }
 
+/// Returns the planned scalar value, the number multiplier value is planned as the
+/// string with the unit suffix in the [UnitsFormat::Suffixed] form.
+fn plan_scalar(ctx: &Context, value: &ValueRef) -> ValueRef {
+    if ctx.plan_opts.units == UnitsFormat::Suffixed && value.is_unit() {
+        let (_, raw, unit) = value.as_unit();
+        ValueRef::str(&format!("{}{}", raw, unit))
+    } else {
+        value.clone()
+    }
+}
+
 fn handle_schema(ctx: &Context, value: &ValueRef) -> Vec<ValueRef> {
+    if let Some(serializer) = ctx.plan_opts.serializers.get(value) {
+        return vec![serializer(value)];
+    }
     let mut filtered = filter_results(ctx, value);
     if filtered.is_empty() {
         return filtered;
@@ -161,9 +265,35 @@ fn handle_schema(ctx: &Context, value: &ValueRef) -> Vec<ValueRef> {
             }
         }
     }
+    if ctx.plan_opts.include_schema_meta {
+        if let Some(v) = filtered.get_mut(0) {
+            if v.is_config() {
+                v.dict_update_key_value(SCHEMA_META_ATTR, schema_meta(value));
+            }
+        }
+    }
     filtered
 }
 
+/// Returns the schema metadata of the schema instance, which holds the source
+/// location when the instance is created by a schema expression.
+fn schema_meta(value: &ValueRef) -> ValueRef {
+    let mut meta = ValueRef::dict(Some(&[(
+        "name",
+        &ValueRef::str(&value_type_path(value, true)),
+    )]));
+    let config_meta = value.schema_config_meta();
+    if let Some(filename) = config_meta.get_by_key(CONFIG_META_FILENAME) {
+        meta.dict_update_key_value("filename", filename);
+        for (meta_key, key) in [(CONFIG_META_LINE, "line"), (CONFIG_META_COLUMN, "column")] {
+            if let Some(v) = config_meta.get_by_key(meta_key) {
+                meta.dict_update_key_value(key, v);
+            }
+        }
+    }
+    meta
+}
+
 /// Returns the type path of the runtime value `v`.
 fn value_type_path(v: &ValueRef, full_name: bool) -> String {
     match v.get_potential_schema_type() {
@@ -191,6 +321,31 @@ fn type_of(v: &ValueRef, full_name: bool) -> String {
     builtin::type_of(v, &ValueRef::bool(full_name)).as_str()
 }
 
+/// Serialize the documents and join them with the separator, where the documents
+/// are serialized with the remaining bytes of `max_bytes`, and 0 denotes no limit.
+/// It panics once the joined output exceeds `max_bytes`.
+fn join_limited<F>(results: &[ValueRef], sep: &str, max_bytes: usize, serialize: F) -> String
+where
+    F: Fn(&ValueRef, usize) -> Result<String, OutputSizeError>,
+{
+    let mut output = String::new();
+    for (i, result) in results.iter().enumerate() {
+        if i > 0 {
+            output.push_str(sep);
+        }
+        let limit = match max_bytes.checked_sub(output.len()) {
+            _ if max_bytes == 0 => 0,
+            Some(limit) if limit > 0 => limit,
+            _ => panic!("{}", OutputSizeError { max_bytes }),
+        };
+        match serialize(result, limit) {
+            Ok(document) => output.push_str(&document),
+            Err(_) => panic!("{}", OutputSizeError { max_bytes }),
+        }
+    }
+    output
+}
+
 impl ValueRef {
     /// Plan the value to JSON and YAML strings.
     pub fn plan(&self, ctx: &Context) -> (String, String) {
@@ -201,44 +356,62 @@ impl ValueRef {
         };
         let yaml_opts = YamlEncodeOptions {
             sort_keys: ctx.plan_opts.sort_keys,
+            yaml_spec: ctx.plan_opts.yaml_spec,
             ..Default::default()
         };
+        // Filter top-level keys
+        let value = self.filter_by_keys(&ctx.plan_opts.include_keys, &ctx.plan_opts.exclude_keys);
         // Filter values with query paths
         let value = if ctx.plan_opts.query_paths.is_empty() {
-            self.clone()
+            value
         } else {
-            self.filter_by_path(&ctx.plan_opts.query_paths)
+            value
+                .filter_by_path(&ctx.plan_opts.query_paths)
                 .unwrap_or_else(|e| panic!("{e}"))
         };
+        let max_bytes = ctx.plan_opts.max_output_bytes;
         if value.is_list_or_config() {
             let results = filter_results(ctx, &value);
             // Plan YAML result
-            let yaml_result = results
-                .iter()
-                .map(|r| {
-                    r.to_yaml_string_with_options(&yaml_opts)
-                        .strip_suffix('\n')
-                        .unwrap()
-                        .to_string()
-                })
-                .collect::<Vec<String>>()
-                .join(YAML_STREAM_SEP);
+            let yaml_result = join_limited(&results, YAML_STREAM_SEP, max_bytes, |r, limit| {
+                // The limit counts the trailing newline which is stripped.
+                let limit = if limit > 0 { limit + 1 } else { 0 };
+                Ok(r.to_yaml_string_with_limit(&yaml_opts, limit)?
+                    .strip_suffix('\n')
+                    .unwrap()
+                    .to_string())
+            });
             // Plan JSON result
-            let json_result = results
-                .iter()
-                .map(|r| r.to_json_string_with_options(&json_opts))
-                .collect::<Vec<String>>()
-                .join(JSON_STREAM_SEP);
+            let json_result = join_limited(&results, JSON_STREAM_SEP, max_bytes, |r, limit| {
+                r.to_json_string_with_limit(&json_opts, limit)
+            });
             (json_result, yaml_result)
         } else {
             (
-                value.to_json_string_with_options(&json_opts),
-                value.to_yaml_string_with_options(&yaml_opts),
+                value
+                    .to_json_string_with_limit(&json_opts, max_bytes)
+                    .unwrap_or_else(|err| panic!("{err}")),
+                value
+                    .to_yaml_string_with_limit(&yaml_opts, max_bytes)
+                    .unwrap_or_else(|err| panic!("{err}")),
             )
         }
     }
 
     /// Filter values using path selectors.
+    /// Keep the top-level keys of the config value in `include_keys` when it is not
+    /// empty, and drop the top-level keys in `exclude_keys`.
+    pub fn filter_by_keys(&self, include_keys: &[String], exclude_keys: &[String]) -> ValueRef {
+        if !self.is_config() || (include_keys.is_empty() && exclude_keys.is_empty()) {
+            return self.clone();
+        }
+        let mut dict = self.as_dict_ref().clone();
+        dict.values.retain(|key, _| {
+            (include_keys.is_empty() || include_keys.contains(key)) && !exclude_keys.contains(key)
+        });
+        ValueRef::from(Value::dict_value(Box::new(dict)))
+    }
+
     pub fn filter_by_path(&self, path_selector: &[String]) -> Result<ValueRef, String> {
         if self.is_config() && !path_selector.is_empty() {
             if path_selector.len() == 1 {
@@ -272,7 +445,9 @@ impl ValueRef {
 
 #[cfg(test)]
 mod test_value_plan {
-    use crate::{schema_runtime_type, Context, PlanOptions, ValueRef, MAIN_PKG_PATH};
+    use crate::{
+        schema_config_meta, schema_runtime_type, Context, PlanOptions, ValueRef, MAIN_PKG_PATH,
+    };
 
     use super::filter_results;
 
@@ -391,4 +566,55 @@ mod test_value_plan {
         let (_, yaml_string) = config.plan(&ctx);
         assert_eq!(yaml_string, "{}");
     }
+
+    #[test]
+    fn test_value_plan_with_schema_meta() {
+        let mut ctx = Context::new();
+        ctx.plan_opts.include_schema_meta = true;
+        let mut config = ValueRef::dict(None);
+        config.dict_update_key_value("data", get_test_schema_value());
+        let (_, yaml_string) = config.plan(&ctx);
+        assert_eq!(yaml_string, "data:\n  __schema__:\n    name: __main__.Data");
+        // The schema instance created by a schema expression has the source location.
+        let mut schema = ValueRef::dict_int(&[("a", 1)]).dict_to_schema(
+            TEST_SCHEMA_NAME,
+            MAIN_PKG_PATH,
+            &[],
+            &schema_config_meta("main.k", 3, 7),
+            &ValueRef::dict(None),
+            None,
+            None,
+        );
+        schema.set_potential_schema_type(&schema_runtime_type(TEST_SCHEMA_NAME, MAIN_PKG_PATH));
+        config.dict_update_key_value("data", schema);
+        let (_, yaml_string) = config.plan(&ctx);
+        assert_eq!(
+            yaml_string,
+            "data:\n  a: 1\n  __schema__:\n    name: __main__.Data\n    filename: main.k\n    line: 3\n    column: 7"
+        );
+    }
+
+    #[test]
+    fn test_value_plan_with_max_output_bytes() {
+        let mut ctx = Context::new();
+        let config = ValueRef::dict_int(&[("a", 1), ("b", 2)]);
+        ctx.plan_opts.max_output_bytes = 16;
+        assert_eq!(
+            config.plan(&ctx),
+            ("{\"a\": 1, \"b\": 2}".to_string(), "a: 1\nb: 2".to_string())
+        );
+        ctx.plan_opts.max_output_bytes = 8;
+        let plan = |value: &ValueRef, ctx: &Context| {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| value.plan(ctx)))
+        };
+        let err = plan(&config, &ctx).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<String>().unwrap(),
+            "the max output size 8 bytes is exceeded"
+        );
+        // The limit applies to the whole stream of the documents.
+        let stream = ValueRef::list_value(Some(&[config.clone(), config.clone()]));
+        ctx.plan_opts.max_output_bytes = 20;
+        assert!(plan(&stream, &ctx).is_err());
+    }
 }
@@ -11,6 +11,13 @@ pub struct UnionContext {
     pub conflict: bool,
     pub obj_json: String,
     pub delta_json: String,
+    /// The dict keys from the union root to the current value, which is only
+    /// tracked by the evaluator to match the merge keys.
+    pub path: Vec<String>,
+    /// The key set by two config entries, and the locations of the former and
+    /// the latter entries, which is only found by the evaluator under the
+    /// [DuplicateKeyPolicy::Error] policy.
+    pub duplicate_key: Option<(String, ConfigEntryLocation, ConfigEntryLocation)>,
 }
 
 /// UnionOptions denotes the union options between runtime values.
@@ -6,6 +6,7 @@ extern crate serde_yaml;
 use crate::*;
 
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
 /// YAML encode options.
 /// - sort_keys: Sort the encode result by keys (defaults to false).
@@ -13,6 +14,8 @@ use serde::{Deserialize, Serialize};
 ///     a character `_` (defaults to false).
 /// - ignore_none: Whether to ignore the attribute whose value is `None` (defaults to false).
 /// - sep: Which separator to use between YAML documents (defaults to "---").
+/// - yaml_spec: Which YAML specification the emitted scalars are unambiguous
+///     under (defaults to YAML 1.2).
 ///
 /// TODO: We have not yet supported the following options because serde_yaml
 /// does not support these capabilities yet.
@@ -29,6 +32,8 @@ pub struct YamlEncodeOptions {
     pub ignore_private: bool,
     pub ignore_none: bool,
     pub sep: String,
+    #[serde(default)]
+    pub yaml_spec: YamlSpec,
 }
 
 impl Default for YamlEncodeOptions {
@@ -38,10 +43,50 @@ impl Default for YamlEncodeOptions {
             ignore_private: false,
             ignore_none: false,
             sep: "---".to_string(),
+            yaml_spec: YamlSpec::default(),
         }
     }
 }
 
+/// The YAML specification version which decides how the ambiguous string
+/// scalars are quoted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YamlSpec {
+    /// Quote the strings which are booleans, nulls or numbers under YAML 1.1,
+    /// e.g., `yes`, `no`, `on`, `off`, `0777` and `1:20`, which is required by
+    /// the YAML 1.1 consumers such as Kubernetes.
+    Yaml11,
+    /// Quote the strings which are booleans, nulls or numbers under YAML 1.2,
+    /// e.g., `true`, `null` and `1.0`, and the boolean words under YAML 1.1,
+    /// i.e., `yes`, `no`, `on` and `off`, which are still read as booleans by
+    /// many YAML consumers.
+    #[default]
+    Yaml12,
+}
+
+/// The plain scalars which are not strings under YAML 1.1.
+/// Ref: https://yaml.org/type/
+const YAML11_NON_STRING_REGEX: &str = concat!(
+    r"^(?:",
+    // Booleans
+    r"y|Y|yes|Yes|YES|n|N|no|No|NO|true|True|TRUE|false|False|FALSE|on|On|ON|off|Off|OFF",
+    // Nulls, merge keys and values
+    r"|~|null|Null|NULL|<<|=",
+    // Integers
+    r"|[-+]?0b[0-1_]+|[-+]?0[0-7_]+|[-+]?(?:0|[1-9][0-9_]*)|[-+]?0x[0-9a-fA-F_]+",
+    r"|[-+]?[1-9][0-9_]*(?::[0-5]?[0-9])+",
+    // Floats
+    r"|[-+]?(?:[0-9][0-9_]*)?\.[0-9.]*(?:[eE][-+][0-9]+)?",
+    r"|[-+]?[0-9][0-9_]*(?::[0-5]?[0-9])+\.[0-9_]*",
+    r"|[-+]?\.(?:inf|Inf|INF)|\.(?:nan|NaN|NAN)",
+    r")$"
+);
+
+/// The plain words which are booleans under YAML 1.1, which are quoted under both
+/// the YAML specifications. The single letters `y` and `n` are left plain, which
+/// are usually the keys of coordinates.
+const YAML11_BOOL_REGEX: &str = r"^(?:yes|Yes|YES|no|No|NO|on|On|ON|off|Off|OFF)$";
+
 impl ValueRef {
     /// Decode a yaml single document string to a ValueRef.
     /// Returns [serde_yaml::Error] when decoding fails.
@@ -54,22 +99,32 @@ impl ValueRef {
     /// Decode yaml stream string that contains `---` to a ValueRef.
     /// Returns [serde_yaml::Error] when decoding fails.
     pub fn from_yaml_stream(ctx: &mut Context, s: &str) -> Result<Self, serde_yaml::Error> {
-        let documents = serde_yaml::Deserializer::from_str(s);
-        let mut result = ValueRef::list_value(None);
-        for document in documents {
-            let json_value: JsonValue = JsonValue::deserialize(document)?;
-            result.list_append(&ValueRef::parse_json(ctx, &json_value))
-        }
-        if result.is_empty() {
+        let mut documents = Self::from_yaml_documents(ctx, s)?;
+        if documents.is_empty() {
             // Empty result returns a empty dict.
             Ok(ValueRef::dict(None))
-        } else if result.len() == 1 {
-            Ok(result.list_get(0).unwrap())
+        } else if documents.len() == 1 {
+            Ok(documents.remove(0))
         } else {
+            let mut result = ValueRef::list_value(None);
+            for document in &documents {
+                result.list_append(document);
+            }
             Ok(result)
         }
     }
 
+    /// Decode each document of the yaml stream string that contains `---` to a ValueRef.
+    /// Returns [serde_yaml::Error] when decoding fails.
+    pub fn from_yaml_documents(ctx: &mut Context, s: &str) -> Result<Vec<Self>, serde_yaml::Error> {
+        let mut result = vec![];
+        for document in serde_yaml::Deserializer::from_str(s) {
+            let json_value: JsonValue = JsonValue::deserialize(document)?;
+            result.push(ValueRef::parse_json(ctx, &json_value));
+        }
+        Ok(result)
+    }
+
     pub fn to_yaml(&self) -> Vec<u8> {
         let json = self.to_json_string();
         let yaml_value: serde_yaml::Value = serde_json::from_str(json.as_ref()).unwrap();
@@ -92,6 +147,19 @@ impl ValueRef {
     }
 
     pub fn to_yaml_string_with_options(&self, opt: &YamlEncodeOptions) -> String {
+        self.to_yaml_string_with_limit(opt, 0)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Serialize the value like [ValueRef::to_yaml_string_with_options], which fails
+    /// when the output exceeds `max_bytes` bytes, where 0 denotes no limit. The YAML
+    /// output is converted from the JSON output, whose serialization is aborted once
+    /// it exceeds the same limit.
+    pub fn to_yaml_string_with_limit(
+        &self,
+        opt: &YamlEncodeOptions,
+        max_bytes: usize,
+    ) -> Result<String, OutputSizeError> {
         // convert Value to json in order to reuse
         // "crate::val_json::JsonValue" to customize the serialized results
         let json_opt = JsonEncodeOptions {
@@ -100,15 +168,106 @@ impl ValueRef {
             ignore_private: opt.ignore_private,
             ignore_none: opt.ignore_none,
         };
-        let json = self.to_json_string_with_options(&json_opt);
-        let yaml_value: serde_yaml::Value = serde_json::from_str(json.as_ref()).unwrap();
-        match serde_yaml::to_string(&yaml_value) {
+        let json = self.to_json_string_with_limit(&json_opt, max_bytes)?;
+        let mut yaml_value: serde_yaml::Value = serde_json::from_str(json.as_ref()).unwrap();
+        let mut quoter = Yaml11Quoter::new(&json, opt.yaml_spec);
+        quoter.replace(&mut yaml_value);
+        let yaml = match serde_yaml::to_string(&yaml_value) {
             Ok(s) => {
                 let s = s.strip_prefix("---\n").unwrap_or_else(|| s.as_ref());
-                s.to_string()
+                quoter.restore(s)
             }
             Err(err) => panic!("{}", err),
+        };
+        if max_bytes > 0 && yaml.len() > max_bytes {
+            return Err(OutputSizeError { max_bytes });
         }
+        Ok(yaml)
+    }
+}
+
+/// Yaml11Quoter quotes the strings which are not strings under YAML 1.1, i.e.,
+/// all the ambiguous scalars with [YamlSpec::Yaml11] and only the boolean words
+/// with [YamlSpec::Yaml12]. The YAML emitter only quotes the ambiguous strings under
+/// YAML 1.2, thus these strings are replaced with unique plain placeholders before
+/// emitting, and the placeholders are replaced with the single-quoted strings after
+/// emitting.
+struct Yaml11Quoter {
+    prefix: String,
+    quoted: Vec<String>,
+    regex: &'static ::regex::Regex,
+}
+
+impl Yaml11Quoter {
+    /// New a quoter whose placeholder prefix does not appear in the JSON text.
+    fn new(json: &str, yaml_spec: YamlSpec) -> Self {
+        static NON_STRING_REGEX: OnceLock<::regex::Regex> = OnceLock::new();
+        static BOOL_REGEX: OnceLock<::regex::Regex> = OnceLock::new();
+        let regex = match yaml_spec {
+            YamlSpec::Yaml11 => NON_STRING_REGEX
+                .get_or_init(|| ::regex::Regex::new(YAML11_NON_STRING_REGEX).unwrap()),
+            YamlSpec::Yaml12 => {
+                BOOL_REGEX.get_or_init(|| ::regex::Regex::new(YAML11_BOOL_REGEX).unwrap())
+            }
+        };
+        let mut prefix = "kcl_yaml11_quoted_".to_string();
+        while json.contains(&prefix) {
+            prefix.push('_');
+        }
+        Self {
+            prefix,
+            quoted: vec![],
+            regex,
+        }
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        format!("{}{}_", self.prefix, index)
+    }
+
+    fn quote(&mut self, s: &str) -> Option<String> {
+        if self.regex.is_match(s) {
+            let placeholder = self.placeholder(self.quoted.len());
+            // The ambiguous scalars never contain the single quote.
+            self.quoted.push(format!("'{}'", s));
+            Some(placeholder)
+        } else {
+            None
+        }
+    }
+
+    /// Replace the ambiguous string keys and values with placeholders.
+    fn replace(&mut self, value: &mut serde_yaml::Value) {
+        match value {
+            serde_yaml::Value::String(s) => {
+                if let Some(placeholder) = self.quote(s) {
+                    *s = placeholder;
+                }
+            }
+            serde_yaml::Value::Sequence(items) => {
+                for item in items {
+                    self.replace(item);
+                }
+            }
+            serde_yaml::Value::Mapping(mapping) => {
+                let entries = std::mem::take(mapping);
+                for (mut key, mut value) in entries {
+                    self.replace(&mut key);
+                    self.replace(&mut value);
+                    mapping.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the placeholders in the emitted YAML string with the quoted strings.
+    fn restore(&self, yaml: &str) -> String {
+        let mut yaml = yaml.to_string();
+        for (index, quoted) in self.quoted.iter().enumerate() {
+            yaml = yaml.replacen(&self.placeholder(index), quoted, 1);
+        }
+        yaml
     }
 }
 
@@ -238,6 +397,7 @@ mod test_value_yaml {
                     ignore_private: false,
                     ignore_none: false,
                     sep: "---".to_string(),
+                    yaml_spec: YamlSpec::Yaml12,
                 },
             ),
             (
@@ -248,6 +408,7 @@ mod test_value_yaml {
                     ignore_private: false,
                     ignore_none: false,
                     sep: "---".to_string(),
+                    yaml_spec: YamlSpec::Yaml12,
                 },
             ),
             (
@@ -258,6 +419,7 @@ mod test_value_yaml {
                     ignore_private: true,
                     ignore_none: false,
                     sep: "---".to_string(),
+                    yaml_spec: YamlSpec::Yaml12,
                 },
             ),
             (
@@ -268,6 +430,7 @@ mod test_value_yaml {
                     ignore_private: true,
                     ignore_none: true,
                     sep: "---".to_string(),
+                    yaml_spec: YamlSpec::Yaml12,
                 },
             ),
             (
@@ -281,6 +444,7 @@ mod test_value_yaml {
                     ignore_private: false,
                     ignore_none: false,
                     sep: "---".to_string(),
+                    yaml_spec: YamlSpec::Yaml12,
                 },
             ),
         ];
@@ -289,4 +453,42 @@ mod test_value_yaml {
             assert_eq!(result, expected);
         }
     }
+
+    #[test]
+    fn test_value_to_yaml_string_with_yaml_spec() {
+        let value = ValueRef::dict(Some(&[
+            ("on", &ValueRef::str("yes")),
+            ("mode", &ValueRef::str("0777")),
+            ("duration", &ValueRef::str("1:20")),
+            ("flag", &ValueRef::str("true")),
+            (
+                "list",
+                &ValueRef::list_str(&["off".to_string(), "y".to_string()]),
+            ),
+            ("name", &ValueRef::str("kcl_yaml11_quoted_0_")),
+            ("enabled", &ValueRef::bool(true)),
+        ]));
+        let cases = [
+            (
+                YamlSpec::Yaml11,
+                "'on': 'yes'\nmode: '0777'\nduration: '1:20'\nflag: 'true'\nlist:\n- 'off'\n- 'y'\nname: kcl_yaml11_quoted_0_\nenabled: true\n",
+            ),
+            (
+                YamlSpec::Yaml12,
+                "'on': 'yes'\nmode: '0777'\nduration: 1:20\nflag: 'true'\nlist:\n- 'off'\n- y\nname: kcl_yaml11_quoted_0_\nenabled: true\n",
+            ),
+        ];
+        let mut ctx = Context::new();
+        for (yaml_spec, expected) in cases {
+            let opts = YamlEncodeOptions {
+                yaml_spec,
+                ..Default::default()
+            };
+            let result = value.to_yaml_string_with_options(&opts);
+            assert_eq!(result, expected);
+            // The strings stay strings after decoding.
+            let decoded = ValueRef::from_yaml(&mut ctx, &result).unwrap();
+            assert_eq!(decoded.to_json_string(), value.to_json_string());
+        }
+    }
 }
@@ -105,6 +105,7 @@ pub extern "C" fn kclvm_yaml_dump_to_file(
     match (data, filename) {
         (Some(data), Some(filename)) => {
             let filename = filename.as_str();
+            ptr_as_ref(ctx).check_sandbox_path(&filename);
 
             let yaml = data.to_yaml_string_with_options(&kwargs_to_opts(kwargs));
             std::fs::write(&filename, yaml)
@@ -133,6 +134,7 @@ pub extern "C" fn kclvm_yaml_dump_all_to_file(
     match (data, filename) {
         (Some(data), Some(filename)) => {
             let filename = filename.as_str();
+            ptr_as_ref(ctx).check_sandbox_path(&filename);
             let opts = kwargs_to_opts(kwargs);
             let results = data
                 .as_list_ref()
@@ -302,7 +302,8 @@ mod tests {
             },
             None,
         )
-        .node_ty_map;
+        .node_types()
+        .clone();
         let gs = AdvancedResolver::resolve_program(&program, gs, node_ty_map);
         let base_path = Path::new(".").canonicalize().unwrap();
         // print_symbols_info(&gs);
@@ -1231,7 +1232,7 @@ mod tests {
             .program;
         let gs = GlobalState::default();
         let gs = Namer::find_symbols(&program, gs);
-        let node_ty_map = resolver::resolve_program(&mut program).node_ty_map;
+        let node_ty_map = resolver::resolve_program(&mut program).node_types().clone();
         let gs = AdvancedResolver::resolve_program(&program, gs, node_ty_map);
         let base_path = Path::new(".").canonicalize().unwrap();
 
@@ -1308,7 +1309,7 @@ mod tests {
             .program;
         let gs = GlobalState::default();
         let gs = Namer::find_symbols(&program, gs);
-        let node_ty_map = resolver::resolve_program(&mut program).node_ty_map;
+        let node_ty_map = resolver::resolve_program(&mut program).node_types().clone();
         let gs = AdvancedResolver::resolve_program(&program, gs, node_ty_map);
         let base_path = Path::new(".").canonicalize().unwrap();
 
@@ -0,0 +1,261 @@
+//! Constant folding of the resolved AST program.
+//!
+//! The pure expressions made up of the number, string and bool literals are
+//! precomputed and replaced with the literal of the result, e.g., `2 * 3 + 1`
+//! is folded to `7`. Expressions depending on any variable, argument or call
+//! are left intact.
+//!
+//! The folding never changes the output of the program. The expressions which
+//! would raise a runtime error or depend on the runtime options are kept, e.g.,
+//! the division by zero and the int results out of the 32-bit range which is
+//! checked by the strict range check option.
+use kclvm_ast::{ast, walker::MutSelfMutWalker};
+
+#[cfg(test)]
+mod tests;
+
+/// Fold the constant expressions in all the modules of the program and returns
+/// the number of the folded expressions.
+pub fn fold_constants(program: &mut ast::Program) -> usize {
+    program
+        .pkgs
+        .values_mut()
+        .flat_map(|modules| modules.iter_mut())
+        .map(fold_module_constants)
+        .sum()
+}
+
+/// Fold the constant expressions in the module and returns the number of the
+/// folded expressions.
+///
+/// # Examples
+///
+/// ```
+/// use kclvm_ast::ast;
+/// use kclvm_parser::parse_file_force_errors;
+/// use kclvm_sema::fold::fold_module_constants;
+///
+/// let mut module = parse_file_force_errors("", Some("a = (1 + 2) * 3".to_string())).unwrap();
+/// assert_eq!(fold_module_constants(&mut module), 1);
+/// if let ast::Stmt::Assign(assign_stmt) = &module.body[0].node {
+///     assert!(matches!(assign_stmt.value.node, ast::Expr::NumberLit(_)));
+/// }
+/// ```
+pub fn fold_module_constants(module: &mut ast::Module) -> usize {
+    let mut folder = ConstantFolder::default();
+    folder.walk_module(module);
+    folder.folded
+}
+
+/// The constant value of a pure expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl Constant {
+    fn into_expr(self) -> ast::Expr {
+        match self {
+            Constant::Int(v) => ast::Expr::NumberLit(ast::NumberLit {
+                binary_suffix: None,
+                value: ast::NumberLitValue::Int(v),
+            }),
+            Constant::Float(v) => ast::Expr::NumberLit(ast::NumberLit {
+                binary_suffix: None,
+                value: ast::NumberLitValue::Float(v),
+            }),
+            Constant::Str(v) => ast::Expr::StringLit(ast::StringLit {
+                is_long_string: false,
+                raw_value: format!("{:?}", v),
+                value: v,
+            }),
+            Constant::Bool(v) => ast::Expr::NameConstantLit(ast::NameConstantLit {
+                value: if v {
+                    ast::NameConstant::True
+                } else {
+                    ast::NameConstant::False
+                },
+            }),
+        }
+    }
+}
+
+/// Returns the constant value of the expression, or [None] when the expression
+/// is not pure or can't be folded without changing the runtime behavior.
+fn constant_value(expr: &ast::Expr) -> Option<Constant> {
+    match expr {
+        ast::Expr::NumberLit(number_lit) if number_lit.binary_suffix.is_none() => {
+            match number_lit.value {
+                ast::NumberLitValue::Int(v) => Some(Constant::Int(v)),
+                ast::NumberLitValue::Float(v) => Some(Constant::Float(v)),
+            }
+        }
+        ast::Expr::StringLit(string_lit) => Some(Constant::Str(string_lit.value.clone())),
+        ast::Expr::NameConstantLit(name_constant_lit) => match name_constant_lit.value {
+            ast::NameConstant::True => Some(Constant::Bool(true)),
+            ast::NameConstant::False => Some(Constant::Bool(false)),
+            _ => None,
+        },
+        ast::Expr::Paren(paren_expr) => constant_value(&paren_expr.expr.node),
+        ast::Expr::Unary(unary_expr) => {
+            let operand = constant_value(&unary_expr.operand.node)?;
+            match (&unary_expr.op, operand) {
+                (ast::UnaryOp::UAdd, Constant::Int(v)) => int_constant(v as i128),
+                (ast::UnaryOp::USub, Constant::Int(v)) => int_constant(-(v as i128)),
+                (ast::UnaryOp::UAdd, Constant::Float(v)) => float_constant(v),
+                (ast::UnaryOp::USub, Constant::Float(v)) => float_constant(-v),
+                (ast::UnaryOp::Not, Constant::Bool(v)) => Some(Constant::Bool(!v)),
+                _ => None,
+            }
+        }
+        ast::Expr::Binary(binary_expr) => {
+            let left = constant_value(&binary_expr.left.node)?;
+            let right = constant_value(&binary_expr.right.node)?;
+            binary_constant(&binary_expr.op, left, right)
+        }
+        _ => None,
+    }
+}
+
+fn binary_constant(op: &ast::BinOp, left: Constant, right: Constant) -> Option<Constant> {
+    match (left, right) {
+        (Constant::Int(a), Constant::Int(b)) => {
+            let (a, b) = (a as i128, b as i128);
+            match op {
+                ast::BinOp::Add => int_constant(a + b),
+                ast::BinOp::Sub => int_constant(a - b),
+                ast::BinOp::Mul => int_constant(a * b),
+                ast::BinOp::Div if b != 0 => float_constant(a as f64 / b as f64),
+                // The floor division and the modulo round towards the negative infinity.
+                ast::BinOp::FloorDiv if b != 0 => {
+                    if (a < 0) != (b < 0) && a % b != 0 {
+                        int_constant(a / b - 1)
+                    } else {
+                        int_constant(a / b)
+                    }
+                }
+                ast::BinOp::Mod if b != 0 => {
+                    if (a < 0) != (b < 0) && a % b != 0 {
+                        int_constant(a % b + b)
+                    } else {
+                        int_constant(a % b)
+                    }
+                }
+                ast::BinOp::Pow if (0..64).contains(&b) => {
+                    a.checked_pow(b as u32).and_then(int_constant)
+                }
+                _ => None,
+            }
+        }
+        (Constant::Str(a), Constant::Str(b)) => match op {
+            ast::BinOp::Add => Some(Constant::Str(a + &b)),
+            _ => None,
+        },
+        (Constant::Bool(a), Constant::Bool(b)) => match op {
+            ast::BinOp::And => Some(Constant::Bool(a && b)),
+            ast::BinOp::Or => Some(Constant::Bool(a || b)),
+            _ => None,
+        },
+        (left, right) => {
+            let (a, b) = (float_operand(&left)?, float_operand(&right)?);
+            match op {
+                ast::BinOp::Add => float_constant(a + b),
+                ast::BinOp::Sub => float_constant(a - b),
+                ast::BinOp::Mul => float_constant(a * b),
+                ast::BinOp::Div if b != 0.0 => float_constant(a / b),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Returns the int constant when the value is in the 32-bit range, out of
+/// which the result depends on the strict range check option.
+fn int_constant(v: i128) -> Option<Constant> {
+    if (i32::MIN as i128..=i32::MAX as i128).contains(&v) {
+        Some(Constant::Int(v as i64))
+    } else {
+        None
+    }
+}
+
+/// Returns the float constant when the value is finite and in the 32-bit range.
+fn float_constant(v: f64) -> Option<Constant> {
+    if v.is_finite() && v.abs() <= f32::MAX as f64 {
+        Some(Constant::Float(v))
+    } else {
+        None
+    }
+}
+
+fn float_operand(constant: &Constant) -> Option<f64> {
+    match constant {
+        Constant::Int(v) => Some(*v as f64),
+        Constant::Float(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// ConstantFolder replaces the outermost pure expressions with the literals of
+/// their values, and walks into the other expressions to fold their children.
+#[derive(Debug, Default)]
+struct ConstantFolder {
+    folded: usize,
+}
+
+impl<'ctx> MutSelfMutWalker<'ctx> for ConstantFolder {
+    fn walk_expr(&mut self, expr: &'ctx mut ast::Expr) {
+        if matches!(
+            expr,
+            ast::Expr::Binary(_) | ast::Expr::Unary(_) | ast::Expr::Paren(_)
+        ) {
+            if let Some(constant) = constant_value(expr) {
+                *expr = constant.into_expr();
+                self.folded += 1;
+                return;
+            }
+        }
+        match expr {
+            ast::Expr::Identifier(identifier) => self.walk_identifier(identifier),
+            ast::Expr::Unary(unary_expr) => self.walk_unary_expr(unary_expr),
+            ast::Expr::Binary(binary_expr) => self.walk_binary_expr(binary_expr),
+            ast::Expr::If(if_expr) => self.walk_if_expr(if_expr),
+            ast::Expr::Selector(selector_expr) => self.walk_selector_expr(selector_expr),
+            ast::Expr::Call(call_expr) => self.walk_call_expr(call_expr),
+            ast::Expr::Paren(paren_expr) => self.walk_paren_expr(paren_expr),
+            ast::Expr::Quant(quant_expr) => self.walk_quant_expr(quant_expr),
+            ast::Expr::List(list_expr) => self.walk_list_expr(list_expr),
+            ast::Expr::ListIfItem(list_if_item_expr) => {
+                self.walk_list_if_item_expr(list_if_item_expr)
+            }
+            ast::Expr::ListComp(list_comp) => self.walk_list_comp(list_comp),
+            ast::Expr::Starred(starred_expr) => self.walk_starred_expr(starred_expr),
+            ast::Expr::DictComp(dict_comp) => self.walk_dict_comp(dict_comp),
+            ast::Expr::ConfigIfEntry(config_if_entry_expr) => {
+                self.walk_config_if_entry_expr(config_if_entry_expr)
+            }
+            ast::Expr::CompClause(comp_clause) => self.walk_comp_clause(comp_clause),
+            ast::Expr::Schema(schema_expr) => self.walk_schema_expr(schema_expr),
+            ast::Expr::Config(config_expr) => self.walk_config_expr(config_expr),
+            ast::Expr::Check(check) => self.walk_check_expr(check),
+            ast::Expr::Lambda(lambda) => self.walk_lambda_expr(lambda),
+            ast::Expr::Subscript(subscript) => self.walk_subscript(subscript),
+            ast::Expr::Keyword(keyword) => self.walk_keyword(keyword),
+            ast::Expr::Arguments(arguments) => self.walk_arguments(arguments),
+            ast::Expr::Compare(compare) => self.walk_compare(compare),
+            ast::Expr::NumberLit(number_lit) => self.walk_number_lit(number_lit),
+            ast::Expr::StringLit(string_lit) => self.walk_string_lit(string_lit),
+            ast::Expr::NameConstantLit(name_constant_lit) => {
+                self.walk_name_constant_lit(name_constant_lit)
+            }
+            ast::Expr::JoinedString(joined_string) => self.walk_joined_string(joined_string),
+            ast::Expr::FormattedValue(formatted_value) => {
+                self.walk_formatted_value(formatted_value)
+            }
+            ast::Expr::Missing(missing_expr) => self.walk_missing_expr(missing_expr),
+        }
+    }
+}
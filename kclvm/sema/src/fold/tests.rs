@@ -0,0 +1,63 @@
+use super::*;
+use kclvm_ast_pretty::print_ast_module;
+use kclvm_parser::parse_file_force_errors;
+
+fn fold_code(code: &str) -> (usize, String) {
+    let mut module = parse_file_force_errors("", Some(code.to_string())).unwrap();
+    let folded = fold_module_constants(&mut module);
+    (folded, print_ast_module(&module))
+}
+
+#[test]
+fn test_fold_constants() {
+    let (folded, code) = fold_code(
+        r#"a = 2 * 3 + 1
+b = (1 + 2) * 3 - -1
+c = 7 / 2
+d = -7 // 2
+e = -7 % 3
+f = 2 ** 10
+g = "ab" + "c"
+h = not (True and False)
+i = 1.5 * 3
+"#,
+    );
+    assert_eq!(folded, 9);
+    assert_eq!(
+        code,
+        r#"a = 7
+b = 10
+c = 3.5
+d = -4
+e = 2
+f = 1024
+g = "abc"
+h = True
+i = 4.5
+"#
+    );
+}
+
+#[test]
+fn test_fold_constants_keeps_non_constants() {
+    let (folded, code) = fold_code(
+        r#"a = option("a") + 1
+b = a + (1 + 2)
+c = 1 / 0
+d = 2 ** 40
+e = 1Ki + 1
+f = [x * (2 + 3) for x in [1, 2]]
+"#,
+    );
+    assert_eq!(folded, 2);
+    assert_eq!(
+        code,
+        r#"a = option("a") + 1
+b = a + 3
+c = 1 / 0
+d = 2 ** 40
+e = 1Ki + 1
+f = [x * 5 for x in [1, 2]]
+"#
+    );
+}
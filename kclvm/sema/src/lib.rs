@@ -2,6 +2,7 @@ pub mod advanced_resolver;
 pub mod builtin;
 pub mod core;
 pub mod eval;
+pub mod fold;
 pub mod info;
 pub mod lint;
 pub mod namer;
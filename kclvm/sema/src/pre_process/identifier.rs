@@ -7,10 +7,24 @@ use kclvm_ast::{ast, walk_if_mut, walk_list_mut};
 use kclvm_error::*;
 
 pub const RAW_IDENTIFIER_PREFIX: &str = "$";
+/// The data file extensions which can be imported as a package.
+pub const DATA_FILE_EXTENSIONS: [&str; 4] = ["yaml", "yml", "json", "toml"];
+/// The variable name of the whole document in the module of an imported data file.
+pub const DATA_VALUE_NAME: &str = "__data__";
+
+/// Whether the file is a data file which can be imported as a package.
+pub fn is_data_file(filename: &str) -> bool {
+    std::path::Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| DATA_FILE_EXTENSIONS.contains(&ext))
+}
 
 #[derive(Default)]
 struct QualifiedIdentifierTransformer {
     pub import_names: IndexMap<String, String>,
+    /// The package paths of the imported data files.
+    pub data_pkgs: IndexSet<String>,
     pub global_names: IndexMap<String, Position>,
     pub local_vars: IndexSet<String>,
     pub scope_level: usize,
@@ -88,10 +102,18 @@ impl<'ctx> MutSelfMutWalker<'ctx> for QualifiedIdentifierTransformer {
     }
     fn walk_import_stmt(&mut self, _: &'ctx mut ast::ImportStmt) {}
     fn walk_lambda_expr(&mut self, lambda_expr: &'ctx mut ast::LambdaExpr) {
+        // The arguments shadow the import names in the lambda body.
+        let local_vars = self.local_vars.clone();
+        if let Some(args) = &lambda_expr.args {
+            for arg in &args.node.args {
+                self.local_vars.insert(arg.node.get_name());
+            }
+        }
         walk_if_mut!(self, walk_arguments, lambda_expr.args);
         self.scope_level += 1;
         walk_list_mut!(self, walk_stmt, lambda_expr.body);
         self.scope_level -= 1;
+        self.local_vars = local_vars;
     }
     fn walk_list_comp(&mut self, list_comp: &'ctx mut ast::ListComp) {
         for gen in &mut list_comp.generators {
@@ -134,14 +156,70 @@ impl<'ctx> MutSelfMutWalker<'ctx> for QualifiedIdentifierTransformer {
         walk_if_mut!(self, walk_expr, quant_expr.if_cond);
         self.local_vars.clear();
     }
+    fn walk_config_expr(&mut self, config_expr: &'ctx mut ast::ConfigExpr) {
+        for config_entry in config_expr.items.iter_mut() {
+            self.walk_config_entry_key(&mut config_entry.node.key);
+            self.walk_expr(&mut config_entry.node.value.node);
+        }
+    }
+    fn walk_config_if_entry_expr(
+        &mut self,
+        config_if_entry_expr: &'ctx mut ast::ConfigIfEntryExpr,
+    ) {
+        self.walk_expr(&mut config_if_entry_expr.if_cond.node);
+        for config_entry in config_if_entry_expr.items.iter_mut() {
+            self.walk_config_entry_key(&mut config_entry.node.key);
+            self.walk_expr(&mut config_entry.node.value.node);
+        }
+        walk_if_mut!(self, walk_expr, config_if_entry_expr.orelse);
+    }
+    fn walk_arguments(&mut self, arguments: &'ctx mut ast::Arguments) {
+        // The argument names are declarations, only the default values are walked.
+        for default in arguments.defaults.iter_mut() {
+            if let Some(default) = default.as_deref_mut() {
+                self.walk_expr(&mut default.node)
+            }
+        }
+    }
     fn walk_identifier(&mut self, identifier: &'ctx mut ast::Identifier) {
+        // skip global name and generator local variables in list/dict comp and quant expression
+        let name = match identifier.names.first() {
+            Some(name) => &name.node,
+            None => return,
+        };
+        if self.global_names.contains_key(name) || self.local_vars.contains(name) {
+            return;
+        }
+        let pkgpath = match self.import_names.get(name) {
+            Some(pkgpath) => pkgpath.clone(),
+            None => return,
+        };
         if identifier.names.len() >= 2 {
-            // skip global name and generator local variables in list/dict comp and quant expression
-            let name = &identifier.names[0].node;
-            if !self.global_names.contains_key(name) && !self.local_vars.contains(name) {
-                if let Some(pkgpath) = self.import_names.get(name) {
-                    identifier.pkgpath = pkgpath.clone()
-                }
+            identifier.pkgpath = pkgpath;
+        } else if matches!(identifier.ctx, ast::ExprContext::Load)
+            && self.data_pkgs.contains(&pkgpath)
+        {
+            // The import name of a data file denotes the whole document.
+            let pos = identifier.names[0].pos();
+            identifier
+                .names
+                .push(Node::node_with_pos(DATA_VALUE_NAME.to_string(), pos));
+            identifier.pkgpath = pkgpath;
+        }
+    }
+}
+
+impl QualifiedIdentifierTransformer {
+    /// Walk the config entry key, a single name key is an attribute name instead
+    /// of a variable reference.
+    fn walk_config_entry_key<'ctx>(&mut self, key: &'ctx mut Option<ast::NodeRef<ast::Expr>>) {
+        if let Some(key) = key.as_deref_mut() {
+            let is_name = matches!(
+                &key.node,
+                ast::Expr::Identifier(identifier) if identifier.names.len() == 1
+            );
+            if !is_name {
+                self.walk_expr(&mut key.node);
             }
         }
     }
@@ -220,6 +298,7 @@ impl<'ctx> MutSelfMutWalker<'ctx> for RawIdentifierTransformer {
 pub fn fix_qualified_identifier<'ctx>(
     module: &'ctx mut ast::Module,
     import_names: &mut IndexMap<String, String>,
+    data_pkgs: &IndexSet<String>,
 ) {
     // 0. init import names.
     for stmt in &module.body {
@@ -230,6 +309,7 @@ pub fn fix_qualified_identifier<'ctx>(
     // 1. fix_global_ident
     let mut global_names_walker = QualifiedIdentifierTransformer {
         import_names: import_names.clone(),
+        data_pkgs: data_pkgs.clone(),
         ..Default::default()
     };
     global_names_walker.walk_module(module);
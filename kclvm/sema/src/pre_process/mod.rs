@@ -2,20 +2,29 @@ mod config;
 mod identifier;
 mod multi_assign;
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use kclvm_ast::ast;
 
 #[cfg(test)]
 mod tests;
 
 pub use config::{fix_config_expr_nest_attr, merge_program};
-pub use identifier::{fix_qualified_identifier, fix_raw_identifier_prefix};
+pub use identifier::{
+    fix_qualified_identifier, fix_raw_identifier_prefix, is_data_file, DATA_FILE_EXTENSIONS,
+    DATA_VALUE_NAME,
+};
 pub use multi_assign::transform_multi_assign;
 
 use crate::resolver::Options;
 
 /// Pre-process AST program.
 pub fn pre_process_program(program: &mut ast::Program, opts: &Options) {
+    let data_pkgs: IndexSet<String> = program
+        .pkgs
+        .iter()
+        .filter(|(_, modules)| modules.len() == 1 && is_data_file(&modules[0].filename))
+        .map(|(pkgpath, _)| pkgpath.clone())
+        .collect();
     for (pkgpath, modules) in program.pkgs.iter_mut() {
         let mut import_names = IndexMap::default();
         if pkgpath == kclvm_ast::MAIN_PKG {
@@ -34,7 +43,7 @@ pub fn pre_process_program(program: &mut ast::Program, opts: &Options) {
             }
             // First we should transform the raw identifier to avoid raw identifier that happens to be a package path.
             fix_raw_identifier_prefix(module);
-            fix_qualified_identifier(module, &mut import_names);
+            fix_qualified_identifier(module, &mut import_names, &data_pkgs);
             fix_config_expr_nest_attr(module);
         }
     }
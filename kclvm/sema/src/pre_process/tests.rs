@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use super::*;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use kclvm_ast::path::get_attr_paths_from_config_expr;
 use kclvm_parser::{load_program, parse_file_force_errors, ParseSession};
 
@@ -10,7 +10,7 @@ fn test_fix_qualified_identifier() {
     let mut module =
         parse_file_force_errors("./src/pre_process/test_data/qualified_identifier.k", None)
             .unwrap();
-    fix_qualified_identifier(&mut module, &mut IndexMap::default());
+    fix_qualified_identifier(&mut module, &mut IndexMap::default(), &IndexSet::default());
     if let ast::Stmt::Assign(assign_stmt) = &module.body[1].node {
         if let ast::Expr::Identifier(identifier) = &assign_stmt.value.node {
             assert_eq!(identifier.pkgpath, "pkg")
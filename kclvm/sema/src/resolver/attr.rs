@@ -2,7 +2,8 @@ use std::sync::Arc;
 
 use crate::builtin::system_module::{get_system_module_members, UNITS, UNITS_NUMBER_MULTIPLIER};
 use crate::builtin::{get_system_member_function_ty, STRING_MEMBER_FUNCTIONS};
-use crate::resolver::Resolver;
+use crate::resolver::scope::UnresolvedRefKind;
+use crate::resolver::{did_you_mean, Resolver};
 use crate::ty::TypeKind::Schema;
 use crate::ty::{
     DictType, ModuleKind, Parameter, Type, TypeKind, TypeRef, SCHEMA_MEMBER_FUNCTIONS,
@@ -121,6 +122,9 @@ impl<'ctx> Resolver<'ctx> {
         };
 
         if !result {
+            if !attr.is_empty() {
+                self.add_unresolved_ref(attr, UnresolvedRefKind::Attribute, range.clone());
+            }
             // The attr user input.
             let (attr, suggestion) = if attr.is_empty() {
                 ("[missing name]", "".to_string())
@@ -138,9 +142,7 @@ impl<'ctx> Resolver<'ctx> {
                             .collect::<Vec<String>>()
                     };
                     let suggs = suggestions::provide_suggestions(attr, &attrs);
-                    if suggs.len() > 0 {
-                        suggestion = format!(", did you mean '{:?}'?", suggs);
-                    }
+                    suggestion = did_you_mean(&suggs);
                 }
                 (attr, suggestion)
             };
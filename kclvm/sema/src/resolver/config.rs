@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use super::{
+    did_you_mean,
     scope::{ScopeKind, ScopeObject, ScopeObjectKind},
     Resolver,
 };
@@ -332,12 +333,9 @@ impl<'ctx> Resolver<'ctx> {
         attr: &str,
         schema_ty: &SchemaType,
     ) -> (Vec<String>, String) {
-        let mut suggestion = String::new();
         // Calculate the closest miss attributes.
         let suggs = suggestions::provide_suggestions(attr, schema_ty.attrs.keys());
-        if suggs.len() > 0 {
-            suggestion = format!(", did you mean '{:?}'?", suggs);
-        }
+        let suggestion = did_you_mean(&suggs);
         (suggs, suggestion)
     }
 
@@ -374,11 +372,7 @@ impl<'ctx> Resolver<'ctx> {
                         } else {
                             format!("schema {}", schema_names[0])
                         },
-                        if total_suggs.is_empty() {
-                            "".to_string()
-                        } else {
-                            format!(", did you mean '{:?}'?", total_suggs)
-                        },
+                        did_you_mean(&total_suggs),
                     ),
                     range.clone(),
                     Some(total_suggs),
@@ -387,10 +381,10 @@ impl<'ctx> Resolver<'ctx> {
         }
     }
 
-    /// Check config attr has been defined.
+    /// Check config attr has been defined, and warn when the attr is deprecated.
     pub(crate) fn check_config_attr(&mut self, attr: &str, range: &Range, schema_ty: &SchemaType) {
         let runtime_type = kclvm_runtime::schema_runtime_type(&schema_ty.name, &schema_ty.pkgpath);
-        match self.ctx.schema_mapping.get(&runtime_type) {
+        let deprecated = match self.ctx.schema_mapping.get(&runtime_type) {
             Some(schema_mapping_ty) => {
                 let schema_ty_ref = schema_mapping_ty.borrow();
                 if schema_ty_ref.get_obj_of_attr(attr).is_none()
@@ -407,6 +401,9 @@ impl<'ctx> Resolver<'ctx> {
                         Some(suggs),
                     );
                 }
+                schema_ty_ref
+                    .get_obj_of_attr(attr)
+                    .and_then(|attr_obj| attr_obj.deprecated.clone())
             }
             None => {
                 if schema_ty.get_obj_of_attr(attr).is_none()
@@ -423,8 +420,14 @@ impl<'ctx> Resolver<'ctx> {
                         Some(suggs),
                     );
                 }
+                schema_ty
+                    .get_obj_of_attr(attr)
+                    .and_then(|attr_obj| attr_obj.deprecated.clone())
             }
         };
+        if let Some(message) = deprecated {
+            self.add_deprecated_attr_warning(attr, &message, range);
+        }
     }
 
     /// Schema load atr
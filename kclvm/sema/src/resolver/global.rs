@@ -13,6 +13,7 @@ use kclvm_ast_pretty::{print_ast_node, print_schema_expr, ASTNode};
 use kclvm_error::*;
 
 use super::doc::parse_doc_string;
+use super::schema::deprecation_message;
 use super::scope::{ScopeObject, ScopeObjectKind};
 use kclvm_ast::pos::GetPos;
 
@@ -575,7 +576,7 @@ impl<'ctx> Resolver<'ctx> {
                 .unwrap_or_default(),
         );
         for stmt in &schema_stmt.body {
-            let (name, ty, is_optional, default, decorators, range) = match &stmt.node {
+            let (name, ty, is_optional, default, decorators, deprecated, range) = match &stmt.node {
                 ast::Stmt::Unification(unification_stmt) => {
                     let name = unification_stmt.value.node.name.node.get_name();
                     let ty = self.parse_ty_str_with_scope(&name, stmt.get_span_pos());
@@ -591,6 +592,7 @@ impl<'ctx> Resolver<'ctx> {
                         is_optional,
                         Some(default),
                         vec![],
+                        None,
                         stmt.get_span_pos(),
                     )
                 }
@@ -612,12 +614,14 @@ impl<'ctx> Resolver<'ctx> {
                         DecoratorTarget::Attribute,
                         &name,
                     );
+                    let deprecated = deprecation_message(&schema_attr.decorators);
                     (
                         name,
                         ty,
                         is_optional,
                         default,
                         decorators,
+                        deprecated,
                         stmt.get_span_pos(),
                     )
                 }
@@ -646,6 +650,7 @@ impl<'ctx> Resolver<'ctx> {
                         range: range.clone(),
                         doc: doc_str,
                         decorators,
+                        deprecated,
                     },
                 );
             }
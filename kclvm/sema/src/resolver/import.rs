@@ -13,7 +13,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 use std::{cell::RefCell, path::Path};
 
-use super::scope::{Scope, ScopeKind, ScopeObject, ScopeObjectKind};
+use super::scope::{Scope, ScopeKind, ScopeObject, ScopeObjectKind, UnresolvedRefKind};
 use kclvm_ast::pos::GetPos;
 use kclvm_utils::pkgpath::parse_external_pkg_name;
 
@@ -37,6 +37,11 @@ impl<'ctx> Resolver<'ctx> {
                         let real_path =
                             Path::new(&self.program.root).join(pkgpath.replace('.', "/"));
                         if !self.program.pkgs.contains_key(pkgpath) {
+                            self.add_unresolved_ref(
+                                &import_stmt.rawpath,
+                                UnresolvedRefKind::Import,
+                                stmt.get_span_pos(),
+                            );
                             self.handler.add_error(
                                 ErrorKind::CannotFindModule,
                                 &[Message {
@@ -34,7 +34,7 @@ use crate::{resolver::scope::Scope, ty::SchemaType};
 use kclvm_ast::ast::Program;
 use kclvm_error::*;
 
-use self::scope::{builtin_scope, KCLScopeCache, NodeTyMap, ProgramScope};
+use self::scope::{builtin_scope, KCLScopeCache, NodeTyMap, ProgramScope, UnresolvedRef};
 
 /// Resolver is responsible for program semantic checking, mainly
 /// including type checking and contract model checking.
@@ -49,8 +49,17 @@ pub struct Resolver<'ctx> {
     pub options: Options,
     pub handler: Handler,
     pub linter: Linter<CombinedLintPass>,
+    pub unresolved_refs: Vec<UnresolvedRef>,
+    /// The emitter of the diagnostics of each resolved package.
+    pub emitter: Option<DiagnosticEmitter>,
+    /// The number of the diagnostics in the handler which have been emitted.
+    emitted: usize,
 }
 
+/// The emitter called with the package path and its diagnostics as soon as the
+/// package is resolved, see [resolve_program_with_emitter].
+pub type DiagnosticEmitter = Arc<dyn Fn(&str, &[Diagnostic]) + Send + Sync>;
+
 impl<'ctx> Resolver<'ctx> {
     pub fn new(program: &'ctx Program, options: Options) -> Self {
         let builtin_scope = Rc::new(RefCell::new(builtin_scope()));
@@ -66,6 +75,9 @@ impl<'ctx> Resolver<'ctx> {
             options,
             handler: Handler::default(),
             linter: Linter::<CombinedLintPass>::new(),
+            unresolved_refs: vec![],
+            emitter: None,
+            emitted: 0,
         }
     }
 
@@ -90,6 +102,25 @@ impl<'ctx> Resolver<'ctx> {
             }
             None => {}
         }
+        self.emit_diagnostics(pkgpath);
+    }
+
+    /// Emit the diagnostics produced since the previous emission, i.e., the ones
+    /// of the package and the imports of it which are checked before it.
+    fn emit_diagnostics(&mut self, pkgpath: &str) {
+        if let Some(emitter) = &self.emitter {
+            let diags: Vec<Diagnostic> = self
+                .handler
+                .diagnostics
+                .iter()
+                .skip(self.emitted)
+                .cloned()
+                .collect();
+            self.emitted = self.handler.diagnostics.len();
+            if !diags.is_empty() {
+                emitter(pkgpath, &diags);
+            }
+        }
     }
 
     pub(crate) fn check_and_lint(&mut self, pkgpath: &str) -> ProgramScope {
@@ -99,10 +130,18 @@ impl<'ctx> Resolver<'ctx> {
             import_names: self.ctx.import_names.clone(),
             node_ty_map: self.node_ty_map.clone(),
             handler: self.handler.clone(),
+            unresolved_refs: self.unresolved_refs.clone(),
         };
         self.lint_check_scope_map();
+        let mut lint_diags = vec![];
         for diag in &self.linter.handler.diagnostics {
-            scope.handler.diagnostics.insert(diag.clone());
+            if scope.handler.diagnostics.insert(diag.clone()) {
+                lint_diags.push(diag.clone());
+            }
+        }
+        // The lint diagnostics are produced after all the packages are resolved.
+        if let (Some(emitter), false) = (&self.emitter, lint_diags.is_empty()) {
+            emitter(pkgpath, &lint_diags);
         }
         scope
     }
@@ -169,14 +208,44 @@ pub fn resolve_program(program: &mut Program) -> ProgramScope {
     resolve_program_with_opts(program, Options::default(), None)
 }
 
+/// Resolve the program and returns the undefined identifiers, unresolved imports
+/// and missing attributes in it, see [ProgramScope::unresolved_references].
+pub fn unresolved_references(program: &mut Program) -> Vec<UnresolvedRef> {
+    resolve_program(program).unresolved_refs
+}
+
 /// Resolve program with options. See [Options]
+#[inline]
 pub fn resolve_program_with_opts(
     program: &mut Program,
     opts: Options,
     cached_scope: Option<KCLScopeCache>,
+) -> ProgramScope {
+    resolve(program, opts, cached_scope, None)
+}
+
+/// Resolve program with options, and the diagnostics are passed to the emitter
+/// package by package as soon as each package is resolved instead of at the end.
+/// The emitted diagnostics are the same set as the diagnostics of the returned
+/// scope, and they are in the same order when sorted.
+#[inline]
+pub fn resolve_program_with_emitter(
+    program: &mut Program,
+    opts: Options,
+    emitter: DiagnosticEmitter,
+) -> ProgramScope {
+    resolve(program, opts, None, Some(emitter))
+}
+
+fn resolve(
+    program: &mut Program,
+    opts: Options,
+    cached_scope: Option<KCLScopeCache>,
+    emitter: Option<DiagnosticEmitter>,
 ) -> ProgramScope {
     pre_process_program(program, &opts);
     let mut resolver = Resolver::new(program, opts.clone());
+    resolver.emitter = emitter;
     resolver.resolve_import();
     if let Some(cached_scope) = cached_scope.as_ref() {
         if let Ok(mut cached_scope) = cached_scope.try_lock() {
@@ -205,3 +274,17 @@ pub fn resolve_program_with_opts(
     }
     scope
 }
+
+/// Returns the message suffix of the suggested names, e.g., `, did you mean 'name'?`,
+/// or an empty string when there is no suggestion.
+pub(crate) fn did_you_mean(suggs: &[String]) -> String {
+    let quoted = suggs
+        .iter()
+        .map(|sugg| format!("'{}'", sugg))
+        .collect::<Vec<String>>();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(", did you mean {}?", last),
+        Some((last, rest)) => format!(", did you mean {} or {}?", rest.join(", "), last),
+    }
+}
@@ -9,7 +9,8 @@ use kclvm_ast::ast;
 use kclvm_ast::pos::GetPos;
 use kclvm_ast::walker::MutSelfTypedResultWalker;
 use kclvm_ast_pretty::{print_ast_node, ASTNode};
-use kclvm_error::{ErrorKind, Message, Position, Style};
+use kclvm_error::{diagnostic::Range, ErrorKind, Message, Position, Style, WarningKind};
+use kclvm_runtime::DEPRECATED_DECORATOR;
 
 use super::node::ResolvedResult;
 use super::scope::{ScopeKind, ScopeObject, ScopeObjectKind};
@@ -241,6 +242,25 @@ impl<'ctx> Resolver<'ctx> {
         decorator_objs
     }
 
+    /// Add the deprecation warning when the deprecated schema attribute is set.
+    pub(crate) fn add_deprecated_attr_warning(&mut self, attr: &str, message: &str, range: &Range) {
+        let message = if message.is_empty() {
+            format!("{} is deprecated", attr)
+        } else {
+            format!("{} is deprecated: {}", attr, message)
+        };
+        self.handler.add_warning(
+            WarningKind::DeprecatedWarning,
+            &[Message {
+                range: range.clone(),
+                style: Style::LineAndColumn,
+                message,
+                note: None,
+                suggested_replacement: None,
+            }],
+        );
+    }
+
     fn arguments_to_string(
         &mut self,
         args: &'ctx [ast::NodeRef<ast::Expr>],
@@ -270,3 +290,31 @@ impl<'ctx> Resolver<'ctx> {
         }
     }
 }
+
+/// Returns the deprecation message of the `@deprecated` decorator in the decorators,
+/// which is the first positional argument or the `reason` keyword argument, e.g.,
+/// `@deprecated("use fullName instead")`. The message is empty when it is not a
+/// string literal.
+pub(crate) fn deprecation_message(decorators: &[ast::NodeRef<ast::CallExpr>]) -> Option<String> {
+    let decorator = decorators.iter().find(|decorator| {
+        matches!(&decorator.node.func.node, ast::Expr::Identifier(identifier)
+            if identifier.get_name() == DEPRECATED_DECORATOR)
+    })?;
+    let message = decorator
+        .node
+        .args
+        .first()
+        .map(|arg| &arg.node)
+        .or_else(|| {
+            decorator
+                .node
+                .keywords
+                .iter()
+                .find(|keyword| keyword.node.arg.node.get_name() == "reason")
+                .and_then(|keyword| keyword.node.value.as_ref().map(|value| &value.node))
+        });
+    match message {
+        Some(ast::Expr::StringLit(string_lit)) => Some(string_lit.value.clone()),
+        _ => Some("".to_string()),
+    }
+}
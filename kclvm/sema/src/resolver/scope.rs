@@ -6,7 +6,7 @@ use kclvm_ast::ast::Stmt;
 use kclvm_ast::ast::Stmt::Import;
 use kclvm_ast::{ast, MAIN_PKG};
 use kclvm_error::diagnostic::Range;
-use kclvm_error::{Handler, Level};
+use kclvm_error::{sort_diagnostics, Diagnostic, Handler, Level};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -17,8 +17,9 @@ use std::{
     rc::{Rc, Weak},
 };
 
-use crate::resolver::Resolver;
-use crate::ty::TypeRef;
+use crate::builtin::system_module::STANDARD_SYSTEM_MODULES;
+use crate::resolver::{did_you_mean, Resolver};
+use crate::ty::{SchemaType, TypeKind, TypeRef};
 use crate::{builtin::BUILTIN_FUNCTIONS, ty::TypeInferMethods};
 use kclvm_ast::ast::AstIndex;
 use kclvm_ast::pos::ContainsPos;
@@ -279,13 +280,44 @@ impl Scope {
 }
 
 /// Program scope is scope contains a multiple scopes related to the
-/// package path.
+/// package path, which is returned by [crate::resolver::resolve_program].
+///
+/// The embedders should read the resolved program with the accessor methods,
+/// e.g., [ProgramScope::schema_definitions], [ProgramScope::variable_types] and
+/// [ProgramScope::diagnostics], which are kept stable when the fields change.
 #[derive(Clone, Debug, Default)]
 pub struct ProgramScope {
-    pub scope_map: IndexMap<String, Rc<RefCell<Scope>>>,
-    pub import_names: IndexMap<String, IndexMap<String, String>>,
-    pub node_ty_map: NodeTyMap,
-    pub handler: Handler,
+    /// The package scopes mapping with the package path.
+    pub(crate) scope_map: IndexMap<String, Rc<RefCell<Scope>>>,
+    /// The import names of each file, mapping the import name to the package path.
+    pub(crate) import_names: IndexMap<String, IndexMap<String, String>>,
+    /// The types of the AST nodes.
+    pub(crate) node_ty_map: NodeTyMap,
+    /// The resolve diagnostics.
+    pub(crate) handler: Handler,
+    /// The references which can not be resolved, in the order they are found.
+    pub(crate) unresolved_refs: Vec<UnresolvedRef>,
+}
+
+/// The kind of the reference which can not be resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnresolvedRefKind {
+    /// An undefined identifier.
+    Variable,
+    /// An import of a module which can not be found.
+    Import,
+    /// A missing attribute of a schema, a module or a value.
+    Attribute,
+}
+
+/// A reference which can not be resolved, e.g., an undefined identifier or an
+/// unresolved import, see [ProgramScope::unresolved_references].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnresolvedRef {
+    /// The referenced name, i.e., the identifier, the import path or the attribute.
+    pub name: String,
+    pub kind: UnresolvedRefKind,
+    pub range: Range,
 }
 
 unsafe impl Send for ProgramScope {}
@@ -306,6 +338,107 @@ impl ProgramScope {
         self.scope_map.get(MAIN_PKG)
     }
 
+    /// Get the scope of the package.
+    #[inline]
+    pub fn package_scope(&self, pkgpath: &str) -> Option<&Rc<RefCell<Scope>>> {
+        self.scope_map.get(pkgpath)
+    }
+
+    /// Returns the schema and rule definitions of the package in the definition order.
+    pub fn schema_definitions(&self, pkgpath: &str) -> IndexMap<String, SchemaType> {
+        self.package_objects(pkgpath, |obj| match (&obj.kind, &obj.ty.kind) {
+            (ScopeObjectKind::Definition, TypeKind::Schema(schema_ty)) => Some(schema_ty.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the types of the top-level variables of the package in the definition order.
+    pub fn variable_types(&self, pkgpath: &str) -> IndexMap<String, TypeRef> {
+        self.package_objects(pkgpath, |obj| match &obj.kind {
+            ScopeObjectKind::Variable => Some(obj.ty.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the type of the top-level object in the package, e.g., a variable,
+    /// a schema, a type alias or an imported module.
+    pub fn lookup_type(&self, pkgpath: &str, name: &str) -> Option<TypeRef> {
+        self.scope_map.get(pkgpath).and_then(|scope| {
+            scope
+                .borrow()
+                .elems
+                .get(name)
+                .map(|obj| obj.borrow().ty.clone())
+        })
+    }
+
+    /// Returns the type of the AST node in the package.
+    pub fn node_type(&self, pkgpath: &str, id: &AstIndex) -> Option<TypeRef> {
+        self.node_ty_map
+            .get(&NodeKey {
+                pkgpath: pkgpath.to_string(),
+                id: id.clone(),
+            })
+            .cloned()
+    }
+
+    /// Returns the types of all the AST nodes.
+    #[inline]
+    pub fn node_types(&self) -> &NodeTyMap {
+        &self.node_ty_map
+    }
+
+    /// Returns the import names of all the files, mapping the import name to the
+    /// package path for each file.
+    #[inline]
+    pub fn imports(&self) -> &IndexMap<String, IndexMap<String, String>> {
+        &self.import_names
+    }
+
+    /// Returns the import names of the file, mapping the import name to the package path.
+    #[inline]
+    pub fn file_imports(&self, filename: &str) -> Option<&IndexMap<String, String>> {
+        self.import_names.get(filename)
+    }
+
+    /// Returns the resolve diagnostics, including the errors and the warnings.
+    #[inline]
+    pub fn diagnostics(&self) -> &IndexSet<Diagnostic> {
+        &self.handler.diagnostics
+    }
+
+    /// Returns the undefined identifiers, unresolved imports and missing attributes,
+    /// which are also reported in the diagnostics.
+    #[inline]
+    pub fn unresolved_references(&self) -> &[UnresolvedRef] {
+        &self.unresolved_refs
+    }
+
+    /// Returns whether the resolved program has any error.
+    pub fn has_errors(&self) -> bool {
+        self.handler
+            .diagnostics
+            .iter()
+            .any(|diag| matches!(diag.level, Level::Error))
+    }
+
+    /// Returns the values of the top-level objects in the package selected by `f`.
+    fn package_objects<T>(
+        &self,
+        pkgpath: &str,
+        f: impl Fn(&ScopeObject) -> Option<T>,
+    ) -> IndexMap<String, T> {
+        match self.scope_map.get(pkgpath) {
+            Some(scope) => scope
+                .borrow()
+                .elems
+                .iter()
+                .filter_map(|(name, obj)| f(&obj.borrow()).map(|value| (name.clone(), value)))
+                .collect(),
+            None => IndexMap::default(),
+        }
+    }
+
     /// Return diagnostic pretty string but do not abort if the session exists any diagnostic.
     pub fn emit_diagnostics_to_string(
         &self,
@@ -314,7 +447,7 @@ impl ProgramScope {
     ) -> Result<(), String> {
         let emit_error = || -> anyhow::Result<()> {
             // Add resolve errors into the session
-            for diag in &self.handler.diagnostics {
+            for diag in sort_diagnostics(&self.handler.diagnostics) {
                 if matches!(diag.level, Level::Error) || matches!(diag.level, Level::Suggestions) {
                     sess.add_err(diag.clone())?;
                 }
@@ -433,9 +566,15 @@ impl<'ctx> Resolver<'ctx> {
                     .cloned()
                     .collect::<Vec<String>>();
                 let suggs = suggestions::provide_suggestions(name, &names);
-                if suggs.len() > 0 {
-                    suggestion = format!(", did you mean '{:?}'?", suggs);
+                suggestion.push_str(&did_you_mean(&suggs));
+                if let Some(pkgpath) = self.importable_pkgpath(&name.replace('@', "")) {
+                    suggestion.push_str(&format!(", try 'import {}'", pkgpath));
                 }
+                self.add_unresolved_ref(
+                    &name.replace('@', ""),
+                    UnresolvedRefKind::Variable,
+                    range.clone(),
+                );
                 self.handler.add_compile_error_with_suggestions(
                     &format!(
                         "name '{}' is not defined{}",
@@ -450,6 +589,23 @@ impl<'ctx> Resolver<'ctx> {
         }
     }
 
+    /// Returns the package path which can be imported to define the name, the
+    /// name is a system module name or the last part of a package path in the
+    /// program.
+    fn importable_pkgpath(&self, name: &str) -> Option<String> {
+        if STANDARD_SYSTEM_MODULES.contains(&name) {
+            return Some(name.to_string());
+        }
+        self.program
+            .pkgs
+            .keys()
+            .filter(|pkgpath| {
+                pkgpath.as_str() != MAIN_PKG && pkgpath.rsplit('.').next() == Some(name)
+            })
+            .min()
+            .cloned()
+    }
+
     /// Set type to the scope exited object, if not found, emit a compile error.
     pub fn set_type_to_scope<T>(&mut self, name: &str, ty: TypeRef, node: &ast::Node<T>) {
         let mut scope = self.scope.borrow_mut();
@@ -470,6 +626,19 @@ impl<'ctx> Resolver<'ctx> {
         }
     }
 
+    /// Record the reference which can not be resolved, the same reference resolved
+    /// more than once is recorded once.
+    pub(crate) fn add_unresolved_ref(&mut self, name: &str, kind: UnresolvedRefKind, range: Range) {
+        let unresolved_ref = UnresolvedRef {
+            name: name.to_string(),
+            kind,
+            range,
+        };
+        if !self.unresolved_refs.contains(&unresolved_ref) {
+            self.unresolved_refs.push(unresolved_ref);
+        }
+    }
+
     /// Insert object into the current scope.
     #[inline]
     pub fn insert_object(&mut self, name: &str, obj: ScopeObject) {
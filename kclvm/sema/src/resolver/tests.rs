@@ -3,6 +3,7 @@ use super::Resolver;
 use crate::builtin::BUILTIN_FUNCTION_NAMES;
 use crate::pre_process::pre_process_program;
 use crate::resolver::resolve_program;
+use crate::resolver::resolve_program_with_emitter;
 use crate::resolver::resolve_program_with_opts;
 use crate::resolver::scope::*;
 use crate::ty::{Type, TypeKind};
@@ -174,7 +175,7 @@ fn test_resolve_program_fail() {
         let path = Path::new(work_dir).join(case);
         let mut program = parse_program(&path.to_string_lossy()).unwrap();
         let scope = resolve_program(&mut program);
-        assert!(scope.handler.diagnostics.len() > 0, "{}", case);
+        assert!(scope.diagnostics().len() > 0, "{}", case);
     }
 }
 
@@ -191,8 +192,8 @@ fn test_resolve_program_redefine() {
     .program;
 
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 2);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 2);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -208,8 +209,8 @@ fn test_resolve_program_redefine() {
 fn test_resolve_program_mismatch_type_fail() {
     let mut program = parse_program("./src/resolver/test_fail_data/config_expr.k").unwrap();
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 1);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 1);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(diag.code, Some(DiagnosticId::Error(ErrorKind::TypeError)));
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(
@@ -239,8 +240,8 @@ fn test_resolve_program_cycle_reference_fail() {
         "Module 'file2' imported but unused",
         "Module 'file1' imported but unused",
     ];
-    assert_eq!(scope.handler.diagnostics.len(), err_messages.len());
-    for (diag, msg) in scope.handler.diagnostics.iter().zip(err_messages.iter()) {
+    assert_eq!(scope.diagnostics().len(), err_messages.len());
+    for (diag, msg) in scope.diagnostics().iter().zip(err_messages.iter()) {
         assert_eq!(diag.messages[0].message, msg.to_string(),);
     }
 }
@@ -257,12 +258,7 @@ fn test_record_used_module() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    let main_scope = scope
-        .scope_map
-        .get(kclvm_runtime::MAIN_PKG_PATH)
-        .unwrap()
-        .borrow_mut()
-        .clone();
+    let main_scope = scope.main_scope().unwrap().borrow_mut().clone();
     for (_, obj) in main_scope.elems {
         let obj = obj.borrow_mut().clone();
         if let ScopeObjectKind::Module(m) = obj.kind {
@@ -281,9 +277,9 @@ fn test_record_used_module() {
 fn test_resolve_program_illegal_attr_fail() {
     let mut program = parse_program("./src/resolver/test_fail_data/attr.k").unwrap();
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 2);
+    assert_eq!(scope.diagnostics().len(), 2);
     let expect_err_msg = "A attribute must be string type, got 'Data'";
-    let diag = &scope.handler.diagnostics[0];
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::IllegalAttributeError))
@@ -291,7 +287,7 @@ fn test_resolve_program_illegal_attr_fail() {
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(diag.messages[0].range.0.line, 4);
     assert_eq!(diag.messages[0].message, expect_err_msg,);
-    let diag = &scope.handler.diagnostics[1];
+    let diag = &scope.diagnostics()[1];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::IllegalAttributeError))
@@ -305,9 +301,9 @@ fn test_resolve_program_illegal_attr_fail() {
 fn test_resolve_program_unmatched_args_fail() {
     let mut program = parse_program("./src/resolver/test_fail_data/unmatched_args.k").unwrap();
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 3);
+    assert_eq!(scope.diagnostics().len(), 3);
     let expect_err_msg = "\"Foo\" takes 1 positional argument but 3 were given";
-    let diag = &scope.handler.diagnostics[0];
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -317,7 +313,7 @@ fn test_resolve_program_unmatched_args_fail() {
     assert_eq!(diag.messages[0].message, expect_err_msg);
 
     let expect_err_msg = "\"f\" takes 1 positional argument but 2 were given";
-    let diag = &scope.handler.diagnostics[1];
+    let diag = &scope.diagnostics()[1];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -327,7 +323,7 @@ fn test_resolve_program_unmatched_args_fail() {
     assert_eq!(diag.messages[0].message, expect_err_msg);
 
     let expect_err_msg = "\"Foo2\" takes 2 positional arguments but 3 were given";
-    let diag = &scope.handler.diagnostics[2];
+    let diag = &scope.diagnostics()[2];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -342,10 +338,10 @@ fn test_resolve_program_module_optional_select_fail() {
     let mut program =
         parse_program("./src/resolver/test_fail_data/module_optional_select.k").unwrap();
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 2);
+    assert_eq!(scope.diagnostics().len(), 2);
     let expect_err_msg =
         "For the module type, the use of '?.log' is unnecessary and it can be modified as '.log'";
-    let diag = &scope.handler.diagnostics[0];
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -355,7 +351,7 @@ fn test_resolve_program_module_optional_select_fail() {
     assert_eq!(diag.messages[0].message, expect_err_msg);
 
     let expect_err_msg = "Module 'math' imported but unused";
-    let diag = &scope.handler.diagnostics[1];
+    let diag = &scope.diagnostics()[1];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Warning(WarningKind::UnusedImportWarning))
@@ -365,6 +361,31 @@ fn test_resolve_program_module_optional_select_fail() {
     assert_eq!(diag.messages[0].message, expect_err_msg);
 }
 
+#[test]
+fn test_resolve_deprecated_attr() {
+    let mut program = parse_program("./src/resolver/test_data/deprecated_attr.k").unwrap();
+    let scope = resolve_program(&mut program);
+    assert!(!scope.has_errors());
+    let warnings: Vec<_> = scope
+        .diagnostics()
+        .iter()
+        .filter(|diag| diag.code == Some(DiagnosticId::Warning(WarningKind::DeprecatedWarning)))
+        .map(|diag| {
+            (
+                diag.messages[0].range.0.line,
+                diag.messages[0].message.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (9, "name is deprecated: use fullName instead".to_string()),
+            (13, "nickname is deprecated".to_string()),
+        ]
+    );
+}
+
 #[test]
 fn test_lint() {
     let sess = Arc::new(ParseSession::default());
@@ -466,12 +487,7 @@ fn test_lint() {
 fn test_resolve_schema_doc() {
     let mut program = parse_program("./src/resolver/test_data/doc.k").unwrap();
     let scope = resolve_program(&mut program);
-    let main_scope = scope
-        .scope_map
-        .get(kclvm_runtime::MAIN_PKG_PATH)
-        .unwrap()
-        .borrow_mut()
-        .clone();
+    let main_scope = scope.main_scope().unwrap().borrow_mut().clone();
 
     let schema_scope_obj = &main_scope.elems[0].borrow().clone();
     let schema_summary = match &schema_scope_obj.ty.kind {
@@ -524,14 +540,9 @@ fn test_pkg_scope() {
     .program;
     let scope = resolve_program(&mut program);
 
-    assert_eq!(scope.scope_map.len(), 2);
-    let main_scope = scope
-        .scope_map
-        .get(kclvm_runtime::MAIN_PKG_PATH)
-        .unwrap()
-        .borrow_mut()
-        .clone();
-    let pkg_scope = scope.scope_map.get("pkg").unwrap().borrow_mut().clone();
+    assert_eq!(scope.pkgpaths().len(), 2);
+    let main_scope = scope.main_scope().unwrap().borrow_mut().clone();
+    let pkg_scope = scope.package_scope("pkg").unwrap().borrow_mut().clone();
 
     let root = &program.root.clone();
     let filename = Path::new(&root.clone())
@@ -562,6 +573,62 @@ fn test_pkg_scope() {
     assert!(pkg_scope.contains_pos(&pos));
 }
 
+#[test]
+fn test_program_scope_accessors() {
+    let sess = Arc::new(ParseSession::default());
+    let mut program = load_program(
+        sess.clone(),
+        &["./src/resolver/test_data/embed_api.k"],
+        None,
+        None,
+    )
+    .unwrap()
+    .program;
+    let scope = resolve_program(&mut program);
+    assert!(!scope.has_errors(), "{:?}", scope.diagnostics());
+
+    let schemas = scope.schema_definitions(kclvm_ast::MAIN_PKG);
+    assert_eq!(
+        schemas.keys().collect::<Vec<&String>>(),
+        vec!["Server", "Backend"]
+    );
+    let backend = &schemas["Backend"];
+    assert_eq!(backend.base.as_ref().unwrap().name, "Server");
+    assert!(backend.attrs.contains_key("replicas"));
+    assert!(!backend.is_instance);
+    let pkg_schemas = scope.schema_definitions("pkg");
+    assert_eq!(
+        pkg_schemas.keys().collect::<Vec<&String>>(),
+        vec!["Name", "Person"]
+    );
+    assert!(scope.schema_definitions("not_found").is_empty());
+
+    let variables = scope.variable_types(kclvm_ast::MAIN_PKG);
+    assert_eq!(
+        variables.keys().collect::<Vec<&String>>(),
+        vec!["owner", "backend", "port"]
+    );
+    assert!(variables["owner"].is_schema());
+    assert_eq!(variables["backend"].ty_str(), "Backend");
+    assert_eq!(variables["port"].ty_str(), "int");
+    assert_eq!(
+        scope
+            .lookup_type(kclvm_ast::MAIN_PKG, "port")
+            .unwrap()
+            .ty_str(),
+        "int"
+    );
+    assert!(scope.lookup_type(kclvm_ast::MAIN_PKG, "missing").is_none());
+
+    let (filename, imports) = scope
+        .imports()
+        .iter()
+        .find(|(filename, _)| filename.ends_with("embed_api.k"))
+        .unwrap();
+    assert_eq!(scope.file_imports(filename), Some(imports));
+    assert_eq!(imports.get("pkg").map(|p| p.as_str()), Some("pkg"));
+}
+
 #[test]
 fn test_system_package() {
     let sess = Arc::new(ParseSession::default());
@@ -574,12 +641,7 @@ fn test_system_package() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    let main_scope = scope
-        .scope_map
-        .get(kclvm_runtime::MAIN_PKG_PATH)
-        .unwrap()
-        .borrow_mut()
-        .clone();
+    let main_scope = scope.main_scope().unwrap().borrow_mut().clone();
 
     assert!(main_scope.lookup("base64").unwrap().borrow().ty.is_module());
     assert!(main_scope
@@ -608,8 +670,8 @@ fn test_resolve_program_import_suggest() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 2);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 2);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -617,7 +679,57 @@ fn test_resolve_program_import_suggest() {
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(
         diag.messages[0].message,
-        "name 's' is not defined, did you mean '[\"s1\"]'?"
+        "name 's' is not defined, did you mean 's1'?"
+    );
+}
+
+#[test]
+fn test_resolve_program_import_pkg_suggest() {
+    let sess = Arc::new(ParseSession::default());
+    let mut program = load_program(
+        sess.clone(),
+        &[
+            "./src/resolver/test_fail_data/import_suggest/main.k",
+            "./src/resolver/test_fail_data/import_suggest/other.k",
+        ],
+        None,
+        None,
+    )
+    .unwrap()
+    .program;
+    let scope = resolve_program(&mut program);
+    let messages: Vec<String> = scope
+        .diagnostics()
+        .iter()
+        .flat_map(|diag| diag.messages.iter().map(|m| m.message.clone()))
+        .collect();
+    assert!(messages
+        .iter()
+        .any(|m| m.starts_with("name 'pkg' is not defined") && m.ends_with(", try 'import pkg'")));
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.starts_with("name 'math' is not defined")
+                && m.ends_with(", try 'import math'"))
+    );
+}
+
+#[test]
+fn test_resolve_program_attr_suggest() {
+    let sess = Arc::new(ParseSession::default());
+    let mut program = load_program(
+        sess.clone(),
+        &["./src/resolver/test_fail_data/attr_suggest.k"],
+        None,
+        None,
+    )
+    .unwrap()
+    .program;
+    let scope = resolve_program(&mut program);
+    let diag = &scope.diagnostics()[0];
+    assert_eq!(
+        diag.messages[0].message,
+        "Cannot add member 'nmae' to schema 'Person', did you mean 'name'?"
     );
 }
 
@@ -633,7 +745,7 @@ fn test_resolve_assignment_in_lambda() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    let main_scope = scope.scope_map.get("__main__").unwrap().clone();
+    let main_scope = scope.main_scope().unwrap().clone();
     assert_eq!(main_scope.borrow().children.len(), 1);
     let lambda_scope = main_scope.borrow().children[0].clone();
     assert_eq!(lambda_scope.borrow().elems.len(), 2);
@@ -653,7 +765,7 @@ fn test_resolve_function_with_default_values() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert!(!scope.handler.has_errors());
+    assert!(!scope.has_errors());
     let main_scope = scope.main_scope().unwrap();
     let func = main_scope.borrow().lookup("is_alpha").unwrap();
     assert!(func.borrow().ty.is_func());
@@ -677,7 +789,7 @@ fn test_assignment_type_annotation_check_in_lambda() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 0);
+    assert_eq!(scope.diagnostics().len(), 0);
 }
 
 #[test]
@@ -692,8 +804,8 @@ fn test_resolve_lambda_assignment_diagnostic() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 1);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 1);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(diag.code, Some(DiagnosticId::Error(ErrorKind::TypeError)));
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(
@@ -714,8 +826,8 @@ fn test_ty_check_in_dict_assign_to_schema() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 2);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 2);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(diag.code, Some(DiagnosticId::Error(ErrorKind::TypeError)));
     assert_eq!(diag.messages.len(), 2);
     assert_eq!(diag.messages[0].message, "expected int, got str(1)");
@@ -725,6 +837,78 @@ fn test_ty_check_in_dict_assign_to_schema() {
     );
 }
 
+#[test]
+fn test_unresolved_references() {
+    let sess = Arc::new(ParseSession::default());
+    let mut program = load_program(
+        sess.clone(),
+        &["./src/resolver/test_data/unresolved_refs.k"],
+        None,
+        None,
+    )
+    .unwrap()
+    .program;
+    let refs = crate::resolver::unresolved_references(&mut program);
+    assert_eq!(
+        refs.iter()
+            .map(|r| (r.name.as_str(), r.kind, r.range.0.line))
+            .collect::<Vec<_>>(),
+        vec![
+            ("not_found_pkg", UnresolvedRefKind::Import, 1),
+            ("undefined_name", UnresolvedRefKind::Variable, 7),
+            ("age", UnresolvedRefKind::Attribute, 8),
+        ]
+    );
+    assert!(refs[1].range.0.filename.ends_with("unresolved_refs.k"));
+}
+
+#[test]
+fn test_resolve_program_with_emitter() {
+    let load = || {
+        load_program(
+            Arc::new(ParseSession::default()),
+            &["./src/resolver/test_data/stream_diags.k"],
+            None,
+            None,
+        )
+        .unwrap()
+        .program
+    };
+    let batched = resolve_program(&mut load()).diagnostics().clone();
+    let streamed = Arc::new(Mutex::new(vec![]));
+    let emitted = streamed.clone();
+    let scope = resolve_program_with_emitter(
+        &mut load(),
+        Options::default(),
+        Arc::new(move |pkgpath, diags| {
+            emitted
+                .lock()
+                .unwrap()
+                .push((pkgpath.to_string(), diags.to_vec()))
+        }),
+    );
+    let streamed = streamed.lock().unwrap();
+    // The diagnostics of the imported packages are emitted before the main package.
+    let pkgpaths: Vec<&str> = streamed
+        .iter()
+        .map(|(pkgpath, _)| pkgpath.as_str())
+        .collect();
+    assert_eq!(
+        pkgpaths[..3],
+        ["stream_diags.base", "stream_diags.app", "__main__"]
+    );
+    assert!(streamed[0].1.iter().all(|diag| diag.is_error()));
+    // The streamed diagnostics are the same as the batched ones when sorted.
+    let streamed: Vec<Diagnostic> = streamed
+        .iter()
+        .flat_map(|(_, diags)| diags.iter().cloned())
+        .collect();
+    assert!(batched.iter().any(|diag| diag.level == Level::Warning));
+    assert_eq!(streamed.len(), batched.len());
+    assert_eq!(sort_diagnostics(&streamed), sort_diagnostics(&batched));
+    assert_eq!(scope.diagnostics(), &batched);
+}
+
 #[test]
 fn test_pkg_not_found_suggestion() {
     let sess = Arc::new(ParseSession::default());
@@ -737,15 +921,15 @@ fn test_pkg_not_found_suggestion() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 4);
-    let diag = &scope.handler.diagnostics[1];
+    assert_eq!(scope.diagnostics().len(), 4);
+    let diag = &scope.diagnostics()[1];
     assert_eq!(diag.code, Some(DiagnosticId::Suggestions));
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(
         diag.messages[0].message,
         "try 'kcl mod add k9s' to download the package not found"
     );
-    let diag = &scope.handler.diagnostics[2];
+    let diag = &scope.diagnostics()[2];
     assert_eq!(diag.code, Some(DiagnosticId::Suggestions));
     assert_eq!(diag.messages.len(), 1);
     assert_eq!(
@@ -766,7 +950,7 @@ fn undef_lambda_param() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 1);
+    assert_eq!(scope.diagnostics().len(), 1);
 
     let root = &program.root.clone();
     let filename = Path::new(&root.clone())
@@ -774,7 +958,7 @@ fn undef_lambda_param() {
         .display()
         .to_string();
 
-    let range = scope.handler.diagnostics[0].messages[0].range.clone();
+    let range = scope.diagnostics()[0].messages[0].range.clone();
 
     assert_eq!(
         range,
@@ -805,8 +989,8 @@ fn test_schema_params_count() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    assert_eq!(scope.handler.diagnostics.len(), 1);
-    let diag = &scope.handler.diagnostics[0];
+    assert_eq!(scope.diagnostics().len(), 1);
+    let diag = &scope.diagnostics()[0];
     assert_eq!(
         diag.code,
         Some(DiagnosticId::Error(ErrorKind::CompileError))
@@ -856,7 +1040,7 @@ fn test_pkg_asname() {
     .unwrap()
     .program;
     let scope = resolve_program(&mut program);
-    let diags = scope.handler.diagnostics;
+    let diags = scope.diagnostics();
     assert_eq!(diags.len(), 6);
     assert_eq!(diags[0].messages[0].message, "name 'pkg' is not defined");
     assert_eq!(diags[2].messages[0].message, "name 'subpkg' is not defined");
@@ -881,7 +1065,7 @@ fn test_builtin_file_invalid() {
             .unwrap()
             .program;
         let scope = resolve_program(&mut program);
-        let diags = scope.handler.diagnostics;
+        let diags = scope.diagnostics();
         assert_eq!(diags.len(), 1);
         assert_eq!(diags[0].messages[0].message, *expected_message);
     }
@@ -257,6 +257,7 @@ impl SchemaType {
                     range: (Position::dummy_pos(), Position::dummy_pos()),
                     doc: None,
                     decorators: vec![],
+                    deprecated: None,
                 };
                 self.attrs.insert(attr.to_string(), schema_attr);
             }
@@ -330,6 +331,9 @@ pub struct SchemaAttr {
     pub range: Range,
     pub doc: Option<String>,
     pub decorators: Vec<Decorator>,
+    /// The deprecation message of the attribute annotated with `@deprecated`, which
+    /// is empty when the decorator has no message.
+    pub deprecated: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -120,6 +120,7 @@ pub(crate) fn convert_code_to_kcl_diag_id(code: &NumberOrString) -> Option<Diagn
             "ImportPositionWarning" => {
                 Some(DiagnosticId::Warning(WarningKind::ImportPositionWarning))
             }
+            "DeprecatedWarning" => Some(DiagnosticId::Warning(WarningKind::DeprecatedWarning)),
             _ => None,
         },
     }
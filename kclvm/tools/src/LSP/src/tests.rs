@@ -262,7 +262,7 @@ fn build_expect_diags() -> Vec<Diagnostic> {
         ),
         build_lsp_diag(
             (10, 8, 10, 10),
-            "name 'nu' is not defined, did you mean '[\"number\", \"n\", \"num\"]'?".to_string(),
+            "name 'nu' is not defined, did you mean 'number', 'n' or 'num'?".to_string(),
             Some(DiagnosticSeverity::ERROR),
             vec![],
             Some(NumberOrString::String("CompileError".to_string())),
@@ -0,0 +1,277 @@
+//! [kclvm_tools::coverage] module mainly contains the function to report which
+//! schema attributes are exercised by a data set, the main API function is
+//! `schema_coverage`, which helps to find the dead schema attributes.
+//!
+//! Every JSON or YAML data file is bound to the schema and evaluated like
+//! `kcl vet`. An attribute is set by a data file when the data provides it and
+//! it holds a non-None value in the evaluated instance, and the data file which
+//! holds a top-level list binds every list item to the schema.
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use kclvm_ast::ast::{Expr, NameConstant, NodeRef};
+use kclvm_query::query::{get_schema_type, GetSchemaOption};
+use kclvm_runner::{execute_module, MapErrorResult};
+use serde_json::{json, Value};
+
+use crate::instance::schema_attrs;
+use crate::util::loader::LoaderKind;
+use crate::vet::expr_builder::ExprBuilder;
+use crate::vet::validator::build_assign;
+
+#[cfg(test)]
+mod tests;
+
+/// The attribute name which the data is bound to in the evaluated program.
+const COVERAGE_ATTR_NAME: &str = "value";
+
+/// The coverage report output format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CoverageFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl TryFrom<&str> for CoverageFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_lowercase().as_str() {
+            "table" => Ok(CoverageFormat::Table),
+            "json" => Ok(CoverageFormat::Json),
+            _ => Err(anyhow::anyhow!(
+                "unsupported coverage format '{format}', expected one of table and json"
+            )),
+        }
+    }
+}
+
+/// The set and unset counts of a schema attribute.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttrCoverage {
+    pub name: String,
+    pub set: usize,
+    pub unset: usize,
+}
+
+/// The coverage of the schema attributes by a data set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub schema: String,
+    /// The data files bound to the schema.
+    pub files: Vec<String>,
+    /// The number of the schema instances in the data files.
+    pub instances: usize,
+    /// The attributes in the schema definition order, including the inherited ones.
+    pub attrs: Vec<AttrCoverage>,
+}
+
+impl CoverageReport {
+    /// Returns the attributes which are not set by any data file.
+    pub fn unused_attrs(&self) -> Vec<&str> {
+        self.attrs
+            .iter()
+            .filter(|attr| attr.set == 0)
+            .map(|attr| attr.name.as_str())
+            .collect()
+    }
+
+    /// Render the report in the output format.
+    pub fn render(&self, format: &CoverageFormat) -> Result<String> {
+        match format {
+            CoverageFormat::Table => Ok(self.to_table()),
+            CoverageFormat::Json => Ok(serde_json::to_string_pretty(&self.to_json())?),
+        }
+    }
+
+    /// Render the report to the markdown table.
+    pub fn to_table(&self) -> String {
+        let mut table = format!(
+            "schema {}: {} instance(s) in {} file(s)\n\n",
+            self.schema,
+            self.instances,
+            self.files.len()
+        );
+        table.push_str("| attribute | set | unset |\n");
+        table.push_str("| --- | --- | --- |\n");
+        for attr in &self.attrs {
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                attr.name, attr.set, attr.unset
+            ));
+        }
+        table
+    }
+
+    /// Render the report to the JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schema": self.schema,
+            "files": self.files,
+            "instances": self.instances,
+            "attributes": self.attrs.iter().map(|attr| json!({
+                "name": attr.name,
+                "set": attr.set,
+                "unset": attr.unset,
+            })).collect::<Vec<Value>>(),
+            "unused": self.unused_attrs(),
+        })
+    }
+}
+
+/// Report the coverage of the attributes of the schema named `schema_name` in the
+/// KCL file by the data files. The `data_path` is a data file or a directory of the
+/// `*.json`, `*.yaml` and `*.yml` data files. The schema name can be omitted when
+/// the KCL file defines only one schema.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kclvm_tools::coverage::schema_coverage;
+///
+/// let report = schema_coverage("schema.k", Some("App"), "data").unwrap();
+/// println!("{}", report.to_table());
+/// ```
+pub fn schema_coverage(
+    file: &str,
+    schema_name: Option<&str>,
+    data_path: &str,
+) -> Result<CoverageReport> {
+    let schemas = get_schema_type(file, None, schema_name, GetSchemaOption::Definitions)?;
+    let schema = match schema_name {
+        Some(name) => schemas.values().find(|s| s.name == name),
+        None if schemas.len() == 1 => schemas.values().next(),
+        None => bail!(
+            "{} schemas are defined in {}, specify the schema name with --schema",
+            schemas.len(),
+            file
+        ),
+    }
+    .ok_or_else(|| {
+        anyhow::anyhow!(
+            "schema '{}' not found in {}",
+            schema_name.unwrap_or_default(),
+            file
+        )
+    })?;
+    let mut report = CoverageReport {
+        schema: schema.name.clone(),
+        attrs: schema_attrs(schema)
+            .into_iter()
+            .map(|(name, _)| AttrCoverage {
+                name: name.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    for data_file in data_files(data_path)? {
+        let instances = bind_data_file(file, &schema.name, &data_file)
+            .with_context(|| format!("failed to bind {} to the schema", data_file.display()))?;
+        for set_attrs in instances {
+            report.instances += 1;
+            for attr in &mut report.attrs {
+                if set_attrs.contains(&attr.name) {
+                    attr.set += 1;
+                } else {
+                    attr.unset += 1;
+                }
+            }
+        }
+        report.files.push(data_file.display().to_string());
+    }
+    Ok(report)
+}
+
+/// Returns the data file or the data files in the directory sorted by the path.
+fn data_files(data_path: &str) -> Result<Vec<PathBuf>> {
+    let path = Path::new(data_path);
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(path)? {
+        let file = entry?.path();
+        if file.is_file() && loader_kind(&file).is_some() {
+            files.push(file);
+        }
+    }
+    if files.is_empty() {
+        bail!("no JSON or YAML data file is found in {}", data_path);
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the loader kind by the file extension.
+fn loader_kind(file: &Path) -> Option<LoaderKind> {
+    match file.extension()?.to_str()?.to_lowercase().as_str() {
+        "json" => Some(LoaderKind::JSON),
+        "yaml" | "yml" => Some(LoaderKind::YAML),
+        _ => None,
+    }
+}
+
+/// Bind the data file to the schema, evaluate it and returns the attributes set by
+/// each instance in the data file.
+fn bind_data_file(file: &str, schema_name: &str, data_file: &Path) -> Result<Vec<Vec<String>>> {
+    let kind = loader_kind(data_file).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unsupported data file {}, expected a JSON or YAML file",
+            data_file.display()
+        )
+    })?;
+    let expr = ExprBuilder::new_with_file_path(kind, data_file.display().to_string())?
+        .build(Some(schema_name.to_string()))?;
+    let provided = match &expr.node {
+        Expr::List(list) => list.elts.iter().map(provided_attrs).collect(),
+        _ => vec![provided_attrs(&expr)],
+    };
+    let mut module = kclvm_parser::parse_file_force_errors(file, None)?;
+    module
+        .body
+        .insert(0, build_assign(COVERAGE_ATTR_NAME, expr));
+    let result = execute_module(module)?.map_err_to_result()?;
+    let value = serde_json::from_str::<Value>(&result.json_result)?
+        .get(COVERAGE_ATTR_NAME)
+        .cloned()
+        .unwrap_or_default();
+    let instances = match value {
+        Value::Array(items) => items,
+        value => vec![value],
+    };
+    Ok(provided
+        .into_iter()
+        .zip(instances)
+        .map(|(attrs, instance)| {
+            attrs
+                .into_iter()
+                .filter(|attr| !instance.get(attr).map_or(true, Value::is_null))
+                .collect()
+        })
+        .collect())
+}
+
+/// Returns the attributes which are provided with the non-None values by the data.
+fn provided_attrs(expr: &NodeRef<Expr>) -> Vec<String> {
+    let config = match &expr.node {
+        Expr::Schema(schema_expr) => &schema_expr.config,
+        _ => return vec![],
+    };
+    let items = match &config.node {
+        Expr::Config(config_expr) => &config_expr.items,
+        _ => return vec![],
+    };
+    items
+        .iter()
+        .filter_map(|item| match (&item.node.key, &item.node.value.node) {
+            (_, Expr::NameConstantLit(lit)) if lit.value == NameConstant::None => None,
+            (Some(key), _) => match &key.node {
+                Expr::StringLit(key) => Some(key.value.clone()),
+                _ => None,
+            },
+            (None, _) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+use super::*;
+
+const TEST_FILE: &str = "./src/coverage/test_data/schema.k";
+const TEST_DATA_DIR: &str = "./src/coverage/test_data/data";
+
+#[test]
+fn test_schema_coverage() {
+    let report = schema_coverage(TEST_FILE, Some("App"), TEST_DATA_DIR).unwrap();
+    assert_eq!(report.schema, "App");
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.instances, 2);
+    let counts = report
+        .attrs
+        .iter()
+        .map(|attr| (attr.name.as_str(), attr.set, attr.unset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        counts,
+        vec![
+            ("metadata", 2, 0),
+            ("image", 2, 0),
+            ("replicas", 1, 1),
+            ("port", 1, 1),
+            // The None value does not set the attribute.
+            ("debug", 0, 2),
+        ]
+    );
+    assert_eq!(report.unused_attrs(), vec!["debug"]);
+    let table = report.render(&CoverageFormat::Table).unwrap();
+    assert!(
+        table.starts_with("schema App: 2 instance(s) in 2 file(s)\n"),
+        "{table}"
+    );
+    assert!(table.contains("| replicas | 1 | 1 |\n"), "{table}");
+    let json = report.to_json();
+    assert_eq!(
+        json["attributes"][2],
+        json!({"name": "replicas", "set": 1, "unset": 1})
+    );
+    assert_eq!(json["unused"], json!(["debug"]));
+}
+
+#[test]
+fn test_schema_coverage_with_single_file() {
+    let data_file = format!("{}/app-a.json", TEST_DATA_DIR);
+    let report = schema_coverage(TEST_FILE, Some("App"), &data_file).unwrap();
+    assert_eq!(report.instances, 1);
+    assert_eq!(report.unused_attrs(), vec!["port", "debug"]);
+}
+
+#[test]
+fn test_schema_coverage_errors() {
+    let err = schema_coverage(TEST_FILE, None, TEST_DATA_DIR)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("specify the schema name with --schema"),
+        "{err}"
+    );
+    let err = schema_coverage(TEST_FILE, Some("NotFound"), TEST_DATA_DIR)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("schema 'NotFound' not found"), "{err}");
+    assert!(CoverageFormat::try_from("csv").is_err());
+}
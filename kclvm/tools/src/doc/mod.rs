@@ -0,0 +1,121 @@
+//! [kclvm_tools::doc] module mainly contains some functions of schema document
+//! generation, the main API function is `gen_doc`, which accepts a KCL file and
+//! returns the document of all schema definitions in it.
+//!
+//! The basic principle is to resolve the KCL program and extract the schema
+//! definitions including the name, attributes, types, default values and the doc
+//! strings attached to the schemas and their attributes.
+use anyhow::Result;
+use kclvm_query::query::{get_schema_type, GetSchemaOption};
+use kclvm_sema::ty::SchemaType;
+use serde_json::{json, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// The document output format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+impl TryFrom<&str> for DocFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(DocFormat::Markdown),
+            "json" => Ok(DocFormat::Json),
+            _ => Err(anyhow::anyhow!(
+                "unsupported doc format '{format}', expected one of markdown and json"
+            )),
+        }
+    }
+}
+
+/// Generate the document of all the schema definitions in the KCL file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kclvm_tools::doc::{gen_doc, DocFormat};
+///
+/// let doc = gen_doc("main.k", &DocFormat::Markdown).unwrap();
+/// ```
+pub fn gen_doc(file: &str, format: &DocFormat) -> Result<String> {
+    let schemas = get_schema_type(file, None, None, GetSchemaOption::Definitions)?;
+    let schemas = schemas.values().collect::<Vec<&SchemaType>>();
+    match format {
+        DocFormat::Markdown => Ok(gen_markdown(&schemas)),
+        DocFormat::Json => Ok(serde_json::to_string_pretty(&gen_json(&schemas))?),
+    }
+}
+
+/// Render schema definitions to the markdown document.
+pub fn gen_markdown(schemas: &[&SchemaType]) -> String {
+    let mut doc = String::new();
+    for schema in schemas {
+        doc.push_str(&format!("## {}\n\n", schema.name));
+        if !schema.doc.is_empty() {
+            doc.push_str(&format!("{}\n\n", schema.doc));
+        }
+        if let Some(base) = &schema.base {
+            doc.push_str(&format!("Inherits: `{}`\n\n", base.name));
+        }
+        if !schema.attrs.is_empty() {
+            doc.push_str("### Attributes\n\n");
+            doc.push_str("| name | type | description | default value | required |\n");
+            doc.push_str("| --- | --- | --- | --- | --- |\n");
+            for (name, attr) in &schema.attrs {
+                doc.push_str(&format!(
+                    "| **{}** | `{}` | {} | {} | {} |\n",
+                    name,
+                    attr.ty.ty_str(),
+                    escape_table_cell(attr.doc.as_deref().unwrap_or_default()),
+                    attr.default
+                        .as_ref()
+                        .map(|d| format!("`{}`", escape_table_cell(d)))
+                        .unwrap_or_default(),
+                    if attr.is_optional {
+                        "optional"
+                    } else {
+                        "required"
+                    }
+                ));
+            }
+            doc.push('\n');
+        }
+    }
+    doc
+}
+
+/// Render schema definitions to the JSON document.
+pub fn gen_json(schemas: &[&SchemaType]) -> Value {
+    Value::Array(
+        schemas
+            .iter()
+            .map(|schema| {
+                json!({
+                    "name": schema.name,
+                    "pkgpath": schema.pkgpath,
+                    "doc": schema.doc,
+                    "base": schema.base.as_ref().map(|base| base.name.clone()),
+                    "attributes": schema.attrs.iter().map(|(name, attr)| json!({
+                        "name": name,
+                        "type": attr.ty.ty_str(),
+                        "doc": attr.doc,
+                        "default": attr.default,
+                        "required": !attr.is_optional,
+                    })).collect::<Vec<Value>>(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Escape the pipes and line breaks in the markdown table cell.
+fn escape_table_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', "<br>")
+}
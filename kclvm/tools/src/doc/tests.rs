@@ -0,0 +1,37 @@
+use super::*;
+
+const TEST_FILE: &str = "./src/doc/test_data/schema.k";
+
+#[test]
+fn test_gen_markdown_doc() {
+    let doc = gen_doc(TEST_FILE, &DocFormat::Markdown).unwrap();
+    assert!(doc.contains("## Server"));
+    assert!(doc.contains("Server is the common user interface for long-running services."));
+    assert!(
+        doc.contains("| **name** | `str` | The name of the long-running service. |  | required |")
+    );
+    assert!(doc.contains(
+        "| **replicas** | `int` | The number of the service replicas. | `1` | optional |"
+    ));
+}
+
+#[test]
+fn test_gen_json_doc() {
+    let doc = gen_doc(TEST_FILE, &DocFormat::Json).unwrap();
+    let doc: Value = serde_json::from_str(&doc).unwrap();
+    assert_eq!(doc[0]["name"], "Server");
+    assert_eq!(doc[0]["attributes"][0]["name"], "name");
+    assert_eq!(
+        doc[0]["attributes"][0]["doc"],
+        "The name of the long-running service."
+    );
+    assert_eq!(doc[0]["attributes"][1]["default"], "1");
+    assert_eq!(doc[0]["attributes"][1]["required"], false);
+}
+
+#[test]
+fn test_doc_format() {
+    assert_eq!(DocFormat::try_from("md").unwrap(), DocFormat::Markdown);
+    assert_eq!(DocFormat::try_from("JSON").unwrap(), DocFormat::Json);
+    assert!(DocFormat::try_from("html").is_err());
+}
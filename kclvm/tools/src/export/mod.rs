@@ -0,0 +1,162 @@
+//! [kclvm_tools::export] module mainly contains the functions to export the
+//! schemas in a KCL file as the type definitions of other languages, the main
+//! API function is `export`.
+//!
+//! Every schema definition and the schemas referenced by its attributes are
+//! exported, the types without the equivalents in the target language are
+//! exported as the dynamic value types of the target language.
+use anyhow::{bail, Result};
+use kclvm_error::{Message, Position, Style};
+use kclvm_query::query::{get_schema_type, GetSchemaOption};
+use kclvm_sema::ty::{SchemaAttr, SchemaType};
+
+mod proto;
+mod rust;
+#[cfg(test)]
+mod tests;
+
+/// The header comment of the generated code.
+const GENERATED_HEADER: &str = "// Code generated by kcl export, DO NOT EDIT.\n";
+
+/// The target language of the exported type definitions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTarget {
+    #[default]
+    Rust,
+    Proto,
+}
+
+impl TryFrom<&str> for ExportTarget {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "rust" | "rs" => Ok(ExportTarget::Rust),
+            "proto" | "protobuf" => Ok(ExportTarget::Proto),
+            _ => bail!(
+                "unsupported export target '{}', expected rust or proto",
+                value
+            ),
+        }
+    }
+}
+
+/// The exported code and the warnings of the features which are not exported.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExportResult {
+    pub code: String,
+    pub warnings: Vec<Message>,
+}
+
+/// Export the schema definitions in the KCL file as the type definitions of the target language.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kclvm_tools::export::{export, ExportTarget};
+///
+/// let result = export("schema.k", ExportTarget::Rust).unwrap();
+/// println!("{}", result.code);
+/// ```
+pub fn export(file: &str, target: ExportTarget) -> Result<ExportResult> {
+    let schemas = get_schema_type(file, None, None, GetSchemaOption::Definitions)?;
+    let schemas: Vec<SchemaType> = schemas.into_values().filter(is_data_schema).collect();
+    let mut exporter = Exporter {
+        schemas,
+        warnings: vec![],
+    };
+    let code = match target {
+        ExportTarget::Rust => rust::gen_rust_structs(&mut exporter),
+        ExportTarget::Proto => proto::gen_proto_messages(&mut exporter),
+    };
+    Ok(ExportResult {
+        code,
+        warnings: exporter.warnings,
+    })
+}
+
+/// Exporter holds the schemas to export and the warnings of the target generators.
+pub(crate) struct Exporter {
+    /// The schemas to export, the schemas referenced by the attributes are
+    /// appended when the types are exported.
+    schemas: Vec<SchemaType>,
+    warnings: Vec<Message>,
+}
+
+impl Exporter {
+    /// Add the schema referenced by an attribute type to be exported.
+    fn add_schema(&mut self, schema: &SchemaType) {
+        if !self.schemas.iter().any(|s| s.name == schema.name) {
+            self.schemas.push(schema.clone());
+        }
+    }
+
+    /// Add a warning at the range of the attribute.
+    fn add_warning(&mut self, attr: Option<&SchemaAttr>, message: String) {
+        self.warnings.push(Message {
+            range: attr.map_or((Position::dummy_pos(), Position::dummy_pos()), |attr| {
+                attr.range.clone()
+            }),
+            style: Style::LineAndColumn,
+            message,
+            note: None,
+            suggested_replacement: None,
+        });
+    }
+}
+
+/// Mixins, protocols and rules do not hold the output data.
+fn is_data_schema(schema: &SchemaType) -> bool {
+    !schema.is_mixin && !schema.is_protocol && !schema.is_rule
+}
+
+/// Get all the schema attributes including the inherited ones.
+fn schema_attrs(schema: &SchemaType) -> Vec<(&String, &SchemaAttr)> {
+    let mut attrs = match &schema.base {
+        Some(base) => schema_attrs(base),
+        None => vec![],
+    };
+    for (name, attr) in &schema.attrs {
+        match attrs.iter().position(|(n, _)| *n == name) {
+            Some(index) => attrs[index] = (name, attr),
+            None => attrs.push((name, attr)),
+        }
+    }
+    attrs
+}
+
+/// Get the snake case identifier of the attribute name, e.g., `apiVersion` -> `api_version`.
+fn snake_case_name(name: &str) -> String {
+    let mut snake_name = String::new();
+    let mut prev_lowercase = false;
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if prev_lowercase {
+                snake_name.push('_');
+            }
+            snake_name.extend(c.to_lowercase());
+            prev_lowercase = false;
+        } else if c.is_alphanumeric() || c == '_' {
+            snake_name.push(c);
+            prev_lowercase = c.is_lowercase() || c.is_ascii_digit();
+        } else {
+            snake_name.push('_');
+            prev_lowercase = false;
+        }
+    }
+    if snake_name.is_empty() || snake_name.starts_with(|c: char| c.is_ascii_digit()) {
+        snake_name.insert(0, '_');
+    }
+    snake_name
+}
+
+/// Get the line comments of the document with the comment prefix e.g., `///` and `//`.
+fn doc_comment(doc: &str, indent: &str, prefix: &str) -> String {
+    doc.trim()
+        .lines()
+        .map(|line| match line.trim() {
+            "" => format!("{}{}\n", indent, prefix),
+            line => format!("{}{} {}\n", indent, prefix, line),
+        })
+        .collect()
+}
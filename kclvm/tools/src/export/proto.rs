@@ -0,0 +1,153 @@
+//! Export the schemas as the proto3 messages. The features without protobuf
+//! equivalents, e.g., the union types and the index signatures, are reported as
+//! warnings, and the attributes of the unsupported types are exported as
+//! `google.protobuf.Value`.
+use kclvm_sema::ty::{SchemaAttr, SchemaType, Type, TypeKind};
+
+use super::{
+    doc_comment, is_data_schema, schema_attrs, snake_case_name, Exporter, GENERATED_HEADER,
+};
+
+const INDENT: &str = "  ";
+const PROTO_VALUE_TYPE: &str = "google.protobuf.Value";
+const PROTO_VALUE_IMPORT: &str = "google/protobuf/struct.proto";
+
+/// The protobuf field type of a schema attribute.
+enum FieldType {
+    Single(String),
+    Repeated(String),
+    Map(String),
+}
+
+/// Generate the proto3 messages of the schemas, the schemas referenced by the
+/// attributes are generated after the definitions.
+pub(crate) fn gen_proto_messages(exporter: &mut Exporter) -> String {
+    let mut messages = String::new();
+    let mut index = 0;
+    while index < exporter.schemas.len() {
+        let schema = exporter.schemas[index].clone();
+        messages.push('\n');
+        messages.push_str(&gen_proto_message(&schema, exporter));
+        index += 1;
+    }
+    let mut code = format!("{}\nsyntax = \"proto3\";\n", GENERATED_HEADER);
+    if messages.contains(PROTO_VALUE_TYPE) {
+        code.push_str(&format!("\nimport \"{}\";\n", PROTO_VALUE_IMPORT));
+    }
+    code.push_str(&messages);
+    code
+}
+
+fn gen_proto_message(schema: &SchemaType, exporter: &mut Exporter) -> String {
+    let mut code = doc_comment(&schema.doc, "", "//");
+    code.push_str(&format!("message {} {{\n", schema.name));
+    for (number, (name, attr)) in schema_attrs(schema).into_iter().enumerate() {
+        code.push_str(&gen_proto_field(name, attr, number + 1, exporter));
+    }
+    if schema.index_signature.is_some() {
+        exporter.add_warning(
+            None,
+            format!(
+                "the index signature of the schema '{}' has no protobuf equivalent",
+                schema.name
+            ),
+        );
+    }
+    code.push_str("}\n");
+    code
+}
+
+fn gen_proto_field(
+    name: &str,
+    attr: &SchemaAttr,
+    number: usize,
+    exporter: &mut Exporter,
+) -> String {
+    let mut code = doc_comment(attr.doc.as_deref().unwrap_or_default(), INDENT, "//");
+    let field_name = snake_case_name(name);
+    let ty = match proto_field_type(name, attr, exporter) {
+        FieldType::Single(ty) if attr.is_optional => format!("optional {}", ty),
+        FieldType::Single(ty) => ty,
+        FieldType::Repeated(ty) => format!("repeated {}", ty),
+        FieldType::Map(ty) => format!("map<string, {}>", ty),
+    };
+    let options = if field_name != name {
+        format!(" [json_name = {:?}]", name)
+    } else {
+        String::new()
+    };
+    code.push_str(&format!(
+        "{}{} {} = {}{};\n",
+        INDENT, ty, field_name, number, options
+    ));
+    code
+}
+
+/// Get the protobuf field type of the attribute, the attributes of the unsupported
+/// types are exported as `google.protobuf.Value` with a warning.
+fn proto_field_type(name: &str, attr: &SchemaAttr, exporter: &mut Exporter) -> FieldType {
+    let field_type = match &attr.ty.kind {
+        TypeKind::List(item_ty) => {
+            proto_type(name, attr, item_ty, exporter).map(FieldType::Repeated)
+        }
+        TypeKind::Dict(dict_ty) => match &dict_ty.key_ty.kind {
+            TypeKind::Str | TypeKind::StrLit(_) | TypeKind::Any => {
+                proto_type(name, attr, &dict_ty.val_ty, exporter).map(FieldType::Map)
+            }
+            _ => None,
+        },
+        _ => proto_type(name, attr, &attr.ty, exporter).map(FieldType::Single),
+    };
+    field_type.unwrap_or_else(|| {
+        exporter.add_warning(
+            Some(attr),
+            format!(
+                "the type '{}' of the attribute '{}' has no protobuf equivalent, use '{}'",
+                attr.ty.ty_str(),
+                name,
+                PROTO_VALUE_TYPE
+            ),
+        );
+        FieldType::Single(PROTO_VALUE_TYPE.to_string())
+    })
+}
+
+/// Get the protobuf type of a single value, returns [None] when the type can not
+/// be mapped to a protobuf scalar type or message.
+fn proto_type(name: &str, attr: &SchemaAttr, ty: &Type, exporter: &mut Exporter) -> Option<String> {
+    match &ty.kind {
+        TypeKind::Any => Some(PROTO_VALUE_TYPE.to_string()),
+        TypeKind::Bool | TypeKind::BoolLit(_) => Some("bool".to_string()),
+        TypeKind::Int | TypeKind::IntLit(_) => Some("int64".to_string()),
+        TypeKind::Float | TypeKind::FloatLit(_) => Some("double".to_string()),
+        TypeKind::Str | TypeKind::StrLit(_) => Some("string".to_string()),
+        // The union type is exported as the same protobuf type of its members
+        // with a warning, because the value constraint is not exported.
+        TypeKind::Union(types) => {
+            let types = types
+                .iter()
+                .map(|ty| proto_type(name, attr, ty, exporter))
+                .collect::<Option<Vec<String>>>()?;
+            match types.first() {
+                Some(first) if types.iter().all(|ty| ty == first) => {
+                    exporter.add_warning(
+                        Some(attr),
+                        format!(
+                            "the union type '{}' of the attribute '{}' is exported as '{}'",
+                            ty.ty_str(),
+                            name,
+                            first
+                        ),
+                    );
+                    Some(first.clone())
+                }
+                _ => None,
+            }
+        }
+        TypeKind::Schema(schema) if is_data_schema(schema) => {
+            exporter.add_schema(schema);
+            Some(schema.name.clone())
+        }
+        _ => None,
+    }
+}
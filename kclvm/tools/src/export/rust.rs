@@ -0,0 +1,135 @@
+//! Export the schemas as the Rust structs with the serde derives, the types
+//! which can not be mapped to Rust types are exported as `serde_json::Value`
+//! with a comment.
+use kclvm_sema::ty::{SchemaAttr, SchemaType, Type, TypeKind};
+
+use super::{
+    doc_comment, is_data_schema, schema_attrs, snake_case_name, Exporter, GENERATED_HEADER,
+};
+
+const INDENT: &str = "    ";
+const RUST_VALUE_TYPE: &str = "serde_json::Value";
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+/// The keywords which can not be used as raw identifiers.
+const RUST_RESERVED_NAMES: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Generate the Rust structs of the schemas, the schemas referenced by the
+/// attributes are generated after the definitions.
+pub(crate) fn gen_rust_structs(exporter: &mut Exporter) -> String {
+    let mut code = format!(
+        "{}\nuse serde::{{Deserialize, Serialize}};\n",
+        GENERATED_HEADER
+    );
+    let mut index = 0;
+    while index < exporter.schemas.len() {
+        let schema = exporter.schemas[index].clone();
+        code.push('\n');
+        code.push_str(&gen_rust_struct(&schema, exporter));
+        index += 1;
+    }
+    code
+}
+
+fn gen_rust_struct(schema: &SchemaType, exporter: &mut Exporter) -> String {
+    let mut code = doc_comment(&schema.doc, "", "///");
+    code.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+    code.push_str(&format!("pub struct {} {{\n", schema.name));
+    for (name, attr) in schema_attrs(schema) {
+        code.push_str(&gen_rust_field(name, attr, exporter));
+    }
+    if schema.index_signature.is_some() {
+        code.push_str(&format!(
+            "{}// unsupported index signature of the schema '{}'\n",
+            INDENT, schema.name
+        ));
+    }
+    code.push_str("}\n");
+    code
+}
+
+fn gen_rust_field(name: &str, attr: &SchemaAttr, exporter: &mut Exporter) -> String {
+    let mut code = doc_comment(attr.doc.as_deref().unwrap_or_default(), INDENT, "///");
+    let ty = match rust_type(&attr.ty, exporter) {
+        Some(ty) => ty,
+        None => {
+            code.push_str(&format!(
+                "{}// unsupported type '{}' of the attribute '{}'\n",
+                INDENT,
+                attr.ty.ty_str(),
+                name
+            ));
+            RUST_VALUE_TYPE.to_string()
+        }
+    };
+    let field_name = rust_field_name(name);
+    let mut serde_attrs = vec![];
+    if field_name.trim_start_matches("r#") != name {
+        serde_attrs.push(format!("rename = {:?}", name));
+    }
+    let ty = if attr.is_optional {
+        serde_attrs.push("default".to_string());
+        serde_attrs.push("skip_serializing_if = \"Option::is_none\"".to_string());
+        format!("Option<{}>", ty)
+    } else {
+        ty
+    };
+    if !serde_attrs.is_empty() {
+        code.push_str(&format!("{}#[serde({})]\n", INDENT, serde_attrs.join(", ")));
+    }
+    code.push_str(&format!("{}pub {}: {},\n", INDENT, field_name, ty));
+    code
+}
+
+/// Get the Rust type of the KCL type and collect the referenced schemas,
+/// returns [None] when the type can not be mapped to a Rust type.
+fn rust_type(ty: &Type, exporter: &mut Exporter) -> Option<String> {
+    match &ty.kind {
+        TypeKind::Any => Some(RUST_VALUE_TYPE.to_string()),
+        TypeKind::Bool | TypeKind::BoolLit(_) => Some("bool".to_string()),
+        TypeKind::Int | TypeKind::IntLit(_) => Some("i64".to_string()),
+        TypeKind::Float | TypeKind::FloatLit(_) => Some("f64".to_string()),
+        TypeKind::Str | TypeKind::StrLit(_) => Some("String".to_string()),
+        TypeKind::List(item_ty) => Some(format!("Vec<{}>", rust_type(item_ty, exporter)?)),
+        TypeKind::Dict(dict_ty) => match &dict_ty.key_ty.kind {
+            TypeKind::Str | TypeKind::StrLit(_) | TypeKind::Any => Some(format!(
+                "std::collections::HashMap<String, {}>",
+                rust_type(&dict_ty.val_ty, exporter)?
+            )),
+            _ => None,
+        },
+        // The union type is only supported when all the types are mapped to the same Rust type,
+        // e.g., the string literal union type `"TCP" | "UDP"`.
+        TypeKind::Union(types) => {
+            let types = types
+                .iter()
+                .map(|ty| rust_type(ty, exporter))
+                .collect::<Option<Vec<String>>>()?;
+            match types.first() {
+                Some(first) if types.iter().all(|ty| ty == first) => Some(first.clone()),
+                _ => None,
+            }
+        }
+        TypeKind::Schema(schema) if is_data_schema(schema) => {
+            exporter.add_schema(schema);
+            Some(schema.name.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Get the Rust field name of the attribute name, e.g., `apiVersion` -> `api_version`
+/// and `type` -> `r#type`.
+pub(crate) fn rust_field_name(name: &str) -> String {
+    let field_name = snake_case_name(name);
+    if RUST_KEYWORDS.contains(&field_name.as_str()) {
+        format!("r#{}", field_name)
+    } else if RUST_RESERVED_NAMES.contains(&field_name.as_str()) {
+        format!("{}_", field_name)
+    } else {
+        field_name
+    }
+}
@@ -0,0 +1,91 @@
+use super::rust::rust_field_name;
+use super::*;
+
+const TEST_FILE: &str = "./src/export/test_data/schema.k";
+const PROTO_GOLDEN_FILE: &str = "./src/export/test_data/schema.proto";
+
+#[test]
+fn test_export_rust() {
+    let result = export(TEST_FILE, ExportTarget::Rust).unwrap();
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        result.code,
+        r#"// Code generated by kcl export, DO NOT EDIT.
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata is the common metadata of the resources.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    /// The name of the resource.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<std::collections::HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Base {
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct App {
+    pub kind: String,
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    pub metadata: Metadata,
+    pub image: String,
+    pub replicas: i64,
+    pub ports: Vec<i64>,
+    pub protocol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    // unsupported type 'int | str' of the attribute 'port'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<serde_json::Value>,
+    pub debug: bool,
+}
+"#
+    );
+}
+
+#[test]
+fn test_export_proto() {
+    let result = export(TEST_FILE, ExportTarget::Proto).unwrap();
+    let expected = std::fs::read_to_string(PROTO_GOLDEN_FILE).unwrap();
+    assert_eq!(result.code, expected.replace("\r\n", "\n"));
+    let warnings: Vec<&str> = result.warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings[0].contains("the union type"), "{}", warnings[0]);
+    assert!(
+        warnings[0].contains("'protocol' is exported as 'string'"),
+        "{}",
+        warnings[0]
+    );
+    assert!(
+        warnings[1].contains("'int | str' of the attribute 'port' has no protobuf equivalent"),
+        "{}",
+        warnings[1]
+    );
+    assert!(result.warnings[1].range.0.filename.ends_with("schema.k"));
+}
+
+#[test]
+fn test_rust_field_name() {
+    assert_eq!(rust_field_name("name"), "name");
+    assert_eq!(rust_field_name("apiVersion"), "api_version");
+    assert_eq!(rust_field_name("type"), "r#type");
+    assert_eq!(rust_field_name("self"), "self_");
+    assert_eq!(rust_field_name("app.kubernetes.io"), "app_kubernetes_io");
+    assert_eq!(rust_field_name("8080"), "_8080");
+}
+
+#[test]
+fn test_export_target() {
+    assert_eq!(ExportTarget::try_from("Rust").unwrap(), ExportTarget::Rust);
+    assert_eq!(
+        ExportTarget::try_from("proto").unwrap(),
+        ExportTarget::Proto
+    );
+    assert!(ExportTarget::try_from("go").is_err());
+}
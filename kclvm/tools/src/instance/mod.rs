@@ -0,0 +1,115 @@
+//! [kclvm_tools::instance] module mainly contains the function to generate a
+//! KCL instance template of a schema, the main API function is `gen_instance`.
+//!
+//! Every schema attribute is set to its default value, required attributes
+//! without default values are set to a placeholder value of the attribute type
+//! and optional attributes without default values are set to `None`.
+use anyhow::Result;
+use kclvm_query::query::{get_schema_type, GetSchemaOption};
+use kclvm_sema::ty::{SchemaAttr, SchemaType, Type, TypeKind};
+
+#[cfg(test)]
+mod tests;
+
+const INDENT: &str = "    ";
+/// The max nested schema depth of placeholder values, which avoids
+/// infinite recursions of self-referenced schemas.
+const MAX_PLACEHOLDER_DEPTH: usize = 16;
+
+/// Generate the KCL code instantiating the schema named `schema_name` in the KCL file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kclvm_tools::instance::gen_instance;
+///
+/// let code = gen_instance("schema.k", "Server").unwrap();
+/// ```
+pub fn gen_instance(file: &str, schema_name: &str) -> Result<String> {
+    let schemas = get_schema_type(file, None, Some(schema_name), GetSchemaOption::Definitions)?;
+    let schema = schemas
+        .values()
+        .find(|s| s.name == schema_name)
+        .ok_or_else(|| anyhow::anyhow!("schema '{}' not found in {}", schema_name, file))?;
+    Ok(format!(
+        "{} = {}\n",
+        instance_name(schema_name),
+        gen_schema_expr(schema, 0)
+    ))
+}
+
+/// Get the instance variable name from the schema name e.g., `AppConfig` -> `app_config`.
+fn instance_name(schema_name: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in schema_name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Get all the schema attributes including the inherited ones.
+pub(crate) fn schema_attrs(schema: &SchemaType) -> Vec<(&String, &SchemaAttr)> {
+    let mut attrs = match &schema.base {
+        Some(base) => schema_attrs(base),
+        None => vec![],
+    };
+    for (name, attr) in &schema.attrs {
+        match attrs.iter().position(|(n, _)| *n == name) {
+            Some(index) => attrs[index] = (name, attr),
+            None => attrs.push((name, attr)),
+        }
+    }
+    attrs
+}
+
+fn gen_schema_expr(schema: &SchemaType, depth: usize) -> String {
+    let attrs = schema_attrs(schema);
+    if attrs.is_empty() {
+        return format!("{} {{}}", schema.name);
+    }
+    let indent = INDENT.repeat(depth + 1);
+    let mut code = format!("{} {{\n", schema.name);
+    for (name, attr) in attrs {
+        match &attr.default {
+            Some(default) => code.push_str(&format!("{}{} = {}\n", indent, name, default)),
+            None if attr.is_optional => code.push_str(&format!("{}{} = None\n", indent, name)),
+            None => code.push_str(&format!(
+                "{}{} = {}  # required\n",
+                indent,
+                name,
+                placeholder(&attr.ty, depth + 1)
+            )),
+        }
+    }
+    code.push_str(&format!("{}}}", INDENT.repeat(depth)));
+    code
+}
+
+/// Get the placeholder value code of the type.
+fn placeholder(ty: &Type, depth: usize) -> String {
+    match &ty.kind {
+        TypeKind::Bool => "False".to_string(),
+        TypeKind::BoolLit(v) => if *v { "True" } else { "False" }.to_string(),
+        TypeKind::Int => "0".to_string(),
+        TypeKind::IntLit(v) => v.to_string(),
+        TypeKind::Float => "0.0".to_string(),
+        TypeKind::FloatLit(v) => format!("{:?}", v),
+        TypeKind::Str => "\"\"".to_string(),
+        TypeKind::StrLit(v) => format!("{:?}", v),
+        TypeKind::List(_) => "[]".to_string(),
+        TypeKind::Dict(_) => "{}".to_string(),
+        TypeKind::Union(types) => match types.first() {
+            Some(ty) => placeholder(ty, depth),
+            None => "None".to_string(),
+        },
+        TypeKind::Schema(schema) if depth < MAX_PLACEHOLDER_DEPTH => gen_schema_expr(schema, depth),
+        _ => "None".to_string(),
+    }
+}
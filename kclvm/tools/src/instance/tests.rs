@@ -0,0 +1,49 @@
+use super::*;
+use kclvm_parser::ParseSession;
+use kclvm_runner::{exec_program, ExecProgramArgs};
+use std::sync::Arc;
+
+const TEST_FILE: &str = "./src/instance/test_data/schema.k";
+
+#[test]
+fn test_instance_name() {
+    assert_eq!(instance_name("App"), "app");
+    assert_eq!(instance_name("AppConfig"), "app_config");
+}
+
+#[test]
+fn test_gen_instance() {
+    let code = gen_instance(TEST_FILE, "App").unwrap();
+    assert_eq!(
+        code,
+        r#"app = App {
+    kind = "App"
+    metadata = Metadata {
+        name = ""  # required
+        labels = None
+    }  # required
+    image = "nginx"
+    replicas = 1
+    port = None
+    protocol = "TCP"
+    debug = False  # required
+}
+"#
+    );
+    assert!(gen_instance(TEST_FILE, "NotFound").is_err());
+}
+
+#[test]
+fn test_gen_instance_evaluation() {
+    let code = gen_instance(TEST_FILE, "App").unwrap();
+    let instance_file = std::env::temp_dir().join("kcl_gen_instance_app.k");
+    std::fs::write(&instance_file, code).unwrap();
+    let args = ExecProgramArgs {
+        k_filename_list: vec![TEST_FILE.to_string(), instance_file.display().to_string()],
+        ..Default::default()
+    };
+    let result = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    std::fs::remove_file(&instance_file).unwrap();
+    assert!(result.err_message.is_empty(), "{}", result.err_message);
+    assert!(result.yaml_result.contains("image: nginx"));
+}
@@ -1,6 +1,11 @@
+pub mod coverage;
+pub mod doc;
+pub mod export;
 pub mod fix;
 pub mod format;
+pub mod instance;
 pub mod lint;
+pub mod schemas;
 pub mod testing;
 pub mod util;
 pub mod vet;
@@ -90,8 +90,8 @@ pub fn lint_files(
             },
             None,
         )
-        .handler
-        .diagnostics,
+        .diagnostics()
+        .clone(),
     )
     .classification()
 }
@@ -0,0 +1,250 @@
+//! [kclvm_tools::schemas] module mainly contains the function to list the
+//! schemas of a KCL file with their base schemas and attributes, the main API
+//! function is `schema_tree`, which helps to understand a large config library.
+//!
+//! The parents of a schema are its base schema and its mixins. A schema is
+//! rendered under each of its parents, and the schemas without parents are
+//! the roots of the inheritance tree. The schema which reaches the same
+//! ancestor through more than one parent is flagged as a diamond inheritance.
+use anyhow::Result;
+use indexmap::IndexMap;
+use kclvm_ast::MAIN_PKG;
+use kclvm_query::query::{get_schema_type, GetSchemaOption};
+use kclvm_sema::ty::SchemaType;
+use serde_json::{json, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// The schema tree output format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SchemaTreeFormat {
+    #[default]
+    Tree,
+    Json,
+}
+
+impl TryFrom<&str> for SchemaTreeFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_lowercase().as_str() {
+            "tree" => Ok(SchemaTreeFormat::Tree),
+            "json" => Ok(SchemaTreeFormat::Json),
+            _ => Err(anyhow::anyhow!(
+                "unsupported schema tree format '{format}', expected one of tree and json"
+            )),
+        }
+    }
+}
+
+/// A schema with its parents and attributes in the inheritance tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaNode {
+    /// The schema name, which is qualified by the package path outside the main package.
+    pub name: String,
+    /// The base schema followed by the mixins.
+    pub parents: Vec<String>,
+    /// The derived schemas and the schemas which mix it in.
+    pub children: Vec<String>,
+    /// The attributes declared in the schema or mixed into it, in the definition order.
+    pub attrs: Vec<String>,
+    /// The ancestors reached through more than one parent.
+    pub diamonds: Vec<String>,
+}
+
+/// The inheritance tree of the schemas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaTree {
+    /// The schemas in the definition order, followed by the ancestors defined
+    /// in other packages.
+    pub schemas: IndexMap<String, SchemaNode>,
+}
+
+impl SchemaTree {
+    /// Returns the schemas without parents.
+    pub fn roots(&self) -> Vec<&SchemaNode> {
+        self.schemas
+            .values()
+            .filter(|schema| schema.parents.is_empty())
+            .collect()
+    }
+
+    /// Returns the schemas with the diamond inheritance.
+    pub fn diamonds(&self) -> Vec<&SchemaNode> {
+        self.schemas
+            .values()
+            .filter(|schema| !schema.diamonds.is_empty())
+            .collect()
+    }
+
+    /// Render the tree in the output format.
+    pub fn render(&self, format: &SchemaTreeFormat) -> Result<String> {
+        match format {
+            SchemaTreeFormat::Tree => Ok(self.to_tree()),
+            SchemaTreeFormat::Json => Ok(serde_json::to_string_pretty(&self.to_json())?),
+        }
+    }
+
+    /// Render the inheritance tree with the box-drawing characters, e.g.,
+    ///
+    /// ```text
+    /// Base: name, labels
+    /// ├── Backend: port
+    /// └── Frontend: path
+    /// ```
+    pub fn to_tree(&self) -> String {
+        let mut tree = String::new();
+        for root in self.roots() {
+            self.render_node(root, "", "", &mut tree);
+        }
+        tree
+    }
+
+    fn render_node(&self, node: &SchemaNode, prefix: &str, child_prefix: &str, tree: &mut String) {
+        tree.push_str(prefix);
+        tree.push_str(&node.name);
+        if !node.attrs.is_empty() {
+            tree.push_str(": ");
+            tree.push_str(&node.attrs.join(", "));
+        }
+        if !node.diamonds.is_empty() {
+            tree.push_str(&format!(
+                " (diamond inheritance of {})",
+                node.diamonds.join(", ")
+            ));
+        }
+        tree.push('\n');
+        for (i, child) in node.children.iter().enumerate() {
+            let last = i + 1 == node.children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            if let Some(child) = self.schemas.get(child) {
+                self.render_node(
+                    child,
+                    &format!("{}{}", child_prefix, branch),
+                    &format!("{}{}", child_prefix, indent),
+                    tree,
+                );
+            }
+        }
+    }
+
+    /// Render the tree to the JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "schemas": self.schemas.values().map(|schema| json!({
+                "name": schema.name,
+                "parents": schema.parents,
+                "children": schema.children,
+                "attributes": schema.attrs,
+                "diamonds": schema.diamonds,
+            })).collect::<Vec<Value>>(),
+            "roots": self.roots().iter().map(|schema| &schema.name).collect::<Vec<&String>>(),
+        })
+    }
+}
+
+/// List the schemas defined in the KCL file and their ancestors as an inheritance tree.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kclvm_tools::schemas::schema_tree;
+///
+/// let tree = schema_tree("main.k").unwrap();
+/// println!("{}", tree.to_tree());
+/// ```
+pub fn schema_tree(file: &str) -> Result<SchemaTree> {
+    let schemas = get_schema_type(file, None, None, GetSchemaOption::Definitions)?;
+    let mut tree = SchemaTree::default();
+    for schema in schemas.values() {
+        add_schema(&mut tree, schema);
+    }
+    let names = tree.schemas.keys().cloned().collect::<Vec<String>>();
+    for name in names {
+        let diamonds = find_diamonds(&tree, &name);
+        if let Some(node) = tree.schemas.get_mut(&name) {
+            node.diamonds = diamonds;
+        }
+    }
+    Ok(tree)
+}
+
+/// Returns the schema name qualified by the package path outside the main package.
+fn schema_name(schema: &SchemaType) -> String {
+    if schema.pkgpath.is_empty() || schema.pkgpath == MAIN_PKG {
+        schema.name.clone()
+    } else {
+        format!("{}.{}", schema.pkgpath, schema.name)
+    }
+}
+
+/// Add the schema and its ancestors into the tree, and returns the schema name.
+fn add_schema(tree: &mut SchemaTree, schema: &SchemaType) -> String {
+    let name = schema_name(schema);
+    if tree.schemas.contains_key(&name) {
+        return name;
+    }
+    tree.schemas.insert(
+        name.clone(),
+        SchemaNode {
+            name: name.clone(),
+            attrs: schema.attrs.keys().cloned().collect(),
+            ..Default::default()
+        },
+    );
+    let parents = schema
+        .base
+        .iter()
+        .map(|base| base.as_ref())
+        .chain(schema.mixins.iter())
+        .map(|parent| add_schema(tree, parent))
+        .collect::<Vec<String>>();
+    for parent in &parents {
+        if let Some(parent) = tree.schemas.get_mut(parent) {
+            if !parent.children.contains(&name) {
+                parent.children.push(name.clone());
+            }
+        }
+    }
+    if let Some(node) = tree.schemas.get_mut(&name) {
+        node.parents = parents;
+    }
+    name
+}
+
+/// Returns the ancestors of the schema which are reached through more than one parent.
+fn find_diamonds(tree: &SchemaTree, name: &str) -> Vec<String> {
+    let mut counts: IndexMap<String, usize> = IndexMap::default();
+    if let Some(node) = tree.schemas.get(name) {
+        for parent in &node.parents {
+            let mut ancestors = vec![];
+            collect_ancestors(tree, parent, &mut ancestors);
+            for ancestor in ancestors {
+                *counts.entry(ancestor).or_default() += 1;
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(ancestor, _)| ancestor)
+        .collect()
+}
+
+/// Collect the schema and all its ancestors without duplicates.
+fn collect_ancestors(tree: &SchemaTree, name: &str, ancestors: &mut Vec<String>) {
+    if ancestors.iter().any(|ancestor| ancestor == name) {
+        return;
+    }
+    ancestors.push(name.to_string());
+    if let Some(node) = tree.schemas.get(name) {
+        for parent in &node.parents {
+            collect_ancestors(tree, parent, ancestors);
+        }
+    }
+}
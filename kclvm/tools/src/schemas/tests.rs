@@ -0,0 +1,55 @@
+use super::*;
+
+const TEST_FILE: &str = "./src/schemas/test_data/main.k";
+const DIAMOND_TEST_FILE: &str = "./src/schemas/test_data/diamond.k";
+
+#[test]
+fn test_schema_tree() {
+    let tree = schema_tree(TEST_FILE).unwrap();
+    assert_eq!(
+        tree.schemas.keys().collect::<Vec<_>>(),
+        vec!["Base", "Backend", "Frontend"]
+    );
+    let base = &tree.schemas["Base"];
+    assert!(base.parents.is_empty());
+    assert_eq!(base.children, vec!["Backend", "Frontend"]);
+    assert_eq!(base.attrs, vec!["name", "labels"]);
+    assert_eq!(tree.schemas["Backend"].parents, vec!["Base"]);
+    assert_eq!(tree.schemas["Frontend"].attrs, vec!["path"]);
+    assert!(tree.diamonds().is_empty());
+    assert_eq!(
+        tree.render(&SchemaTreeFormat::Tree).unwrap(),
+        "Base: name, labels\n├── Backend: port\n└── Frontend: path\n"
+    );
+    let json = tree.to_json();
+    assert_eq!(json["roots"], json!(["Base"]));
+    assert_eq!(
+        json["schemas"][1],
+        json!({
+            "name": "Backend",
+            "parents": ["Base"],
+            "children": [],
+            "attributes": ["port"],
+            "diamonds": [],
+        })
+    );
+}
+
+#[test]
+fn test_schema_tree_with_diamond_inheritance() {
+    let tree = schema_tree(DIAMOND_TEST_FILE).unwrap();
+    assert_eq!(tree.schemas["App"].parents, vec!["Base", "LabelsMixin"]);
+    assert_eq!(tree.schemas["LabelsMixin"].children, vec!["Base", "App"]);
+    let diamonds = tree
+        .diamonds()
+        .iter()
+        .map(|schema| (schema.name.as_str(), schema.diamonds.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(diamonds, vec![("App", vec!["LabelsMixin".to_string()])]);
+    let rendered = tree.to_tree();
+    assert!(rendered.starts_with("LabelsMixin: labels\n"), "{rendered}");
+    assert!(
+        rendered.contains("└── App: replicas, labels (diamond inheritance of LabelsMixin)\n"),
+        "{rendered}"
+    );
+}
@@ -43,7 +43,6 @@ impl DataLoader {
     }
 
     /// If `DataLoader` is constructed using a Json/Yaml string, then `content` is the string
-    #[allow(dead_code)]
     pub(crate) fn new_with_str(loader_kind: LoaderKind, content: &str) -> Result<Self> {
         let sm = SourceMap::new(FilePathMapping::empty());
         sm.new_source_file(PathBuf::from("").into(), content.to_string());
@@ -329,7 +329,7 @@ mod test_validater {
         util::loader::LoaderKind,
         vet::{
             tests::deal_windows_filepath,
-            validator::{validate, ValidateOption},
+            validator::{validate, validate_str, ValidateOption},
         },
     };
 
@@ -357,6 +357,39 @@ mod test_validater {
         println!("test_invalid_validate_with_json_pos - PASS");
         test_invalid_validate_with_yaml_pos();
         println!("test_invalid_validate_with_yaml_pos - PASS");
+        test_validate_str();
+        println!("test_validate_str - PASS");
+    }
+
+    fn test_validate_str() {
+        for (i, file_suffix) in VALIDATED_FILE_TYPE.iter().enumerate() {
+            for (dir, valid) in [("validate_cases", true), ("invalid_validate_cases", false)] {
+                for case in KCL_TEST_CASES {
+                    let validated_file_path = construct_full_path(&format!(
+                        "{}.{}",
+                        Path::new(dir).join(case).display(),
+                        file_suffix
+                    ))
+                    .unwrap();
+                    let kcl_file_path =
+                        construct_full_path(&Path::new(dir).join(case).display().to_string())
+                            .unwrap();
+                    let content = fs::read_to_string(&validated_file_path).unwrap();
+
+                    let opt = ValidateOption::new(
+                        None,
+                        "value".to_string(),
+                        validated_file_path,
+                        *LOADER_KIND[i],
+                        Some(kcl_file_path),
+                        None,
+                    );
+
+                    // The content is validated like the file of the same content.
+                    assert_eq!(validate_str(opt, &content).is_ok(), valid, "{case}");
+                }
+            }
+        }
     }
 
     fn test_validate() {
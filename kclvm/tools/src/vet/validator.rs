@@ -172,6 +172,25 @@ const TMP_FILE: &str = "validationTempKCLCode.k";
 /// }
 /// ```
 pub fn validate(val_opt: ValidateOption) -> Result<bool> {
+    let file_path = val_opt.validated_file_path.clone();
+    validate_with_builder(val_opt, |kind| {
+        ExprBuilder::new_with_file_path(kind, file_path)
+    })
+}
+
+/// Validate the JSON or YAML content like [validate] instead of the content of
+/// `validated_file_path`, e.g., the content is one document of a YAML stream,
+/// thus the documents are validated one at a time without loading the whole file.
+pub fn validate_str(val_opt: ValidateOption, content: &str) -> Result<bool> {
+    validate_with_builder(val_opt, |kind| {
+        ExprBuilder::new_with_str(kind, content.to_string())
+    })
+}
+
+fn validate_with_builder(
+    val_opt: ValidateOption,
+    new_builder: impl FnOnce(LoaderKind) -> Result<ExprBuilder>,
+) -> Result<bool> {
     let k_path = match val_opt.kcl_path {
         Some(path) => path,
         None => TMP_FILE.to_string(),
@@ -185,8 +204,7 @@ pub fn validate(val_opt: ValidateOption) -> Result<bool> {
         None => schemas.get(0).map(|schema| schema.name.node.clone()),
     };
 
-    let expr_builder =
-        ExprBuilder::new_with_file_path(val_opt.validated_file_kind, val_opt.validated_file_path)?;
+    let expr_builder = new_builder(val_opt.validated_file_kind)?;
 
     let validated_expr = expr_builder.build(schema_name)?;
 
@@ -197,7 +215,7 @@ pub fn validate(val_opt: ValidateOption) -> Result<bool> {
     execute_module(module).map_err_to_result().map(|_| true)
 }
 
-fn build_assign(attr_name: &str, node: NodeRef<Expr>) -> NodeRef<Stmt> {
+pub(crate) fn build_assign(attr_name: &str, node: NodeRef<Expr>) -> NodeRef<Stmt> {
     node_ref!(Stmt::Assign(AssignStmt {
         targets: vec![node_ref!(Identifier {
             names: vec![Node::dummy_node(attr_name.to_string())],